@@ -1,21 +1,85 @@
-use actix::{Actor, Context, Handler, Message};
-use sgx_types::{sgx_report_t, sgx_target_info_t, SgxResult};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
-use crate::traits::VaultEnclave;
+use actix::{Actor, Handler, Message, SyncContext};
+use sgx_types::{sgx_report_t, sgx_status_t, sgx_target_info_t, SgxResult};
+
+use crate::metrics::EnclaveMetrics;
+use crate::traits::{OperationLabel, SealedResponse, VaultEnclave};
+
+/// Initial sealed response capacity for vault operations: 1 KiB.
+pub(crate) const INITIAL_RESPONSE_CAPACITY: usize = 1 << 10;
+
+/// Source of request IDs for [`VaultEnclave::vault_operation`].
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(0);
 
 /// This actor lets [`crate::resources`] interact with the vault enclave.
+///
+/// This runs in a [`SyncArbiter`](actix::SyncArbiter) pool: each instance blocks its own
+/// thread while calling into the enclave, and shares the enclave with the others.
+///
+/// Each enclave call is recorded in [`EnclaveMetrics`].
 pub(crate) struct VaultEnclaveActor {
-    pub(crate) vault_enclave: Box<dyn VaultEnclave>,
+    pub(crate) vault_enclave: Arc<dyn VaultEnclave>,
+    pub(crate) metrics: Arc<EnclaveMetrics>,
+}
+
+impl VaultEnclaveActor {
+    /// Call `ecall`, and record its latency and outcome.
+    fn observed<T, F>(&self, ecall: &str, call: F) -> SgxResult<SgxResult<T>>
+    where
+        T: Outcome,
+        F: FnOnce(&dyn VaultEnclave) -> SgxResult<SgxResult<T>>,
+    {
+        let started = Instant::now();
+        let result = call(self.vault_enclave.as_ref());
+        // Failures are labelled by status, like SGX_ERROR_UNEXPECTED.
+        let outcome = match &result {
+            Ok(Ok(value)) => value.outcome().to_string(),
+            Ok(Err(sgx_error)) | Err(sgx_error) => format!("{:?}", sgx_error),
+        };
+        self.metrics
+            .observe_ecall(ecall, started.elapsed(), &outcome);
+        result
+    }
 }
 
 impl Actor for VaultEnclaveActor {
-    type Context = Context<Self>;
+    type Context = SyncContext<Self>;
+}
+
+/// Metric label for a successful enclave call.
+trait Outcome {
+    fn outcome(&self) -> &'static str {
+        "ok"
+    }
+}
+
+impl Outcome for (sgx_report_t, [u8; 32]) {}
+
+impl Outcome for Box<[u8]> {}
+
+impl Outcome for SealedResponse {
+    fn outcome(&self) -> &'static str {
+        match self {
+            SealedResponse::Complete(_) => "ok",
+            SealedResponse::Pending { .. } => "pending",
+        }
+    }
+}
+
+impl Outcome for (SealedResponse, OperationLabel) {
+    fn outcome(&self) -> &'static str {
+        self.0.outcome()
+    }
 }
 
 // CreateReport message:
 
 pub(crate) struct CreateReportMessage {
     pub(crate) target_info: sgx_target_info_t,
+    pub(crate) user_data: [u8; 32],
 }
 
 impl Message for CreateReportMessage {
@@ -26,7 +90,9 @@ impl Handler<CreateReportMessage> for VaultEnclaveActor {
     type Result = <CreateReportMessage as Message>::Result;
 
     fn handle(&mut self, msg: CreateReportMessage, _ctx: &mut Self::Context) -> Self::Result {
-        self.vault_enclave.create_report(msg.target_info)
+        self.observed("create_report", |vault_enclave| {
+            vault_enclave.create_report(msg.target_info, msg.user_data)
+        })
     }
 }
 
@@ -43,8 +109,84 @@ impl Message for VaultOperationMessage {
 impl Handler<VaultOperationMessage> for VaultEnclaveActor {
     type Result = <VaultOperationMessage as Message>::Result;
 
+    /// Execute the operation once, and fetch its response if it exceeds
+    /// [`INITIAL_RESPONSE_CAPACITY`], without executing the operation again.
     fn handle(&mut self, msg: VaultOperationMessage, _ctx: &mut Self::Context) -> Self::Result {
-        self.vault_enclave
-            .vault_operation_with_retry(&msg.sealed_request_bytes)
+        let request_id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+        let result = match self.observed("vault_operation", |vault_enclave| {
+            vault_enclave.vault_operation(
+                request_id,
+                &msg.sealed_request_bytes,
+                INITIAL_RESPONSE_CAPACITY,
+            )
+        }) {
+            Ok(Ok((sealed_response, label))) => {
+                self.metrics.inc_vault_operations(label);
+                match sealed_response {
+                    SealedResponse::Pending { size } => {
+                        self.metrics.inc_response_fetches();
+                        self.observed("fetch_vault_response", |vault_enclave| {
+                            vault_enclave.fetch_vault_response(request_id, size)
+                        })
+                    }
+                    complete => Ok(Ok(complete)),
+                }
+            }
+            Ok(Err(sgx_error)) => Ok(Err(sgx_error)),
+            Err(sgx_error) => Err(sgx_error),
+        };
+        match result {
+            Ok(Ok(SealedResponse::Complete(sealed_response))) => Ok(Ok(sealed_response)),
+            // The held response should fit its own size: don't chase it further.
+            Ok(Ok(SealedResponse::Pending { .. })) => {
+                Ok(Err(sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT))
+            }
+            Ok(Err(sgx_error)) => Ok(Err(sgx_error)),
+            Err(sgx_error) => Err(sgx_error),
+        }
+    }
+}
+
+// SessionHandshake message:
+
+pub(crate) struct SessionHandshakeMessage {
+    pub(crate) hello_bytes: Box<[u8]>,
+}
+
+impl Message for SessionHandshakeMessage {
+    type Result = SgxResult<SgxResult<Box<[u8]>>>;
+}
+
+impl Handler<SessionHandshakeMessage> for VaultEnclaveActor {
+    type Result = <SessionHandshakeMessage as Message>::Result;
+
+    fn handle(&mut self, msg: SessionHandshakeMessage, _ctx: &mut Self::Context) -> Self::Result {
+        self.observed("session_handshake", |vault_enclave| {
+            vault_enclave.session_handshake(&msg.hello_bytes)
+        })
+    }
+}
+
+// CheckReady message:
+
+pub(crate) struct CheckReadyMessage;
+
+impl Message for CheckReadyMessage {
+    type Result = Result<(), String>;
+}
+
+impl Handler<CheckReadyMessage> for VaultEnclaveActor {
+    type Result = <CheckReadyMessage as Message>::Result;
+
+    fn handle(&mut self, _msg: CheckReadyMessage, _ctx: &mut Self::Context) -> Self::Result {
+        let started = Instant::now();
+        let result = self.vault_enclave.check_ready();
+        let outcome = match result {
+            Ok(()) => "ok",
+            Err(_) => "not_ready",
+        };
+        self.metrics
+            .observe_ecall("check_ready", started.elapsed(), outcome);
+        result
     }
 }
@@ -0,0 +1,86 @@
+//! Configuration for [`crate::server::run_server`].
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::server::{DEFAULT_ENCLAVE_WORKERS, DEFAULT_MAX_REQUEST_SIZE};
+
+/// Settings of the vault HTTP service.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ServerConfig {
+    /// Address to listen on, like `127.0.0.1:8080`.
+    pub bind_addr: String,
+
+    /// Number of concurrent enclave calls: see [`crate::server::AppState::start`].
+    pub enclave_workers: usize,
+
+    /// Origins that may call the service from a browser.
+    pub cors_origins: CorsOrigins,
+
+    /// Larger request bodies are refused with 413.
+    pub max_request_size: usize,
+
+    /// Time allowed for a client to send its request head.
+    pub client_request_timeout: Duration,
+
+    /// Time allowed for a client to acknowledge a connection shutdown.
+    pub client_disconnect_timeout: Duration,
+
+    /// How long idle connections stay open.
+    pub keep_alive: Duration,
+
+    /// Time allowed for in-flight requests to finish when the server stops.
+    pub shutdown_timeout: Duration,
+
+    /// Serve HTTPS instead of plain HTTP, if set.
+    pub tls: Option<TlsConfig>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: "127.0.0.1:8080".to_string(),
+            enclave_workers: DEFAULT_ENCLAVE_WORKERS,
+            cors_origins: CorsOrigins::default(),
+            max_request_size: DEFAULT_MAX_REQUEST_SIZE,
+            client_request_timeout: Duration::from_secs(5),
+            client_disconnect_timeout: Duration::from_secs(1),
+            keep_alive: Duration::from_secs(5),
+            shutdown_timeout: Duration::from_secs(30),
+            tls: None,
+        }
+    }
+}
+
+/// Cross-origin request policy.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum CorsOrigins {
+    /// Allow any origin.
+    Any,
+
+    /// Allow only these origins, like `https://wallet.example.com`.
+    ///
+    /// If empty, no cross-origin requests are allowed.
+    List(Vec<String>),
+}
+
+impl Default for CorsOrigins {
+    fn default() -> Self {
+        Self::List(Vec::new())
+    }
+}
+
+/// How to serve HTTPS.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum TlsConfig {
+    /// Use a certificate chain and private key from PEM files.
+    Pem {
+        cert_path: PathBuf,
+        key_path: PathBuf,
+    },
+
+    /// RA-TLS: use a self-signed certificate bound to an enclave report.
+    ///
+    /// See [`crate::tls::ra_tls_certificate`].
+    RemoteAttestation { subject_alt_names: Vec<String> },
+}
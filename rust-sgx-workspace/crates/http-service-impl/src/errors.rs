@@ -0,0 +1,146 @@
+//! Error responses of the vault service.
+//!
+//! Failures are logged with their details, and reported to clients as an [`ErrorBody`]
+//! that carries no internal debug data.
+
+use std::fmt;
+
+use actix::MailboxError;
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+use sgx_types::sgx_status_t;
+
+/// What went wrong, as reported to clients.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The request could not be read or processed: 400.
+    MalformedRequest,
+
+    /// The request body exceeds the size limit: 413.
+    RequestTooLarge,
+
+    /// The enclave is at capacity: 503, retryable.
+    EnclaveBusy,
+
+    /// The service is not ready to handle requests yet: 503, retryable.
+    NotReady,
+
+    /// The enclave is gone, and needs the server to restart: 503.
+    EnclaveUnavailable,
+
+    /// The enclave failed to handle the request: 500.
+    EnclaveFailed,
+
+    /// The server failed to handle the request: 500.
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn status_code(self) -> StatusCode {
+        match self {
+            Self::MalformedRequest => StatusCode::BAD_REQUEST,
+            Self::RequestTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::EnclaveBusy | Self::NotReady | Self::EnclaveUnavailable => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            Self::EnclaveFailed | Self::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Whether the same request may succeed if sent again later.
+    pub fn retryable(self) -> bool {
+        matches!(self, Self::EnclaveBusy | Self::NotReady)
+    }
+}
+
+/// JSON body of the vault service's error responses.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub message: String,
+    pub retryable: bool,
+}
+
+/// Error returned by the vault service's resources.
+///
+/// The constructors log the failure's details, and keep only a client-safe `message`.
+#[derive(Debug)]
+pub(crate) struct ServiceError {
+    code: ErrorCode,
+    message: &'static str,
+}
+
+impl ServiceError {
+    pub(crate) fn new(code: ErrorCode, message: &'static str) -> Self {
+        Self { code, message }
+    }
+
+    /// Failed to reach the [`crate::actors::VaultEnclaveActor`] pool.
+    pub(crate) fn from_mailbox_error(context: &str, mailbox_error: MailboxError) -> Self {
+        println!("{}: failed to reach actor: {:?}", context, mailbox_error);
+        match mailbox_error {
+            MailboxError::Timeout => Self::new(ErrorCode::EnclaveBusy, "enclave is busy"),
+            MailboxError::Closed => {
+                Self::new(ErrorCode::EnclaveUnavailable, "enclave is unavailable")
+            }
+        }
+    }
+
+    /// The ECALL itself failed: the outer status of a [`crate::traits::VaultEnclave`] call.
+    pub(crate) fn from_ecall_error(context: &str, sgx_error: sgx_status_t) -> Self {
+        println!("{}: failed to call enclave: {:?}", context, sgx_error);
+        match sgx_error {
+            sgx_status_t::SGX_ERROR_OUT_OF_TCS | sgx_status_t::SGX_ERROR_BUSY => {
+                Self::new(ErrorCode::EnclaveBusy, "enclave is busy")
+            }
+            sgx_status_t::SGX_ERROR_ENCLAVE_LOST
+            | sgx_status_t::SGX_ERROR_ENCLAVE_CRASHED
+            | sgx_status_t::SGX_ERROR_INVALID_ENCLAVE_ID => {
+                Self::new(ErrorCode::EnclaveUnavailable, "enclave is unavailable")
+            }
+            _ => Self::new(ErrorCode::EnclaveFailed, "enclave call failed"),
+        }
+    }
+
+    /// The enclave reported failure: the inner status of a [`crate::traits::VaultEnclave`] call.
+    pub(crate) fn from_enclave_error(context: &str, sgx_error: sgx_status_t) -> Self {
+        println!("{}: enclave failed: {}", context, sgx_error);
+        match sgx_error {
+            sgx_status_t::SGX_ERROR_OUT_OF_TCS | sgx_status_t::SGX_ERROR_BUSY => {
+                Self::new(ErrorCode::EnclaveBusy, "enclave is busy")
+            }
+            _ => Self::new(
+                ErrorCode::EnclaveFailed,
+                "enclave failed to handle the request",
+            ),
+        }
+    }
+
+    pub(crate) fn to_body(&self) -> ErrorBody {
+        ErrorBody {
+            code: self.code,
+            message: self.message.to_string(),
+            retryable: self.code.retryable(),
+        }
+    }
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)
+    }
+}
+
+impl ResponseError for ServiceError {
+    fn status_code(&self) -> StatusCode {
+        self.code.status_code()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(self.to_body())
+    }
+}
@@ -1,6 +1,13 @@
 extern crate alloc;
 
 mod actors;
+pub mod config;
+pub mod errors;
+mod metrics;
 mod resources;
 pub mod server;
+pub mod tls;
 pub mod traits;
+pub mod verify;
+
+pub use resources::enclave_report::attestation_report;
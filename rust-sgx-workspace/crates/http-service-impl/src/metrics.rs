@@ -0,0 +1,119 @@
+//! Prometheus metrics of enclave calls, served by `GET /metrics`.
+//!
+//! These only record ECALL names, timings and statuses, and the type and outcome of each
+//! vault operation as the enclave labels it: never request or response content.
+
+use std::time::Duration;
+
+use prometheus::{
+    exponential_buckets,
+    Encoder,
+    HistogramOpts,
+    HistogramVec,
+    IntCounter,
+    IntCounterVec,
+    Opts,
+    Registry,
+    TextEncoder,
+};
+
+use crate::traits::OperationLabel;
+
+/// Metrics collected by [`crate::actors::VaultEnclaveActor`].
+pub(crate) struct EnclaveMetrics {
+    registry: Registry,
+
+    /// ECALL latency, by ECALL.
+    ecall_duration: HistogramVec,
+
+    /// ECALL outcomes, by ECALL and outcome.
+    ecall_outcomes: IntCounterVec,
+
+    /// Vault operations whose response needed a second ECALL to fetch.
+    response_fetches: IntCounter,
+
+    /// Vault operations, by operation and outcome.
+    vault_operations: IntCounterVec,
+}
+
+impl EnclaveMetrics {
+    pub(crate) fn new() -> Self {
+        let ecall_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "vault_enclave_ecall_duration_seconds",
+                "Latency of enclave calls.",
+            )
+            // 0.5 ms to 16 s
+            .buckets(exponential_buckets(0.0005, 2.0, 16).unwrap()),
+            &["ecall"],
+        )
+        .unwrap();
+        let ecall_outcomes = IntCounterVec::new(
+            Opts::new(
+                "vault_enclave_ecall_outcomes_total",
+                "Outcomes of enclave calls.",
+            ),
+            &["ecall", "outcome"],
+        )
+        .unwrap();
+        let response_fetches = IntCounter::new(
+            "vault_enclave_response_fetches_total",
+            "Vault operation responses that exceeded the initial buffer, and were fetched.",
+        )
+        .unwrap();
+
+        let vault_operations = IntCounterVec::new(
+            Opts::new(
+                "vault_operations_total",
+                "Vault operations, by type and outcome.",
+            ),
+            &["operation", "outcome"],
+        )
+        .unwrap();
+
+        let registry = Registry::new();
+        registry.register(Box::new(ecall_duration.clone())).unwrap();
+        registry.register(Box::new(ecall_outcomes.clone())).unwrap();
+        registry
+            .register(Box::new(response_fetches.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(vault_operations.clone()))
+            .unwrap();
+        Self {
+            registry,
+            ecall_duration,
+            ecall_outcomes,
+            response_fetches,
+            vault_operations,
+        }
+    }
+
+    /// Record a call of `ecall` that took `duration`, with `outcome`.
+    pub(crate) fn observe_ecall(&self, ecall: &str, duration: Duration, outcome: &str) {
+        self.ecall_duration
+            .with_label_values(&[ecall])
+            .observe(duration.as_secs_f64());
+        self.ecall_outcomes
+            .with_label_values(&[ecall, outcome])
+            .inc();
+    }
+
+    pub(crate) fn inc_response_fetches(&self) {
+        self.response_fetches.inc();
+    }
+
+    /// Record a vault operation that the enclave labelled `label`.
+    pub(crate) fn inc_vault_operations(&self, label: OperationLabel) {
+        self.vault_operations
+            .with_label_values(&[label.operation_name(), label.outcome_name()])
+            .inc();
+    }
+
+    /// Encode the metrics in the Prometheus text format.
+    pub(crate) fn encode_text(&self) -> prometheus::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(buffer)
+    }
+}
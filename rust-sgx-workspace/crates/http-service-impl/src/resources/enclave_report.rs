@@ -1,29 +1,95 @@
-use actix_web::{error, get, web};
+use actix_web::{get, post, web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+use sgx_types::sgx_target_info_t;
 
 use crate::actors::CreateReportMessage;
-use crate::resources::enclave_report::attestation_report::{to_msgpack, AttestationReport};
+use crate::errors::{ErrorCode, ServiceError};
+use crate::resources::enclave_report::attestation_report::{
+    to_msgpack,
+    AttestationReport,
+    EnclaveReportRequest,
+    Nonce,
+};
+use crate::resources::read_request_body;
 use crate::server::AppState;
 
+/// Query parameters of `GET /enclave-report`.
+#[derive(Deserialize)]
+struct EnclaveReportQuery {
+    /// Hex-encoded [`Nonce`].
+    nonce: Option<String>,
+}
+
+/// Report targeting the default target info, binding the `nonce` query parameter, if any.
+///
+/// Only the enclave itself can verify this report: use `POST /enclave-report` to target
+/// another enclave, like a quoting enclave.
 #[get("/enclave-report")]
 pub(crate) async fn get_enclave_report(
     app_state: web::Data<AppState>,
-) -> actix_web::Result<impl actix_web::Responder> {
+    request: HttpRequest,
+) -> actix_web::Result<HttpResponse> {
+    let invalid_nonce = || ServiceError::new(ErrorCode::MalformedRequest, "invalid nonce");
+    let query = web::Query::<EnclaveReportQuery>::from_query(request.query_string())
+        .map_err(|_| invalid_nonce())?;
+    let nonce = match &query.nonce {
+        Some(nonce_hex) => {
+            let mut nonce = Nonce::default();
+            hex::decode_to_slice(nonce_hex, &mut nonce).map_err(|_| invalid_nonce())?;
+            Some(nonce)
+        }
+        None => None,
+    };
+    Ok(enclave_report("get_enclave_report", &app_state, Default::default(), nonce).await?)
+}
+
+/// Report targeting the target info of an [`EnclaveReportRequest`], binding its nonce, if any.
+#[post("/enclave-report")]
+pub(crate) async fn post_enclave_report(
+    app_state: web::Data<AppState>,
+    request_body: web::Payload,
+) -> actix_web::Result<HttpResponse> {
+    let request_bytes = read_request_body(request_body, app_state.max_request_size).await?;
+    let request: EnclaveReportRequest =
+        rmp_serde::from_read_ref(&request_bytes).map_err(|err| {
+            println!("post_enclave_report: invalid request: {}", err);
+            ServiceError::new(ErrorCode::MalformedRequest, "invalid report request")
+        })?;
+    let target_info = request
+        .target_info
+        .to_target_info()
+        .ok_or_else(|| ServiceError::new(ErrorCode::MalformedRequest, "invalid target info"))?;
+    Ok(enclave_report(
+        "post_enclave_report",
+        &app_state,
+        target_info,
+        request.nonce,
+    )
+    .await?)
+}
+
+/// Create a report for `target_info` that binds `nonce`, and respond with its
+/// [`AttestationReport`].
+///
+/// Without a nonce, the report binds zeroes, and a client cannot tell a fresh report
+/// from a replayed one.
+async fn enclave_report(
+    context: &str,
+    app_state: &AppState,
+    target_info: sgx_target_info_t,
+    nonce: Option<Nonce>,
+) -> Result<HttpResponse, ServiceError> {
     let message = CreateReportMessage {
-        target_info: Default::default(),
+        target_info,
+        user_data: nonce.unwrap_or_default(),
     };
     let (report, enclave_data) = app_state
         .vault_enclave_addr
         .send(message)
         .await
-        .map_err(|mailbox_error| {
-            error::ErrorInternalServerError(format!("Failed to reach actor: {:?}", mailbox_error))
-        })?
-        .map_err(|sgx_error| {
-            error::ErrorInternalServerError(format!("Failed to call enclave: {:?}", sgx_error))
-        })?
-        .map_err(|sgx_error| {
-            error::ErrorInternalServerError(format!("enclave_create_report failed: {}", sgx_error))
-        })?;
+        .map_err(|mailbox_error| ServiceError::from_mailbox_error(context, mailbox_error))?
+        .map_err(|sgx_error| ServiceError::from_ecall_error(context, sgx_error))?
+        .map_err(|sgx_error| ServiceError::from_enclave_error(context, sgx_error))?;
 
     let attestation_report = &AttestationReport {
         report: report.into(),
@@ -32,25 +98,26 @@ pub(crate) async fn get_enclave_report(
     let response_body = to_msgpack(attestation_report)
         .map(Vec::from)
         .map_err(|err| {
-            error::ErrorInternalServerError(format!(
-                "failed to serialize AttestationReport: {}",
-                err
-            ))
+            println!(
+                "{}: failed to serialize AttestationReport: {}",
+                context, err
+            );
+            ServiceError::new(ErrorCode::InternalError, "failed to serialize report")
         })?;
-    Ok(actix_web::HttpResponse::Ok()
+    Ok(HttpResponse::Ok()
         .content_type("application/x-msgpack")
         .body(response_body))
 }
 
 /// XXX: Stop-gap
 
-mod attestation_report {
+pub mod attestation_report {
     use rmp_serde::{encode, Serializer};
     use serde::{Deserialize, Serialize};
     use sgx_types::*;
 
     /// See [`../../crates/sgx_vault_impl::schema::msgpack::ToMessagePack::to_msgpack`]
-    pub(crate) fn to_msgpack(message: &impl Serialize) -> Result<Box<[u8]>, encode::Error> {
+    pub fn to_msgpack(message: &impl Serialize) -> Result<Box<[u8]>, encode::Error> {
         // XXX: Like rmp_serde::to_vec_named, but we need string variants too.
         let mut wr = Vec::with_capacity(128);
         let mut se = Serializer::new(&mut wr)
@@ -62,6 +129,63 @@ mod attestation_report {
 
     type PublicKey = [u8; 32];
 
+    /// A verifier's challenge, bound into the second half of the report data.
+    ///
+    /// See [`crate::verify::ReportExpectations::user_data`].
+    pub type Nonce = [u8; 32];
+
+    /// Request body of `POST /enclave-report`.
+    #[derive(Clone, Eq, PartialEq, Debug)] // core
+    #[derive(Deserialize, Serialize)] // serde
+    pub struct EnclaveReportRequest {
+        /// The enclave that the report should target, like the quoting enclave.
+        pub target_info: SgxTargetInfo,
+
+        /// Bind this into the report, if given.
+        pub nonce: Option<Nonce>,
+    }
+
+    #[derive(Clone, Eq, PartialEq, Debug)] // core
+    #[derive(Deserialize, Serialize)] // serde
+    pub struct SgxTargetInfo {
+        pub mr_enclave: [uint8_t; SGX_HASH_SIZE],
+        pub attributes_flags: uint64_t,
+        pub attributes_xfrm: uint64_t,
+        pub config_svn: sgx_config_svn_t,
+        pub misc_select: sgx_misc_select_t,
+        pub config_id: Box<[uint8_t]>, // XXX [uint8_t; SGX_CONFIGID_SIZE]
+    }
+
+    impl SgxTargetInfo {
+        /// Convert to [`sgx_target_info_t`], or `None` if `config_id` has the wrong size.
+        pub fn to_target_info(&self) -> Option<sgx_target_info_t> {
+            Some(sgx_target_info_t {
+                mr_enclave: sgx_measurement_t { m: self.mr_enclave },
+                attributes: sgx_attributes_t {
+                    flags: self.attributes_flags,
+                    xfrm: self.attributes_xfrm,
+                },
+                config_svn: self.config_svn,
+                misc_select: self.misc_select,
+                config_id: self.config_id.as_ref().try_into().ok()?,
+                ..Default::default()
+            })
+        }
+    }
+
+    impl From<sgx_target_info_t> for SgxTargetInfo {
+        fn from(target_info: sgx_target_info_t) -> Self {
+            Self {
+                mr_enclave: target_info.mr_enclave.m,
+                attributes_flags: target_info.attributes.flags,
+                attributes_xfrm: target_info.attributes.xfrm,
+                config_svn: target_info.config_svn,
+                misc_select: target_info.misc_select,
+                config_id: target_info.config_id.into(),
+            }
+        }
+    }
+
     #[derive(Clone, Eq, PartialEq, Debug)] // core
     #[derive(Deserialize, Serialize)] // serde
     pub struct AttestationReport {
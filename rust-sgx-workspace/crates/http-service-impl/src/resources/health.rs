@@ -0,0 +1,41 @@
+//! Liveness, readiness, and metrics endpoints, for orchestrators and monitoring.
+
+use actix_web::{get, web, HttpResponse};
+
+use crate::actors::CheckReadyMessage;
+use crate::errors::{ErrorCode, ServiceError};
+use crate::server::AppState;
+
+/// The process is up and serving HTTP.
+#[get("/healthz")]
+pub(crate) async fn get_healthz() -> HttpResponse {
+    HttpResponse::Ok().content_type("text/plain").body("ok")
+}
+
+/// The enclave is initialised and its store is writable: see
+/// [`crate::traits::VaultEnclave::check_ready`].
+#[get("/readyz")]
+pub(crate) async fn get_readyz(app_state: web::Data<AppState>) -> actix_web::Result<HttpResponse> {
+    app_state
+        .vault_enclave_addr
+        .send(CheckReadyMessage)
+        .await
+        .map_err(|mailbox_error| ServiceError::from_mailbox_error("get_readyz", mailbox_error))?
+        .map_err(|problem| {
+            println!("get_readyz: not ready: {}", problem);
+            ServiceError::new(ErrorCode::NotReady, "service is not ready")
+        })?;
+    Ok(HttpResponse::Ok().content_type("text/plain").body("ok"))
+}
+
+/// Enclave call metrics, in the Prometheus text format.
+#[get("/metrics")]
+pub(crate) async fn get_metrics(app_state: web::Data<AppState>) -> actix_web::Result<HttpResponse> {
+    let body = app_state.metrics.encode_text().map_err(|err| {
+        println!("get_metrics: failed to encode metrics: {}", err);
+        ServiceError::new(ErrorCode::InternalError, "failed to encode metrics")
+    })?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(body))
+}
@@ -1,2 +1,31 @@
+use actix_web::web;
+use futures_util::StreamExt;
+
+use crate::errors::{ErrorCode, ServiceError};
+
 pub(crate) mod enclave_report;
+pub(crate) mod health;
+pub(crate) mod session;
 pub(crate) mod vault_operation;
+
+/// Read the request body, up to `max_request_size` bytes.
+pub(crate) async fn read_request_body(
+    mut request_body: web::Payload,
+    max_request_size: usize,
+) -> Result<Box<[u8]>, ServiceError> {
+    let mut body = Vec::new();
+    while let Some(chunk) = request_body.next().await {
+        let chunk = chunk.map_err(|payload_error| {
+            println!("read_request_body: {}", payload_error);
+            ServiceError::new(ErrorCode::MalformedRequest, "failed to read request body")
+        })?;
+        if max_request_size < body.len() + chunk.len() {
+            return Err(ServiceError::new(
+                ErrorCode::RequestTooLarge,
+                "request body too large",
+            ));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body.into_boxed_slice())
+}
@@ -0,0 +1,37 @@
+use actix_web::{post, web, HttpResponse};
+use sgx_types::sgx_status_t;
+
+use crate::actors::SessionHandshakeMessage;
+use crate::errors::{ErrorCode, ServiceError};
+use crate::resources::read_request_body;
+use crate::server::AppState;
+
+/// Start a session, for forward secrecy of later `POST /vault-operation` requests.
+///
+/// Request: a msgpack session hello. Response: the enclave's sealed session accept.
+#[post("/session")]
+pub(crate) async fn post_session(
+    app_state: web::Data<AppState>,
+    request_body: web::Payload,
+) -> actix_web::Result<HttpResponse> {
+    let hello_bytes = read_request_body(request_body, app_state.max_request_size).await?;
+    if hello_bytes.is_empty() {
+        return Err(ServiceError::new(ErrorCode::MalformedRequest, "empty request body").into());
+    }
+    let sealed_accept_bytes = app_state
+        .vault_enclave_addr
+        .send(SessionHandshakeMessage { hello_bytes })
+        .await
+        .map_err(|mailbox_error| ServiceError::from_mailbox_error("post_session", mailbox_error))?
+        .map_err(|sgx_error| ServiceError::from_ecall_error("post_session", sgx_error))?
+        .map_err(|sgx_error| match sgx_error {
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER => {
+                println!("post_session: enclave refused hello");
+                ServiceError::new(ErrorCode::MalformedRequest, "invalid session hello")
+            }
+            sgx_error => ServiceError::from_enclave_error("post_session", sgx_error),
+        })?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-msgpack")
+        .body(sealed_accept_bytes.into_vec()))
+}
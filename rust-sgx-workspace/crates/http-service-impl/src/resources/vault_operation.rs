@@ -1,14 +1,20 @@
-use actix_web::{error, post, web};
+use actix_web::{post, web};
+use sgx_types::sgx_status_t;
 
 use crate::actors::VaultOperationMessage;
+use crate::errors::{ErrorCode, ServiceError};
+use crate::resources::read_request_body;
 use crate::server::AppState;
 
 #[post("/vault-operation")]
 pub(crate) async fn post_vault_operation(
     app_state: web::Data<AppState>,
-    request_body: web::Bytes,
+    request_body: web::Payload,
 ) -> actix_web::Result<impl actix_web::Responder> {
-    let sealed_request_bytes = request_body.to_vec().into_boxed_slice();
+    let sealed_request_bytes = read_request_body(request_body, app_state.max_request_size).await?;
+    if sealed_request_bytes.is_empty() {
+        return Err(ServiceError::new(ErrorCode::MalformedRequest, "empty request body").into());
+    }
     let message = VaultOperationMessage {
         sealed_request_bytes,
     };
@@ -17,13 +23,16 @@ pub(crate) async fn post_vault_operation(
         .send(message)
         .await
         .map_err(|mailbox_error| {
-            error::ErrorInternalServerError(format!("Failed to reach actor: {:?}", mailbox_error))
+            ServiceError::from_mailbox_error("post_vault_operation", mailbox_error)
         })?
-        .map_err(|sgx_error| {
-            error::ErrorInternalServerError(format!("Failed to call enclave: {:?}", sgx_error))
-        })?
-        .map_err(|sgx_error| {
-            error::ErrorInternalServerError(format!("vault_operation failed: {}", sgx_error))
+        .map_err(|sgx_error| ServiceError::from_ecall_error("post_vault_operation", sgx_error))?
+        .map_err(|sgx_error| match sgx_error {
+            // The enclave refuses requests that it can't unseal.
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER => {
+                println!("post_vault_operation: enclave refused request");
+                ServiceError::new(ErrorCode::MalformedRequest, "invalid sealed request")
+            }
+            sgx_error => ServiceError::from_enclave_error("post_vault_operation", sgx_error),
         })?;
     let response_body = sealed_response_bytes.into_vec();
     Ok(actix_web::HttpResponse::Ok()
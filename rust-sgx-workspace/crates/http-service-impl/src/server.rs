@@ -1,42 +1,136 @@
-use std::net::ToSocketAddrs;
+use std::io;
+use std::sync::Arc;
 
-use actix::{Actor, Addr, Arbiter};
+use actix::{Addr, SyncArbiter};
 use actix_cors::Cors;
+use actix_web::http::header;
 use actix_web::{web, App, HttpServer};
 
 use crate::actors::VaultEnclaveActor;
-use crate::resources;
-use crate::traits::VaultEnclave;
+use crate::config::{CorsOrigins, ServerConfig};
+use crate::metrics::EnclaveMetrics;
+use crate::traits::{QuotingEnclave, VaultEnclave};
+use crate::{resources, tls};
+
+/// Default number of enclave worker threads: one, which any enclave's `TCSNum` allows.
+///
+/// Each concurrent enclave call needs its own TCS, so hosts should raise this to their
+/// enclave's `TCSNum`, but not above it.
+pub const DEFAULT_ENCLAVE_WORKERS: usize = 1;
+
+/// Default limit on request body sizes: 256 KiB.
+pub const DEFAULT_MAX_REQUEST_SIZE: usize = 256 << 10;
 
 #[derive(Clone)]
-pub(crate) struct AppState {
+pub struct AppState {
     pub(crate) vault_enclave_addr: Addr<VaultEnclaveActor>,
+
+    /// Larger request bodies are refused: see [`crate::errors::ErrorCode::RequestTooLarge`].
+    pub(crate) max_request_size: usize,
+
+    /// Served by `GET /metrics`.
+    pub(crate) metrics: Arc<EnclaveMetrics>,
+}
+
+impl AppState {
+    /// Start a pool of `enclave_workers` threads, each calling into `vault_enclave`.
+    ///
+    /// # Panics
+    ///
+    /// If `enclave_workers` is zero, or if called outside a running actix system.
+    pub fn start(vault_enclave: Arc<dyn VaultEnclave>, enclave_workers: usize) -> Self {
+        assert!(
+            0 < enclave_workers,
+            "AppState::start: need at least one enclave worker"
+        );
+        let metrics = Arc::new(EnclaveMetrics::new());
+        let actor_metrics = metrics.clone();
+        let vault_enclave_addr = SyncArbiter::start(enclave_workers, move || VaultEnclaveActor {
+            vault_enclave: vault_enclave.clone(),
+            metrics: actor_metrics.clone(),
+        });
+        Self {
+            vault_enclave_addr,
+            max_request_size: DEFAULT_MAX_REQUEST_SIZE,
+            metrics,
+        }
+    }
+
+    /// Refuse request bodies larger than `max_request_size`.
+    pub fn with_max_request_size(self, max_request_size: usize) -> Self {
+        Self {
+            max_request_size,
+            ..self
+        }
+    }
+}
+
+/// Register the vault service's resources, for an [`App`] with [`AppState`].
+pub fn configure_services(config: &mut web::ServiceConfig) {
+    config
+        .service(resources::enclave_report::get_enclave_report)
+        .service(resources::enclave_report::post_enclave_report)
+        .service(resources::vault_operation::post_vault_operation)
+        .service(resources::session::post_session)
+        .service(resources::health::get_healthz)
+        .service(resources::health::get_readyz)
+        .service(resources::health::get_metrics);
 }
 
-/// Run the server on the given address.
-pub async fn run_server<Addr>(
-    vault_enclave: Box<dyn VaultEnclave>,
-    bind_addr: Addr,
-) -> std::io::Result<()>
-where
-    Addr: ToSocketAddrs,
-{
-    let enclave_arbiter = Arbiter::new();
-    let vault_enclave_addr = Actor::start_in_arbiter(&enclave_arbiter.handle(), |_ctx| {
-        VaultEnclaveActor { vault_enclave }
-    });
-
-    // TODO: Test coverage
+/// CORS middleware for `cors_origins`.
+pub fn cors(cors_origins: &CorsOrigins) -> Cors {
+    match cors_origins {
+        CorsOrigins::Any => Cors::permissive(),
+        CorsOrigins::List(origins) => origins.iter().fold(
+            Cors::default()
+                .allowed_methods(["GET", "POST"])
+                .allowed_header(header::CONTENT_TYPE)
+                .max_age(3600),
+            |cors, origin| cors.allowed_origin(origin),
+        ),
+    }
+}
+
+/// Run the server, as configured.
+///
+/// `quoting_enclave` quotes the RA-TLS certificate's report, if `config.tls` asks for RA-TLS.
+pub async fn run_server(
+    vault_enclave: Arc<dyn VaultEnclave>,
+    quoting_enclave: &dyn QuotingEnclave,
+    config: ServerConfig,
+) -> io::Result<()> {
+    if config.enclave_workers == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "run_server: enclave_workers must be at least 1",
+        ));
+    }
+    // This calls into the enclave for RA-TLS, so do it before starting the workers.
+    let rustls_config = match &config.tls {
+        Some(tls) => Some(tls::rustls_server_config(
+            tls,
+            vault_enclave.as_ref(),
+            quoting_enclave,
+        )?),
+        None => None,
+    };
+    let app_state = AppState::start(vault_enclave, config.enclave_workers)
+        .with_max_request_size(config.max_request_size);
+
+    let cors_origins = config.cors_origins.clone();
     let server = HttpServer::new(move || {
-        let app_state = AppState {
-            vault_enclave_addr: vault_enclave_addr.clone(),
-        };
-        let cors = Cors::permissive(); // TODO: Tighten this a bit more?
         App::new()
-            .wrap(cors)
-            .app_data(web::Data::new(app_state))
-            .service(resources::enclave_report::get_enclave_report)
-            .service(resources::vault_operation::post_vault_operation)
-    });
-    server.bind(bind_addr)?.run().await
+            .wrap(cors(&cors_origins))
+            .app_data(web::Data::new(app_state.clone()))
+            .configure(configure_services)
+    })
+    .client_request_timeout(config.client_request_timeout)
+    .client_disconnect_timeout(config.client_disconnect_timeout)
+    .keep_alive(config.keep_alive)
+    .shutdown_timeout(config.shutdown_timeout.as_secs());
+    let server = match rustls_config {
+        Some(rustls_config) => server.bind_rustls(&config.bind_addr, rustls_config)?,
+        None => server.bind(&config.bind_addr)?,
+    };
+    server.run().await
 }
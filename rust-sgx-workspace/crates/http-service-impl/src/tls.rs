@@ -0,0 +1,220 @@
+//! HTTPS for the vault service: see [`TlsConfig`].
+//!
+//! # RA-TLS
+//!
+//! With [`TlsConfig::RemoteAttestation`], the server generates a fresh key pair at startup,
+//! and asks the enclave for a report that binds its public key (see [`ra_tls_report_data`]).
+//! The quoting enclave quotes that report, and the self-signed certificate for the key
+//! carries the quote in a [`RA_TLS_EVIDENCE_OID`] extension (see [`RaTlsEvidence`]).
+//!
+//! The binding is domain-separated, and fills the first half of the report data, where every
+//! other report has `sha256(enclave_public_key)`: no nonce sent to `/enclave-report`
+//! can produce it.
+//!
+//! A client that verifies the certificate with [`crate::verify::verify_ra_tls_certificate`]
+//! knows that the TLS connection ends at the host of that enclave. The key pair lives in the
+//! host, so this does not protect requests from the host: seal them to the enclave, as usual.
+
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+use rcgen::{Certificate, CertificateParams, CustomExtension, KeyPair, PKCS_ECDSA_P256_SHA256};
+use rustls_pemfile::Item;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::attestation_report::to_msgpack;
+use crate::config::TlsConfig;
+use crate::traits::{QuotingEnclave, VaultEnclave};
+
+/// Certificate extension holding the msgpack-encoded [`RaTlsEvidence`].
+///
+/// This is a UUID-based (`2.25`) OID specific to this service.
+pub const RA_TLS_EVIDENCE_OID: &[u64] = &[2, 25, 11_870_394_202_584_771_063];
+
+/// Domain separation for [`ra_tls_report_data`].
+///
+/// This must match `sgx_vault_impl::ported::attestation::RA_TLS_LABEL`.
+pub const RA_TLS_LABEL: &[u8] = b"NTC-RA-TLS";
+
+/// The report data of an enclave report that binds a certificate's `tls_public_key`:
+/// `sha256(RA_TLS_LABEL || enclave_public_key || tls_public_key)`, then zeroes.
+pub fn ra_tls_report_data(enclave_public_key: &[u8; 32], tls_public_key: &[u8]) -> [u8; 64] {
+    let binding = Sha256::new()
+        .chain_update(RA_TLS_LABEL)
+        .chain_update(enclave_public_key)
+        .chain_update(tls_public_key)
+        .finalize();
+    let mut report_data = [0; 64];
+    report_data[..32].copy_from_slice(&binding);
+    report_data
+}
+
+/// The content of the [`RA_TLS_EVIDENCE_OID`] certificate extension.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct RaTlsEvidence {
+    /// The quote of the enclave report that binds the certificate's public key.
+    pub quote: Box<[u8]>,
+
+    /// The enclave public key that the report binds, with the certificate's public key.
+    pub enclave_public_key: [u8; 32],
+}
+
+/// A self-signed certificate bound to an enclave report.
+pub struct RaTlsCertificate {
+    pub cert_der: Vec<u8>,
+
+    /// PKCS #8 private key of the certificate.
+    pub key_der: Vec<u8>,
+
+    /// The evidence embedded in the certificate.
+    pub evidence: RaTlsEvidence,
+}
+
+/// Generate a [`RaTlsCertificate`] for `vault_enclave`, valid for `subject_alt_names`.
+///
+/// The report targets `quoting_enclave`, which quotes it.
+pub fn ra_tls_certificate(
+    vault_enclave: &dyn VaultEnclave,
+    quoting_enclave: &dyn QuotingEnclave,
+    subject_alt_names: &[String],
+) -> io::Result<RaTlsCertificate> {
+    let key_pair = KeyPair::generate(&PKCS_ECDSA_P256_SHA256).map_err(|err| {
+        other_error(format!(
+            "ra_tls_certificate: key generation failed: {}",
+            err
+        ))
+    })?;
+
+    let target_info = quoting_enclave.target_info().map_err(|message| {
+        other_error(format!(
+            "ra_tls_certificate: quoting enclave target info failed: {}",
+            message
+        ))
+    })?;
+    let (report, enclave_public_key) = vault_enclave
+        .create_tls_report(target_info, key_pair.public_key_raw())
+        .and_then(|result| result)
+        .map_err(|sgx_error| {
+            other_error(format!(
+                "ra_tls_certificate: create_tls_report failed: {:?}",
+                sgx_error
+            ))
+        })?;
+    let quote = quoting_enclave
+        .quote(&report)
+        .map_err(|message| other_error(format!("ra_tls_certificate: quote failed: {}", message)))?;
+    let evidence = RaTlsEvidence {
+        quote: quote.into_boxed_slice(),
+        enclave_public_key,
+    };
+    let evidence_msgpack = to_msgpack(&evidence).map_err(|err| {
+        other_error(format!(
+            "ra_tls_certificate: failed to serialize RaTlsEvidence: {}",
+            err
+        ))
+    })?;
+
+    let mut params = CertificateParams::new(subject_alt_names.to_vec());
+    params.alg = &PKCS_ECDSA_P256_SHA256;
+    params.key_pair = Some(key_pair);
+    params
+        .custom_extensions
+        .push(CustomExtension::from_oid_content(
+            RA_TLS_EVIDENCE_OID,
+            evidence_msgpack.into_vec(),
+        ));
+    let certificate = Certificate::from_params(params).map_err(|err| {
+        other_error(format!(
+            "ra_tls_certificate: certificate generation failed: {}",
+            err
+        ))
+    })?;
+    let cert_der = certificate.serialize_der().map_err(|err| {
+        other_error(format!(
+            "ra_tls_certificate: certificate signing failed: {}",
+            err
+        ))
+    })?;
+    Ok(RaTlsCertificate {
+        cert_der,
+        key_der: certificate.serialize_private_key_der(),
+        evidence,
+    })
+}
+
+/// Build the rustls configuration for `tls`.
+pub fn rustls_server_config(
+    tls: &TlsConfig,
+    vault_enclave: &dyn VaultEnclave,
+    quoting_enclave: &dyn QuotingEnclave,
+) -> io::Result<rustls::ServerConfig> {
+    match tls {
+        TlsConfig::Pem {
+            cert_path,
+            key_path,
+        } => {
+            let cert_chain = load_pem_certs(cert_path)?;
+            let key_der = load_pem_private_key(key_path)?;
+            single_cert_server_config(cert_chain, key_der)
+        }
+        TlsConfig::RemoteAttestation { subject_alt_names } => {
+            let certificate =
+                ra_tls_certificate(vault_enclave, quoting_enclave, subject_alt_names)?;
+            single_cert_server_config(vec![certificate.cert_der], certificate.key_der)
+        }
+    }
+}
+
+fn single_cert_server_config(
+    cert_chain: Vec<Vec<u8>>,
+    key_der: Vec<u8>,
+) -> io::Result<rustls::ServerConfig> {
+    rustls::ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(
+            cert_chain.into_iter().map(rustls::Certificate).collect(),
+            rustls::PrivateKey(key_der),
+        )
+        .map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid TLS certificate or key: {}", err),
+            )
+        })
+}
+
+fn load_pem_certs(cert_path: &Path) -> io::Result<Vec<Vec<u8>>> {
+    let cert_chain = rustls_pemfile::certs(&mut BufReader::new(File::open(cert_path)?))?;
+    if cert_chain.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("no certificates in {:?}", cert_path),
+        ));
+    }
+    Ok(cert_chain)
+}
+
+/// Load the first private key in `key_path`.
+fn load_pem_private_key(key_path: &Path) -> io::Result<Vec<u8>> {
+    let reader = &mut BufReader::new(File::open(key_path)?);
+    while let Some(item) = rustls_pemfile::read_one(reader)? {
+        match item {
+            Item::PKCS8Key(key_der) | Item::RSAKey(key_der) | Item::ECKey(key_der) => {
+                return Ok(key_der)
+            }
+            _ => continue,
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("no private key in {:?}", key_path),
+    ))
+}
+
+fn other_error(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, message)
+}
@@ -1,42 +1,121 @@
-use sgx_types::{sgx_report_t, sgx_status_t, sgx_target_info_t, SgxResult};
+use sgx_types::{sgx_report_t, sgx_target_info_t, SgxResult};
+
+/// Result of [`VaultEnclave::vault_operation`] and [`VaultEnclave::fetch_vault_response`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum SealedResponse {
+    /// The sealed response fit the given capacity.
+    Complete(Box<[u8]>),
+
+    /// The sealed response exceeds the given capacity.
+    ///
+    /// The enclave holds it for [`VaultEnclave::fetch_vault_response`].
+    Pending { size: usize },
+}
+
+/// The type of a vault operation, and how it turned out, as the enclave reports them.
+///
+/// These are the declaration indexes of `sgx_vault_impl::schema::entities::AuditOperation`
+/// and `AuditOutcome`: see [`Self::operation_name`] and [`Self::outcome_name`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct OperationLabel {
+    pub operation: u8,
+    pub outcome: u8,
+}
+
+impl OperationLabel {
+    const OPERATION_NAMES: &'static [&'static str] = &[
+        "CreateVault",
+        "OpenVault",
+        "SignTransaction",
+        "ImportAlgorandAccount",
+        "ExportAlgorandAccount",
+        "UpdateVaultPolicy",
+        "SummarizeTransaction",
+        "GetAuditLog",
+    ];
+
+    const OUTCOME_NAMES: &'static [&'static str] =
+        &["Succeeded", "InvalidAuth", "Refused", "Failed", "Started"];
+
+    /// The name of [`Self::operation`], or `"unknown"` if this enclave is newer than the host.
+    pub fn operation_name(&self) -> &'static str {
+        Self::name(Self::OPERATION_NAMES, self.operation)
+    }
+
+    /// The name of [`Self::outcome`], or `"unknown"` if this enclave is newer than the host.
+    pub fn outcome_name(&self) -> &'static str {
+        Self::name(Self::OUTCOME_NAMES, self.outcome)
+    }
+
+    fn name(names: &[&'static str], index: u8) -> &'static str {
+        names.get(usize::from(index)).copied().unwrap_or("unknown")
+    }
+}
 
 /// Interface for working with vault enclave.
-pub trait VaultEnclave: Send + 'static {
+///
+/// The server calls this from several worker threads at once: see [`crate::server::AppState::start`].
+pub trait VaultEnclave: Send + Sync + 'static {
+    /// Create a report for `target_info`, with `user_data` in the second half of its report data.
+    ///
+    /// Return the report, and the enclave's public key.
     fn create_report(
         &self,
         target_info: sgx_target_info_t,
+        user_data: [u8; 32],
     ) -> SgxResult<SgxResult<(sgx_report_t, [u8; 32])>>;
 
+    /// Create a report for `target_info` that binds `tls_public_key`, for RA-TLS.
+    ///
+    /// Unlike [`Self::create_report`], the caller does not choose this report's data:
+    /// see [`crate::tls::ra_tls_report_data`].
+    ///
+    /// Return the report, and the enclave's public key.
+    fn create_tls_report(
+        &self,
+        target_info: sgx_target_info_t,
+        tls_public_key: &[u8],
+    ) -> SgxResult<SgxResult<(sgx_report_t, [u8; 32])>>;
+
+    /// Execute a vault operation.
+    ///
+    /// `request_id` must be unique: it identifies the response to [`Self::fetch_vault_response`].
+    ///
+    /// Never retry this: the operation may have taken effect. Fetch its response instead.
+    ///
+    /// Return the operation's label along with its response, even if that is pending.
     fn vault_operation(
         &self,
+        request_id: u64,
         sealed_request: &[u8],
         sealed_response_capacity: usize,
-    ) -> SgxResult<SgxResult<Box<[u8]>>>;
-
-    /// Wrapper: Retry [`Self::vault_operation`] with increasing capacity.
-    fn vault_operation_with_retry(&self, sealed_request: &[u8]) -> SgxResult<SgxResult<Box<[u8]>>> {
-        // Attempt sizes: 1 KiB, 64 KiB, 1 MiB
-        for &sealed_response_capacity in &[1 << 10, 1 << 16, 1 << 20] {
-            let result = self.vault_operation(sealed_request, sealed_response_capacity);
-            match result {
-                Ok(Err(sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT)) => {
-                    println!(
-                        "DEBUG: vault_operation_with_retry: capacity={} too short, retrying…",
-                        sealed_response_capacity
-                    )
-                }
-                result => {
-                    if cfg!(feature = "verbose-debug-logging") {
-                        println!(
-                            "DEBUG: vault_operation_with_retry: capacity={} returning {:?}",
-                            sealed_response_capacity, result
-                        );
-                    }
-                    return result;
-                }
-            }
-        }
-        println!("DEBUG: vault_operation_with_retry: giving up!",);
-        Ok(Err(sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT))
-    }
+    ) -> SgxResult<SgxResult<(SealedResponse, OperationLabel)>>;
+
+    /// Fetch a [`SealedResponse::Pending`] response of [`Self::vault_operation`].
+    fn fetch_vault_response(
+        &self,
+        request_id: u64,
+        sealed_response_capacity: usize,
+    ) -> SgxResult<SgxResult<SealedResponse>>;
+
+    /// Start a session: see `sgx_vault_impl::schema::session`.
+    ///
+    /// Return the sealed session accept.
+    fn session_handshake(&self, hello: &[u8]) -> SgxResult<SgxResult<Box<[u8]>>>;
+
+    /// Check that the enclave is ready to serve requests: see `GET /readyz`.
+    ///
+    /// Return a description of the problem, if not.
+    fn check_ready(&self) -> Result<(), String>;
+}
+
+/// Interface for quoting enclave reports, so that remote parties can verify them.
+///
+/// On SGX platforms with DCAP, this is the quoting enclave of the DCAP quote library.
+pub trait QuotingEnclave {
+    /// The quoting enclave's target info, for reports to target.
+    fn target_info(&self) -> Result<sgx_target_info_t, String>;
+
+    /// Quote `report`, which targets [`Self::target_info`].
+    fn quote(&self, report: &sgx_report_t) -> Result<Vec<u8>, String>;
 }
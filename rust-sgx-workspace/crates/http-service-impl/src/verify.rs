@@ -0,0 +1,209 @@
+//! Client-side checks of an [`AttestationReport`] from `GET` or `POST /enclave-report`.
+//!
+//! The enclave binds its report data as `sha256(enclave_public_key) || user_data`.
+//! [`verify_attestation_report`] checks that binding, so a verified report vouches for
+//! the public key that came with it.
+//!
+//! This does not check that the report itself is genuine: only its target enclave can
+//! check its MAC, and a remote verifier needs a quote of it, from the quoting enclave
+//! that `POST /enclave-report` targeted.
+//!
+//! [`verify_ra_tls_certificate`] checks an RA-TLS certificate (see [`crate::tls`]), which
+//! carries such a quote.
+
+use std::fmt;
+
+use sgx_types::SGX_FLAGS_DEBUG;
+use sha2::{Digest, Sha256};
+use x509_parser::der_parser::oid::Oid;
+
+use crate::attestation_report::AttestationReport;
+use crate::tls::{ra_tls_report_data, RaTlsEvidence, RA_TLS_EVIDENCE_OID};
+
+/// What a verifier expects of an [`AttestationReport`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ReportExpectations {
+    /// The second half of the report data: the [`Nonce`](crate::attestation_report::Nonce) sent with the report request.
+    ///
+    /// Send a fresh random nonce with each request, so that a replayed report fails this check.
+    /// Without a nonce, this is zeroes.
+    pub user_data: [u8; 32],
+
+    /// Accept debug enclaves, whose memory the host can read.
+    ///
+    /// Enclaves in SGX simulation mode run as debug enclaves: only enable this for testing.
+    pub allow_debug: bool,
+}
+
+/// Why [`verify_attestation_report`] rejected a report.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ReportVerifyError {
+    /// The report data is not 64 bytes.
+    MalformedReportData,
+
+    /// The report does not bind the enclave public key that came with it.
+    PublicKeyMismatch,
+
+    /// The report does not bind the expected user data.
+    UserDataMismatch,
+
+    /// The report is from a debug enclave.
+    DebugEnclave,
+}
+
+impl fmt::Display for ReportVerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::MalformedReportData => "malformed report data",
+            Self::PublicKeyMismatch => "report does not bind the enclave public key",
+            Self::UserDataMismatch => "report does not bind the expected user data",
+            Self::DebugEnclave => "report is from a debug enclave",
+        })
+    }
+}
+
+impl std::error::Error for ReportVerifyError {}
+
+/// Check that `report` binds its enclave public key and `expected.user_data`.
+pub fn verify_attestation_report(
+    report: &AttestationReport,
+    expected: &ReportExpectations,
+) -> Result<(), ReportVerifyError> {
+    let body = &report.report.body;
+    if body.report_data.len() != 64 {
+        return Err(ReportVerifyError::MalformedReportData);
+    }
+    let (public_key_hash, user_data) = body.report_data.split_at(32);
+    if public_key_hash != Sha256::digest(report.enclave_public_key).as_slice() {
+        return Err(ReportVerifyError::PublicKeyMismatch);
+    }
+    if user_data != &expected.user_data[..] {
+        return Err(ReportVerifyError::UserDataMismatch);
+    }
+    if body.attributes_flags & SGX_FLAGS_DEBUG != 0 && !expected.allow_debug {
+        return Err(ReportVerifyError::DebugEnclave);
+    }
+    Ok(())
+}
+
+/// What a verifier expects of an RA-TLS certificate's enclave.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct RaTlsExpectations {
+    /// The expected enclave measurement (MRENCLAVE), if any.
+    pub mr_enclave: Option<[u8; 32]>,
+
+    /// The expected enclave signer (MRSIGNER), if any.
+    ///
+    /// Expect at least one of this and [`Self::mr_enclave`]: otherwise any enclave passes.
+    pub mr_signer: Option<[u8; 32]>,
+
+    /// Accept debug enclaves, whose memory the host can read.
+    pub allow_debug: bool,
+}
+
+/// Why [`verify_ra_tls_certificate`] rejected a certificate.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum RaTlsVerifyError {
+    /// The certificate is not valid DER X.509.
+    MalformedCertificate,
+
+    /// The certificate has no [`RA_TLS_EVIDENCE_OID`] extension.
+    MissingEvidence,
+
+    /// The evidence extension is not a valid [`RaTlsEvidence`].
+    MalformedEvidence,
+
+    /// The quote is too short to hold a report body.
+    MalformedQuote,
+
+    /// The quote failed verification: see [`verify_ra_tls_certificate`].
+    QuoteRejected(String),
+
+    /// The quoted report does not bind the certificate's public key.
+    PublicKeyMismatch,
+
+    /// The quoted report is from an unexpected enclave (MRENCLAVE).
+    EnclaveMismatch,
+
+    /// The quoted report is from an unexpected signer (MRSIGNER).
+    SignerMismatch,
+
+    /// The quoted report is from a debug enclave.
+    DebugEnclave,
+}
+
+impl fmt::Display for RaTlsVerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MalformedCertificate => f.write_str("malformed certificate"),
+            Self::MissingEvidence => f.write_str("certificate has no RA-TLS evidence"),
+            Self::MalformedEvidence => f.write_str("malformed RA-TLS evidence"),
+            Self::MalformedQuote => f.write_str("malformed quote"),
+            Self::QuoteRejected(reason) => write!(f, "quote rejected: {}", reason),
+            Self::PublicKeyMismatch => {
+                f.write_str("quoted report does not bind the certificate's public key")
+            }
+            Self::EnclaveMismatch => f.write_str("quoted report is from an unexpected enclave"),
+            Self::SignerMismatch => f.write_str("quoted report is from an unexpected signer"),
+            Self::DebugEnclave => f.write_str("quoted report is from a debug enclave"),
+        }
+    }
+}
+
+impl std::error::Error for RaTlsVerifyError {}
+
+/// Offset of the report body in an SGX ECDSA (DCAP) quote: it follows the 48-byte header.
+const QUOTE_REPORT_BODY_OFFSET: usize = 48;
+
+/// Size of an SGX report body (`sgx_report_body_t`).
+const REPORT_BODY_SIZE: usize = 384;
+
+/// Check that `cert_der` is an RA-TLS certificate for an enclave that meets `expected`.
+///
+/// `verify_quote` must check the quote's signature and TCB status, with a quote verification
+/// library like Intel's DCAP QVL, and return why it rejects the quote, if it does.
+/// This function trusts what a quote that passes says.
+///
+/// Return the enclave public key, for sealing requests to the enclave.
+pub fn verify_ra_tls_certificate(
+    cert_der: &[u8],
+    expected: &RaTlsExpectations,
+    verify_quote: impl FnOnce(&[u8]) -> Result<(), String>,
+) -> Result<[u8; 32], RaTlsVerifyError> {
+    let (_, certificate) = x509_parser::parse_x509_certificate(cert_der)
+        .map_err(|_| RaTlsVerifyError::MalformedCertificate)?;
+    let evidence_oid =
+        Oid::from(RA_TLS_EVIDENCE_OID).map_err(|_| RaTlsVerifyError::MalformedCertificate)?;
+    let extension = certificate
+        .extensions()
+        .iter()
+        .find(|extension| extension.oid == evidence_oid)
+        .ok_or(RaTlsVerifyError::MissingEvidence)?;
+    let evidence: RaTlsEvidence = rmp_serde::from_read_ref(extension.value)
+        .map_err(|_| RaTlsVerifyError::MalformedEvidence)?;
+
+    let body = evidence
+        .quote
+        .get(QUOTE_REPORT_BODY_OFFSET..QUOTE_REPORT_BODY_OFFSET + REPORT_BODY_SIZE)
+        .ok_or(RaTlsVerifyError::MalformedQuote)?;
+    verify_quote(&evidence.quote).map_err(RaTlsVerifyError::QuoteRejected)?;
+
+    // Field offsets of sgx_report_body_t.
+    let attributes_flags = u64::from_le_bytes(body[48..56].try_into().unwrap());
+    let (mr_enclave, mr_signer, report_data) = (&body[64..96], &body[128..160], &body[320..384]);
+
+    let tls_public_key = certificate.public_key().subject_public_key.data;
+    if report_data != ra_tls_report_data(&evidence.enclave_public_key, tls_public_key) {
+        return Err(RaTlsVerifyError::PublicKeyMismatch);
+    }
+    if matches!(expected.mr_enclave, Some(expected) if mr_enclave != expected) {
+        return Err(RaTlsVerifyError::EnclaveMismatch);
+    }
+    if matches!(expected.mr_signer, Some(expected) if mr_signer != expected) {
+        return Err(RaTlsVerifyError::SignerMismatch);
+    }
+    if attributes_flags & SGX_FLAGS_DEBUG != 0 && !expected.allow_debug {
+        return Err(RaTlsVerifyError::DebugEnclave);
+    }
+    Ok(evidence.enclave_public_key)
+}
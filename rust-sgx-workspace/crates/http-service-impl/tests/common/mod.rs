@@ -0,0 +1,583 @@
+//! Test helpers: a mock [`VaultEnclave`] that runs without SGX.
+
+// Not every test uses every helper.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use http_service_impl::errors::ErrorBody;
+use http_service_impl::server::AppState;
+use http_service_impl::tls::ra_tls_report_data;
+use http_service_impl::traits::{OperationLabel, QuotingEnclave, SealedResponse, VaultEnclave};
+use serde::{Deserialize, Serialize};
+use sgx_types::{sgx_report_body_t, sgx_report_t, sgx_status_t, sgx_target_info_t, SgxResult};
+use sha2::{Digest, Sha256};
+
+/// The enclave public key that [`MockVaultEnclave`] reports.
+pub const MOCK_ENCLAVE_PUBLIC_KEY: [u8; 32] = [0x42; 32];
+
+/// The enclave measurement (MRENCLAVE) of [`MockVaultEnclave`]'s TLS reports.
+pub const MOCK_MR_ENCLAVE: [u8; 32] = [0x4d; 32];
+
+/// The `mr_enclave` of [`MockQuotingEnclave`]'s target info.
+pub const MOCK_QE_MR_ENCLAVE: [u8; 32] = [0x51; 32];
+
+/// The public key of the client that [`seal_request`] seals from.
+pub const MOCK_CLIENT_PUBLIC_KEY: [u8; 32] = [0x43; 32];
+
+/// A sealed message, like the real enclave's `SealedMessage`.
+///
+/// The mock sealing does not encrypt: the ciphertext is the message's msgpack,
+/// after a tag that binds it to the sender, receiver, and nonce (see [`mock_seal`]).
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct MockSealedMessage {
+    pub ciphertext: Vec<u8>,
+    pub nonce: [u8; 24],
+    pub sender_public_key: [u8; 32],
+}
+
+/// The (unsealed) requests that [`MockVaultEnclave`] handles, like the real `VaultRequest`.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub enum MockVaultRequest {
+    CreateVault {
+        username: String,
+        auth_password: String,
+    },
+    OpenVault {
+        vault_id: String,
+        auth_password: String,
+    },
+    SignTransaction {
+        vault_id: String,
+        auth_password: String,
+        transaction_bytes: Vec<u8>,
+    },
+}
+
+/// The (unsealed) responses of [`MockVaultEnclave`], like the real `VaultResponse`.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub enum MockVaultResponse {
+    CreateVault(MockCreateVaultResult),
+    OpenVault(MockOpenVaultResult),
+    SignTransaction(MockSignTransactionResult),
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub enum MockCreateVaultResult {
+    Created(MockVaultDisplay),
+    Failed(String),
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub enum MockOpenVaultResult {
+    Opened(MockVaultDisplay),
+    InvalidAuth,
+    Failed(String),
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub enum MockSignTransactionResult {
+    /// The transaction, followed by the mock signature: see [`mock_signature`].
+    Signed {
+        signed_transaction_bytes: Vec<u8>,
+    },
+    InvalidAuth,
+    Failed(String),
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct MockVaultDisplay {
+    pub vault_id: String,
+    pub username: String,
+}
+
+impl MockVaultResponse {
+    /// The label that the real enclave reports for this response.
+    pub fn label(&self) -> OperationLabel {
+        // Indexes of the real AuditOperation and AuditOutcome.
+        const SUCCEEDED: u8 = 0;
+        const INVALID_AUTH: u8 = 1;
+        const FAILED: u8 = 3;
+        let (operation, outcome) = match self {
+            Self::CreateVault(MockCreateVaultResult::Created(_)) => (0, SUCCEEDED),
+            Self::CreateVault(MockCreateVaultResult::Failed(_)) => (0, FAILED),
+            Self::OpenVault(MockOpenVaultResult::Opened(_)) => (1, SUCCEEDED),
+            Self::OpenVault(MockOpenVaultResult::InvalidAuth) => (1, INVALID_AUTH),
+            Self::OpenVault(MockOpenVaultResult::Failed(_)) => (1, FAILED),
+            Self::SignTransaction(MockSignTransactionResult::Signed { .. }) => (2, SUCCEEDED),
+            Self::SignTransaction(MockSignTransactionResult::InvalidAuth) => (2, INVALID_AUTH),
+            Self::SignTransaction(MockSignTransactionResult::Failed(_)) => (2, FAILED),
+        };
+        OperationLabel { operation, outcome }
+    }
+}
+
+/// A vault held by [`MockVaultEnclave`].
+pub(crate) struct MockVault {
+    username: String,
+    auth_password: String,
+}
+
+/// The (unsealed) session hello that [`MockVaultEnclave`] handles.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct MockSessionHello {
+    pub client_public_key: [u8; 32],
+}
+
+/// The (unsealed) session accept that [`MockVaultEnclave`] returns.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct MockSessionAccept {
+    pub session_id: u64,
+    pub client_public_key: [u8; 32],
+}
+
+/// Mock vault enclave.
+///
+/// This follows the real enclave's ECALL contract: see [`Self::vault_operation_impl`].
+/// Its vaults are held in memory, and have no keys.
+#[derive(Default)]
+pub struct MockVaultEnclave {
+    /// Fail every ECALL with this status (like a lost enclave), if set.
+    pub ecall_error: Option<sgx_status_t>,
+
+    /// Return this status from every ECALL, if set.
+    pub enclave_error: Option<sgx_status_t>,
+
+    /// Fail [`VaultEnclave::check_ready`] with this problem, if set.
+    pub readiness_error: Option<String>,
+
+    /// The `mr_enclave` of each report's target info, in order.
+    pub report_targets: Mutex<Vec<[u8; 32]>>,
+
+    /// Block each vault operation for this long, like a slow ECALL.
+    pub operation_time: Duration,
+
+    /// The response capacity of each vault operation and fetch call, in order.
+    pub capacities: Mutex<Vec<usize>>,
+
+    /// Responses held for [`VaultEnclave::fetch_vault_response`], by request ID.
+    pub(crate) pending: Mutex<HashMap<u64, Box<[u8]>>>,
+
+    /// The vaults, by vault ID.
+    pub(crate) vaults: Mutex<HashMap<String, MockVault>>,
+
+    pub(crate) executions: AtomicUsize,
+    pub(crate) sessions: AtomicUsize,
+    pub(crate) in_flight: AtomicUsize,
+    pub(crate) max_in_flight: AtomicUsize,
+}
+
+impl MockVaultEnclave {
+    /// Add a vault with `vault_id` as its ID and username.
+    pub fn with_vault(self, vault_id: &str, auth_password: &str) -> Self {
+        self.vaults.lock().unwrap().insert(
+            vault_id.to_string(),
+            MockVault {
+                username: vault_id.to_string(),
+                auth_password: auth_password.to_string(),
+            },
+        );
+        self
+    }
+
+    /// Like the real enclave's `vault_operation_impl`, with mock sealing.
+    ///
+    /// Like the real ECALL wrapper, this reports requests that can't be unpacked, unsealed,
+    /// or decoded as [`sgx_status_t::SGX_ERROR_INVALID_PARAMETER`]. Operations that fail
+    /// get a sealed `Failed` response instead, like the real enclave's.
+    pub fn vault_operation_impl(
+        &self,
+        sealed_request: &[u8],
+    ) -> Result<(Box<[u8]>, OperationLabel), sgx_status_t> {
+        let sealed: MockSealedMessage = rmp_serde::from_read_ref(sealed_request)
+            .map_err(|_| sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        let request: MockVaultRequest = mock_unseal(&sealed, &MOCK_ENCLAVE_PUBLIC_KEY)
+            .ok_or(sgx_status_t::SGX_ERROR_INVALID_PARAMETER)?;
+        let response = self.handle_request(request);
+        let sealed_response = mock_seal(
+            &response,
+            &MOCK_ENCLAVE_PUBLIC_KEY,
+            &sealed.sender_public_key,
+        );
+        Ok((
+            encode(&sealed_response).into_boxed_slice(),
+            response.label(),
+        ))
+    }
+
+    fn handle_request(&self, request: MockVaultRequest) -> MockVaultResponse {
+        let mut vaults = self.vaults.lock().unwrap();
+        let display = |vault_id: &str, vault: &MockVault| MockVaultDisplay {
+            vault_id: vault_id.to_string(),
+            username: vault.username.clone(),
+        };
+        // Like the real unlock_vault: unknown vaults and wrong passwords look the same.
+        let unlock = |vault_id: &str, auth_password: &str| {
+            vaults
+                .get(vault_id)
+                .filter(|vault| vault.auth_password == auth_password)
+                .map(|vault| display(vault_id, vault))
+        };
+
+        match request {
+            MockVaultRequest::CreateVault {
+                username,
+                auth_password,
+            } => MockVaultResponse::CreateVault(match vaults.contains_key(&username) {
+                true => MockCreateVaultResult::Failed("vault already exists".to_string()),
+                false => {
+                    let vault = MockVault {
+                        username: username.clone(),
+                        auth_password,
+                    };
+                    let created = display(&username, &vault);
+                    vaults.insert(username, vault);
+                    MockCreateVaultResult::Created(created)
+                }
+            }),
+            MockVaultRequest::OpenVault {
+                vault_id,
+                auth_password,
+            } => MockVaultResponse::OpenVault(match unlock(&vault_id, &auth_password) {
+                Some(display) => MockOpenVaultResult::Opened(display),
+                None => MockOpenVaultResult::InvalidAuth,
+            }),
+            MockVaultRequest::SignTransaction {
+                vault_id,
+                auth_password,
+                transaction_bytes,
+            } => MockVaultResponse::SignTransaction(match unlock(&vault_id, &auth_password) {
+                None => MockSignTransactionResult::InvalidAuth,
+                Some(_) if transaction_bytes.is_empty() => {
+                    MockSignTransactionResult::Failed("empty transaction".to_string())
+                }
+                Some(_) => MockSignTransactionResult::Signed {
+                    signed_transaction_bytes: [
+                        &transaction_bytes[..],
+                        &mock_signature(&vault_id, &transaction_bytes),
+                    ]
+                    .concat(),
+                },
+            }),
+        }
+    }
+
+    /// How many vault operations executed.
+    pub fn executions(&self) -> usize {
+        self.executions.load(SeqCst)
+    }
+
+    /// The most vault operations that ran concurrently.
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight.load(SeqCst)
+    }
+
+    pub fn report_targets(&self) -> Vec<[u8; 32]> {
+        self.report_targets.lock().unwrap().clone()
+    }
+
+    pub fn capacities(&self) -> Vec<usize> {
+        self.capacities.lock().unwrap().clone()
+    }
+}
+
+impl VaultEnclave for MockVaultEnclave {
+    fn create_report(
+        &self,
+        target_info: sgx_target_info_t,
+        user_data: [u8; 32],
+    ) -> SgxResult<SgxResult<(sgx_report_t, [u8; 32])>> {
+        self.report_targets
+            .lock()
+            .unwrap()
+            .push(target_info.mr_enclave.m);
+        if let Some(status) = self.ecall_error {
+            return Err(status);
+        }
+        Ok(match self.enclave_error {
+            Some(status) => Err(status),
+            None => {
+                // Like the real enclave: bind the public key and user_data into the report data.
+                let mut report = sgx_report_t::default();
+                report.body.report_data.d[..32]
+                    .copy_from_slice(&Sha256::digest(MOCK_ENCLAVE_PUBLIC_KEY));
+                report.body.report_data.d[32..].copy_from_slice(&user_data);
+                Ok((report, MOCK_ENCLAVE_PUBLIC_KEY))
+            }
+        })
+    }
+
+    fn create_tls_report(
+        &self,
+        target_info: sgx_target_info_t,
+        tls_public_key: &[u8],
+    ) -> SgxResult<SgxResult<(sgx_report_t, [u8; 32])>> {
+        self.report_targets
+            .lock()
+            .unwrap()
+            .push(target_info.mr_enclave.m);
+        if let Some(status) = self.ecall_error {
+            return Err(status);
+        }
+        Ok(match self.enclave_error {
+            Some(status) => Err(status),
+            None => {
+                // Like the real enclave: bind the public keys, under the RA-TLS label.
+                let mut report = sgx_report_t::default();
+                report.body.mr_enclave.m = MOCK_MR_ENCLAVE;
+                report.body.report_data.d =
+                    ra_tls_report_data(&MOCK_ENCLAVE_PUBLIC_KEY, tls_public_key);
+                Ok((report, MOCK_ENCLAVE_PUBLIC_KEY))
+            }
+        })
+    }
+
+    fn vault_operation(
+        &self,
+        request_id: u64,
+        sealed_request: &[u8],
+        sealed_response_capacity: usize,
+    ) -> SgxResult<SgxResult<(SealedResponse, OperationLabel)>> {
+        self.capacities
+            .lock()
+            .unwrap()
+            .push(sealed_response_capacity);
+        if let Some(status) = self.ecall_error {
+            return Err(status);
+        }
+        if let Some(status) = self.enclave_error {
+            return Ok(Err(status));
+        }
+
+        self.executions.fetch_add(1, SeqCst);
+        let in_flight = self.in_flight.fetch_add(1, SeqCst) + 1;
+        self.max_in_flight.fetch_max(in_flight, SeqCst);
+        thread::sleep(self.operation_time);
+        self.in_flight.fetch_sub(1, SeqCst);
+
+        // Like the real ECALL wrapper: check capacity before copying out, or hold the response.
+        Ok(match self.vault_operation_impl(sealed_request) {
+            Ok((response, label)) if response.len() <= sealed_response_capacity => {
+                Ok((SealedResponse::Complete(response), label))
+            }
+            Ok((response, label)) => {
+                let size = response.len();
+                self.pending.lock().unwrap().insert(request_id, response);
+                Ok((SealedResponse::Pending { size }, label))
+            }
+            Err(status) => Err(status),
+        })
+    }
+
+    fn fetch_vault_response(
+        &self,
+        request_id: u64,
+        sealed_response_capacity: usize,
+    ) -> SgxResult<SgxResult<SealedResponse>> {
+        self.capacities
+            .lock()
+            .unwrap()
+            .push(sealed_response_capacity);
+        if let Some(status) = self.ecall_error {
+            return Err(status);
+        }
+
+        let mut pending = self.pending.lock().unwrap();
+        Ok(match pending.get(&request_id) {
+            None => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+            Some(response) if sealed_response_capacity < response.len() => {
+                Ok(SealedResponse::Pending {
+                    size: response.len(),
+                })
+            }
+            Some(_) => Ok(SealedResponse::Complete(
+                pending.remove(&request_id).unwrap(),
+            )),
+        })
+    }
+
+    fn session_handshake(&self, hello: &[u8]) -> SgxResult<SgxResult<Box<[u8]>>> {
+        if let Some(status) = self.ecall_error {
+            return Err(status);
+        }
+        if let Some(status) = self.enclave_error {
+            return Ok(Err(status));
+        }
+        // Like the real enclave: refuse hellos that can't be decoded.
+        Ok(
+            match rmp_serde::from_read_ref::<_, MockSessionHello>(hello) {
+                Ok(hello) => {
+                    let session_id = self.sessions.fetch_add(1, SeqCst) as u64;
+                    let accept = MockSessionAccept {
+                        session_id,
+                        client_public_key: hello.client_public_key,
+                    };
+                    Ok(encode(&accept).into_boxed_slice())
+                }
+                Err(_) => Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER),
+            },
+        )
+    }
+
+    fn check_ready(&self) -> Result<(), String> {
+        match &self.readiness_error {
+            Some(problem) => Err(problem.clone()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Mock quoting enclave.
+///
+/// Its quotes have the layout of an SGX ECDSA (DCAP) quote, with a zeroed header
+/// and no signature.
+#[derive(Default)]
+pub struct MockQuotingEnclave {
+    /// Fail [`QuotingEnclave::quote`] with this problem, if set.
+    pub quote_error: Option<String>,
+}
+
+impl QuotingEnclave for MockQuotingEnclave {
+    fn target_info(&self) -> Result<sgx_target_info_t, String> {
+        let mut target_info = sgx_target_info_t::default();
+        target_info.mr_enclave.m = MOCK_QE_MR_ENCLAVE;
+        Ok(target_info)
+    }
+
+    fn quote(&self, report: &sgx_report_t) -> Result<Vec<u8>, String> {
+        if let Some(problem) = &self.quote_error {
+            return Err(problem.clone());
+        }
+        Ok(mock_quote(&report.body))
+    }
+}
+
+/// A quote of `body`, like [`MockQuotingEnclave::quote`].
+pub fn mock_quote(body: &sgx_report_body_t) -> Vec<u8> {
+    // SAFETY: sgx_report_body_t is a plain C struct of bytes and integers, without padding.
+    let body_bytes = unsafe {
+        std::slice::from_raw_parts(
+            (body as *const sgx_report_body_t).cast::<u8>(),
+            std::mem::size_of::<sgx_report_body_t>(),
+        )
+    };
+    [&[0; 48][..], body_bytes].concat()
+}
+
+/// Start [`AppState`] with one enclave worker.
+pub fn mock_app_state(vault_enclave: &Arc<MockVaultEnclave>) -> AppState {
+    AppState::start(vault_enclave.clone(), 1)
+}
+
+/// The tag that binds a [`MockSealedMessage`]'s message to its keys and nonce.
+fn mock_seal_tag(
+    message_bytes: &[u8],
+    nonce: &[u8; 24],
+    sender_public_key: &[u8; 32],
+    receiver_public_key: &[u8; 32],
+) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(sender_public_key)
+        .chain_update(receiver_public_key)
+        .chain_update(nonce)
+        .chain_update(message_bytes)
+        .finalize()
+        .into()
+}
+
+/// Seal `message` from `sender_public_key` to `receiver_public_key`.
+pub fn mock_seal(
+    message: &impl Serialize,
+    sender_public_key: &[u8; 32],
+    receiver_public_key: &[u8; 32],
+) -> MockSealedMessage {
+    let message_bytes = encode(message);
+    let nonce = [0; 24];
+    let tag = mock_seal_tag(
+        &message_bytes,
+        &nonce,
+        sender_public_key,
+        receiver_public_key,
+    );
+    MockSealedMessage {
+        ciphertext: [&tag[..], &message_bytes].concat(),
+        nonce,
+        sender_public_key: *sender_public_key,
+    }
+}
+
+/// Unseal a message sealed to `receiver_public_key`, or [`None`] if its tag doesn't match.
+pub fn mock_unseal<T>(sealed: &MockSealedMessage, receiver_public_key: &[u8; 32]) -> Option<T>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    if sealed.ciphertext.len() < 32 {
+        return None;
+    }
+    let (tag, message_bytes) = sealed.ciphertext.split_at(32);
+    let expected_tag = mock_seal_tag(
+        message_bytes,
+        &sealed.nonce,
+        &sealed.sender_public_key,
+        receiver_public_key,
+    );
+    match tag == expected_tag {
+        true => rmp_serde::from_read_ref(message_bytes).ok(),
+        false => None,
+    }
+}
+
+/// The signature that [`MockVaultEnclave`] appends to signed transactions.
+pub fn mock_signature(vault_id: &str, transaction_bytes: &[u8]) -> [u8; 32] {
+    Sha256::new()
+        .chain_update(vault_id)
+        .chain_update(transaction_bytes)
+        .finalize()
+        .into()
+}
+
+/// Seal `request` from [`MOCK_CLIENT_PUBLIC_KEY`] to the mock enclave.
+pub fn seal_request(request: &MockVaultRequest) -> Vec<u8> {
+    encode(&mock_seal(
+        request,
+        &MOCK_CLIENT_PUBLIC_KEY,
+        &MOCK_ENCLAVE_PUBLIC_KEY,
+    ))
+}
+
+/// Unseal a response from the mock enclave to [`MOCK_CLIENT_PUBLIC_KEY`].
+pub fn unseal_response(bytes: &[u8]) -> MockVaultResponse {
+    let sealed: MockSealedMessage = decode(bytes);
+    assert_eq!(sealed.sender_public_key, MOCK_ENCLAVE_PUBLIC_KEY);
+    mock_unseal(&sealed, &MOCK_CLIENT_PUBLIC_KEY).unwrap()
+}
+
+pub fn encode(value: &impl Serialize) -> Vec<u8> {
+    rmp_serde::to_vec_named(value).unwrap()
+}
+
+pub fn decode<T>(bytes: &[u8]) -> T
+where
+    T: for<'de> Deserialize<'de>,
+{
+    rmp_serde::from_read_ref(bytes).unwrap()
+}
+
+/// Decode an error response body.
+pub fn decode_error(bytes: &[u8]) -> ErrorBody {
+    serde_json::from_slice(bytes).unwrap()
+}
@@ -0,0 +1,67 @@
+//! Test [`http_service_impl::server::cors`].
+
+mod common;
+
+use std::sync::Arc;
+
+use actix_web::http::header;
+use actix_web::{test, web, App};
+use http_service_impl::config::CorsOrigins;
+use http_service_impl::server::{configure_services, cors};
+
+use crate::common::{mock_app_state, MockVaultEnclave};
+
+/// Get the report with `origin`, and return the allowed origin, if any.
+async fn allowed_origin(cors_origins: &CorsOrigins, origin: &str) -> Option<String> {
+    let app = test::init_service(
+        App::new()
+            .wrap(cors(cors_origins))
+            .app_data(web::Data::new(mock_app_state(&Arc::new(
+                MockVaultEnclave::default(),
+            ))))
+            .configure(configure_services),
+    )
+    .await;
+    let request = test::TestRequest::get()
+        .uri("/enclave-report")
+        .insert_header((header::ORIGIN, origin))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    response
+        .headers()
+        .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+        .map(|value| value.to_str().unwrap().to_string())
+}
+
+#[actix_rt::test]
+async fn cors_any_origin() {
+    let origin = "https://anywhere.example";
+    assert_eq!(
+        allowed_origin(&CorsOrigins::Any, origin).await.as_deref(),
+        Some(origin)
+    );
+}
+
+#[actix_rt::test]
+async fn cors_listed_origins() {
+    let cors_origins = &CorsOrigins::List(vec!["https://wallet.example".to_string()]);
+    assert_eq!(
+        allowed_origin(cors_origins, "https://wallet.example")
+            .await
+            .as_deref(),
+        Some("https://wallet.example")
+    );
+    assert_eq!(
+        allowed_origin(cors_origins, "https://elsewhere.example").await,
+        None
+    );
+}
+
+#[actix_rt::test]
+async fn cors_no_origins() {
+    let cors_origins = &CorsOrigins::default();
+    assert_eq!(
+        allowed_origin(cors_origins, "https://wallet.example").await,
+        None
+    );
+}
@@ -0,0 +1,199 @@
+//! Test `GET /enclave-report` and `POST /enclave-report`.
+
+mod common;
+
+use std::sync::Arc;
+
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App};
+use http_service_impl::attestation_report::{
+    AttestationReport,
+    EnclaveReportRequest,
+    SgxTargetInfo,
+};
+use http_service_impl::errors::ErrorCode;
+use http_service_impl::server::configure_services;
+use http_service_impl::verify::{verify_attestation_report, ReportExpectations, ReportVerifyError};
+use sgx_types::{sgx_status_t, sgx_target_info_t};
+
+use crate::common::{
+    decode,
+    decode_error,
+    encode,
+    mock_app_state,
+    MockVaultEnclave,
+    MOCK_ENCLAVE_PUBLIC_KEY,
+};
+
+/// Expect a report without user data, from a production enclave.
+const EXPECT_DEFAULT: ReportExpectations = ReportExpectations {
+    user_data: [0; 32],
+    allow_debug: false,
+};
+
+/// Get the report from a fresh app for `vault_enclave`, and return the status and body.
+async fn get_enclave_report(vault_enclave: MockVaultEnclave) -> (StatusCode, web::Bytes) {
+    get_enclave_report_uri(vault_enclave, "/enclave-report").await
+}
+
+/// Like [`get_enclave_report`], with query parameters in `uri`.
+async fn get_enclave_report_uri(
+    vault_enclave: MockVaultEnclave,
+    uri: &str,
+) -> (StatusCode, web::Bytes) {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(mock_app_state(&Arc::new(vault_enclave))))
+            .configure(configure_services),
+    )
+    .await;
+    let request = test::TestRequest::get().uri(uri).to_request();
+    let response = test::call_service(&app, request).await;
+    (response.status(), test::read_body(response).await)
+}
+
+#[actix_rt::test]
+async fn enclave_report_works() {
+    let (status, body) = get_enclave_report(MockVaultEnclave::default()).await;
+    assert_eq!(status, StatusCode::OK);
+    let report: AttestationReport = decode(&body);
+    assert_eq!(report.enclave_public_key, MOCK_ENCLAVE_PUBLIC_KEY);
+    verify_attestation_report(&report, &EXPECT_DEFAULT).unwrap();
+}
+
+#[actix_rt::test]
+async fn enclave_report_ecall_error() {
+    let (status, body) = get_enclave_report(MockVaultEnclave {
+        ecall_error: Some(sgx_status_t::SGX_ERROR_ENCLAVE_LOST),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(decode_error(&body).code, ErrorCode::EnclaveUnavailable);
+}
+
+#[actix_rt::test]
+async fn enclave_report_enclave_error() {
+    let (status, body) = get_enclave_report(MockVaultEnclave {
+        enclave_error: Some(sgx_status_t::SGX_ERROR_UNEXPECTED),
+        ..Default::default()
+    })
+    .await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(decode_error(&body).code, ErrorCode::EnclaveFailed);
+}
+
+#[actix_rt::test]
+async fn enclave_report_nonce_works() {
+    let nonce = [0xa5; 32];
+    let uri = format!("/enclave-report?nonce={}", "a5".repeat(32));
+    let (status, body) = get_enclave_report_uri(MockVaultEnclave::default(), &uri).await;
+    assert_eq!(status, StatusCode::OK);
+    let report: AttestationReport = decode(&body);
+    let expected = ReportExpectations {
+        user_data: nonce,
+        ..EXPECT_DEFAULT
+    };
+    verify_attestation_report(&report, &expected).unwrap();
+
+    // A replayed report fails a fresh nonce.
+    assert_eq!(
+        verify_attestation_report(&report, &EXPECT_DEFAULT),
+        Err(ReportVerifyError::UserDataMismatch)
+    );
+}
+
+#[actix_rt::test]
+async fn enclave_report_invalid_nonce() {
+    for uri in [
+        "/enclave-report?nonce=a5",
+        "/enclave-report?nonce=not-hex",
+        &format!("/enclave-report?nonce={}", "a5".repeat(33)),
+    ] {
+        let (status, body) = get_enclave_report_uri(MockVaultEnclave::default(), uri).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+        assert_eq!(decode_error(&body).code, ErrorCode::MalformedRequest);
+    }
+}
+
+/// Post `request_body` to a fresh app for `vault_enclave`, and return the status and body.
+async fn post_enclave_report(
+    vault_enclave: &Arc<MockVaultEnclave>,
+    request_body: Vec<u8>,
+) -> (StatusCode, web::Bytes) {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(mock_app_state(vault_enclave)))
+            .configure(configure_services),
+    )
+    .await;
+    let request = test::TestRequest::post()
+        .uri("/enclave-report")
+        .set_payload(request_body)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    (response.status(), test::read_body(response).await)
+}
+
+/// A target info for a quoting enclave.
+fn qe_target_info() -> SgxTargetInfo {
+    let mut target_info = sgx_target_info_t::default();
+    target_info.mr_enclave.m = [0x0e; 32];
+    target_info.into()
+}
+
+#[actix_rt::test]
+async fn enclave_report_post_works() {
+    let vault_enclave = &Arc::new(MockVaultEnclave::default());
+    let request = EnclaveReportRequest {
+        target_info: qe_target_info(),
+        nonce: None,
+    };
+    let (status, body) = post_enclave_report(vault_enclave, encode(&request)).await;
+    assert_eq!(status, StatusCode::OK);
+    let report: AttestationReport = decode(&body);
+    verify_attestation_report(&report, &EXPECT_DEFAULT).unwrap();
+    assert_eq!(vault_enclave.report_targets(), [[0x0e; 32]]);
+}
+
+#[actix_rt::test]
+async fn enclave_report_post_nonce_works() {
+    let vault_enclave = &Arc::new(MockVaultEnclave::default());
+    let request = EnclaveReportRequest {
+        target_info: qe_target_info(),
+        nonce: Some([0x5a; 32]),
+    };
+    let (status, body) = post_enclave_report(vault_enclave, encode(&request)).await;
+    assert_eq!(status, StatusCode::OK);
+    let report: AttestationReport = decode(&body);
+    let expected = ReportExpectations {
+        user_data: [0x5a; 32],
+        ..EXPECT_DEFAULT
+    };
+    verify_attestation_report(&report, &expected).unwrap();
+}
+
+#[actix_rt::test]
+async fn enclave_report_post_malformed_body() {
+    let vault_enclave = &Arc::new(MockVaultEnclave::default());
+    let (status, body) = post_enclave_report(vault_enclave, b"garbage".to_vec()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(decode_error(&body).code, ErrorCode::MalformedRequest);
+    assert!(vault_enclave.report_targets().is_empty());
+}
+
+#[actix_rt::test]
+async fn enclave_report_post_invalid_target_info() {
+    let vault_enclave = &Arc::new(MockVaultEnclave::default());
+    let request = EnclaveReportRequest {
+        target_info: SgxTargetInfo {
+            config_id: vec![0; 3].into(),
+            ..qe_target_info()
+        },
+        nonce: None,
+    };
+    let (status, body) = post_enclave_report(vault_enclave, encode(&request)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(decode_error(&body).code, ErrorCode::MalformedRequest);
+    assert!(vault_enclave.report_targets().is_empty());
+}
@@ -0,0 +1,90 @@
+//! Load test: vault operation throughput scales with the number of enclave workers.
+
+mod common;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use actix_web::{test, web, App};
+use futures_util::future::join_all;
+use http_service_impl::server::{configure_services, AppState};
+
+use crate::common::{
+    seal_request,
+    unseal_response,
+    MockCreateVaultResult,
+    MockVaultEnclave,
+    MockVaultRequest,
+    MockVaultResponse,
+};
+
+/// How long each mock enclave call blocks its worker.
+const OPERATION_TIME: Duration = Duration::from_millis(50);
+
+/// Concurrent requests per load run.
+const REQUESTS: usize = 16;
+
+/// Send [`REQUESTS`] concurrent vault operations through `enclave_workers` workers.
+///
+/// Return the elapsed time, and the most concurrent enclave calls seen.
+async fn run_load(enclave_workers: usize) -> (Duration, usize) {
+    let vault_enclave = Arc::new(MockVaultEnclave {
+        operation_time: OPERATION_TIME,
+        ..Default::default()
+    });
+    let app_state = AppState::start(vault_enclave.clone(), enclave_workers);
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_services),
+    )
+    .await;
+
+    let started = Instant::now();
+    let responses = join_all((0..REQUESTS).map(|i| {
+        let request = test::TestRequest::post()
+            .uri("/vault-operation")
+            .set_payload(seal_request(&MockVaultRequest::CreateVault {
+                username: format!("vault {}", i),
+                auth_password: "123456".to_string(),
+            }))
+            .to_request();
+        test::call_service(&app, request)
+    }))
+    .await;
+    let elapsed = started.elapsed();
+
+    for (i, response) in responses.into_iter().enumerate() {
+        assert!(response.status().is_success(), "{:?}", response.status());
+        let body = test::read_body(response).await;
+        match unseal_response(&body) {
+            MockVaultResponse::CreateVault(MockCreateVaultResult::Created(display)) => {
+                assert_eq!(display.vault_id, format!("vault {}", i))
+            }
+            otherwise => panic!("{:?}", otherwise),
+        }
+    }
+    (elapsed, vault_enclave.max_in_flight())
+}
+
+#[actix_rt::test]
+async fn throughput_scales_with_enclave_workers() {
+    let (elapsed_1, max_in_flight_1) = run_load(1).await;
+    let (elapsed_4, max_in_flight_4) = run_load(4).await;
+    let throughput = |elapsed: Duration| REQUESTS as f64 / elapsed.as_secs_f64();
+    println!(
+        "1 worker: {:.1} requests/s, 4 workers: {:.1} requests/s",
+        throughput(elapsed_1),
+        throughput(elapsed_4)
+    );
+
+    assert_eq!(max_in_flight_1, 1);
+    assert_eq!(max_in_flight_4, 4);
+    // Ideally 4×, but allow for scheduling overhead.
+    assert!(
+        elapsed_4 * 2 < elapsed_1,
+        "{:?} with 4 workers, {:?} with 1",
+        elapsed_4,
+        elapsed_1
+    );
+}
@@ -0,0 +1,112 @@
+//! Test `GET /healthz`, `GET /readyz`, and `GET /metrics`.
+
+mod common;
+
+use std::sync::Arc;
+
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App};
+use http_service_impl::errors::ErrorCode;
+use http_service_impl::server::configure_services;
+
+use crate::common::{
+    decode_error,
+    mock_app_state,
+    seal_request,
+    MockVaultEnclave,
+    MockVaultRequest,
+};
+
+/// Get `uri` from a fresh app for `vault_enclave`, and return the status and body.
+async fn get(vault_enclave: MockVaultEnclave, uri: &str) -> (StatusCode, web::Bytes) {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(mock_app_state(&Arc::new(vault_enclave))))
+            .configure(configure_services),
+    )
+    .await;
+    let request = test::TestRequest::get().uri(uri).to_request();
+    let response = test::call_service(&app, request).await;
+    (response.status(), test::read_body(response).await)
+}
+
+#[actix_rt::test]
+async fn healthz_works() {
+    let (status, body) = get(
+        MockVaultEnclave {
+            readiness_error: Some("not yet".to_string()),
+            ..Default::default()
+        },
+        "/healthz",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "ok");
+}
+
+#[actix_rt::test]
+async fn readyz_works() {
+    let (status, body) = get(MockVaultEnclave::default(), "/readyz").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, "ok");
+}
+
+#[actix_rt::test]
+async fn readyz_not_ready() {
+    let (status, body) = get(
+        MockVaultEnclave {
+            readiness_error: Some("vault store is not writable".to_string()),
+            ..Default::default()
+        },
+        "/readyz",
+    )
+    .await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    let error = decode_error(&body);
+    assert_eq!(error.code, ErrorCode::NotReady);
+    assert!(error.retryable);
+    // The problem is logged, not reported.
+    assert!(!error.message.contains("writable"));
+}
+
+#[actix_rt::test]
+async fn metrics_works() {
+    let vault_enclave = &Arc::new(MockVaultEnclave::default().with_vault("vault", "123456"));
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(mock_app_state(vault_enclave)))
+            .configure(configure_services),
+    )
+    .await;
+    // Larger than the initial response capacity, to need a fetch.
+    let request = test::TestRequest::post()
+        .uri("/vault-operation")
+        .set_payload(seal_request(&MockVaultRequest::SignTransaction {
+            vault_id: "vault".to_string(),
+            auth_password: "123456".to_string(),
+            transaction_bytes: vec![1; 2000],
+        }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let request = test::TestRequest::get().uri("/metrics").to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = test::read_body(response).await;
+    let text = std::str::from_utf8(&body).unwrap();
+    for expected in [
+        r#"vault_enclave_ecall_duration_seconds_count{ecall="vault_operation"} 1"#,
+        r#"vault_enclave_ecall_outcomes_total{ecall="vault_operation",outcome="pending"} 1"#,
+        r#"vault_enclave_ecall_outcomes_total{ecall="fetch_vault_response",outcome="ok"} 1"#,
+        "vault_enclave_response_fetches_total 1",
+        r#"vault_operations_total{operation="SignTransaction",outcome="Succeeded"} 1"#,
+    ] {
+        assert!(
+            text.contains(expected),
+            "missing {:?} in:\n{}",
+            expected,
+            text
+        );
+    }
+}
@@ -0,0 +1,97 @@
+//! Test `POST /session`.
+
+mod common;
+
+use std::sync::Arc;
+
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App};
+use http_service_impl::errors::ErrorCode;
+use http_service_impl::server::configure_services;
+use sgx_types::sgx_status_t;
+
+use crate::common::{
+    decode,
+    decode_error,
+    encode,
+    mock_app_state,
+    MockSessionAccept,
+    MockSessionHello,
+    MockVaultEnclave,
+};
+
+/// Post `body` to a fresh app for `vault_enclave`, and return the status and body.
+async fn post_session(vault_enclave: MockVaultEnclave, body: Vec<u8>) -> (StatusCode, web::Bytes) {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(mock_app_state(&Arc::new(vault_enclave))))
+            .configure(configure_services),
+    )
+    .await;
+    let request = test::TestRequest::post()
+        .uri("/session")
+        .set_payload(body)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    (response.status(), test::read_body(response).await)
+}
+
+fn hello() -> Vec<u8> {
+    encode(&MockSessionHello {
+        client_public_key: [0xc1; 32],
+    })
+}
+
+#[actix_rt::test]
+async fn session_works() {
+    let (status, body) = post_session(MockVaultEnclave::default(), hello()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        decode::<MockSessionAccept>(&body),
+        MockSessionAccept {
+            session_id: 0,
+            client_public_key: [0xc1; 32],
+        }
+    );
+}
+
+#[actix_rt::test]
+async fn session_empty_body() {
+    let (status, body) = post_session(MockVaultEnclave::default(), Vec::new()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(decode_error(&body).code, ErrorCode::MalformedRequest);
+}
+
+#[actix_rt::test]
+async fn session_malformed_hello() {
+    let (status, body) = post_session(MockVaultEnclave::default(), b"garbage".to_vec()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let error = decode_error(&body);
+    assert_eq!(error.code, ErrorCode::MalformedRequest);
+    assert_eq!(error.message, "invalid session hello");
+}
+
+#[actix_rt::test]
+async fn session_ecall_error() {
+    let vault_enclave = MockVaultEnclave {
+        ecall_error: Some(sgx_status_t::SGX_ERROR_ENCLAVE_LOST),
+        ..Default::default()
+    };
+    let (status, body) = post_session(vault_enclave, hello()).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(decode_error(&body).code, ErrorCode::EnclaveUnavailable);
+}
+
+/// The enclave refuses new sessions while all its held sessions are recent.
+#[actix_rt::test]
+async fn session_too_many_sessions() {
+    let vault_enclave = MockVaultEnclave {
+        enclave_error: Some(sgx_status_t::SGX_ERROR_BUSY),
+        ..Default::default()
+    };
+    let (status, body) = post_session(vault_enclave, hello()).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    let error = decode_error(&body);
+    assert_eq!(error.code, ErrorCode::EnclaveBusy);
+    assert!(error.retryable);
+}
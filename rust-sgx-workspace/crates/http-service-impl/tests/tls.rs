@@ -0,0 +1,312 @@
+//! Test [`http_service_impl::tls`].
+
+mod common;
+
+use std::path::PathBuf;
+use std::{fs, io};
+
+use http_service_impl::attestation_report::to_msgpack;
+use http_service_impl::config::TlsConfig;
+use http_service_impl::tls::{
+    ra_tls_certificate,
+    ra_tls_report_data,
+    rustls_server_config,
+    RaTlsEvidence,
+    RA_TLS_EVIDENCE_OID,
+};
+use http_service_impl::traits::VaultEnclave;
+use http_service_impl::verify::{verify_ra_tls_certificate, RaTlsExpectations, RaTlsVerifyError};
+use rcgen::{
+    generate_simple_self_signed,
+    Certificate,
+    CertificateParams,
+    CustomExtension,
+    KeyPair,
+    PKCS_ECDSA_P256_SHA256,
+};
+use sgx_types::{sgx_report_body_t, sgx_status_t, sgx_target_info_t, SGX_FLAGS_DEBUG};
+use sha2::{Digest, Sha256};
+
+use crate::common::{
+    mock_quote,
+    MockQuotingEnclave,
+    MockVaultEnclave,
+    MOCK_ENCLAVE_PUBLIC_KEY,
+    MOCK_MR_ENCLAVE,
+    MOCK_QE_MR_ENCLAVE,
+};
+
+#[test]
+fn ra_tls_certificate_works() {
+    let vault_enclave = &MockVaultEnclave::default();
+    let quoting_enclave = &MockQuotingEnclave::default();
+    let certificate =
+        ra_tls_certificate(vault_enclave, quoting_enclave, &["localhost".to_string()]).unwrap();
+    assert_eq!(
+        certificate.evidence.enclave_public_key,
+        MOCK_ENCLAVE_PUBLIC_KEY
+    );
+
+    // The report targets the quoting enclave.
+    assert_eq!(vault_enclave.report_targets(), vec![MOCK_QE_MR_ENCLAVE]);
+
+    // The certificate embeds the quote, which binds the certificate's key.
+    let mut verified_quote = None;
+    let enclave_public_key =
+        verify_ra_tls_certificate(&certificate.cert_der, &expect_mock(), |quote| {
+            verified_quote = Some(quote.to_vec());
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(enclave_public_key, MOCK_ENCLAVE_PUBLIC_KEY);
+    assert_eq!(
+        verified_quote.as_deref(),
+        Some(&certificate.evidence.quote[..])
+    );
+
+    let key_pair = KeyPair::from_der(&certificate.key_der).unwrap();
+    let report_data = ra_tls_report_data(&MOCK_ENCLAVE_PUBLIC_KEY, key_pair.public_key_raw());
+    assert_eq!(certificate.evidence.quote[48 + 320..], report_data);
+}
+
+#[test]
+fn ra_tls_certificate_enclave_error() {
+    let vault_enclave = &MockVaultEnclave {
+        enclave_error: Some(sgx_status_t::SGX_ERROR_UNEXPECTED),
+        ..Default::default()
+    };
+    let err = ra_tls_certificate(
+        vault_enclave,
+        &MockQuotingEnclave::default(),
+        &["localhost".to_string()],
+    )
+    .err()
+    .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "ra_tls_certificate: create_tls_report failed: SGX_ERROR_UNEXPECTED"
+    );
+}
+
+#[test]
+fn ra_tls_certificate_quote_error() {
+    let quoting_enclave = &MockQuotingEnclave {
+        quote_error: Some("no PCK certificate".to_string()),
+    };
+    let err = ra_tls_certificate(
+        &MockVaultEnclave::default(),
+        quoting_enclave,
+        &["localhost".to_string()],
+    )
+    .err()
+    .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "ra_tls_certificate: quote failed: no PCK certificate"
+    );
+}
+
+#[test]
+fn verify_ra_tls_quote_rejected() {
+    let certificate = mock_certificate();
+    let result = verify_ra_tls_certificate(&certificate, &expect_mock(), |_| {
+        Err("TCB out of date".to_string())
+    });
+    assert_eq!(
+        result,
+        Err(RaTlsVerifyError::QuoteRejected(
+            "TCB out of date".to_string()
+        ))
+    );
+}
+
+#[test]
+fn verify_ra_tls_enclave_mismatch() {
+    let certificate = mock_certificate();
+    let expected = &RaTlsExpectations {
+        mr_enclave: Some([0x66; 32]),
+        ..expect_mock()
+    };
+    let result = verify_ra_tls_certificate(&certificate, expected, |_| Ok(()));
+    assert_eq!(result, Err(RaTlsVerifyError::EnclaveMismatch));
+}
+
+#[test]
+fn verify_ra_tls_signer_mismatch() {
+    let certificate = mock_certificate();
+    let expected = &RaTlsExpectations {
+        mr_signer: Some([0x66; 32]),
+        ..expect_mock()
+    };
+    let result = verify_ra_tls_certificate(&certificate, expected, |_| Ok(()));
+    assert_eq!(result, Err(RaTlsVerifyError::SignerMismatch));
+}
+
+#[test]
+fn verify_ra_tls_debug_enclave() {
+    let certificate = &certificate_with_quote(|tls_public_key| {
+        let mut body = mock_tls_report_body(tls_public_key);
+        body.attributes.flags |= SGX_FLAGS_DEBUG;
+        body
+    });
+    let result = verify_ra_tls_certificate(certificate, &expect_mock(), |_| Ok(()));
+    assert_eq!(result, Err(RaTlsVerifyError::DebugEnclave));
+
+    let expected = &RaTlsExpectations {
+        allow_debug: true,
+        ..expect_mock()
+    };
+    let result = verify_ra_tls_certificate(certificate, expected, |_| Ok(()));
+    assert_eq!(result, Ok(MOCK_ENCLAVE_PUBLIC_KEY));
+}
+
+/// A report from the nonce path can't pass for an RA-TLS report, whatever the nonce.
+#[test]
+fn verify_ra_tls_nonce_report() {
+    let certificate = &certificate_with_quote(|tls_public_key| {
+        let nonce = Sha256::digest(tls_public_key).into();
+        let (report, _) = MockVaultEnclave::default()
+            .create_report(sgx_target_info_t::default(), nonce)
+            .unwrap()
+            .unwrap();
+        report.body
+    });
+    let result = verify_ra_tls_certificate(certificate, &expect_mock(), |_| Ok(()));
+    assert_eq!(result, Err(RaTlsVerifyError::PublicKeyMismatch));
+}
+
+/// Evidence copied from another certificate does not bind this certificate's key.
+#[test]
+fn verify_ra_tls_copied_evidence() {
+    let certificate = &certificate_with_quote(|_| {
+        let other_key_pair = KeyPair::generate(&PKCS_ECDSA_P256_SHA256).unwrap();
+        mock_tls_report_body(other_key_pair.public_key_raw())
+    });
+    let result = verify_ra_tls_certificate(certificate, &expect_mock(), |_| Ok(()));
+    assert_eq!(result, Err(RaTlsVerifyError::PublicKeyMismatch));
+}
+
+#[test]
+fn verify_ra_tls_missing_evidence() {
+    let certificate = generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let result = verify_ra_tls_certificate(
+        &certificate.serialize_der().unwrap(),
+        &expect_mock(),
+        |_| Ok(()),
+    );
+    assert_eq!(result, Err(RaTlsVerifyError::MissingEvidence));
+
+    let result = verify_ra_tls_certificate(b"not a certificate", &expect_mock(), |_| Ok(()));
+    assert_eq!(result, Err(RaTlsVerifyError::MalformedCertificate));
+}
+
+#[test]
+fn rustls_server_config_remote_attestation() {
+    let tls = &TlsConfig::RemoteAttestation {
+        subject_alt_names: vec!["localhost".to_string()],
+    };
+    rustls_server_config(
+        tls,
+        &MockVaultEnclave::default(),
+        &MockQuotingEnclave::default(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn rustls_server_config_pem() {
+    let dir = &temp_dir("rustls_server_config_pem");
+    let certificate = generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let cert_path = dir.join("cert.pem");
+    let key_path = dir.join("key.pem");
+    fs::write(&cert_path, certificate.serialize_pem().unwrap()).unwrap();
+    fs::write(&key_path, certificate.serialize_private_key_pem()).unwrap();
+
+    let tls = &TlsConfig::Pem {
+        cert_path: cert_path.clone(),
+        key_path: key_path.clone(),
+    };
+    rustls_server_config(
+        tls,
+        &MockVaultEnclave::default(),
+        &MockQuotingEnclave::default(),
+    )
+    .unwrap();
+
+    // Swapped paths: no certificate or key found.
+    let tls = &TlsConfig::Pem {
+        cert_path: key_path,
+        key_path: cert_path,
+    };
+    let err = rustls_server_config(
+        tls,
+        &MockVaultEnclave::default(),
+        &MockQuotingEnclave::default(),
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+/// Helper: What [`MockVaultEnclave`]'s TLS reports meet.
+fn expect_mock() -> RaTlsExpectations {
+    RaTlsExpectations {
+        mr_enclave: Some(MOCK_MR_ENCLAVE),
+        mr_signer: Some([0; 32]),
+        allow_debug: false,
+    }
+}
+
+/// Helper: An RA-TLS certificate from the mock enclaves.
+fn mock_certificate() -> Vec<u8> {
+    ra_tls_certificate(
+        &MockVaultEnclave::default(),
+        &MockQuotingEnclave::default(),
+        &["localhost".to_string()],
+    )
+    .unwrap()
+    .cert_der
+}
+
+/// Helper: A report body like [`MockVaultEnclave`]'s TLS reports, for `tls_public_key`.
+fn mock_tls_report_body(tls_public_key: &[u8]) -> sgx_report_body_t {
+    let (report, _) = MockVaultEnclave::default()
+        .create_tls_report(sgx_target_info_t::default(), tls_public_key)
+        .unwrap()
+        .unwrap();
+    report.body
+}
+
+/// Helper: A certificate for a fresh key pair, with a mock quote of the report body
+/// that `quoted` returns for the key pair's public key.
+fn certificate_with_quote(quoted: impl FnOnce(&[u8]) -> sgx_report_body_t) -> Vec<u8> {
+    let key_pair = KeyPair::generate(&PKCS_ECDSA_P256_SHA256).unwrap();
+    let body = quoted(key_pair.public_key_raw());
+    let evidence = RaTlsEvidence {
+        quote: mock_quote(&body).into_boxed_slice(),
+        enclave_public_key: MOCK_ENCLAVE_PUBLIC_KEY,
+    };
+
+    let mut params = CertificateParams::new(vec!["localhost".to_string()]);
+    params.alg = &PKCS_ECDSA_P256_SHA256;
+    params.key_pair = Some(key_pair);
+    params
+        .custom_extensions
+        .push(CustomExtension::from_oid_content(
+            RA_TLS_EVIDENCE_OID,
+            to_msgpack(&evidence).unwrap().into_vec(),
+        ));
+    Certificate::from_params(params)
+        .unwrap()
+        .serialize_der()
+        .unwrap()
+}
+
+/// Helper: Create a fresh temporary directory for `name`.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("http-service-impl-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,333 @@
+//! Test `POST /vault-operation`.
+
+mod common;
+
+use std::sync::Arc;
+
+use actix_web::http::StatusCode;
+use actix_web::{test, web, App};
+use http_service_impl::errors::{ErrorBody, ErrorCode};
+use http_service_impl::server::{configure_services, AppState, DEFAULT_MAX_REQUEST_SIZE};
+use sgx_types::sgx_status_t;
+
+use crate::common::{
+    decode_error,
+    encode,
+    mock_app_state,
+    mock_seal,
+    mock_signature,
+    seal_request,
+    unseal_response,
+    MockCreateVaultResult,
+    MockOpenVaultResult,
+    MockSignTransactionResult,
+    MockVaultDisplay,
+    MockVaultEnclave,
+    MockVaultRequest,
+    MockVaultResponse,
+    MOCK_CLIENT_PUBLIC_KEY,
+    MOCK_ENCLAVE_PUBLIC_KEY,
+};
+
+/// Post `body` to a fresh app for `vault_enclave`, and return the status and body.
+async fn post_vault_operation(
+    vault_enclave: &Arc<MockVaultEnclave>,
+    body: Vec<u8>,
+) -> (StatusCode, web::Bytes) {
+    post_vault_operation_to(mock_app_state(vault_enclave), body).await
+}
+
+/// Post `body` to a fresh app with `app_state`, and return the status and body.
+async fn post_vault_operation_to(app_state: AppState, body: Vec<u8>) -> (StatusCode, web::Bytes) {
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(app_state))
+            .configure(configure_services),
+    )
+    .await;
+    let request = test::TestRequest::post()
+        .uri("/vault-operation")
+        .set_payload(body)
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    (response.status(), test::read_body(response).await)
+}
+
+/// A [`MockVaultRequest::OpenVault`] request, for `vault_id` with `auth_password`.
+fn open_vault(vault_id: &str, auth_password: &str) -> MockVaultRequest {
+    MockVaultRequest::OpenVault {
+        vault_id: vault_id.to_string(),
+        auth_password: auth_password.to_string(),
+    }
+}
+
+/// A [`MockVaultRequest::SignTransaction`] request for the vault "vault", with password "123456".
+fn sign_transaction(transaction_bytes: Vec<u8>) -> MockVaultRequest {
+    MockVaultRequest::SignTransaction {
+        vault_id: "vault".to_string(),
+        auth_password: "123456".to_string(),
+        transaction_bytes,
+    }
+}
+
+/// A mock enclave holding the vault "vault", with password "123456".
+fn enclave_with_vault() -> Arc<MockVaultEnclave> {
+    Arc::new(MockVaultEnclave::default().with_vault("vault", "123456"))
+}
+
+#[actix_rt::test]
+async fn vault_operation_works() {
+    let vault_enclave = &Arc::new(MockVaultEnclave::default());
+    let request = &MockVaultRequest::CreateVault {
+        username: "new".to_string(),
+        auth_password: "123456".to_string(),
+    };
+    let display = MockVaultDisplay {
+        vault_id: "new".to_string(),
+        username: "new".to_string(),
+    };
+
+    let (status, body) = post_vault_operation(vault_enclave, seal_request(request)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        unseal_response(&body),
+        MockVaultResponse::CreateVault(MockCreateVaultResult::Created(display.clone()))
+    );
+    assert_eq!(vault_enclave.capacities(), vec![1 << 10]);
+
+    let request = &open_vault("new", "123456");
+    let (status, body) = post_vault_operation(vault_enclave, seal_request(request)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        unseal_response(&body),
+        MockVaultResponse::OpenVault(MockOpenVaultResult::Opened(display))
+    );
+}
+
+#[actix_rt::test]
+async fn vault_operation_invalid_auth() {
+    let vault_enclave = &enclave_with_vault();
+    for request in [
+        open_vault("vault", "000000"),
+        open_vault("missing", "123456"),
+    ] {
+        let (status, body) = post_vault_operation(vault_enclave, seal_request(&request)).await;
+        // Not an HTTP error: the enclave seals its response.
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            unseal_response(&body),
+            MockVaultResponse::OpenVault(MockOpenVaultResult::InvalidAuth)
+        );
+    }
+}
+
+#[actix_rt::test]
+async fn vault_operation_failed() {
+    let vault_enclave = &enclave_with_vault();
+    let request = &sign_transaction(vec![]);
+
+    let (status, body) = post_vault_operation(vault_enclave, seal_request(request)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        unseal_response(&body),
+        MockVaultResponse::SignTransaction(MockSignTransactionResult::Failed(
+            "empty transaction".to_string()
+        ))
+    );
+}
+
+/// Requests that the enclave can't unseal fail without effect.
+#[actix_rt::test]
+async fn vault_operation_unseal_failure() {
+    let vault_enclave = &Arc::new(MockVaultEnclave::default());
+    let create_vault = &MockVaultRequest::CreateVault {
+        username: "new".to_string(),
+        auth_password: "123456".to_string(),
+    };
+    // Sealed to the client instead of the enclave.
+    let misdirected = mock_seal(
+        create_vault,
+        &MOCK_CLIENT_PUBLIC_KEY,
+        &MOCK_CLIENT_PUBLIC_KEY,
+    );
+    let mut tampered = mock_seal(
+        create_vault,
+        &MOCK_CLIENT_PUBLIC_KEY,
+        &MOCK_ENCLAVE_PUBLIC_KEY,
+    );
+    tampered.nonce[0] ^= 1;
+
+    for sealed in [misdirected, tampered] {
+        let (status, body) = post_vault_operation(vault_enclave, encode(&sealed)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            decode_error(&body),
+            ErrorBody {
+                code: ErrorCode::MalformedRequest,
+                message: "invalid sealed request".to_string(),
+                retryable: false,
+            }
+        );
+    }
+
+    // The vault was not created.
+    let request = &open_vault("new", "123456");
+    let (_, body) = post_vault_operation(vault_enclave, seal_request(request)).await;
+    assert_eq!(
+        unseal_response(&body),
+        MockVaultResponse::OpenVault(MockOpenVaultResult::InvalidAuth)
+    );
+}
+
+/// The transaction, signed by the vault "vault".
+fn signed(transaction_bytes: &[u8]) -> MockVaultResponse {
+    let signature = mock_signature("vault", transaction_bytes);
+    MockVaultResponse::SignTransaction(MockSignTransactionResult::Signed {
+        signed_transaction_bytes: [transaction_bytes, &signature].concat(),
+    })
+}
+
+#[actix_rt::test]
+async fn vault_operation_fetches_buffer_too_short() {
+    let vault_enclave = &enclave_with_vault();
+    let request = &sign_transaction(vec![1; 2000]);
+
+    let (status, body) = post_vault_operation(vault_enclave, seal_request(request)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(unseal_response(&body), signed(&[1; 2000]));
+    // Executed once, then fetched with the exact size.
+    assert_eq!(vault_enclave.executions(), 1);
+    assert_eq!(vault_enclave.capacities(), vec![1 << 10, body.len()]);
+}
+
+#[actix_rt::test]
+async fn vault_operation_large_response_works() {
+    let vault_enclave = &enclave_with_vault();
+    let transaction_bytes = vec![1; 4 << 20];
+    let request = &sign_transaction(transaction_bytes.clone());
+    // The response is about as large as the request.
+    let app_state = mock_app_state(vault_enclave).with_max_request_size(8 << 20);
+
+    let (status, body) = post_vault_operation_to(app_state, seal_request(request)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(unseal_response(&body), signed(&transaction_bytes));
+    assert_eq!(vault_enclave.executions(), 1);
+    assert_eq!(vault_enclave.capacities(), vec![1 << 10, body.len()]);
+}
+
+#[actix_rt::test]
+async fn vault_operation_ecall_error() {
+    let vault_enclave = &Arc::new(MockVaultEnclave {
+        ecall_error: Some(sgx_status_t::SGX_ERROR_ENCLAVE_LOST),
+        ..Default::default()
+    });
+    let request = &open_vault("vault", "123456");
+
+    let (status, body) = post_vault_operation(vault_enclave, seal_request(request)).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        decode_error(&body),
+        ErrorBody {
+            code: ErrorCode::EnclaveUnavailable,
+            message: "enclave is unavailable".to_string(),
+            retryable: false,
+        }
+    );
+    assert_eq!(vault_enclave.capacities(), vec![1 << 10]);
+}
+
+#[actix_rt::test]
+async fn vault_operation_enclave_busy() {
+    let vault_enclave = &Arc::new(MockVaultEnclave {
+        ecall_error: Some(sgx_status_t::SGX_ERROR_OUT_OF_TCS),
+        ..Default::default()
+    });
+    let request = &open_vault("vault", "123456");
+
+    let (status, body) = post_vault_operation(vault_enclave, seal_request(request)).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        decode_error(&body),
+        ErrorBody {
+            code: ErrorCode::EnclaveBusy,
+            message: "enclave is busy".to_string(),
+            retryable: true,
+        }
+    );
+}
+
+#[actix_rt::test]
+async fn vault_operation_enclave_error() {
+    let vault_enclave = &Arc::new(MockVaultEnclave {
+        enclave_error: Some(sgx_status_t::SGX_ERROR_UNEXPECTED),
+        ..Default::default()
+    });
+    let request = &open_vault("vault", "123456");
+
+    let (status, body) = post_vault_operation(vault_enclave, seal_request(request)).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    // No internal details leak out.
+    assert_eq!(
+        decode_error(&body),
+        ErrorBody {
+            code: ErrorCode::EnclaveFailed,
+            message: "enclave failed to handle the request".to_string(),
+            retryable: false,
+        }
+    );
+}
+
+#[actix_rt::test]
+async fn vault_operation_malformed_body() {
+    // Empty bodies are refused without calling the enclave.
+    let vault_enclave = &Arc::new(MockVaultEnclave::default());
+    let (status, body) = post_vault_operation(vault_enclave, vec![]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(decode_error(&body).code, ErrorCode::MalformedRequest);
+    assert_eq!(vault_enclave.capacities(), vec![]);
+
+    // Malformed requests fail in the enclave, without fetching.
+    let vault_enclave = &Arc::new(MockVaultEnclave::default());
+    let (status, body) = post_vault_operation(vault_enclave, b"not msgpack".to_vec()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        decode_error(&body),
+        ErrorBody {
+            code: ErrorCode::MalformedRequest,
+            message: "invalid sealed request".to_string(),
+            retryable: false,
+        }
+    );
+    assert_eq!(vault_enclave.capacities(), vec![1 << 10]);
+}
+
+#[actix_rt::test]
+async fn vault_operation_request_too_large() {
+    let vault_enclave = &Arc::new(MockVaultEnclave::default());
+    let body = vec![0; DEFAULT_MAX_REQUEST_SIZE + 1];
+
+    let (status, body) = post_vault_operation(vault_enclave, body).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(decode_error(&body).code, ErrorCode::RequestTooLarge);
+    assert_eq!(vault_enclave.capacities(), vec![]);
+}
+
+#[actix_rt::test]
+async fn vault_operation_configured_max_request_size() {
+    let vault_enclave = &Arc::new(MockVaultEnclave::default());
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(
+                mock_app_state(vault_enclave).with_max_request_size(16),
+            ))
+            .configure(configure_services),
+    )
+    .await;
+    let request = test::TestRequest::post()
+        .uri("/vault-operation")
+        .set_payload(vec![0; 17])
+        .to_request();
+    let response = test::call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(vault_enclave.capacities(), vec![]);
+}
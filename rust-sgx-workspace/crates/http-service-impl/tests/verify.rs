@@ -0,0 +1,83 @@
+//! Test [`http_service_impl::verify`].
+
+mod common;
+
+use http_service_impl::attestation_report::AttestationReport;
+use http_service_impl::traits::VaultEnclave;
+use http_service_impl::verify::{verify_attestation_report, ReportExpectations, ReportVerifyError};
+use sgx_types::{sgx_target_info_t, SGX_FLAGS_DEBUG};
+
+use crate::common::MockVaultEnclave;
+
+/// A report from the mock enclave, with `user_data`.
+fn mock_report(user_data: [u8; 32]) -> AttestationReport {
+    let (report, enclave_public_key) = MockVaultEnclave::default()
+        .create_report(sgx_target_info_t::default(), user_data)
+        .unwrap()
+        .unwrap();
+    AttestationReport {
+        report: report.into(),
+        enclave_public_key,
+    }
+}
+
+fn expect(user_data: [u8; 32]) -> ReportExpectations {
+    ReportExpectations {
+        user_data,
+        allow_debug: false,
+    }
+}
+
+#[test]
+fn verify_works() {
+    let report = &mock_report([7; 32]);
+    assert_eq!(verify_attestation_report(report, &expect([7; 32])), Ok(()));
+}
+
+#[test]
+fn verify_public_key_mismatch() {
+    let report = &AttestationReport {
+        enclave_public_key: [0x66; 32],
+        ..mock_report([0; 32])
+    };
+    assert_eq!(
+        verify_attestation_report(report, &expect([0; 32])),
+        Err(ReportVerifyError::PublicKeyMismatch)
+    );
+}
+
+#[test]
+fn verify_user_data_mismatch() {
+    let report = &mock_report([7; 32]);
+    assert_eq!(
+        verify_attestation_report(report, &expect([8; 32])),
+        Err(ReportVerifyError::UserDataMismatch)
+    );
+}
+
+#[test]
+fn verify_malformed_report_data() {
+    let mut report = mock_report([0; 32]);
+    report.report.body.report_data = vec![0; 32].into();
+    assert_eq!(
+        verify_attestation_report(&report, &expect([0; 32])),
+        Err(ReportVerifyError::MalformedReportData)
+    );
+}
+
+#[test]
+fn verify_debug_enclave() {
+    let mut report = mock_report([0; 32]);
+    report.report.body.attributes_flags |= SGX_FLAGS_DEBUG;
+    assert_eq!(
+        verify_attestation_report(&report, &expect([0; 32])),
+        Err(ReportVerifyError::DebugEnclave)
+    );
+
+    // Like a test against an enclave in simulation mode.
+    let allow_debug = ReportExpectations {
+        allow_debug: true,
+        ..expect([0; 32])
+    };
+    assert_eq!(verify_attestation_report(&report, &allow_debug), Ok(()));
+}
@@ -0,0 +1,57 @@
+//! File support for filesystem-based [`KvStore`](crate::KvStore) implementations
+
+pub mod std_filer;
+
+#[cfg(not(feature = "sgx"))]
+pub(crate) use std::fs as untrusted_fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::prelude::v1::Vec;
+#[cfg(feature = "sgx")]
+pub(crate) use std::untrusted::fs as untrusted_fs;
+
+pub use std_filer::StdFiler;
+
+/// Simplified interface for reading and writing files.
+pub trait Filer {
+    /// Read content of `path`, if any.
+    ///
+    /// Return [`None`] if `path` doesn't exist.
+    ///
+    fn get(&self, path: impl AsRef<Path>) -> io::Result<Option<Vec<u8>>>;
+
+    /// Write `content` to `path`. Discard any existing content.
+    ///
+    /// This should be atomic: if interrupted, `path` should keep its existing content.
+    ///
+    fn put(&self, path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> io::Result<()>;
+
+    /// Delete `path`. Discard any existing content.
+    fn delete(&self, path: impl AsRef<Path>) -> io::Result<()>;
+}
+
+/// Directory for temporary files, next to the files being written.
+pub const TEMP_DIR_NAME: &str = ".tmp";
+
+/// Resolve the temporary file for atomically writing `path`, creating its directory if needed.
+///
+/// The temporary file keeps the file name of `path`.
+pub fn temp_path_for(path: &Path) -> io::Result<PathBuf> {
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Filer: no file name in {:?}", path),
+        )
+    })?;
+    let temp_dir = parent_dir(path).join(TEMP_DIR_NAME);
+    untrusted_fs::create_dir_all(&temp_dir)?;
+    Ok(temp_dir.join(file_name))
+}
+
+/// The directory containing `path`: `.` for bare file names.
+pub fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
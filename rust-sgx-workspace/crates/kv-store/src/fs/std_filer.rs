@@ -0,0 +1,57 @@
+//! Plain file support
+//!
+//! Unlike the enclave's `SgxFiler`, this does not protect file contents:
+//! use it for testing, or for stores holding no secrets.
+//! It uses only the `std::fs` API (via `sgx_tstd::untrusted` in the enclave),
+//! so it works the same inside and outside an enclave.
+
+use std::io::ErrorKind::NotFound;
+use std::io::{Result, Write};
+use std::path::Path;
+use std::prelude::v1::Vec;
+
+use super::{parent_dir, temp_path_for, untrusted_fs as fs, Filer};
+
+/// [`Filer`] for plain files.
+///
+/// Like the enclave's `SgxFiler`, this writes files atomically.
+#[derive(Copy, Clone, Default, Debug)] // core
+pub struct StdFiler {
+    /// Flush writes to disk (`fsync`) before completing them.
+    pub sync_writes: bool,
+}
+
+impl Filer for StdFiler {
+    fn get(&self, path: impl AsRef<Path>) -> Result<Option<Vec<u8>>> {
+        match fs::read(path) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(error) if error.kind() == NotFound => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    fn put(&self, path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> Result<()> {
+        let path = path.as_ref();
+        let temp_path = temp_path_for(path)?;
+
+        let mut temp_file = fs::File::create(&temp_path)?;
+        temp_file.write_all(content.as_ref())?;
+        if self.sync_writes {
+            temp_file.sync_all()?;
+        }
+        drop(temp_file);
+
+        fs::rename(&temp_path, path)?;
+        if self.sync_writes {
+            fs::File::open(parent_dir(path))?.sync_all()?;
+        }
+        Ok(())
+    }
+
+    fn delete(&self, path: impl AsRef<Path>) -> Result<()> {
+        match fs::remove_file(path) {
+            Err(error) if error.kind() == NotFound => Ok(()),
+            result => result,
+        }
+    }
+}
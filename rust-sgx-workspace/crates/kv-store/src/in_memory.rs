@@ -0,0 +1,91 @@
+//! In-memory [`KvStore`] implementation, for testing
+//!
+//! This uses only `core` and `alloc`, so it works the same inside and outside an enclave.
+
+use core::fmt;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::io;
+use std::prelude::v1::{Box, Vec};
+
+use super::{Key, KvStore, Version};
+
+/// [`KvStore`] holding cloned values in memory.
+#[derive(Clone, Debug)] // core
+pub struct InMemoryStore<V> {
+    entries: BTreeMap<Box<Key>, (Version, V)>,
+
+    /// The most recently assigned version: versions keep increasing, even across deletes.
+    last_version: Version,
+}
+
+impl<V> Default for InMemoryStore<V> {
+    fn default() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            last_version: 0,
+        }
+    }
+}
+
+impl<V> KvStore<V> for InMemoryStore<V>
+where
+    V: Clone,
+{
+    type Error = Never;
+
+    fn load_versioned(&self, key: &Key) -> Result<Option<(Version, V)>, Self::Error> {
+        Ok(self.entries.get(key).cloned())
+    }
+
+    fn compare_and_swap(
+        &mut self,
+        key: &Key,
+        expected_version: Option<Version>,
+        new_value: Option<&V>,
+    ) -> Result<bool, Self::Error> {
+        let current_version = self.entries.get(key).map(|(version, _)| *version);
+        if current_version != expected_version {
+            return Ok(false);
+        }
+        match new_value {
+            Some(value) => self.save(key, value)?,
+            None => self.delete(key)?,
+        }
+        Ok(true)
+    }
+
+    fn save(&mut self, key: &Key, value: &V) -> Result<(), Self::Error> {
+        self.last_version += 1;
+        self.entries
+            .insert(key.into(), (self.last_version, value.clone()));
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &Key) -> Result<(), Self::Error> {
+        self.entries.remove(key);
+        Ok(())
+    }
+
+    fn keys(&self) -> Result<Vec<Box<Key>>, Self::Error> {
+        Ok(self.entries.keys().cloned().collect())
+    }
+}
+
+/// Error type for stores that can't fail.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)] // core
+pub enum Never {}
+
+impl fmt::Display for Never {
+    fn fmt(&self, _: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {}
+    }
+}
+
+impl Error for Never {}
+
+impl From<Never> for io::Error {
+    fn from(never: Never) -> Self {
+        match never {}
+    }
+}
@@ -0,0 +1,139 @@
+//! Simple key-value store abstraction
+//!
+//! This builds on the host (the default), and in SGX enclaves (with the `sgx` feature),
+//! so code that is generic over [`KvStore`] can be tested on the host with [`InMemoryStore`]
+//! or [`StdFiler`](fs::StdFiler).
+//!
+//! The enclave's sealed file store is `sgx_vault_impl::ported::kv_store::fs::FsStore`.
+//!
+//! [`InMemoryStore`]: in_memory::InMemoryStore
+
+#![cfg_attr(feature = "sgx", no_std)]
+
+#[cfg(feature = "sgx")]
+#[macro_use]
+extern crate sgx_tstd as std;
+
+pub mod fs;
+pub mod in_memory;
+
+use std::prelude::v1::{Box, Vec};
+
+pub type Key = [u8];
+
+/// Version of a saved value: this changes every time a value is saved.
+pub type Version = u64;
+
+/// Iterator returned by [`KvStore::iter`].
+pub type KvIter<'a, V, E> = Box<dyn Iterator<Item = Result<(Box<Key>, V), E>> + 'a>;
+
+/// A key-value store.
+///
+/// These methods borrow key and value references,
+/// to suit cloning / serialising implementations.
+///
+pub trait KvStore<V> {
+    type Error;
+
+    /// Load the saved value for `key`, if any.
+    ///
+    /// Return [`None`] if `key` has no previous value.
+    ///
+    fn load(&self, key: &Key) -> Result<Option<V>, Self::Error> {
+        Ok(self.load_versioned(key)?.map(|(_, value)| value))
+    }
+
+    /// Like [`Self::load`], but also return the value's version, for [`Self::compare_and_swap`].
+    fn load_versioned(&self, key: &Key) -> Result<Option<(Version, V)>, Self::Error>;
+
+    /// Replace the value of `key` with `new_value` (or delete it, if [`None`]),
+    /// but only if its version is still `expected_version` ([`None`] for no value).
+    ///
+    /// Return false, and change nothing, if the version differs.
+    ///
+    fn compare_and_swap(
+        &mut self,
+        key: &Key,
+        expected_version: Option<Version>,
+        new_value: Option<&V>,
+    ) -> Result<bool, Self::Error>;
+
+    /// Save a new value for `key`.
+    ///
+    /// This will replace any existing value.
+    ///
+    fn save(&mut self, key: &Key, value: &V) -> Result<(), Self::Error>;
+
+    /// Delete the saved value for `key`.
+    fn delete(&mut self, key: &Key) -> Result<(), Self::Error>;
+
+    /// List the keys with saved values, in sorted order.
+    fn keys(&self) -> Result<Vec<Box<Key>>, Self::Error>;
+
+    /// Iterate over the saved key-value pairs, in key order.
+    ///
+    /// This lists the keys up front, and loads each value as the iterator advances.
+    /// Keys whose value disappears in the meantime are skipped.
+    ///
+    fn iter<'a>(&'a self) -> Result<KvIter<'a, V, Self::Error>, Self::Error>
+    where
+        V: 'a,
+        Self::Error: 'a,
+    {
+        let keys = self.keys()?;
+        Ok(Box::new(keys.into_iter().filter_map(
+            move |key| match self.load(&key) {
+                Ok(Some(value)) => Some(Ok((key, value))),
+                Ok(None) => None,
+                Err(err) => Some(Err(err)),
+            },
+        )))
+    }
+
+    /// Alter the value of `key`.
+    ///
+    /// This operation is a generalisation of [`Self::load`], [`Self::save`], and [`Self::delete`].
+    ///
+    /// This is atomic: if the value changes concurrently, `alter_fn` is applied again
+    /// to the new value.
+    ///
+    fn alter<F>(&mut self, key: &Key, mut alter_fn: F) -> Result<Option<V>, Self::Error>
+    where
+        F: FnMut(Option<V>) -> Option<V>,
+    {
+        loop {
+            let (version, loaded) = match self.load_versioned(key)? {
+                None => (None, None),
+                Some((version, value)) => (Some(version), Some(value)),
+            };
+            let altered: Option<V> = alter_fn(loaded);
+            if self.compare_and_swap(key, version, altered.as_ref())? {
+                return Ok(altered);
+            }
+        }
+    }
+
+    /// Mutate the value of `key`.
+    ///
+    /// This is like [`Self::alter`], but only operates on existing values.
+    fn mutate<F>(&mut self, key: &Key, mut mutate_fn: F) -> Result<Option<V>, Self::Error>
+    where
+        F: FnMut(V) -> V,
+    {
+        self.alter(key, |opt_v| opt_v.map(&mut mutate_fn))
+    }
+
+    /// Insert a value for `key`, if absent. If `key` already has a value, do nothing.
+    ///
+    /// Return the key's prior value (`None` if `value` was inserted)
+    fn try_insert(&mut self, key: &Key, value: &V) -> Result<Option<V>, Self::Error> {
+        loop {
+            if let Some((_, loaded)) = self.load_versioned(key)? {
+                return Ok(Some(loaded));
+            }
+            if self.compare_and_swap(key, None, Some(value))? {
+                return Ok(None);
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Test [`kv_store::in_memory`]
+
+use kv_store::in_memory::InMemoryStore;
+use kv_store::{Key, KvStore};
+
+#[test]
+fn in_memory_load_save_delete() {
+    let mut store = InMemoryStore::default();
+    assert_eq!(store.load(b"key").unwrap(), None);
+
+    store.save(b"key", &1).unwrap();
+    assert_eq!(store.load(b"key").unwrap(), Some(1));
+    store.save(b"key", &2).unwrap();
+    assert_eq!(store.load(b"key").unwrap(), Some(2));
+
+    store.delete(b"key").unwrap();
+    store.delete(b"key").unwrap();
+    assert_eq!(store.load(b"key").unwrap(), None);
+}
+
+#[test]
+fn in_memory_alter() {
+    let mut store = InMemoryStore::default();
+
+    assert_eq!(store.alter(b"key", |_| Some(1)).unwrap(), Some(1));
+    assert_eq!(store.alter(b"key", |n| n.map(|n| n + 1)).unwrap(), Some(2));
+    assert_eq!(store.load(b"key").unwrap(), Some(2));
+
+    assert_eq!(store.alter(b"key", |_| None).unwrap(), None);
+    assert_eq!(store.load(b"key").unwrap(), None);
+}
+
+#[test]
+fn in_memory_mutate() {
+    let mut store = InMemoryStore::default();
+
+    assert_eq!(store.mutate(b"missing", |n| n + 1).unwrap(), None);
+    assert_eq!(store.load(b"missing").unwrap(), None);
+
+    store.save(b"existing", &2).unwrap();
+    assert_eq!(store.mutate(b"existing", |n| n + 1).unwrap(), Some(3));
+    assert_eq!(store.load(b"existing").unwrap(), Some(3));
+}
+
+#[test]
+fn in_memory_try_insert() {
+    let mut store = InMemoryStore::default();
+
+    assert_eq!(store.try_insert(b"missing", &42).unwrap(), None);
+    assert_eq!(store.load(b"missing").unwrap(), Some(42));
+
+    store.save(b"existing", &5).unwrap();
+    assert_eq!(store.try_insert(b"existing", &42).unwrap(), Some(5));
+    assert_eq!(store.load(b"existing").unwrap(), Some(5));
+}
+
+#[test]
+fn in_memory_keys() {
+    let mut store = InMemoryStore::default();
+    assert_eq!(store.keys().unwrap(), keys(&[]));
+
+    store.save(b"b", &1).unwrap();
+    store.save(b"a", &2).unwrap();
+    assert_eq!(store.keys().unwrap(), keys(&["a", "b"]));
+
+    store.delete(b"a").unwrap();
+    store.delete(b"missing").unwrap();
+    assert_eq!(store.keys().unwrap(), keys(&["b"]));
+}
+
+#[test]
+fn in_memory_iter() {
+    let mut store = InMemoryStore::default();
+    store.save(b"b", &1).unwrap();
+    store.save(b"a", &2).unwrap();
+
+    let entries: Vec<(Box<Key>, i32)> = store.iter().unwrap().map(Result::unwrap).collect();
+    assert_eq!(
+        entries,
+        vec![(b"a".to_vec().into(), 2), (b"b".to_vec().into(), 1)]
+    );
+}
+
+#[test]
+fn in_memory_compare_and_swap() {
+    let mut store = InMemoryStore::default();
+    assert!(store.compare_and_swap(b"key", None, Some(&1)).unwrap());
+    assert!(!store.compare_and_swap(b"key", None, Some(&2)).unwrap());
+
+    let (version, value) = store.load_versioned(b"key").unwrap().unwrap();
+    assert_eq!(value, 1);
+    assert!(store.compare_and_swap(b"key", Some(version), None).unwrap());
+    assert!(!store
+        .compare_and_swap(b"key", Some(version), Some(&3))
+        .unwrap());
+    assert_eq!(store.load(b"key").unwrap(), None);
+
+    // Versions keep increasing across deletes.
+    store.save(b"key", &4).unwrap();
+    let (new_version, _) = store.load_versioned(b"key").unwrap().unwrap();
+    assert!(new_version > version);
+}
+
+// Helper: Owned keys, for comparison.
+fn keys(keys: &[&str]) -> Vec<Box<Key>> {
+    keys.iter().map(|key| key.as_bytes().into()).collect()
+}
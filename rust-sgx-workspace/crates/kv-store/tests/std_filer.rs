@@ -0,0 +1,72 @@
+//! Test [`kv_store::fs::StdFiler`]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use kv_store::fs::{Filer, StdFiler, TEMP_DIR_NAME};
+
+#[test]
+fn std_filer_roundtrips() {
+    let root = &TempDir::create();
+    let path = root.as_ref().join("file");
+
+    for filer in [StdFiler::default(), StdFiler { sync_writes: true }] {
+        assert_eq!(filer.get(&path).unwrap(), None);
+        filer.put(&path, b"contents").unwrap();
+        assert_eq!(filer.get(&path).unwrap(), Some(b"contents".to_vec()));
+        filer.put(&path, b"new contents").unwrap();
+        assert_eq!(filer.get(&path).unwrap(), Some(b"new contents".to_vec()));
+        filer.delete(&path).unwrap();
+        filer.delete(&path).unwrap();
+        assert_eq!(filer.get(&path).unwrap(), None);
+    }
+}
+
+/// [`StdFiler`] writes plain files.
+#[test]
+fn std_filer_is_plain() {
+    let root = &TempDir::create();
+    let path = root.as_ref().join("file");
+
+    StdFiler::default().put(&path, b"contents").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"contents");
+}
+
+/// [`StdFiler::put`] writes via a temporary file, which it doesn't leave behind.
+#[test]
+fn std_filer_put_leaves_no_temp_file() {
+    let root = &TempDir::create();
+    let path = root.as_ref().join("file");
+
+    StdFiler::default().put(&path, b"contents").unwrap();
+    assert!(!root.as_ref().join(TEMP_DIR_NAME).join("file").exists());
+}
+
+/// Temporary directory, removed on drop.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn create() -> Self {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "kv-store-test-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
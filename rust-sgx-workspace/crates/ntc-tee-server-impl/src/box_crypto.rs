@@ -0,0 +1,61 @@
+//! NaCl box key pairs: data providers seal [`crate::data_package`]s to the public key.
+
+use std::vec;
+use std::vec::Vec;
+
+use zeroize::{Zeroize, Zeroizing};
+
+pub type PublicKey = [u8; 32];
+pub type Nonce = [u8; 24];
+
+/// C NaCl Box API: Zero padding for plaintext.
+const CRYPTO_BOX_ZEROBYTES: usize = 32;
+
+/// C NaCl Box API: Zero padding for ciphertext.
+const CRYPTO_BOX_BOXZEROBYTES: usize = 16;
+
+/// An X25519 key pair, for opening NaCl boxes (X25519, XSalsa20-Poly1305).
+pub struct BoxKeyPair {
+    public_key: PublicKey,
+    private_key: Zeroizing<[u8; 32]>,
+}
+
+impl BoxKeyPair {
+    /// Derive the key pair from `seed`, which is zeroized.
+    pub fn from_seed(seed: &mut [u8; 32]) -> Self {
+        let mut public_key = [0_u8; 32];
+        let mut private_key = Zeroizing::new([0_u8; 32]);
+        sodalite::box_keypair_seed(&mut public_key, &mut private_key, seed);
+        seed.zeroize();
+
+        Self {
+            public_key,
+            private_key,
+        }
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        self.public_key
+    }
+
+    /// Open a NaCl box from `sender_public_key`, or `None` if it does not authenticate.
+    pub fn open(
+        &self,
+        ciphertext: &[u8],
+        nonce: &Nonce,
+        sender_public_key: &PublicKey,
+    ) -> Option<Zeroizing<Vec<u8>>> {
+        // See the vault enclave's SodaBoxCrypto::decrypt_message for the box padding.
+        let padded_ciphertext = &[&[0_u8; CRYPTO_BOX_BOXZEROBYTES] as &[u8], ciphertext].concat();
+        let mut message = Zeroizing::new(vec![0_u8; padded_ciphertext.len()]);
+        sodalite::box_open(
+            &mut message,
+            padded_ciphertext,
+            nonce,
+            sender_public_key,
+            &self.private_key,
+        )
+        .ok()?;
+        Some(Zeroizing::new(message[CRYPTO_BOX_ZEROBYTES..].to_vec()))
+    }
+}
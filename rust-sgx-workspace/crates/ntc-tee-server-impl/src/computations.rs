@@ -0,0 +1,265 @@
+//! Built-in computations over the loaded [`crate::data_package`].
+//!
+//! Each computation aggregates one field of the records, and returns only the aggregate.
+//! Records without the field (or with `null`) are skipped: each result counts the records
+//! that contributed to it.
+//!
+//! # Disclosure model
+//!
+//! Results are aggregates, but an aggregate over one or a few records can disclose
+//! those records: the mean of one salary is that salary, and a histogram bin with a count
+//! of one places that record's value within the bin. To limit this, small groups of records
+//! are never reported:
+//!
+//! - [`Computation::Sum`], [`Computation::Mean`], [`Computation::Histogram`], and
+//!   [`Computation::Count`] of a field fail with [`ComputationError::TooFewRecords`]
+//!   if fewer than [`MIN_AGGREGATE_COUNT`] (but more than zero) records have the field.
+//! - Each histogram cell (bin, `below`, or `above`) counting fewer than [`MIN_AGGREGATE_COUNT`]
+//!   (but more than zero) records is suppressed, and reported as `null`.
+//!   If only one cell would be suppressed, the smallest other non-zero cell is suppressed too,
+//!   so the suppressed count can't be recovered by subtracting the others from `count`.
+//!
+//! This is a threshold on each result, not differential privacy: it does not stop
+//! a client from combining the results of several computations (for example, histograms with
+//! shifted edges, or counts of overlapping fields) to narrow down individual records.
+//! Only parties trusted with such aggregates should be able to run computations.
+
+use std::string::String;
+use std::vec;
+use std::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::data_package::DataPackage;
+
+/// The fewest records that an aggregate or histogram cell may count, other than none.
+///
+/// See the [disclosure model](self#disclosure-model).
+pub const MIN_AGGREGATE_COUNT: u64 = 5;
+
+/// A computation request, as JSON.
+///
+/// For example: `{"computation": "mean", "field": "salary"}`
+#[derive(Clone, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+#[serde(tag = "computation", rename_all = "snake_case")]
+pub enum Computation {
+    /// Count the records, or only those with `field`.
+    Count {
+        #[serde(default)]
+        field: Option<String>,
+    },
+
+    /// Sum the numbers in `field`.
+    Sum { field: String },
+
+    /// Average the numbers in `field`.
+    Mean { field: String },
+
+    /// Count the numbers in `field` that fall into each bin between consecutive `edges`.
+    ///
+    /// Each bin includes its lower edge, and excludes its upper edge, except for the last bin,
+    /// which includes both.
+    Histogram { field: String, edges: Vec<f64> },
+}
+
+/// The result of a [`Computation`], as JSON.
+#[derive(Clone, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+#[serde(tag = "computation", rename_all = "snake_case")]
+pub enum ComputationResult {
+    Count {
+        count: u64,
+    },
+    Sum {
+        count: u64,
+        sum: f64,
+    },
+    /// `mean` is `None` if no records contributed.
+    Mean {
+        count: u64,
+        mean: Option<f64>,
+    },
+    /// `counts` has one entry per bin. Numbers outside the edges count as `below` or `above`.
+    ///
+    /// Suppressed cells are `None`: see the [disclosure model](self#disclosure-model).
+    Histogram {
+        count: u64,
+        edges: Vec<f64>,
+        counts: Vec<Option<u64>>,
+        below: Option<u64>,
+        above: Option<u64>,
+    },
+}
+
+#[derive(Clone, PartialEq, Debug, Error)]
+pub enum ComputationError {
+    #[error("invalid computation request: {0}")]
+    InvalidRequest(String),
+
+    #[error("no data package is loaded")]
+    NoPackageLoaded,
+
+    #[error("record {index} has a non-number value in field {field:?}")]
+    NotANumber { index: usize, field: String },
+
+    #[error("too few records have field {field:?} to report an aggregate (minimum {minimum})")]
+    TooFewRecords { field: String, minimum: u64 },
+
+    #[error("failed to encode computation result: {0}")]
+    EncodeResult(String),
+}
+
+/// Implementation for the enclave's `run_computation` ECALL, over the loaded `package`, if any.
+///
+/// This processes an exchange of the following:
+///
+/// Request: [`Computation`] (JSON)
+///
+/// Response: [`ComputationResult`] (JSON)
+pub fn run_computation_json(
+    request_json: &[u8],
+    package: Option<&DataPackage>,
+) -> Result<Vec<u8>, ComputationError> {
+    let computation: Computation = serde_json::from_slice(request_json)
+        .map_err(|err| ComputationError::InvalidRequest(format!("{}", err)))?;
+    let package = package.ok_or(ComputationError::NoPackageLoaded)?;
+    let result = run_computation(&computation, package)?;
+    serde_json::to_vec(&result).map_err(|err| ComputationError::EncodeResult(format!("{}", err)))
+}
+
+/// Run `computation` over the records of `package`.
+pub fn run_computation(
+    computation: &Computation,
+    package: &DataPackage,
+) -> Result<ComputationResult, ComputationError> {
+    let records = &package.data;
+    match computation {
+        Computation::Count { field: None } => Ok(ComputationResult::Count {
+            count: records.len() as u64,
+        }),
+        Computation::Count { field: Some(field) } => Ok(ComputationResult::Count {
+            count: check_aggregate_count(field, field_values(records, field).count())?,
+        }),
+        Computation::Sum { field } => {
+            let numbers = field_numbers(records, field)?;
+            check_aggregate_count(field, numbers.len())?;
+            Ok(ComputationResult::Sum {
+                count: numbers.len() as u64,
+                sum: numbers.iter().sum(),
+            })
+        }
+        Computation::Mean { field } => {
+            let numbers = field_numbers(records, field)?;
+            check_aggregate_count(field, numbers.len())?;
+            let mean = match numbers.len() {
+                0 => None,
+                count => Some(numbers.iter().sum::<f64>() / count as f64),
+            };
+            Ok(ComputationResult::Mean {
+                count: numbers.len() as u64,
+                mean,
+            })
+        }
+        Computation::Histogram { field, edges } => {
+            let increasing = edges.windows(2).all(|pair| pair[0] < pair[1]);
+            if edges.len() < 2 || !increasing || edges.iter().any(|edge| !edge.is_finite()) {
+                return Err(ComputationError::InvalidRequest(
+                    "histogram edges must be at least two increasing finite numbers".into(),
+                ));
+            }
+            let numbers = field_numbers(records, field)?;
+            check_aggregate_count(field, numbers.len())?;
+            let (mut counts, mut below, mut above) = (vec![0_u64; edges.len() - 1], 0, 0);
+            let last_edge = edges[edges.len() - 1];
+            for &number in &numbers {
+                if number < edges[0] {
+                    below += 1;
+                } else if last_edge < number {
+                    above += 1;
+                } else {
+                    // The last bin includes its upper edge.
+                    let bin = edges[1..]
+                        .iter()
+                        .position(|&upper| number < upper)
+                        .unwrap_or(counts.len() - 1);
+                    counts[bin] += 1;
+                }
+            }
+            // Suppress the cells together, so they complement each other.
+            counts.extend([below, above]);
+            let mut cells = suppress_small_cells(&counts);
+            let above = cells.pop().flatten();
+            let below = cells.pop().flatten();
+            Ok(ComputationResult::Histogram {
+                count: numbers.len() as u64,
+                edges: edges.clone(),
+                counts: cells,
+                below,
+                above,
+            })
+        }
+    }
+}
+
+/// Check that an aggregate over `count` records of `field` may be reported.
+fn check_aggregate_count(field: &str, count: usize) -> Result<u64, ComputationError> {
+    match count as u64 {
+        count if 0 < count && count < MIN_AGGREGATE_COUNT => Err(ComputationError::TooFewRecords {
+            field: field.into(),
+            minimum: MIN_AGGREGATE_COUNT,
+        }),
+        count => Ok(count),
+    }
+}
+
+/// Suppress (as `None`) the cells counting fewer than [`MIN_AGGREGATE_COUNT`] records,
+/// other than empty cells.
+///
+/// If that suppresses only one cell, also suppress the smallest other non-zero cell,
+/// so the total doesn't reveal the suppressed count.
+fn suppress_small_cells(counts: &[u64]) -> Vec<Option<u64>> {
+    let is_small = |count: u64| 0 < count && count < MIN_AGGREGATE_COUNT;
+    let mut cells: Vec<Option<u64>> = counts
+        .iter()
+        .map(|&count| if is_small(count) { None } else { Some(count) })
+        .collect();
+    if counts.iter().filter(|&&count| is_small(count)).count() == 1 {
+        let complement = cells
+            .iter()
+            .enumerate()
+            .filter_map(|(index, cell)| Some((index, (*cell)?)))
+            .filter(|&(_, count)| 0 < count)
+            .min_by_key(|&(_, count)| count);
+        if let Some((index, _)) = complement {
+            cells[index] = None;
+        }
+    }
+    cells
+}
+
+/// The non-null values of `field`, with their record index.
+fn field_values<'a>(
+    records: &'a [Value],
+    field: &'a str,
+) -> impl Iterator<Item = (usize, &'a Value)> + 'a {
+    records
+        .iter()
+        .enumerate()
+        .filter_map(move |(index, record)| Some((index, record.get(field)?)))
+        .filter(|(_, value)| !value.is_null())
+}
+
+/// The numbers in `field`: fail if any value is not a number.
+fn field_numbers(records: &[Value], field: &str) -> Result<Vec<f64>, ComputationError> {
+    field_values(records, field)
+        .map(|(index, value)| {
+            value.as_f64().ok_or_else(|| ComputationError::NotANumber {
+                index,
+                field: field.into(),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,67 @@
+//! Data packages: a dataset and its JSON Schema, sealed to the enclave.
+//!
+//! The enclave holds the last package it loaded, for [`crate::computations`] to run over.
+//! It doesn't return the records themselves, but aggregates over them can still disclose
+//! individual records: see [`crate::computations`]' disclosure model.
+
+use std::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::box_crypto::{BoxKeyPair, Nonce, PublicKey};
+use crate::json_schema::{check_schema, validate, SchemaError, ValidationError};
+
+/// The unsealed contents of a sealed data package, as JSON.
+#[derive(Clone, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct DataPackage {
+    /// The JSON Schema that each record must match (see [`crate::json_schema`]).
+    pub data_schema: Value,
+
+    /// The records.
+    pub data: Vec<Value>,
+}
+
+#[derive(Debug, Error)]
+pub enum DataPackageError {
+    #[error("failed to unseal data package: it is not sealed to this enclave, or was altered")]
+    Unseal,
+
+    #[error("invalid data package JSON: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+
+    #[error("invalid data schema: {0}")]
+    InvalidSchema(#[from] SchemaError),
+
+    #[error("record {index} does not match the data schema: {error}")]
+    InvalidRecord {
+        index: usize,
+        error: ValidationError,
+    },
+}
+
+/// Open a sealed data package's `ciphertext` with `key_pair`, and decode it.
+pub fn unseal_data_package(
+    key_pair: &BoxKeyPair,
+    ciphertext: &[u8],
+    nonce: &Nonce,
+    sender_public_key: &PublicKey,
+) -> Result<DataPackage, DataPackageError> {
+    let package_json = key_pair
+        .open(ciphertext, nonce, sender_public_key)
+        .ok_or(DataPackageError::Unseal)?;
+    let package = serde_json::from_slice(&package_json)?;
+    Ok(package)
+}
+
+/// Check the package's schema, and validate each of its records against it.
+pub fn validate_data_package(package: &DataPackage) -> Result<(), DataPackageError> {
+    check_schema(&package.data_schema)?;
+    for (index, record) in package.data.iter().enumerate() {
+        validate(&package.data_schema, record)
+            .map_err(|error| DataPackageError::InvalidRecord { index, error })?;
+    }
+    Ok(())
+}
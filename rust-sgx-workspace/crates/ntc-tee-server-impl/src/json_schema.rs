@@ -0,0 +1,259 @@
+//! Validate data records against their JSON Schema, inside the enclave.
+//!
+//! This supports the subset of JSON Schema (Draft 7) that data schemas use:
+//!
+//! * `type` (a type name, or an array of them)
+//! * `properties`, `required`, `additionalProperties`
+//! * `items` (a single schema for all items)
+//! * `enum`, `const`
+//! * `minimum`, `maximum`, `minLength`, `maxLength`, `minItems`, `maxItems`
+//!
+//! Annotations like `title` and `description` are ignored, and boolean schemas are supported.
+//! [`check_schema`] rejects any other keyword, rather than ignoring it: a schema should not
+//! appear to constrain data that it does not.
+
+use std::string::{String, ToString};
+
+use serde_json::Value;
+use thiserror::Error;
+
+/// Keywords that do not constrain data.
+const ANNOTATIONS: &[&str] = &[
+    "$schema",
+    "$id",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+];
+
+const TYPE_NAMES: &[&str] = &[
+    "null", "boolean", "object", "array", "number", "integer", "string",
+];
+
+/// [`check_schema`] rejected a schema.
+#[derive(Clone, Eq, PartialEq, Debug, Error)]
+#[error("{path}: {message}")]
+pub struct SchemaError {
+    /// JSON Pointer to the offending schema.
+    pub path: String,
+    pub message: String,
+}
+
+/// [`validate`] rejected a value.
+#[derive(Clone, Eq, PartialEq, Debug, Error)]
+#[error("{path}: {message}")]
+pub struct ValidationError {
+    /// JSON Pointer to the offending value.
+    pub path: String,
+    pub message: String,
+}
+
+/// Check that `schema` is a schema that [`validate`] supports.
+pub fn check_schema(schema: &Value) -> Result<(), SchemaError> {
+    check_schema_at(schema, "")
+}
+
+fn check_schema_at(schema: &Value, path: &str) -> Result<(), SchemaError> {
+    let error = |message: String| SchemaError {
+        path: path.to_string(),
+        message,
+    };
+    let keywords = match schema {
+        Value::Bool(_) => return Ok(()),
+        Value::Object(keywords) => keywords,
+        _ => return Err(error("schema must be an object or boolean".to_string())),
+    };
+    for (keyword, value) in keywords {
+        let keyword_path = &format!("{}/{}", path, keyword);
+        match keyword.as_str() {
+            "type" => {
+                let is_type_name = |value: &Value| {
+                    value
+                        .as_str()
+                        .map_or(false, |name| TYPE_NAMES.contains(&name))
+                };
+                let valid = match value {
+                    Value::Array(names) => names.iter().all(is_type_name),
+                    name => is_type_name(name),
+                };
+                if !valid {
+                    return Err(error(format!("invalid type: {}", value)));
+                }
+            }
+            "properties" => {
+                let properties = value
+                    .as_object()
+                    .ok_or_else(|| error("properties must be an object".to_string()))?;
+                for (name, property_schema) in properties {
+                    check_schema_at(property_schema, &format!("{}/{}", keyword_path, name))?;
+                }
+            }
+            "required" => {
+                let valid = value
+                    .as_array()
+                    .map_or(false, |names| names.iter().all(Value::is_string));
+                if !valid {
+                    return Err(error("required must be an array of strings".to_string()));
+                }
+            }
+            "additionalProperties" | "items" => check_schema_at(value, keyword_path)?,
+            "enum" => {
+                if !value.is_array() {
+                    return Err(error("enum must be an array".to_string()));
+                }
+            }
+            "const" => {}
+            "minimum" | "maximum" => {
+                if !value.is_number() {
+                    return Err(error(format!("{} must be a number", keyword)));
+                }
+            }
+            "minLength" | "maxLength" | "minItems" | "maxItems" => {
+                if !value.is_u64() {
+                    return Err(error(format!("{} must be a non-negative integer", keyword)));
+                }
+            }
+            annotation if ANNOTATIONS.contains(&annotation) => {}
+            unsupported => return Err(error(format!("unsupported keyword: {}", unsupported))),
+        }
+    }
+    Ok(())
+}
+
+/// Validate `value` against `schema`, which [`check_schema`] accepted.
+pub fn validate(schema: &Value, value: &Value) -> Result<(), ValidationError> {
+    validate_at(schema, value, "")
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str) -> Result<(), ValidationError> {
+    let error = |message: String| ValidationError {
+        path: path.to_string(),
+        message,
+    };
+    let keywords = match schema {
+        Value::Bool(true) => return Ok(()),
+        Value::Bool(false) => return Err(error("no value is allowed".to_string())),
+        Value::Object(keywords) => keywords,
+        _ => return Err(error("invalid schema".to_string())),
+    };
+    for (keyword, constraint) in keywords {
+        match keyword.as_str() {
+            "type" => {
+                let matches = match constraint {
+                    Value::Array(names) => names.iter().any(|name| has_type(value, name)),
+                    name => has_type(value, name),
+                };
+                if !matches {
+                    return Err(error(format!("expected type {}", constraint)));
+                }
+            }
+            "properties" => {
+                if let (Some(properties), Some(object)) =
+                    (constraint.as_object(), value.as_object())
+                {
+                    for (name, property_schema) in properties {
+                        if let Some(property) = object.get(name) {
+                            validate_at(property_schema, property, &format!("{}/{}", path, name))?;
+                        }
+                    }
+                }
+            }
+            "required" => {
+                if let (Some(names), Some(object)) = (constraint.as_array(), value.as_object()) {
+                    for name in names.iter().filter_map(Value::as_str) {
+                        if !object.contains_key(name) {
+                            return Err(error(format!("missing required property: {}", name)));
+                        }
+                    }
+                }
+            }
+            "additionalProperties" => {
+                if let Some(object) = value.as_object() {
+                    let properties = keywords.get("properties").and_then(Value::as_object);
+                    for (name, property) in object {
+                        if !properties.map_or(false, |properties| properties.contains_key(name)) {
+                            validate_at(constraint, property, &format!("{}/{}", path, name))?;
+                        }
+                    }
+                }
+            }
+            "items" => {
+                if let Some(items) = value.as_array() {
+                    for (index, item) in items.iter().enumerate() {
+                        validate_at(constraint, item, &format!("{}/{}", path, index))?;
+                    }
+                }
+            }
+            "enum" => {
+                let allowed = constraint
+                    .as_array()
+                    .map_or(false, |allowed| allowed.contains(value));
+                if !allowed {
+                    return Err(error(format!("not one of {}", constraint)));
+                }
+            }
+            "const" => {
+                if value != constraint {
+                    return Err(error(format!("expected {}", constraint)));
+                }
+            }
+            "minimum" | "maximum" => {
+                if let (Some(bound), Some(number)) = (constraint.as_f64(), value.as_f64()) {
+                    let within = match keyword.as_str() {
+                        "minimum" => bound <= number,
+                        _ => number <= bound,
+                    };
+                    if !within {
+                        return Err(error(format!("{} is {}", keyword, bound)));
+                    }
+                }
+            }
+            "minLength" | "maxLength" => {
+                if let (Some(bound), Some(string)) = (constraint.as_u64(), value.as_str()) {
+                    check_length(keyword, bound, string.chars().count()).map_err(error)?;
+                }
+            }
+            "minItems" | "maxItems" => {
+                if let (Some(bound), Some(items)) = (constraint.as_u64(), value.as_array()) {
+                    check_length(keyword, bound, items.len()).map_err(error)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, name: &Value) -> bool {
+    match name.as_str() {
+        Some("null") => value.is_null(),
+        Some("boolean") => value.is_boolean(),
+        Some("object") => value.is_object(),
+        Some("array") => value.is_array(),
+        Some("number") => value.is_number(),
+        Some("integer") => {
+            value.is_i64() || value.is_u64() || value.as_f64().map_or(false, is_integral)
+        }
+        Some("string") => value.is_string(),
+        _ => false,
+    }
+}
+
+fn is_integral(number: f64) -> bool {
+    number.is_finite() && number.fract() == 0.0
+}
+
+fn check_length(keyword: &str, bound: u64, length: usize) -> Result<(), String> {
+    let length = length as u64;
+    let within = match keyword {
+        "minLength" | "minItems" => bound <= length,
+        _ => length <= bound,
+    };
+    if within {
+        Ok(())
+    } else {
+        Err(format!("{} is {}", keyword, bound))
+    }
+}
@@ -0,0 +1,26 @@
+//! Execution enclave implementation: data packages and computations
+//!
+//! This builds on the host (the `std` feature, by default), for testing,
+//! and in the `ntc-tee-server` enclave (with `default-features = false, features = ["sgx"]`).
+//! The enclave adds its key derivation, the held package, and the ECALLs.
+
+#![cfg_attr(feature = "sgx", no_std)]
+
+#[cfg(all(feature = "std", feature = "sgx"))]
+compile_error!("enable only one of the \"std\" and \"sgx\" features");
+
+#[cfg(feature = "sgx")]
+#[macro_use]
+extern crate sgx_tstd as std;
+
+#[cfg(feature = "sgx")]
+extern crate serde_json_sgx as serde_json;
+#[cfg(feature = "sgx")]
+extern crate serde_sgx as serde;
+#[cfg(feature = "sgx")]
+extern crate thiserror_sgx as thiserror;
+
+pub mod box_crypto;
+pub mod computations;
+pub mod data_package;
+pub mod json_schema;
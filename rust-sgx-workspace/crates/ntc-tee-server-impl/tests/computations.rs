@@ -0,0 +1,211 @@
+//! Test [`ntc_tee_server_impl::computations`]
+
+use ntc_tee_server_impl::computations::{
+    run_computation,
+    run_computation_json,
+    Computation,
+    ComputationError,
+    ComputationResult,
+    MIN_AGGREGATE_COUNT,
+};
+use ntc_tee_server_impl::data_package::DataPackage;
+use serde_json::{json, Value};
+
+#[test]
+fn count_records() {
+    let package = &package_of(vec![json!({"x": 1}), json!({}), json!({ "x": null })]);
+    assert_eq!(
+        run_computation(&Computation::Count { field: None }, package),
+        Ok(ComputationResult::Count { count: 3 })
+    );
+    assert_eq!(
+        run_computation(&field_count("missing"), package),
+        Ok(ComputationResult::Count { count: 0 })
+    );
+}
+
+#[test]
+fn sum_and_mean() {
+    let package = &numbers_package(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+    assert_eq!(
+        run_computation(&Computation::Sum { field: "x".into() }, package),
+        Ok(ComputationResult::Sum {
+            count: 5,
+            sum: 15.0
+        })
+    );
+    assert_eq!(
+        run_computation(&Computation::Mean { field: "x".into() }, package),
+        Ok(ComputationResult::Mean {
+            count: 5,
+            mean: Some(3.0)
+        })
+    );
+}
+
+#[test]
+fn mean_empty() {
+    let package = &package_of(vec![json!({"y": 1}), json!({ "x": null })]);
+    assert_eq!(
+        run_computation(&Computation::Mean { field: "x".into() }, package),
+        Ok(ComputationResult::Mean {
+            count: 0,
+            mean: None
+        })
+    );
+}
+
+#[test]
+fn not_a_number() {
+    let mut records = numbers_package(&[1.0, 2.0, 3.0, 4.0, 5.0]).data;
+    records.insert(2, json!({"x": "three"}));
+    let package = &package_of(records);
+    assert_eq!(
+        run_computation(&Computation::Sum { field: "x".into() }, package),
+        Err(ComputationError::NotANumber {
+            index: 2,
+            field: "x".into()
+        })
+    );
+}
+
+/// Aggregates over fewer than [`MIN_AGGREGATE_COUNT`] records are refused.
+#[test]
+fn too_few_records() {
+    let package = &numbers_package(&[1.0, 2.0, 3.0, 4.0]);
+    let too_few = Err(ComputationError::TooFewRecords {
+        field: "x".into(),
+        minimum: MIN_AGGREGATE_COUNT,
+    });
+    for computation in [
+        field_count("x"),
+        Computation::Sum { field: "x".into() },
+        Computation::Mean { field: "x".into() },
+        Computation::Histogram {
+            field: "x".into(),
+            edges: vec![0.0, 10.0],
+        },
+    ] {
+        assert_eq!(run_computation(&computation, package), too_few);
+    }
+    assert_eq!(
+        run_computation(&Computation::Count { field: None }, package),
+        Ok(ComputationResult::Count { count: 4 })
+    );
+}
+
+/// Each bin includes its lower edge, and the last bin also includes its upper edge.
+#[test]
+fn histogram_edge_inclusion() {
+    let numbers = &[[-1.0; 5], [0.0; 5], [1.0; 5], [2.0; 5], [3.0; 5]].concat();
+    let package = &numbers_package(numbers);
+    assert_eq!(
+        run_computation(&histogram(&[0.0, 1.0, 2.0]), package),
+        Ok(ComputationResult::Histogram {
+            count: 25,
+            edges: vec![0.0, 1.0, 2.0],
+            counts: vec![Some(5), Some(10)],
+            below: Some(5),
+            above: Some(5),
+        })
+    );
+}
+
+/// Small cells are suppressed, with a complementary cell if only one is small.
+#[test]
+fn histogram_suppresses_small_cells() {
+    let numbers = &[vec![0.5; 6], vec![1.5; 2], vec![2.5; 7]].concat();
+    let package = &numbers_package(numbers);
+    assert_eq!(
+        run_computation(&histogram(&[0.0, 1.0, 2.0, 3.0]), package),
+        Ok(ComputationResult::Histogram {
+            count: 15,
+            edges: vec![0.0, 1.0, 2.0, 3.0],
+            counts: vec![None, None, Some(7)],
+            below: Some(0),
+            above: Some(0),
+        })
+    );
+
+    let numbers = &[vec![-1.0; 1], vec![0.5; 6], vec![1.5; 2], vec![2.5; 7]].concat();
+    let package = &numbers_package(numbers);
+    assert_eq!(
+        run_computation(&histogram(&[0.0, 1.0, 2.0, 3.0]), package),
+        Ok(ComputationResult::Histogram {
+            count: 16,
+            edges: vec![0.0, 1.0, 2.0, 3.0],
+            counts: vec![Some(6), None, Some(7)],
+            below: None,
+            above: Some(0),
+        })
+    );
+}
+
+#[test]
+fn histogram_invalid_edges() {
+    let package = &numbers_package(&[1.0; 5]);
+    for edges in [
+        &[][..],
+        &[1.0],
+        &[1.0, 1.0],
+        &[2.0, 1.0],
+        &[0.0, f64::INFINITY],
+    ] {
+        assert_eq!(
+            run_computation(&histogram(edges), package),
+            Err(ComputationError::InvalidRequest(
+                "histogram edges must be at least two increasing finite numbers".into()
+            ))
+        );
+    }
+}
+
+#[test]
+fn run_computation_json_works() {
+    let package = &numbers_package(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+    let result = run_computation_json(br#"{"computation": "sum", "field": "x"}"#, Some(package));
+    let result: Value = serde_json::from_slice(&result.unwrap()).unwrap();
+    assert_eq!(
+        result,
+        json!({"computation": "sum", "count": 5, "sum": 15.0})
+    );
+}
+
+#[test]
+fn run_computation_json_errors() {
+    let package = &numbers_package(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+    assert!(matches!(
+        run_computation_json(br#"{"computation": "median", "field": "x"}"#, Some(package)),
+        Err(ComputationError::InvalidRequest(_))
+    ));
+    assert_eq!(
+        run_computation_json(br#"{"computation": "count"}"#, None),
+        Err(ComputationError::NoPackageLoaded)
+    );
+}
+
+// Helpers:
+
+fn package_of(data: Vec<Value>) -> DataPackage {
+    DataPackage {
+        data_schema: json!(true),
+        data,
+    }
+}
+
+fn numbers_package(numbers: &[f64]) -> DataPackage {
+    package_of(numbers.iter().map(|x| json!({ "x": x })).collect())
+}
+
+fn field_count(field: &str) -> Computation {
+    Computation::Count {
+        field: Some(field.into()),
+    }
+}
+
+fn histogram(edges: &[f64]) -> Computation {
+    Computation::Histogram {
+        field: "x".into(),
+        edges: edges.to_vec(),
+    }
+}
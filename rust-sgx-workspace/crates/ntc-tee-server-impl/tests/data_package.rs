@@ -0,0 +1,121 @@
+//! Test [`ntc_tee_server_impl::data_package`]
+
+use ntc_tee_server_impl::box_crypto::{BoxKeyPair, Nonce, PublicKey};
+use ntc_tee_server_impl::data_package::{
+    unseal_data_package,
+    validate_data_package,
+    DataPackage,
+    DataPackageError,
+};
+use ntc_tee_server_impl::json_schema::SchemaError;
+use serde_json::json;
+
+const NONCE: Nonce = [7; 24];
+
+#[test]
+fn unseal_works() {
+    let (enclave, (sender_public_key, sender_private_key)) = (&key_pair(1), sender_keys(2));
+    let package_json = json!({
+        "data_schema": {"type": "object"},
+        "data": [{"x": 1}, {"x": 2}],
+    });
+    let ciphertext = &seal(&package_json.to_string(), enclave, &sender_private_key);
+
+    let package = unseal_data_package(enclave, ciphertext, &NONCE, &sender_public_key).unwrap();
+    assert_eq!(package.data_schema, json!({"type": "object"}));
+    assert_eq!(package.data, vec![json!({"x": 1}), json!({"x": 2})]);
+}
+
+#[test]
+fn unseal_wrong_key() {
+    let (enclave, other) = (&key_pair(1), &key_pair(3));
+    let (sender_public_key, sender_private_key) = sender_keys(2);
+    let ciphertext = &seal("{}", other, &sender_private_key);
+
+    let result = unseal_data_package(enclave, ciphertext, &NONCE, &sender_public_key);
+    assert!(matches!(result, Err(DataPackageError::Unseal)));
+}
+
+#[test]
+fn unseal_tampered() {
+    let (enclave, (sender_public_key, sender_private_key)) = (&key_pair(1), sender_keys(2));
+    let ciphertext = &mut seal("{}", enclave, &sender_private_key);
+    ciphertext[0] ^= 1;
+
+    let result = unseal_data_package(enclave, ciphertext, &NONCE, &sender_public_key);
+    assert!(matches!(result, Err(DataPackageError::Unseal)));
+
+    let result = unseal_data_package(enclave, &[], &NONCE, &sender_public_key);
+    assert!(matches!(result, Err(DataPackageError::Unseal)));
+}
+
+#[test]
+fn unseal_invalid_json() {
+    let (enclave, (sender_public_key, sender_private_key)) = (&key_pair(1), sender_keys(2));
+    for package_json in ["not json", r#"{"data": []}"#] {
+        let ciphertext = &seal(package_json, enclave, &sender_private_key);
+        let result = unseal_data_package(enclave, ciphertext, &NONCE, &sender_public_key);
+        assert!(matches!(result, Err(DataPackageError::InvalidJson(_))));
+    }
+}
+
+#[test]
+fn validate_invalid_schema() {
+    let package = &DataPackage {
+        data_schema: json!({"type": "object", "patternProperties": {}}),
+        data: vec![],
+    };
+    match validate_data_package(package) {
+        Err(DataPackageError::InvalidSchema(error)) => assert_eq!(
+            error,
+            SchemaError {
+                path: "".into(),
+                message: "unsupported keyword: patternProperties".into(),
+            }
+        ),
+        otherwise => panic!("{:?}", otherwise),
+    }
+}
+
+#[test]
+fn validate_invalid_record() {
+    let package = &DataPackage {
+        data_schema: json!({"type": "object", "required": ["x"]}),
+        data: vec![json!({"x": 1}), json!({"y": 2})],
+    };
+    match validate_data_package(package) {
+        Err(err @ DataPackageError::InvalidRecord { index: 1, .. }) => assert_eq!(
+            err.to_string(),
+            "record 1 does not match the data schema: : missing required property: x"
+        ),
+        otherwise => panic!("{:?}", otherwise),
+    }
+}
+
+// Helpers:
+
+fn key_pair(seed_byte: u8) -> BoxKeyPair {
+    BoxKeyPair::from_seed(&mut [seed_byte; 32])
+}
+
+/// A data provider's key pair: `(public_key, private_key)`.
+fn sender_keys(seed_byte: u8) -> (PublicKey, [u8; 32]) {
+    let (mut public_key, mut private_key) = ([0; 32], [0; 32]);
+    sodalite::box_keypair_seed(&mut public_key, &mut private_key, &[seed_byte; 32]);
+    (public_key, private_key)
+}
+
+/// Seal `message` to `recipient`, like a data provider.
+fn seal(message: &str, recipient: &BoxKeyPair, sender_private_key: &[u8; 32]) -> Vec<u8> {
+    let padded_message = &[&[0_u8; 32], message.as_bytes()].concat();
+    let mut ciphertext = vec![0_u8; padded_message.len()];
+    sodalite::box_(
+        &mut ciphertext,
+        padded_message,
+        &NONCE,
+        &recipient.public_key(),
+        sender_private_key,
+    )
+    .unwrap();
+    ciphertext[16..].to_vec()
+}
@@ -0,0 +1,174 @@
+//! Test [`ntc_tee_server_impl::json_schema`]
+
+use ntc_tee_server_impl::json_schema::{check_schema, validate, SchemaError, ValidationError};
+use serde_json::{json, Value};
+
+#[test]
+fn check_schema_unsupported_keywords() {
+    for (schema, path, message) in [
+        (json!({"pattern": "^a"}), "", "unsupported keyword: pattern"),
+        (json!({"anyOf": []}), "", "unsupported keyword: anyOf"),
+        (
+            json!({"properties": {"name": {"format": "email"}}}),
+            "/properties/name",
+            "unsupported keyword: format",
+        ),
+        (
+            json!({"items": {"$ref": "#"}}),
+            "/items",
+            "unsupported keyword: $ref",
+        ),
+    ] {
+        assert_eq!(check_schema(&schema), Err(schema_error(path, message)));
+    }
+}
+
+#[test]
+fn check_schema_malformed() {
+    assert_eq!(
+        check_schema(&json!(42)),
+        Err(schema_error("", "schema must be an object or boolean"))
+    );
+    assert_eq!(
+        check_schema(&json!({"type": "integer!"})),
+        Err(schema_error("", "invalid type: \"integer!\""))
+    );
+    assert_eq!(
+        check_schema(&json!({"minLength": -1})),
+        Err(schema_error("", "minLength must be a non-negative integer"))
+    );
+}
+
+#[test]
+fn check_schema_annotations() {
+    let schema = json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "Record",
+        "description": "A record",
+        "type": "object",
+        "properties": {"name": {"type": "string", "examples": ["Alice"]}},
+    });
+    assert_eq!(check_schema(&schema), Ok(()));
+}
+
+#[test]
+fn validate_integer() {
+    let schema = &json!({"type": "integer"});
+    assert_eq!(validate(schema, &json!(1)), Ok(()));
+    assert_eq!(validate(schema, &json!(-1)), Ok(()));
+    assert_eq!(validate(schema, &json!(1.0)), Ok(()));
+    assert_eq!(
+        validate(schema, &json!(1.5)),
+        Err(validation_error("", "expected type \"integer\""))
+    );
+    assert_eq!(
+        validate(schema, &json!("1")),
+        Err(validation_error("", "expected type \"integer\""))
+    );
+}
+
+#[test]
+fn validate_additional_properties() {
+    let schema = &json!({
+        "properties": {"name": {"type": "string"}},
+        "additionalProperties": {"type": "number"},
+    });
+    assert_eq!(validate(schema, &json!({"name": "a", "age": 1})), Ok(()));
+    assert_eq!(
+        validate(schema, &json!({"name": "a", "nickname": "b"})),
+        Err(validation_error("/nickname", "expected type \"number\""))
+    );
+
+    let closed = &json!({"properties": {"name": {}}, "additionalProperties": false});
+    assert_eq!(validate(closed, &json!({"name": "a"})), Ok(()));
+    assert_eq!(
+        validate(closed, &json!({"name": "a", "extra": 1})),
+        Err(validation_error("/extra", "no value is allowed"))
+    );
+}
+
+#[test]
+fn validate_boolean_schemas() {
+    assert_eq!(check_schema(&json!(true)), Ok(()));
+    assert_eq!(check_schema(&json!(false)), Ok(()));
+    assert_eq!(validate(&json!(true), &json!({"any": "thing"})), Ok(()));
+    assert_eq!(
+        validate(&json!(false), &Value::Null),
+        Err(validation_error("", "no value is allowed"))
+    );
+    assert_eq!(
+        validate(&json!({"items": false}), &json!([1])),
+        Err(validation_error("/0", "no value is allowed"))
+    );
+    assert_eq!(validate(&json!({"items": false}), &json!([])), Ok(()));
+}
+
+#[test]
+fn validate_constraints() {
+    let schema = &json!({
+        "type": "object",
+        "required": ["name", "age"],
+        "properties": {
+            "name": {"type": "string", "minLength": 1, "maxLength": 3},
+            "age": {"type": "number", "minimum": 0, "maximum": 150},
+            "tags": {"type": "array", "items": {"enum": ["a", "b"]}, "maxItems": 2},
+            "kind": {"const": "person"},
+        },
+    });
+    check_schema(schema).unwrap();
+    assert_eq!(
+        validate(
+            schema,
+            &json!({"name": "Al", "age": 30, "tags": ["a"], "kind": "person"})
+        ),
+        Ok(())
+    );
+
+    for (value, path, message) in [
+        (json!({"name": "Al"}), "", "missing required property: age"),
+        (json!({"name": "", "age": 1}), "/name", "minLength is 1"),
+        (
+            json!({"name": "Alice", "age": 1}),
+            "/name",
+            "maxLength is 3",
+        ),
+        (json!({"name": "Al", "age": -1}), "/age", "minimum is 0"),
+        (json!({"name": "Al", "age": 151}), "/age", "maximum is 150"),
+        (
+            json!({"name": "Al", "age": 1, "tags": ["c"]}),
+            "/tags/0",
+            "not one of [\"a\",\"b\"]",
+        ),
+        (
+            json!({"name": "Al", "age": 1, "tags": ["a", "a", "b"]}),
+            "/tags",
+            "maxItems is 2",
+        ),
+        (
+            json!({"name": "Al", "age": 1, "kind": "robot"}),
+            "/kind",
+            "expected \"person\"",
+        ),
+    ] {
+        assert_eq!(
+            validate(schema, &value),
+            Err(validation_error(path, message))
+        );
+    }
+}
+
+// Helpers:
+
+fn schema_error(path: &str, message: &str) -> SchemaError {
+    SchemaError {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
+
+fn validation_error(path: &str, message: &str) -> ValidationError {
+    ValidationError {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
@@ -1,9 +1,13 @@
+use std::slice;
+
 use secrecy::Zeroize;
-use sgx_types::{sgx_report_t, sgx_status_t, sgx_target_info_t};
+use sgx_types::{sgx_report_t, sgx_status_t, sgx_target_info_t, size_t, uint8_t};
 
-use crate::ported::attestation::create_report_impl;
+use crate::ported::attestation::{create_report_with_user_data, create_tls_report};
 
-/// ECALL wrapper for [`create_report_impl`].
+/// ECALL wrapper for [`create_report_with_user_data`].
+///
+/// `user_data` goes into the second half of the report data: pass zeroes if unused.
 ///
 /// # EDL
 ///
@@ -24,14 +28,71 @@ use crate::ported::attestation::create_report_impl;
 #[no_mangle]
 pub unsafe extern "C" fn enclave_create_report(
     p_qe3_target: *const sgx_target_info_t,
+    user_data: *const [u8; 32],
+    p_report: *mut sgx_report_t,
+    enclave_pubkey: *mut [u8; 32],
+) -> sgx_status_t {
+    if p_qe3_target.is_null()
+        || user_data.is_null()
+        || enclave_pubkey.is_null()
+        || p_report.is_null()
+    {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    let qe_target_info = &unsafe { *p_qe3_target };
+    let user_data = &unsafe { *user_data };
+    let (key, report) = match create_report_with_user_data(qe_target_info, user_data) {
+        Ok(res) => res,
+        Err(x) => {
+            unsafe {
+                (*enclave_pubkey).zeroize();
+            }
+            return x;
+        }
+    };
+
+    unsafe {
+        *p_report = report;
+        (*enclave_pubkey).copy_from_slice(&key);
+    }
+    sgx_status_t::SGX_SUCCESS
+}
+
+/// ECALL wrapper for [`create_tls_report`].
+///
+/// # EDL
+///
+/// ```edl
+/// include "sgx_report.h"
+/// ```
+///
+/// # Errors
+///
+/// * [`sgx_status_t::SGX_ERROR_INVALID_PARAMETER`] - null pointer passed
+///
+/// # Safety
+///
+/// Expects to be called from SGX bridge, with validated input.
+///
+#[no_mangle]
+pub unsafe extern "C" fn enclave_create_tls_report(
+    p_qe3_target: *const sgx_target_info_t,
+    tls_public_key_buffer: *const uint8_t,
+    tls_public_key_size: size_t,
     p_report: *mut sgx_report_t,
     enclave_pubkey: *mut [u8; 32],
 ) -> sgx_status_t {
-    if p_qe3_target.is_null() || enclave_pubkey.is_null() || p_report.is_null() {
+    if p_qe3_target.is_null()
+        || tls_public_key_buffer.is_null()
+        || enclave_pubkey.is_null()
+        || p_report.is_null()
+    {
         return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
     }
     let qe_target_info = &unsafe { *p_qe3_target };
-    let (key, report) = match create_report_impl(qe_target_info) {
+    let tls_public_key =
+        unsafe { slice::from_raw_parts(tls_public_key_buffer, tls_public_key_size) };
+    let (key, report) = match create_tls_report(qe_target_info, tls_public_key) {
         Ok(res) => res,
         Err(x) => {
             unsafe {
@@ -0,0 +1,36 @@
+use sgx_tse::rsgx_self_target;
+use sgx_types::{sgx_status_t, sgx_target_info_t};
+
+/// ECALL: Get this enclave's target info, for other enclaves to create reports for it.
+///
+/// See [`crate::vault_operations::handover`].
+///
+/// # EDL
+///
+/// ```edl
+/// include "sgx_report.h"
+/// ```
+///
+/// # Errors
+///
+/// * [`sgx_status_t::SGX_ERROR_INVALID_PARAMETER`] - null pointer passed
+///
+/// # Safety
+///
+/// Expects to be called from SGX bridge, with validated input.
+///
+#[no_mangle]
+pub unsafe extern "C" fn enclave_target_info(
+    p_target_info: *mut sgx_target_info_t,
+) -> sgx_status_t {
+    if p_target_info.is_null() {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    match rsgx_self_target() {
+        Ok(target_info) => {
+            unsafe { *p_target_info = target_info };
+            sgx_status_t::SGX_SUCCESS
+        }
+        Err(status) => status,
+    }
+}
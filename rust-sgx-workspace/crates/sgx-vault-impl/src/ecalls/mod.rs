@@ -1,4 +1,9 @@
 //! ECALL wrappers: these bridge from SGX to our safe Rust implementations.
 
 pub mod enclave_create_report;
+pub mod enclave_target_info;
+pub mod session_handshake;
+pub mod vault_handover;
 pub mod vault_operation;
+pub mod vault_store_init;
+pub mod vault_store_migrate;
@@ -0,0 +1,82 @@
+use std::prelude::v1::ToString;
+use std::{ptr, slice};
+
+use sgx_types::{sgx_status_t, size_t, uint8_t};
+
+use crate::ecall_helpers::catch_unwind_message;
+use crate::vault_operations::sessions::{session_handshake_impl, SessionHandshakeError};
+
+/// ECALL wrapper for [`session_handshake_impl`].
+///
+/// Unlike [`crate::ecalls::vault_operation::vault_operation`], this holds no response:
+/// if the sealed accept exceeds the buffer, the session is still held, but the host should
+/// retry with a larger buffer, starting a new session.
+///
+/// # Errors
+///
+/// * [`sgx_status_t::SGX_ERROR_INVALID_PARAMETER`] - received null pointers,
+///   or a hello that can't be decoded
+///
+/// * [`sgx_status_t::SGX_ERROR_BUSY`] - too many recent sessions to start another:
+///   see [`SessionHandshakeError::TooManySessions`]
+///
+/// * [`sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT`] - sealed accept exceeds buffer capacity:
+///   `sealed_accept_used` receives its size
+///
+/// * [`sgx_status_t::SGX_ERROR_UNEXPECTED`] - starting the session failed
+///   (see [`SessionHandshakeError::Failed`]), or unwinding panic occurred
+///
+/// # Safety
+///
+/// Expects to be called from SGX bridge, with validated input.
+#[no_mangle]
+pub unsafe extern "C" fn session_handshake(
+    hello_buffer: *const uint8_t,
+    hello_size: size_t,
+    sealed_accept_buffer: *mut uint8_t,
+    sealed_accept_capacity: size_t,
+    sealed_accept_used: *mut size_t,
+) -> sgx_status_t {
+    if hello_buffer.is_null() || sealed_accept_buffer.is_null() || sealed_accept_used.is_null() {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+
+    let hello = unsafe { slice::from_raw_parts(hello_buffer, hello_size) };
+
+    let sealed_accept = match catch_unwind_message(|| session_handshake_impl(hello)) {
+        Ok(Ok(sealed_accept)) => sealed_accept,
+        Ok(Err(SessionHandshakeError::InvalidRequest(invalid_request))) => {
+            println!("session_handshake: {}", invalid_request);
+            return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+        }
+        Ok(Err(too_many @ SessionHandshakeError::TooManySessions)) => {
+            println!("session_handshake: {}", too_many);
+            return sgx_status_t::SGX_ERROR_BUSY;
+        }
+        Ok(Err(failed @ SessionHandshakeError::Failed(_))) => {
+            println!("session_handshake: {}", failed);
+            return sgx_status_t::SGX_ERROR_UNEXPECTED;
+        }
+        Err(message) => {
+            println!(
+                "PANIC in session_handshake_impl ECALL: {}",
+                message.unwrap_or_else(|| ("XXX").to_string())
+            );
+            return sgx_status_t::SGX_ERROR_UNEXPECTED;
+        }
+    };
+
+    unsafe { *sealed_accept_used = sealed_accept.len() };
+    if sealed_accept.len() <= sealed_accept_capacity {
+        unsafe {
+            ptr::copy_nonoverlapping(
+                sealed_accept.as_ptr(),
+                sealed_accept_buffer,
+                sealed_accept.len(),
+            );
+        }
+        sgx_status_t::SGX_SUCCESS
+    } else {
+        sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT
+    }
+}
@@ -0,0 +1,208 @@
+use std::ffi::CStr;
+use std::prelude::v1::ToString;
+use std::{ptr, slice};
+
+use sgx_types::{c_char, sgx_report_t, sgx_status_t, sgx_target_info_t, size_t, uint8_t};
+
+use crate::ecall_helpers::catch_unwind_message;
+use crate::ported::kv_store::fs::decode_from_fs_safe;
+use crate::vault_operations::handover::{
+    handover_begin,
+    handover_export,
+    handover_import,
+    HandoverError,
+};
+
+/// ECALL wrapper for [`handover_begin`].
+///
+/// # EDL
+///
+/// ```edl
+/// include "sgx_report.h"
+/// ```
+///
+/// # Errors
+///
+/// * [`sgx_status_t::SGX_ERROR_INVALID_PARAMETER`] - null pointer passed
+///
+/// * [`sgx_status_t::SGX_ERROR_UNEXPECTED`] - handover failed, or unwinding panic occurred
+///
+/// # Safety
+///
+/// Expects to be called from SGX bridge, with validated input.
+#[no_mangle]
+pub unsafe extern "C" fn vault_handover_begin(
+    p_predecessor_target: *const sgx_target_info_t,
+    p_report: *mut sgx_report_t,
+    successor_public_key: *mut [u8; 32],
+) -> sgx_status_t {
+    if p_predecessor_target.is_null() || p_report.is_null() || successor_public_key.is_null() {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    let predecessor_target = &unsafe { *p_predecessor_target };
+
+    match catch_unwind_message(|| handover_begin(predecessor_target)) {
+        Ok(Ok((public_key, report))) => {
+            unsafe {
+                *p_report = report;
+                *successor_public_key = public_key;
+            }
+            sgx_status_t::SGX_SUCCESS
+        }
+        Ok(Err(err)) => handover_error_status("vault_handover_begin", err),
+        Err(message) => {
+            println!(
+                "PANIC in vault_handover_begin ECALL: {}",
+                message.unwrap_or_else(|| ("XXX").to_string())
+            );
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+/// ECALL wrapper for [`handover_export`].
+///
+/// `record_name` is the record's file name in the vault store directory.
+///
+/// # EDL
+///
+/// ```edl
+/// include "sgx_report.h"
+/// ```
+///
+/// # Errors
+///
+/// * [`sgx_status_t::SGX_ERROR_INVALID_PARAMETER`] - null pointer or invalid record name passed,
+///   or record not found
+///
+/// * [`sgx_status_t::SGX_ERROR_MAC_MISMATCH`] - successor report failed verification
+///
+/// * [`sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT`] - sealed record exceeds buffer capacity
+///
+/// * [`sgx_status_t::SGX_ERROR_UNEXPECTED`] - handover failed, or unwinding panic occurred
+///
+/// # Safety
+///
+/// Expects to be called from SGX bridge, with validated input.
+#[no_mangle]
+pub unsafe extern "C" fn vault_handover_export(
+    p_successor_report: *const sgx_report_t,
+    successor_public_key: *const [u8; 32],
+    record_name: *const c_char,
+    p_report: *mut sgx_report_t,
+    sealed_record_buffer: *mut uint8_t,
+    sealed_record_capacity: size_t,
+    sealed_record_used: *mut size_t,
+) -> sgx_status_t {
+    if p_successor_report.is_null()
+        || successor_public_key.is_null()
+        || record_name.is_null()
+        || p_report.is_null()
+        || sealed_record_buffer.is_null()
+        || sealed_record_used.is_null()
+    {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    let successor_report = &unsafe { *p_successor_report };
+    let successor_public_key = &unsafe { *successor_public_key };
+    let key = match unsafe { CStr::from_ptr(record_name) }
+        .to_str()
+        .map_err(|err| err.to_string())
+        .and_then(decode_from_fs_safe)
+    {
+        Ok(key) => key,
+        Err(message) => {
+            println!("vault_handover_export: invalid record name: {}", message);
+            return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+        }
+    };
+
+    let (sealed_record, report) = match catch_unwind_message(|| {
+        handover_export(successor_report, successor_public_key, &key)
+    }) {
+        Ok(Ok(exported)) => exported,
+        Ok(Err(err)) => return handover_error_status("vault_handover_export", err),
+        Err(message) => {
+            println!(
+                "PANIC in vault_handover_export ECALL: {}",
+                message.unwrap_or_else(|| ("XXX").to_string())
+            );
+            return sgx_status_t::SGX_ERROR_UNEXPECTED;
+        }
+    };
+
+    // Check capacity, and copy the sealed record out.
+    if sealed_record.len() <= sealed_record_capacity {
+        unsafe {
+            ptr::copy_nonoverlapping(
+                sealed_record.as_ptr(),
+                sealed_record_buffer,
+                sealed_record.len(),
+            );
+            *sealed_record_used = sealed_record.len();
+            *p_report = report;
+        }
+        sgx_status_t::SGX_SUCCESS
+    } else {
+        sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT
+    }
+}
+
+/// ECALL wrapper for [`handover_import`].
+///
+/// # EDL
+///
+/// ```edl
+/// include "sgx_report.h"
+/// ```
+///
+/// # Errors
+///
+/// * [`sgx_status_t::SGX_ERROR_INVALID_PARAMETER`] - null pointer passed
+///
+/// * [`sgx_status_t::SGX_ERROR_MAC_MISMATCH`] - predecessor report failed verification
+///
+/// * [`sgx_status_t::SGX_ERROR_INVALID_STATE`] - no handover started, or the record
+///   already exists
+///
+/// * [`sgx_status_t::SGX_ERROR_UNEXPECTED`] - handover failed, or unwinding panic occurred
+///
+/// # Safety
+///
+/// Expects to be called from SGX bridge, with validated input.
+#[no_mangle]
+pub unsafe extern "C" fn vault_handover_import(
+    p_predecessor_report: *const sgx_report_t,
+    sealed_record_buffer: *const uint8_t,
+    sealed_record_size: size_t,
+) -> sgx_status_t {
+    if p_predecessor_report.is_null() || sealed_record_buffer.is_null() {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    let predecessor_report = &unsafe { *p_predecessor_report };
+    let sealed_record = unsafe { slice::from_raw_parts(sealed_record_buffer, sealed_record_size) };
+
+    match catch_unwind_message(|| handover_import(predecessor_report, sealed_record)) {
+        Ok(Ok(())) => sgx_status_t::SGX_SUCCESS,
+        Ok(Err(err)) => handover_error_status("vault_handover_import", err),
+        Err(message) => {
+            println!(
+                "PANIC in vault_handover_import ECALL: {}",
+                message.unwrap_or_else(|| ("XXX").to_string())
+            );
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
+
+fn handover_error_status(ecall: &str, err: HandoverError) -> sgx_status_t {
+    println!("{}: handover failed: {}", ecall, err);
+    match err {
+        HandoverError::InvalidReport(status) | HandoverError::Sgx(status) => status,
+        HandoverError::NotFound => sgx_status_t::SGX_ERROR_INVALID_PARAMETER,
+        HandoverError::NotStarted | HandoverError::AlreadyExists => {
+            sgx_status_t::SGX_ERROR_INVALID_STATE
+        }
+        _ => sgx_status_t::SGX_ERROR_UNEXPECTED,
+    }
+}
@@ -4,15 +4,32 @@ use std::{ptr, slice};
 use sgx_types::{sgx_status_t, size_t, uint8_t};
 
 use crate::ecall_helpers::catch_unwind_message;
-use crate::vault_operations::dispatch::vault_operation_impl;
+use crate::vault_operations::dispatch::{vault_operation_impl, InvalidRequest};
+use crate::vault_operations::response_cache::{
+    cache_response,
+    take_cached_response,
+    CachedResponse,
+    RequestId,
+};
 
 /// ECALL wrapper for [`vault_operation_impl`].
 ///
+/// `request_id` identifies the request to [`vault_operation_fetch_response`]:
+/// the host must not reuse it for other requests while its response may be held.
+///
+/// `operation_kind` and `operation_outcome` receive the request's
+/// [`OperationLabel`](crate::vault_operations::dispatch::OperationLabel), as the declaration
+/// indexes of its [`AuditOperation`](crate::schema::entities::AuditOperation) and
+/// [`AuditOutcome`](crate::schema::entities::AuditOutcome), unless the request is invalid.
+///
 /// # Errors
 ///
-/// * [`sgx_status_t::SGX_ERROR_INVALID_PARAMETER`] - received null or empty request or response buffers
+/// * [`sgx_status_t::SGX_ERROR_INVALID_PARAMETER`] - received null or empty request or response buffers,
+///   or a request that can't be unsealed (see [`InvalidRequest`])
 ///
-/// * [`sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT`] - response exceeds buffer capacity
+/// * [`sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT`] - response exceeds buffer capacity:
+///   `sealed_response_used` receives the response's size, and the response is held
+///   for [`vault_operation_fetch_response`]
 ///
 /// * [`sgx_status_t::SGX_ERROR_UNEXPECTED`] - unwinding panic occurred
 ///
@@ -20,13 +37,21 @@ use crate::vault_operations::dispatch::vault_operation_impl;
 /// Expects to be called from SGX bridge, with validated input.
 #[no_mangle]
 pub unsafe extern "C" fn vault_operation(
+    request_id: RequestId,
     sealed_request_buffer: *const uint8_t,
     sealed_request_size: size_t,
     sealed_response_buffer: *mut uint8_t,
     sealed_response_capacity: size_t,
     sealed_response_used: *mut size_t,
+    operation_kind: *mut uint8_t,
+    operation_outcome: *mut uint8_t,
 ) -> sgx_status_t {
-    if sealed_request_buffer.is_null() || sealed_response_buffer.is_null() {
+    if sealed_request_buffer.is_null()
+        || sealed_response_buffer.is_null()
+        || sealed_response_used.is_null()
+        || operation_kind.is_null()
+        || operation_outcome.is_null()
+    {
         return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
     }
 
@@ -34,16 +59,25 @@ pub unsafe extern "C" fn vault_operation(
     let sealed_request =
         unsafe { slice::from_raw_parts(sealed_request_buffer, sealed_request_size) };
 
-    let sealed_response = match catch_unwind_message(|| vault_operation_impl(sealed_request)) {
-        Ok(ok) => ok,
-        Err(message) => {
-            println!(
-                "PANIC in vault_operation_impl ECALL: {}",
-                message.unwrap_or_else(|| ("XXX").to_string())
-            );
-            return sgx_status_t::SGX_ERROR_UNEXPECTED;
-        }
-    };
+    let (sealed_response, label) =
+        match catch_unwind_message(|| vault_operation_impl(sealed_request)) {
+            Ok(Ok(sealed_response_and_label)) => sealed_response_and_label,
+            Ok(Err(invalid_request)) => {
+                println!("vault_operation: {}", invalid_request);
+                return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+            }
+            Err(message) => {
+                println!(
+                    "PANIC in vault_operation_impl ECALL: {}",
+                    message.unwrap_or_else(|| ("XXX").to_string())
+                );
+                return sgx_status_t::SGX_ERROR_UNEXPECTED;
+            }
+        };
+    unsafe {
+        *operation_kind = label.operation as uint8_t;
+        *operation_outcome = label.outcome as uint8_t;
+    }
 
     // Check capacity, and copy the response buffer out.
     if sealed_response.len() <= sealed_response_capacity {
@@ -57,6 +91,59 @@ pub unsafe extern "C" fn vault_operation(
         }
         sgx_status_t::SGX_SUCCESS
     } else {
+        // The operation already took effect: hold its response instead of discarding it.
+        unsafe { *sealed_response_used = sealed_response.len() };
+        cache_response(request_id, sealed_response);
         sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT
     }
 }
+
+/// ECALL: Fetch the response that [`vault_operation`] held for `request_id`.
+///
+/// # Errors
+///
+/// * [`sgx_status_t::SGX_ERROR_INVALID_PARAMETER`] - null pointer passed,
+///   or no response held for `request_id` (already fetched, or evicted)
+///
+/// * [`sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT`] - response exceeds buffer capacity:
+///   `sealed_response_used` receives the response's size, and the response remains held
+///
+/// # Safety
+///
+/// Expects to be called from SGX bridge, with validated input.
+#[no_mangle]
+pub unsafe extern "C" fn vault_operation_fetch_response(
+    request_id: RequestId,
+    sealed_response_buffer: *mut uint8_t,
+    sealed_response_capacity: size_t,
+    sealed_response_used: *mut size_t,
+) -> sgx_status_t {
+    if sealed_response_buffer.is_null() || sealed_response_used.is_null() {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+
+    match take_cached_response(request_id, sealed_response_capacity) {
+        CachedResponse::Missing => {
+            println!(
+                "vault_operation_fetch_response: no response held for request {}",
+                request_id
+            );
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+        }
+        CachedResponse::TooLarge(size) => {
+            unsafe { *sealed_response_used = size };
+            sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT
+        }
+        CachedResponse::Taken(sealed_response) => {
+            unsafe {
+                ptr::copy_nonoverlapping(
+                    sealed_response.as_ptr(),
+                    sealed_response_buffer,
+                    sealed_response.len(),
+                );
+                *sealed_response_used = sealed_response.len();
+            }
+            sgx_status_t::SGX_SUCCESS
+        }
+    }
+}
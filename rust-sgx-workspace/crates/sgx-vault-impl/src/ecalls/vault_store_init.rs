@@ -0,0 +1,53 @@
+use std::ffi::CStr;
+use std::path::Path;
+use std::prelude::v1::ToString;
+
+use sgx_types::{c_char, sgx_status_t};
+
+use crate::ecall_helpers::catch_unwind_message;
+use crate::vault_operations::store::init_vault_store;
+
+/// ECALL wrapper for [`init_vault_store`].
+///
+/// `store_dir` is the vault store directory, as seen by the host.
+///
+/// # Errors
+///
+/// * [`sgx_status_t::SGX_ERROR_INVALID_PARAMETER`] - null pointer or invalid directory passed
+///
+/// * [`sgx_status_t::SGX_ERROR_UNEXPECTED`] - unwinding panic occurred
+///
+/// # Safety
+///
+/// Expects to be called from SGX bridge, with validated input.
+#[no_mangle]
+pub unsafe extern "C" fn vault_store_init(store_dir: *const c_char) -> sgx_status_t {
+    if store_dir.is_null() {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    let store_dir = match unsafe { CStr::from_ptr(store_dir) }.to_str() {
+        Ok(store_dir) => Path::new(store_dir),
+        Err(err) => {
+            println!("vault_store_init: invalid store directory: {}", err);
+            return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+        }
+    };
+
+    match catch_unwind_message(|| init_vault_store(store_dir)) {
+        Ok(Ok(())) => sgx_status_t::SGX_SUCCESS,
+        Ok(Err(err)) => {
+            println!(
+                "vault_store_init: failed to initialise {:?}: {}",
+                store_dir, err
+            );
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+        }
+        Err(message) => {
+            println!(
+                "PANIC in vault_store_init ECALL: {}",
+                message.unwrap_or_else(|| ("XXX").to_string())
+            );
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::ffi::CStr;
+use std::prelude::v1::ToString;
+
+use sgx_types::{c_char, sgx_status_t, uint8_t};
+
+use crate::ecall_helpers::catch_unwind_message;
+use crate::ported::kv_store::fs::decode_from_fs_safe;
+use crate::vault_operations::migration::migrate_vault_record;
+
+/// ECALL wrapper for [`migrate_vault_record`].
+///
+/// `record_name` is the record's file name in the vault store directory.
+/// On success, `migrated` is set to 1 if the record was migrated,
+/// or 0 if it was not found or already current.
+///
+/// # Errors
+///
+/// * [`sgx_status_t::SGX_ERROR_INVALID_PARAMETER`] - null pointer or invalid record name passed
+///
+/// * [`sgx_status_t::SGX_ERROR_UNEXPECTED`] - migration failed, or unwinding panic occurred
+///
+/// # Safety
+///
+/// Expects to be called from SGX bridge, with validated input.
+#[no_mangle]
+pub unsafe extern "C" fn vault_store_migrate(
+    record_name: *const c_char,
+    migrated: *mut uint8_t,
+) -> sgx_status_t {
+    if record_name.is_null() || migrated.is_null() {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    let key = match unsafe { CStr::from_ptr(record_name) }
+        .to_str()
+        .map_err(|err| err.to_string())
+        .and_then(decode_from_fs_safe)
+    {
+        Ok(key) => key,
+        Err(message) => {
+            println!("vault_store_migrate: invalid record name: {}", message);
+            return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+        }
+    };
+
+    match catch_unwind_message(|| migrate_vault_record(&key)) {
+        Ok(Ok(was_migrated)) => {
+            unsafe { *migrated = was_migrated.into() };
+            sgx_status_t::SGX_SUCCESS
+        }
+        Ok(Err(err)) => {
+            println!("vault_store_migrate: migration failed: {}", err);
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+        Err(message) => {
+            println!(
+                "PANIC in vault_store_migrate ECALL: {}",
+                message.unwrap_or_else(|| ("XXX").to_string())
+            );
+            sgx_status_t::SGX_ERROR_UNEXPECTED
+        }
+    }
+}
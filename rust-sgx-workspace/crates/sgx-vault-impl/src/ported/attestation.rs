@@ -1,21 +1,67 @@
+use std::prelude::v1::Vec;
+
 use sgx_tcrypto::rsgx_sha256_slice;
 use sgx_tse::rsgx_create_report;
-use sgx_types::{sgx_report_data_t, sgx_report_t, sgx_target_info_t, SgxResult};
+use sgx_types::{sgx_report_data_t, sgx_report_t, sgx_status_t, sgx_target_info_t, SgxResult};
 
-use crate::ported::crypto::{PublicKey, SodaBoxCrypto};
+use crate::ported::crypto::{CryptoError, PublicKey, SodaBoxCrypto};
 
 /// XXX see `rtc_tenclave::enclave::create_report_impl`
 pub fn create_report_impl(
     qe_target_info: &sgx_target_info_t,
 ) -> SgxResult<(PublicKey, sgx_report_t)> {
-    let crypto = SodaBoxCrypto::new();
-    let pubkey = crypto.get_pubkey();
+    create_report_with_user_data(qe_target_info, &[0; 32])
+}
+
+/// Like [`create_report_impl`], but bind `user_data` into the report.
+///
+/// The report data holds `sha256(pubkey)` in its first 32 bytes, and `user_data` in the rest.
+pub fn create_report_with_user_data(
+    qe_target_info: &sgx_target_info_t,
+    user_data: &[u8; 32],
+) -> SgxResult<(PublicKey, sgx_report_t)> {
+    let pubkey = enclave_public_key()?;
 
     let pubkey_hash = rsgx_sha256_slice(&pubkey)?;
 
     let mut p_data = sgx_report_data_t::default();
     p_data.d[0..32].copy_from_slice(&pubkey_hash);
+    p_data.d[32..64].copy_from_slice(user_data);
+
+    let report = rsgx_create_report(qe_target_info, &p_data)?;
+    Ok((pubkey, report))
+}
+
+/// Domain separation for [`create_tls_report`].
+///
+/// This must match `http_service_impl::tls::RA_TLS_LABEL`.
+pub const RA_TLS_LABEL: &[u8] = b"NTC-RA-TLS";
+
+/// Create a report that binds `tls_public_key`, for an RA-TLS certificate.
+///
+/// The report data holds `sha256(RA_TLS_LABEL || pubkey || tls_public_key)` in its first
+/// 32 bytes, and zeroes in the rest. Reports from [`create_report_with_user_data`] always
+/// hold `sha256(pubkey)` there instead, so no choice of their user data can pass for this.
+pub fn create_tls_report(
+    qe_target_info: &sgx_target_info_t,
+    tls_public_key: &[u8],
+) -> SgxResult<(PublicKey, sgx_report_t)> {
+    let pubkey = enclave_public_key()?;
+
+    let binding_input: Vec<u8> = [RA_TLS_LABEL, &pubkey, tls_public_key].concat();
+    let binding = rsgx_sha256_slice(&binding_input)?;
+
+    let mut p_data = sgx_report_data_t::default();
+    p_data.d[0..32].copy_from_slice(&binding);
 
     let report = rsgx_create_report(qe_target_info, &p_data)?;
     Ok((pubkey, report))
 }
+
+fn enclave_public_key() -> SgxResult<PublicKey> {
+    let crypto = SodaBoxCrypto::new().map_err(|err| match err {
+        CryptoError::KeyDerivation(status) => status,
+        _ => sgx_status_t::SGX_ERROR_UNEXPECTED,
+    })?;
+    Ok(crypto.get_pubkey())
+}
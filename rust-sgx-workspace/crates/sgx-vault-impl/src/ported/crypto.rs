@@ -4,8 +4,11 @@ use std::boxed::Box;
 use std::vec;
 use std::vec::Vec;
 
+use chacha20poly1305::aead::{Aead, NewAead};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
 use rand::{Rng, RngCore};
 use secrecy::{ExposeSecret, Secret, Zeroize};
+use serde::{Deserialize, Serialize};
 use sgx_tse::{rsgx_get_key, rsgx_self_report};
 use sgx_types::*;
 use thiserror::Error;
@@ -18,16 +21,39 @@ pub struct EncryptedMessage {
 }
 
 /// XXX see `rtc_types::CryptoError`
-#[derive(Copy, Clone, PartialEq, Eq, Ord, PartialOrd, Debug, Error)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Error)]
 pub enum CryptoError {
+    /// The RNG failed, with this error code (or 0 if it has none).
     #[error("Crypto rng error: {}", .0)]
     Rand(u32),
-    #[error("Unknown crypto error")]
-    Unknown,
+
+    /// [`NonceStrategy::Counter`] ran out of counter values.
+    #[error("Crypto nonces exhausted")]
+    NoncesExhausted,
+
+    /// The ciphertext does not authenticate: it was tampered with, or sealed with another key,
+    /// nonce, or [`BoxAlgorithm`].
+    #[error("Crypto authentication failed")]
+    AuthenticationFailed,
+
+    /// Key material is not the expected length.
+    #[error("Crypto key length: expected {expected} bytes, got {actual}")]
+    KeyLength { expected: usize, actual: usize },
+
+    /// The enclave could not derive its key (see [`get_enclave_key`]).
+    #[error("Crypto key derivation failed: {:?}", .0)]
+    KeyDerivation(sgx_status_t),
+
+    /// The cipher could not encrypt the message.
+    #[error("Crypto encryption failed")]
+    Encryption,
 }
 
 pub(crate) type PublicKey = [u8; 32];
 type PrivateKey = Secret<[u8; 32]>;
+
+/// A symmetric key, like a session key.
+pub type SecretKey = Secret<[u8; 32]>;
 pub(crate) type Nonce = [u8; 24];
 
 // FIXME: sodalite should expose these padding constants.
@@ -41,30 +67,88 @@ pub const CRYPTO_BOX_BOXZEROBYTES: usize = 16;
 
 pub type SecretBytes = Secret<Box<[u8]>>;
 
-pub struct SodaBoxCrypto {
-    public_key: PublicKey,
-    private_key: PrivateKey,
-    rng: Box<dyn RngCore>,
+/// The authenticated encryption of a [`SodaBoxCrypto`] box.
+///
+/// Both use the same X25519 key exchange: they differ only in the cipher.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub enum BoxAlgorithm {
+    /// NaCl box: XSalsa20-Poly1305.
+    XSalsa20Poly1305,
+
+    /// XChaCha20-Poly1305, keyed with a hash of [`SodaBoxCrypto::shared_key`],
+    /// so that it never shares a key with XSalsa20-Poly1305.
+    ///
+    /// The ciphertext is followed by its 16-byte tag.
+    XChaCha20Poly1305,
 }
 
-impl Default for SodaBoxCrypto {
+impl Default for BoxAlgorithm {
     fn default() -> Self {
-        SodaBoxCrypto::new()
+        Self::XSalsa20Poly1305
     }
 }
 
+/// How [`SodaBoxCrypto`] picks the nonce of each message it encrypts.
+///
+/// The enclave's own sealing (like [`crate::schema::sealing::seal_from_enclave`]) always uses
+/// [`NonceStrategy::Random`]: the other strategies are library-only, for callers that build
+/// their own [`SodaBoxCrypto`] with [`SodaBoxCrypto::with_nonce_strategy`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum NonceStrategy {
+    /// A random nonce for each message (the default).
+    ///
+    /// Nonces are 24 bytes, so random nonces do not collide in practice,
+    /// as long as the RNG is sound.
+    Random,
+
+    /// A random 16-byte prefix, drawn once, followed by a 64-bit big-endian message counter.
+    ///
+    /// This never repeats a nonce for the same [`SodaBoxCrypto`], and draws from the RNG only once.
+    Counter,
+
+    /// A nonce derived from the private key, the receiver, and the message.
+    ///
+    /// This needs no RNG or state. It repeats a nonce only for the same message to the same
+    /// receiver, which reveals that the message was repeated, but nothing else.
+    Synthetic,
+}
+
+impl Default for NonceStrategy {
+    fn default() -> Self {
+        Self::Random
+    }
+}
+
+pub struct SodaBoxCrypto {
+    public_key: PublicKey,
+    private_key: PrivateKey,
+    rng: Box<dyn RngCore>,
+    nonce_strategy: NonceStrategy,
+    /// [`NonceStrategy::Counter`]: the nonce prefix, and the next counter value.
+    nonce_counter: Option<([u8; 16], u64)>,
+}
+
 impl SodaBoxCrypto {
-    pub fn new() -> Self {
-        let enclave_key = get_enclave_key();
+    /// The enclave's long-term key pair, derived from [`get_enclave_key`].
+    pub fn new() -> Result<Self, CryptoError> {
+        let enclave_key = get_enclave_key()?;
         let mut seed = [0_u8; 32];
         let (left, right) = seed.split_at_mut(16);
 
-        // This should never panic since the file_key is size 16
-        // TODO: Guard against the panics
-        left.copy_from_slice(enclave_key.expose_secret());
-        right.copy_from_slice(enclave_key.expose_secret());
+        copy_key(left, enclave_key.expose_secret())?;
+        copy_key(right, enclave_key.expose_secret())?;
+
+        Ok(SodaBoxCrypto::from_seed(seed))
+    }
 
-        SodaBoxCrypto::from_seed(seed)
+    /// Generate a fresh random key pair, like for a session.
+    pub fn new_ephemeral() -> Result<Self, CryptoError> {
+        let mut seed = [0_u8; 32];
+        fill_random(&mut seed)?;
+        let crypto = SodaBoxCrypto::from_seed(seed);
+        seed.zeroize();
+        Ok(crypto)
     }
 
     pub fn from_seed(mut seed: [u8; 32]) -> Self {
@@ -80,14 +164,109 @@ impl SodaBoxCrypto {
             public_key: pub_key,
             private_key: Secret::new(priv_key),
             rng: Box::new(rand::thread_rng()),
+            nonce_strategy: NonceStrategy::default(),
+            nonce_counter: None,
         }
     }
 
+    /// Draw nonces from `rng` instead of the thread RNG: for example, a seeded RNG in tests.
+    ///
+    /// Library-only, like [`Self::with_nonce_strategy`]: the enclave's own sealing uses the thread RNG.
+    pub fn with_rng(mut self, rng: Box<dyn RngCore>) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// Pick nonces with `nonce_strategy` instead of [`NonceStrategy::Random`].
+    pub fn with_nonce_strategy(mut self, nonce_strategy: NonceStrategy) -> Self {
+        self.nonce_strategy = nonce_strategy;
+        self.nonce_counter = None;
+        self
+    }
+
+    /// [`Self::decrypt_message_with`] the default [`BoxAlgorithm`].
     pub fn decrypt_message(
         &self,
         ciphertext: &[u8],
         their_pk: &PublicKey,
         nonce: &Nonce,
+    ) -> Result<SecretBytes, CryptoError> {
+        self.decrypt_message_with(BoxAlgorithm::default(), ciphertext, their_pk, nonce)
+    }
+
+    pub fn decrypt_message_with(
+        &self,
+        algorithm: BoxAlgorithm,
+        ciphertext: &[u8],
+        their_pk: &PublicKey,
+        nonce: &Nonce,
+    ) -> Result<SecretBytes, CryptoError> {
+        match algorithm {
+            BoxAlgorithm::XSalsa20Poly1305 => self.box_open(ciphertext, their_pk, nonce),
+            BoxAlgorithm::XChaCha20Poly1305 => {
+                let cipher = self.xchacha20poly1305(their_pk);
+                match cipher.decrypt(XNonce::from_slice(nonce), ciphertext) {
+                    Ok(message) => Ok(Secret::new(message.into_boxed_slice())),
+                    Err(_) => Err(CryptoError::AuthenticationFailed),
+                }
+            }
+        }
+    }
+
+    /// [`Self::encrypt_message_with`] the default [`BoxAlgorithm`].
+    pub fn encrypt_message(
+        &mut self,
+        message: &SecretBytes,
+        their_pk: &PublicKey,
+    ) -> Result<EncryptedMessage, CryptoError> {
+        self.encrypt_message_with(BoxAlgorithm::default(), message, their_pk)
+    }
+
+    pub fn encrypt_message_with(
+        &mut self,
+        algorithm: BoxAlgorithm,
+        message: &SecretBytes,
+        their_pk: &PublicKey,
+    ) -> Result<EncryptedMessage, CryptoError> {
+        let nonce = self.get_nonce(algorithm, message, their_pk)?;
+        let ciphertext = match algorithm {
+            BoxAlgorithm::XSalsa20Poly1305 => self.box_seal(message, their_pk, &nonce)?,
+            BoxAlgorithm::XChaCha20Poly1305 => {
+                let cipher = self.xchacha20poly1305(their_pk);
+                match cipher.encrypt(XNonce::from_slice(&nonce), message.expose_secret().as_ref()) {
+                    Ok(ciphertext) => ciphertext.into_boxed_slice(),
+                    Err(_) => return Err(CryptoError::Encryption),
+                }
+            }
+        };
+        Ok(EncryptedMessage { ciphertext, nonce })
+    }
+
+    /// The [`BoxAlgorithm::XChaCha20Poly1305`] cipher for `their_pk`.
+    ///
+    /// Its key is derived from [`Self::shared_key`], rather than being the shared key itself,
+    /// which XSalsa20-Poly1305 boxes already use.
+    fn xchacha20poly1305(&self, their_pk: &PublicKey) -> XChaCha20Poly1305 {
+        let shared_key = self.shared_key(their_pk);
+        let mut input = [
+            shared_key.expose_secret() as &[u8],
+            b"SodaBoxCrypto XChaCha20Poly1305 key",
+        ]
+        .concat();
+        let mut hash = [0_u8; 64];
+        sodalite::hash(&mut hash, &input);
+        input.zeroize();
+
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(&hash[..32]));
+        hash.zeroize();
+        cipher
+    }
+
+    fn box_open(
+        &self,
+        ciphertext: &[u8],
+        their_pk: &PublicKey,
+        nonce: &Nonce,
     ) -> Result<SecretBytes, CryptoError> {
         // It is the responsibility of the caller to pad ciphertext
         // see: https://github.com/registreerocks/rtc-data/issues/51
@@ -111,17 +290,16 @@ impl SodaBoxCrypto {
             Ok(_) => Ok(Secret::new(
                 drop_prefix(CRYPTO_BOX_ZEROBYTES, message).into_boxed_slice(),
             )),
-            // TODO: return compound error type
-            Err(_) => Err(CryptoError::Unknown),
+            Err(_) => Err(CryptoError::AuthenticationFailed),
         }
     }
 
-    pub fn encrypt_message(
-        &mut self,
+    fn box_seal(
+        &self,
         message: &SecretBytes,
         their_pk: &PublicKey,
-    ) -> Result<EncryptedMessage, CryptoError> {
-        let nonce = self.get_nonce()?;
+        nonce: &Nonce,
+    ) -> Result<Box<[u8]>, CryptoError> {
         // Length is padded here since the message needs to be padded with 32 `0_u8`
         // at the front
         let mut ciphertext = vec![0_u8; message.expose_secret().len() + CRYPTO_BOX_ZEROBYTES];
@@ -140,15 +318,12 @@ impl SodaBoxCrypto {
                 message.expose_secret(),
             ]
             .concat(),
-            &nonce,
+            nonce,
             their_pk,
             self.private_key.expose_secret(),
         ) {
-            Ok(_) => Ok(EncryptedMessage {
-                ciphertext: drop_prefix(CRYPTO_BOX_BOXZEROBYTES, ciphertext).into_boxed_slice(),
-                nonce,
-            }),
-            Err(_) => Err(CryptoError::Unknown),
+            Ok(_) => Ok(drop_prefix(CRYPTO_BOX_BOXZEROBYTES, ciphertext).into_boxed_slice()),
+            Err(_) => Err(CryptoError::Encryption),
         }
     }
 
@@ -156,19 +331,119 @@ impl SodaBoxCrypto {
         self.public_key
     }
 
-    fn get_nonce(&mut self) -> Result<Nonce, CryptoError> {
+    /// The key that a box between this key pair and `their_pk` uses.
+    ///
+    /// Both sides of a key exchange get the same key.
+    pub fn shared_key(&self, their_pk: &PublicKey) -> SecretKey {
+        let mut key = [0_u8; 32];
+        sodalite::box_beforenm(&mut key, their_pk, self.private_key.expose_secret());
+        Secret::new(key)
+    }
+
+    /// Pick the nonce to encrypt `message` for `their_pk`, following its [`NonceStrategy`].
+    fn get_nonce(
+        &mut self,
+        algorithm: BoxAlgorithm,
+        message: &SecretBytes,
+        their_pk: &PublicKey,
+    ) -> Result<Nonce, CryptoError> {
         let mut nonce = [0_u8; 24];
-        // TODO: don't use just random nonces, since it might not
-        // be applicable to all situations
-        match self.rng.try_fill(&mut nonce) {
-            Ok(_) => Ok(nonce),
-            // TODO: Better conversion from rand::Error? (See also data_upload.rs)
-            Err(err) => Err(CryptoError::Rand(err.code().map_or(0, |code| code.get()))),
+        match self.nonce_strategy {
+            NonceStrategy::Random => {
+                self.rng.try_fill(&mut nonce).map_err(rand_error)?;
+            }
+            NonceStrategy::Counter => {
+                if self.nonce_counter.is_none() {
+                    let mut prefix = [0_u8; 16];
+                    self.rng.try_fill(&mut prefix).map_err(rand_error)?;
+                    self.nonce_counter = Some((prefix, 0));
+                }
+                if let Some((prefix, next)) = &mut self.nonce_counter {
+                    nonce[..16].copy_from_slice(prefix);
+                    nonce[16..].copy_from_slice(&next.to_be_bytes());
+                    *next = next.checked_add(1).ok_or(CryptoError::NoncesExhausted)?;
+                }
+            }
+            NonceStrategy::Synthetic => {
+                let algorithm_label: &[u8] = match algorithm {
+                    BoxAlgorithm::XSalsa20Poly1305 => b"XSalsa20Poly1305",
+                    BoxAlgorithm::XChaCha20Poly1305 => b"XChaCha20Poly1305",
+                };
+                let mut input = [
+                    b"SodaBoxCrypto synthetic nonce" as &[u8],
+                    algorithm_label,
+                    self.private_key.expose_secret(),
+                    their_pk,
+                    message.expose_secret(),
+                ]
+                .concat();
+                let mut hash = [0_u8; 64];
+                sodalite::hash(&mut hash, &input);
+                input.zeroize();
+                nonce.copy_from_slice(&hash[..24]);
+                hash.zeroize();
+            }
         }
+        Ok(nonce)
+    }
+}
+
+/// Encrypt `message` with the symmetric `key`, and `nonce`.
+///
+/// The caller must never use a nonce twice with the same key.
+pub fn secretbox_seal(
+    message: &SecretBytes,
+    nonce: Nonce,
+    key: &SecretKey,
+) -> Result<EncryptedMessage, CryptoError> {
+    // Like SodaBoxCrypto::encrypt_message, secretbox needs the same zero padding as box.
+    let mut ciphertext = vec![0_u8; message.expose_secret().len() + CRYPTO_BOX_ZEROBYTES];
+    match sodalite::secretbox(
+        &mut ciphertext,
+        &[
+            &[0u8; CRYPTO_BOX_ZEROBYTES] as &[u8],
+            message.expose_secret(),
+        ]
+        .concat(),
+        &nonce,
+        key.expose_secret(),
+    ) {
+        Ok(_) => Ok(EncryptedMessage {
+            ciphertext: drop_prefix(CRYPTO_BOX_BOXZEROBYTES, ciphertext).into_boxed_slice(),
+            nonce,
+        }),
+        Err(_) => Err(CryptoError::Encryption),
     }
 }
 
-pub fn get_enclave_key() -> Secret<sgx_key_128bit_t> {
+/// Decrypt and authenticate `ciphertext` with the symmetric `key`.
+pub fn secretbox_open(
+    ciphertext: &[u8],
+    nonce: &Nonce,
+    key: &SecretKey,
+) -> Result<SecretBytes, CryptoError> {
+    let padded_ciphertext = &[&[0u8; CRYPTO_BOX_BOXZEROBYTES] as &[u8], ciphertext].concat();
+    let mut message = vec![0_u8; padded_ciphertext.len()];
+    match sodalite::secretbox_open(&mut message, padded_ciphertext, nonce, key.expose_secret()) {
+        Ok(_) => Ok(Secret::new(
+            drop_prefix(CRYPTO_BOX_ZEROBYTES, message).into_boxed_slice(),
+        )),
+        Err(_) => Err(CryptoError::AuthenticationFailed),
+    }
+}
+
+/// Fill `bytes` from the enclave's random number generator.
+pub(crate) fn fill_random(bytes: &mut [u8]) -> Result<(), CryptoError> {
+    rand::thread_rng().try_fill(bytes).map_err(rand_error)
+}
+
+// TODO: Better conversion from rand::Error? (See also data_upload.rs)
+fn rand_error(err: rand::Error) -> CryptoError {
+    CryptoError::Rand(err.code().map_or(0, |code| code.get()))
+}
+
+/// Derive the enclave's sealing key, bound to its identity and signer.
+pub fn get_enclave_key() -> Result<Secret<sgx_key_128bit_t>, CryptoError> {
     // From my testing, this is deterministic if the environment and binary is the same
     // TODO: Test in Azure VM using HW mode
     // TODO: Find documentation that confirms that the effect is normative
@@ -192,9 +467,22 @@ pub fn get_enclave_key() -> Secret<sgx_key_128bit_t> {
         reserved2: [0_u8; SGX_KEY_REQUEST_RESERVED2_BYTES],
     };
 
-    // This should never fail since the input values are constant
-    // TODO: remove unwrap and deal with error?
-    Secret::new(rsgx_get_key(&key_request).unwrap())
+    match rsgx_get_key(&key_request) {
+        Ok(key) => Ok(Secret::new(key)),
+        Err(status) => Err(CryptoError::KeyDerivation(status)),
+    }
+}
+
+/// Copy `key` to `dest`, if they are the same length.
+fn copy_key(dest: &mut [u8], key: &[u8]) -> Result<(), CryptoError> {
+    if dest.len() != key.len() {
+        return Err(CryptoError::KeyLength {
+            expected: dest.len(),
+            actual: key.len(),
+        });
+    }
+    dest.copy_from_slice(key);
+    Ok(())
 }
 
 /// Drop the first `prefix_len` elements of `vec`, keeping the rest.
@@ -0,0 +1,104 @@
+//! Value encodings for [`FsStore`](super::fs::FsStore).
+
+use core::marker::PhantomData;
+use std::error::Error;
+use std::io;
+use std::io::Write;
+use std::prelude::v1::{Box, Vec};
+
+use rmp_serde::Serializer;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use zeroize::Zeroizing;
+
+pub type CodecError = Box<dyn Error + Send + Sync>;
+
+/// Encode and decode stored values.
+///
+/// Encoded values may hold secrets, so they are returned in a [`Zeroizing`] buffer.
+pub trait Codec {
+    fn encode<V: Serialize>(value: &V) -> Result<Zeroizing<Vec<u8>>, CodecError>;
+
+    fn decode<V: DeserializeOwned>(encoded: &[u8]) -> Result<V, CodecError>;
+}
+
+/// Encode values as JSON.
+#[derive(Copy, Clone, Default, Debug)] // core
+pub struct JsonCodec;
+
+impl Codec for JsonCodec {
+    fn encode<V: Serialize>(value: &V) -> Result<Zeroizing<Vec<u8>>, CodecError> {
+        encode_exact(|writer| Ok(serde_json::to_writer(writer, value)?))
+    }
+
+    fn decode<V: DeserializeOwned>(encoded: &[u8]) -> Result<V, CodecError> {
+        Ok(serde_json::from_slice(encoded)?)
+    }
+}
+
+/// Encode values as MessagePack, like [`crate::schema::msgpack::ToMessagePack`].
+///
+/// This stores byte strings and arrays much more compactly than JSON.
+#[derive(Copy, Clone, Default, Debug)] // core
+pub struct MessagePackCodec;
+
+impl Codec for MessagePackCodec {
+    fn encode<V: Serialize>(value: &V) -> Result<Zeroizing<Vec<u8>>, CodecError> {
+        encode_exact(|writer| {
+            let mut serializer = Serializer::new(writer)
+                .with_struct_map()
+                .with_string_variants();
+            Ok(value.serialize(&mut serializer)?)
+        })
+    }
+
+    fn decode<V: DeserializeOwned>(encoded: &[u8]) -> Result<V, CodecError> {
+        Ok(rmp_serde::from_read_ref(encoded)?)
+    }
+}
+
+/// Encode values with `C`, but also decode values previously stored as JSON.
+///
+/// This lets a store switch from [`JsonCodec`] to `C`: values get re-encoded as they are saved.
+#[derive(Copy, Clone, Default, Debug)] // core
+pub struct JsonFallbackCodec<C: Codec>(PhantomData<C>);
+
+impl<C: Codec> Codec for JsonFallbackCodec<C> {
+    fn encode<V: Serialize>(value: &V) -> Result<Zeroizing<Vec<u8>>, CodecError> {
+        C::encode(value)
+    }
+
+    fn decode<V: DeserializeOwned>(encoded: &[u8]) -> Result<V, CodecError> {
+        C::decode(encoded).or_else(|err| JsonCodec::decode(encoded).map_err(|_| err))
+    }
+}
+
+/// Run `encode` twice: once to measure the encoding, and once to write it into a buffer
+/// of exactly that size.
+///
+/// The buffer never grows, so it can't leave unzeroized copies of the encoding behind.
+fn encode_exact<F>(encode: F) -> Result<Zeroizing<Vec<u8>>, CodecError>
+where
+    F: Fn(&mut dyn Write) -> Result<(), CodecError>,
+{
+    let mut counter = ByteCounter(0);
+    encode(&mut counter)?;
+    let mut encoded = Zeroizing::new(Vec::with_capacity(counter.0));
+    encode(&mut *encoded)?;
+    debug_assert_eq!(encoded.len(), counter.0);
+    Ok(encoded)
+}
+
+/// Count the bytes written, and discard them.
+struct ByteCounter(usize);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
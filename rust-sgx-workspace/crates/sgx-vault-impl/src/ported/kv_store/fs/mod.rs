@@ -1,65 +1,95 @@
 //! Filesystem-based [`KvStore`] implementation
 //!
-//! XXX port note: Added phantom `value_type` to `FsStore`.
+//! XXX port note: Added phantom `value_type` and `codec` to `FsStore`.
 
 pub mod sgx_filer;
 
-// sgx_tstd (v1.1.3) does not support `fs::read_dir`, so limit the following to tests, for now.
+// sgx_tstd (v1.1.3) does not support `fs::read_dir`, so `FsStore` lists its keys using an index file.
 //
 // See: https://github.com/apache/incubator-teaclave-sgx-sdk/blob/v1.1.3/release_notes.md#partially-supported-modstraits-in-sgx_tstd
 use core::marker::PhantomData;
+use std::collections::{BTreeMap, BTreeSet};
 use std::io;
 use std::path::{Path, PathBuf};
 use std::prelude::v1::*;
+use std::sync::{PoisonError, SgxMutex, SgxMutexGuard};
 
+pub use kv_store::fs::{parent_dir, temp_path_for, Filer, StdFiler, TEMP_DIR_NAME};
+use lazy_static::lazy_static;
 use serde::de::DeserializeOwned;
-use serde::Serialize;
-pub use sgx_filer::SgxFiler;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "test-fault-injection")]
+pub use sgx_filer::WriteFault;
+pub use sgx_filer::{SgxFileKeyPolicy, SgxFiler};
+use thiserror::Error;
+use zeroize::Zeroizing;
 
 use super::KvStore;
-use crate::ported::kv_store::Key;
+use crate::ported::kv_store::codec::{Codec, CodecError, JsonCodec};
+use crate::ported::kv_store::{Key, Version};
 
-/// Simplified interface for reading and writing files.
-pub trait Filer {
-    /// Read content of `path`, if any.
-    ///
-    /// Return [`None`] if `path` doesn't exist.
-    ///
-    fn get(&self, path: impl AsRef<Path>) -> io::Result<Option<Vec<u8>>>;
-
-    /// Write `content` to `path`. Discard any existing content.
-    fn put(&self, path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> io::Result<()>;
-
-    /// Delete `path`. Discard any existing content.
-    fn delete(&self, path: impl AsRef<Path>) -> io::Result<()>;
-}
-
-/// [`KvStore`] using a file per key under `root_dir`.
-pub struct FsStore<F, V>
+/// [`KvStore`] using a file per key under `root_dir`, with values encoded by `C`.
+///
+/// Each value file also holds the value's [`Version`], for [`KvStore::compare_and_swap`],
+/// so loading a value reads only its own file.
+///
+/// Because `sgx_tstd` can't list directories, [`KvStore::keys`] is backed by an index file
+/// (written with the same `filer`), kept alongside the value files.
+/// It is only rewritten when a key is added or removed. It is updated before saving a new key,
+/// and after deleting one, so an interrupted write leaves at most extra index entries,
+/// never unlisted values. [`KvStore::iter`] skips such extra entries.
+///
+/// The index also holds the latest version assigned to a new key, so that a value that is
+/// deleted and saved again gets a new version. If the index can't be decoded, it is replaced
+/// with an empty one: the values stay readable, but [`KvStore::keys`] only lists them
+/// again once they are saved, and versions of deleted values may be reused.
+/// Any other failure to read the index (such as an I/O or sealing key error) fails the
+/// operation instead, and leaves the index as it is.
+///
+/// Updates are serialised by a lock shared by all instances in the enclave,
+/// because each ECALL opens its own store.
+///
+/// The index itself is always JSON: it holds no secrets.
+pub struct FsStore<F, V, C = JsonCodec>
 where
     F: Filer,
     V: Serialize + DeserializeOwned,
+    C: Codec,
 {
     pub(crate) root_dir: PathBuf,
     pub(crate) filer: F,
     value_type: PhantomData<V>,
+    codec: PhantomData<C>,
 }
 
 impl<F, V> FsStore<F, V>
 where
     F: Filer,
     V: Serialize + DeserializeOwned,
+{
+    /// Like [`FsStore::with_codec`], using [`JsonCodec`].
+    pub fn new(root: impl AsRef<Path>, filer: F) -> Self {
+        Self::with_codec(root, filer)
+    }
+}
+
+impl<F, V, C> FsStore<F, V, C>
+where
+    F: Filer,
+    V: Serialize + DeserializeOwned,
+    C: Codec,
 {
     /// # Note
     ///
     /// The caller must ensure that `root` exists as a directory.
     ///
-    pub fn new(root: impl AsRef<Path>, filer: F) -> Self {
+    pub fn with_codec(root: impl AsRef<Path>, filer: F) -> Self {
         let root_dir = root.as_ref().to_path_buf();
         FsStore {
             root_dir,
             filer,
             value_type: PhantomData,
+            codec: PhantomData,
         }
     }
 
@@ -68,54 +98,297 @@ where
         let file_name = encode_to_fs_safe(key);
         self.root_dir.join(file_name)
     }
+
+    fn index_path(&self) -> PathBuf {
+        self.root_dir.join(INDEX_FILE_NAME)
+    }
+
+    /// Read and decode `path` with `D`.
+    fn read_file<T, D>(&self, path: PathBuf) -> Result<Option<T>, FsStoreError>
+    where
+        T: DeserializeOwned,
+        D: Codec,
+    {
+        // Note: Read all the data into memory first, then deserialize, for efficiency.
+        // See the docs for [`serde_json::de::from_reader`],
+        // and https://github.com/serde-rs/json/issues/160
+        let loaded = match self.filer.get(&path) {
+            Ok(loaded) => loaded.map(Zeroizing::new),
+            Err(source) => return Err(FsStoreError::Read { path, source }),
+        };
+        loaded
+            .map(|encoded| D::decode(&encoded))
+            .transpose()
+            .map_err(|source| FsStoreError::Decode { path, source })
+    }
+
+    /// Encode `value` with `E`, and write it to `path`.
+    fn write_file<T, E>(&self, path: PathBuf, value: &T) -> Result<(), FsStoreError>
+    where
+        T: Serialize,
+        E: Codec,
+    {
+        let encoded = match E::encode(value) {
+            Ok(encoded) => encoded,
+            Err(source) => return Err(FsStoreError::Encode { path, source }),
+        };
+        self.filer
+            .put(&path, &*encoded)
+            .map_err(|source| FsStoreError::Write { path, source })
+    }
+
+    /// Read the index, or start a new one if it can't be decoded (see [`FsStore`]).
+    fn load_index(&self) -> Result<FsStoreIndex, FsStoreError> {
+        match self.read_file::<StoredIndex, JsonCodec>(self.index_path()) {
+            Ok(index) => Ok(index.map(FsStoreIndex::from).unwrap_or_default()),
+            Err(err @ FsStoreError::Decode { .. }) => {
+                println!("FsStore: replacing undecodable index: {}", err);
+                Ok(FsStoreIndex::default())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn save_index(&self, index: &FsStoreIndex) -> Result<(), FsStoreError> {
+        self.write_file::<FsStoreIndex, JsonCodec>(self.index_path(), index)
+    }
+
+    /// Read the value of `key`, with its version.
+    ///
+    /// Values saved without a version have version 0.
+    fn read_versioned(&self, key: &Key) -> Result<Option<(Version, V)>, FsStoreError> {
+        let path = self.value_path(key);
+        let loaded = match self.filer.get(&path) {
+            Ok(loaded) => loaded.map(Zeroizing::new),
+            Err(source) => return Err(FsStoreError::Read { path, source }),
+        };
+        loaded
+            .map(|encoded| {
+                C::decode::<VersionedValue<V>>(&encoded)
+                    .map(|versioned| (versioned.version, versioned.value))
+                    .or_else(|err| {
+                        C::decode::<V>(&encoded)
+                            .map(|value| (0, value))
+                            .map_err(|_| err)
+                    })
+            })
+            .transpose()
+            .map_err(|source| FsStoreError::Decode { path, source })
+    }
+
+    /// The version of the value that saving or deleting `key` replaces.
+    ///
+    /// A value that can't be read can still be replaced: it counts as missing.
+    fn overwritten_version(&self, key: &Key) -> Option<Version> {
+        match self.read_versioned(key) {
+            Ok(current) => current.map(|(version, _)| version),
+            Err(_) => None,
+        }
+    }
+
+    /// Save `value` for `key`, replacing `current_version`. The caller must hold the store lock.
+    fn save_locked(
+        &self,
+        key: &Key,
+        current_version: Option<Version>,
+        value: &V,
+    ) -> Result<(), FsStoreError> {
+        let mut index = self.load_index()?;
+        let file_name = encode_to_fs_safe(key);
+        let version = match current_version {
+            Some(current_version) if index.file_names.contains(&file_name) => current_version + 1,
+            _ => {
+                index.last_version = index.last_version.max(current_version.unwrap_or(0)) + 1;
+                index.file_names.insert(file_name);
+                self.save_index(&index)?;
+                index.last_version
+            }
+        };
+
+        let versioned = VersionedValue { version, value };
+        self.write_file::<VersionedValue<&V>, C>(self.value_path(key), &versioned)
+    }
+
+    /// Delete the value of `key`, at `current_version`. The caller must hold the store lock.
+    fn delete_locked(
+        &self,
+        key: &Key,
+        current_version: Option<Version>,
+    ) -> Result<(), FsStoreError> {
+        let path = self.value_path(key);
+        if let Err(source) = self.filer.delete(&path) {
+            return Err(FsStoreError::Delete { path, source });
+        }
+        let mut index = self.load_index()?;
+        let last_version = index.last_version.max(current_version.unwrap_or(0));
+        if index.file_names.remove(&encode_to_fs_safe(key)) || last_version != index.last_version {
+            index.last_version = last_version;
+            self.save_index(&index)?;
+        }
+        Ok(())
+    }
+}
+
+/// Contents of an [`FsStore`] value file.
+#[derive(Deserialize, Serialize)] // serde
+struct VersionedValue<V> {
+    version: Version,
+    value: V,
+}
+
+/// File name of the index of [`FsStore`].
+///
+/// This does not clash with value file names, which start with `x` (see [`encode_to_fs_safe`]).
+pub const INDEX_FILE_NAME: &str = "index";
+
+/// Contents of the [`FsStore`] index file.
+#[derive(Default, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+struct FsStoreIndex {
+    /// The version most recently assigned to a new key: this keeps increasing, even across deletes.
+    last_version: Version,
+
+    /// Value file names.
+    file_names: BTreeSet<String>,
+}
+
+/// The index file formats.
+#[derive(Deserialize)] // serde
+#[serde(untagged)]
+enum StoredIndex {
+    Current(FsStoreIndex),
+
+    /// The index used to hold the versions, before the value files did.
+    /// Those values now load with version 0.
+    Versioned {
+        last_version: Version,
+        versions: BTreeMap<String, Version>,
+    },
+
+    /// Before versioning, the index only listed the value file names.
+    Unversioned(BTreeSet<String>),
+}
+
+impl From<StoredIndex> for FsStoreIndex {
+    fn from(stored: StoredIndex) -> Self {
+        match stored {
+            StoredIndex::Current(index) => index,
+            StoredIndex::Versioned {
+                last_version,
+                versions,
+            } => FsStoreIndex {
+                last_version,
+                file_names: versions.into_keys().collect(),
+            },
+            StoredIndex::Unversioned(file_names) => FsStoreIndex {
+                last_version: 0,
+                file_names,
+            },
+        }
+    }
+}
+
+lazy_static! {
+    static ref FS_STORE_LOCK: SgxMutex<()> = SgxMutex::new(());
+}
+
+/// Take the lock shared by all [`FsStore`] instances.
+///
+/// A panic while holding the lock can't leave files half-written (see [`Filer::put`]),
+/// so a poisoned lock is safe to reuse.
+fn lock_fs_store() -> SgxMutexGuard<'static, ()> {
+    FS_STORE_LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// [`FsStore`] failed.
+#[derive(Debug, Error)]
+pub enum FsStoreError {
+    #[error("FsStore: read from {path:?} failed: {source}")]
+    Read { path: PathBuf, source: io::Error },
+
+    #[error("FsStore: write to {path:?} failed: {source}")]
+    Write { path: PathBuf, source: io::Error },
+
+    #[error("FsStore: delete of {path:?} failed: {source}")]
+    Delete { path: PathBuf, source: io::Error },
+
+    #[error("FsStore: encoding value for {path:?} failed: {source}")]
+    Encode { path: PathBuf, source: CodecError },
+
+    #[error("FsStore: decoding value from {path:?} failed: {source}")]
+    Decode { path: PathBuf, source: CodecError },
+
+    #[error("FsStore: invalid index entry: {0}")]
+    InvalidIndexEntry(String),
+}
+
+impl From<FsStoreError> for io::Error {
+    fn from(err: FsStoreError) -> Self {
+        let kind = match &err {
+            FsStoreError::Read { source, .. }
+            | FsStoreError::Write { source, .. }
+            | FsStoreError::Delete { source, .. } => source.kind(),
+            FsStoreError::Encode { .. } => io::ErrorKind::InvalidInput,
+            FsStoreError::Decode { .. } | FsStoreError::InvalidIndexEntry(_) => {
+                io::ErrorKind::InvalidData
+            }
+        };
+        io::Error::new(kind, err)
+    }
 }
 
-impl<F, V> KvStore<V> for FsStore<F, V>
+impl<F, V, C> KvStore<V> for FsStore<F, V, C>
 where
     F: Filer,
     V: Serialize + DeserializeOwned,
+    C: Codec,
 {
-    // XXX: More explicit handling of serde_json::Error?
-    type Error = io::Error;
+    type Error = FsStoreError;
 
-    fn load(&self, key: &Key) -> Result<Option<V>, Self::Error> {
-        let value_file_name = self.value_path(key);
+    fn load_versioned(&self, key: &Key) -> Result<Option<(Version, V)>, Self::Error> {
+        let _lock = lock_fs_store();
+        self.read_versioned(key)
+    }
 
-        // Note: Read all the data into memory first, then deserialize, for efficiency.
-        // See the docs for [`serde_json::de::from_reader`],
-        // and https://github.com/serde-rs/json/issues/160
-        let loaded: Option<Vec<u8>> = self.filer.get(&value_file_name).map_err(|err| {
-            // XXX: Annotate err with some basic debugging context, for now.
-            Self::Error::new(
-                err.kind(),
-                format!("FsStore: read from {:?} failed: {}", value_file_name, err),
-            )
-        })?;
-        let value: Option<V> = loaded
-            .map(|serialised: Vec<u8>| serde_json::from_slice(serialised.as_slice()))
-            .transpose()?;
-        Ok(value)
+    fn compare_and_swap(
+        &mut self,
+        key: &Key,
+        expected_version: Option<Version>,
+        new_value: Option<&V>,
+    ) -> Result<bool, Self::Error> {
+        let _lock = lock_fs_store();
+        let current_version = self.read_versioned(key)?.map(|(version, _)| version);
+        if current_version != expected_version {
+            return Ok(false);
+        }
+        match new_value {
+            Some(value) => self.save_locked(key, current_version, value)?,
+            None => self.delete_locked(key, current_version)?,
+        }
+        Ok(true)
     }
 
     fn save(&mut self, key: &Key, value: &V) -> Result<(), Self::Error> {
-        let value_file_name = self.value_path(key);
-        let serialized: Vec<u8> = serde_json::to_vec(&value)?;
-        self.filer
-            .put(&value_file_name, serialized)
-            .map_err(|err| {
-                // XXX: Annotate err with some basic debugging context, for now.
-                Self::Error::new(
-                    err.kind(),
-                    format!("FsStore: write to {:?} failed: {}", value_file_name, err),
-                )
-            })?;
-        Ok(())
+        let _lock = lock_fs_store();
+        self.save_locked(key, self.overwritten_version(key), value)
     }
 
     fn delete(&mut self, key: &Key) -> Result<(), Self::Error> {
-        let path = self.value_path(key);
-        self.filer.delete(path)?;
-        Ok(())
+        let _lock = lock_fs_store();
+        self.delete_locked(key, self.overwritten_version(key))
+    }
+
+    fn keys(&self) -> Result<Vec<Box<Key>>, Self::Error> {
+        let _lock = lock_fs_store();
+        self.load_index()?
+            .file_names
+            .iter()
+            .map(|file_name| {
+                decode_from_fs_safe(file_name)
+                    .map(Vec::into_boxed_slice)
+                    .map_err(FsStoreError::InvalidIndexEntry)
+            })
+            .collect()
     }
 }
 
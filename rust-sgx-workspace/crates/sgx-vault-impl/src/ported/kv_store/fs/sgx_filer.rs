@@ -1,38 +1,187 @@
 //! [`SgxFile`] support
 
 use std::io::ErrorKind::NotFound;
-use std::io::{Result, Write};
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom, Write};
+use std::mem;
 use std::path::Path;
 use std::prelude::v1::Vec;
+use std::untrusted::fs;
 
+use sgx_tse::{rsgx_get_key, rsgx_self_report};
 use sgx_tstd::sgxfs;
 use sgx_tstd::sgxfs::SgxFile;
+use sgx_types::{
+    sgx_attributes_t,
+    sgx_key_128bit_t,
+    sgx_key_id_t,
+    sgx_key_request_t,
+    SGX_KEYPOLICY_MRENCLAVE,
+    SGX_KEYPOLICY_MRSIGNER,
+    SGX_KEYSELECT_SEAL,
+    TSEAL_DEFAULT_FLAGSMASK,
+    TSEAL_DEFAULT_MISCMASK,
+};
+use zeroize::{Zeroize, Zeroizing};
 
-use super::Filer;
+use super::{parent_dir, temp_path_for, Filer};
 
-/// TODO: key management policy
-pub struct SgxFiler;
+/// Identifies a derived [`SgxFileKeyPolicy`] key. Change this to rotate to a new key.
+pub type SgxFileKeyId = [u8; 32];
+
+/// How [`SgxFiler`] keys the files it protects.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)] // core
+pub enum SgxFileKeyPolicy {
+    /// The SDK's default: a random key per file, sealed into the file's metadata.
+    ///
+    /// * `protected_fs_file::generate_random_meta_data_key`
+    ///   https://github.com/intel/linux-sgx/blob/sgx_2.13.3/sdk/protected_fs/sgx_tprotected_fs/file_crypto.cpp#L197
+    AutoKey,
+
+    /// A seal key bound to this exact enclave build (MRENCLAVE).
+    ///
+    /// Files become unreadable after any enclave upgrade.
+    MrEnclave { key_id: SgxFileKeyId },
+
+    /// A seal key bound to the enclave signer and product (MRSIGNER, ISVPRODID).
+    ///
+    /// Files stay readable by upgraded enclaves from the same signer, as long as their
+    /// ISV SVN is at least `isv_svn`, and unreadable to enclaves from other signers.
+    /// Raise `isv_svn` (and rotate) to lock out older enclave versions.
+    MrSigner { key_id: SgxFileKeyId, isv_svn: u16 },
+}
+
+/// Fail [`SgxFiler::put`] part-way, to simulate a crash in tests.
+#[cfg(feature = "test-fault-injection")]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)] // core
+pub enum WriteFault {
+    /// Stop after writing this many bytes of the new content.
+    PartialWrite(usize),
+
+    /// Stop after writing the new content, before replacing the old file.
+    BeforeRename,
+}
+
+/// [`Filer`] for SGX protected files, with an explicit [`SgxFileKeyPolicy`].
+///
+/// Files written under any of `previous_key_policies` can still be read,
+/// and get rewritten under `key_policy` when they are: this is how keys are rotated.
+///
+/// Writes go to a temporary file in [`TEMP_DIR_NAME`](super::TEMP_DIR_NAME),
+/// which then replaces the target file, so an interrupted write never leaves a partially-written file behind.
+/// (The temporary file keeps the target's file name, because SGX protected files
+/// are bound to their file name.)
+#[derive(Clone, Debug)] // core
+pub struct SgxFiler {
+    pub key_policy: SgxFileKeyPolicy,
+    pub previous_key_policies: Vec<SgxFileKeyPolicy>,
+
+    /// Flush writes to disk (`fsync`) before completing them.
+    pub sync_writes: bool,
+
+    #[cfg(feature = "test-fault-injection")]
+    write_fault: Option<WriteFault>,
+}
+
+impl SgxFiler {
+    pub fn new(key_policy: SgxFileKeyPolicy) -> Self {
+        Self::with_previous_keys(key_policy, Vec::new())
+    }
+
+    pub fn with_previous_keys(
+        key_policy: SgxFileKeyPolicy,
+        previous_key_policies: Vec<SgxFileKeyPolicy>,
+    ) -> Self {
+        Self {
+            key_policy,
+            previous_key_policies,
+            sync_writes: false,
+            #[cfg(feature = "test-fault-injection")]
+            write_fault: None,
+        }
+    }
+
+    pub fn with_sync_writes(self, sync_writes: bool) -> Self {
+        Self {
+            sync_writes,
+            ..self
+        }
+    }
+
+    /// Fail every [`Filer::put`] with `write_fault`.
+    #[cfg(feature = "test-fault-injection")]
+    pub fn with_write_fault(self, write_fault: WriteFault) -> Self {
+        Self {
+            write_fault: Some(write_fault),
+            ..self
+        }
+    }
+
+    /// Write `contents` to a new file at `path`.
+    fn create_with_contents(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        let mut file = match &self.key_policy {
+            SgxFileKeyPolicy::AutoKey => SgxFile::create(path)?,
+            key_policy => {
+                let mut key = derive_key(key_policy)?;
+                let created = SgxFile::create_ex(path, &key);
+                key.zeroize();
+                created?
+            }
+        };
+        #[cfg(feature = "test-fault-injection")]
+        if let Some(WriteFault::PartialWrite(len)) = self.write_fault {
+            file.write_all(&contents[..len.min(contents.len())])?;
+            return Err(injected_fault());
+        }
+        file.write_all(contents)?;
+        file.flush()?;
+        drop(file);
+
+        if self.sync_writes {
+            fs::File::open(path)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for SgxFiler {
+    /// Use [`SgxFileKeyPolicy::AutoKey`].
+    fn default() -> Self {
+        Self::new(SgxFileKeyPolicy::AutoKey)
+    }
+}
 
-// Default key management:
-//
-// * `protected_fs_file::generate_random_meta_data_key`
-//   https://github.com/intel/linux-sgx/blob/sgx_2.13.3/sdk/protected_fs/sgx_tprotected_fs/file_crypto.cpp#L197
-//
 impl Filer for SgxFiler {
     fn get(&self, path: impl AsRef<Path>) -> Result<Option<Vec<u8>>> {
-        // TODO: Create `read_ex` to read file with key
-        match sgxfs::read(path) {
-            Ok(contents) => Ok(Some(contents)),
-            Err(error) if error.kind() == NotFound => Ok(None),
-            Err(error) => Err(error),
+        let path = path.as_ref();
+        let current_error = match read_with_key(path, &self.key_policy) {
+            Err(error) if error.kind() != NotFound => error,
+            result => return result,
+        };
+
+        // The file may still be keyed under a previous policy: rotate it if so.
+        for previous_key_policy in &self.previous_key_policies {
+            if let Ok(Some(contents)) = read_with_key(path, previous_key_policy) {
+                self.put(path, &contents)?;
+                return Ok(Some(contents));
+            }
         }
+        Err(current_error)
     }
 
     fn put(&self, path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> Result<()> {
-        let contents: &[u8] = content.as_ref();
-        // TODO: create_ex with key
-        let mut value_file = SgxFile::create(path)?;
-        value_file.write_all(contents)
+        let path = path.as_ref();
+        let temp_path = temp_path_for(path)?;
+        self.create_with_contents(&temp_path, content.as_ref())?;
+        #[cfg(feature = "test-fault-injection")]
+        if self.write_fault == Some(WriteFault::BeforeRename) {
+            return Err(injected_fault());
+        }
+
+        fs::rename(&temp_path, path)?;
+        if self.sync_writes {
+            fs::File::open(parent_dir(path))?.sync_all()?;
+        }
+        Ok(())
     }
 
     fn delete(&self, path: impl AsRef<Path>) -> Result<()> {
@@ -42,3 +191,76 @@ impl Filer for SgxFiler {
         }
     }
 }
+
+#[cfg(feature = "test-fault-injection")]
+fn injected_fault() -> Error {
+    Error::new(ErrorKind::Other, "SgxFiler: injected write fault")
+}
+
+/// Like [`sgxfs::read`], but with the key of `key_policy`.
+fn read_with_key(path: &Path, key_policy: &SgxFileKeyPolicy) -> Result<Option<Vec<u8>>> {
+    let opened = match key_policy {
+        SgxFileKeyPolicy::AutoKey => SgxFile::open(path),
+        key_policy => {
+            let mut key = derive_key(key_policy)?;
+            let opened = SgxFile::open_ex(path, &key);
+            key.zeroize();
+            opened
+        }
+    };
+    let mut file = match opened {
+        Ok(file) => file,
+        Err(error) if error.kind() == NotFound => return Ok(None),
+        Err(error) => return Err(error),
+    };
+    // Read into a buffer of the file's exact size: growing it would leave copies of the
+    // contents behind, unzeroized.
+    let len = file.seek(SeekFrom::End(0))?;
+    let len = usize::try_from(len).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+    file.seek(SeekFrom::Start(0))?;
+    let mut contents = Zeroizing::new(vec![0; len]);
+    file.read_exact(&mut contents)?;
+    Ok(Some(mem::take(&mut *contents)))
+}
+
+/// Derive the seal key for `key_policy`.
+///
+/// Unlike the SDK's seal functions (and [`crate::ported::crypto::get_enclave_key`]), this requests
+/// the key for CPU SVN zero, rather than the platform's current CPU SVN.
+///
+/// This is a deliberate downgrade: a protected file keyed this way does not record the key request
+/// it was written with (unlike [`SgxFileKeyPolicy::AutoKey`] files, whose metadata does),
+/// so a key bound to the current CPU SVN could not be derived again after a microcode (TCB) update,
+/// and every file would become unreadable.
+/// As a consequence, TCB recovery does not rotate these keys by itself:
+/// after a TCB recovery, rotate them explicitly, with a new `key_id` (and the old policy as a previous key).
+fn derive_key(key_policy: &SgxFileKeyPolicy) -> Result<sgx_key_128bit_t> {
+    let (sgx_key_policy, key_id, isv_svn) = match *key_policy {
+        SgxFileKeyPolicy::AutoKey => unreachable!("derive_key: AutoKey has no derived key"),
+        // MRENCLAVE already binds the key to this build: bind it to its ISV SVN too.
+        SgxFileKeyPolicy::MrEnclave { key_id } => (
+            SGX_KEYPOLICY_MRENCLAVE,
+            key_id,
+            rsgx_self_report().body.isv_svn,
+        ),
+        SgxFileKeyPolicy::MrSigner { key_id, isv_svn } => (SGX_KEYPOLICY_MRSIGNER, key_id, isv_svn),
+    };
+    let key_request = sgx_key_request_t {
+        key_name: SGX_KEYSELECT_SEAL,
+        key_policy: sgx_key_policy,
+        isv_svn,
+        key_id: sgx_key_id_t { id: key_id },
+        attribute_mask: sgx_attributes_t {
+            flags: TSEAL_DEFAULT_FLAGSMASK,
+            xfrm: 0,
+        },
+        misc_mask: TSEAL_DEFAULT_MISCMASK,
+        ..Default::default()
+    };
+    rsgx_get_key(&key_request).map_err(|status| {
+        Error::new(
+            ErrorKind::Other,
+            format!("SgxFiler: key derivation failed: {:?}", status),
+        )
+    })
+}
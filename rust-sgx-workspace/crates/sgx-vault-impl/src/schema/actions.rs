@@ -4,13 +4,23 @@
 //!
 //! * <https://developer.algorand.org/docs/reference/rest-apis/kmd/>
 
-use std::prelude::v1::{String, ToString};
+use std::prelude::v1::{String, ToString, Vec};
 
 use serde::{Deserialize, Serialize};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
-use crate::schema::entities::VaultDisplay;
-use crate::schema::types::{Bytes, VaultId, VaultPassword};
+use crate::schema::entities::{AuditLogPage, VaultDisplay, VaultPolicy};
+use crate::schema::types::{
+    AlgorandAccountMnemonic,
+    AlgorandAccountSeedBytes,
+    AlgorandAddressBase32,
+    AlgorandAssetId,
+    Bytes,
+    EthereumAddressHex,
+    VaultId,
+    VaultPassword,
+};
+use crate::schema::{serde_bytes_array, serde_bytes_seq};
 use crate::vault_operations::store::UnlockVaultError;
 
 #[derive(Clone, Eq, PartialEq, Debug)] // core
@@ -86,12 +96,79 @@ pub enum TransactionToSign {
         #[serde(with = "serde_bytes")]
         transaction_bytes: Bytes,
     },
+
+    /// An atomic group of unsigned Algorand transactions.
+    ///
+    /// Every transaction must carry the group ID computed for the whole group.
+    /// Only the transactions sent by the vault's account get signed.
+    AlgorandTransactionGroup {
+        #[serde(with = "serde_bytes_seq")]
+        transactions_bytes: Vec<Bytes>,
+    },
+
+    /// An unsigned Algorand transaction sent by a multisig account that the vault's account belongs to.
+    AlgorandMultisigTransaction {
+        multisig: AlgorandMultisigParameters,
+
+        #[serde(with = "serde_bytes")]
+        transaction_bytes: Bytes,
+    },
+
+    /// A partially-signed Algorand multisig transaction, to merge the vault's signature into.
+    AlgorandMultisigSignedTransaction {
+        multisig: AlgorandMultisigParameters,
+
+        #[serde(with = "serde_bytes")]
+        signed_transaction_bytes: Bytes,
+    },
+
+    /// Arbitrary data, signed with Algorand's `MX` prefix.
+    ///
+    /// This matches algonaut's `Account::sign_bytes`, and verifies against the vault's address.
+    /// Data with an Algorand transaction (`TX`) prefix is refused.
+    AlgorandBytes {
+        #[serde(with = "serde_bytes")]
+        data_bytes: Bytes,
+    },
+
+    /// A raw message, signed with plain Ed25519 as
+    /// `"NTC-MSG" || domain_tag || 0x00 || message_bytes`.
+    ///
+    /// The domain tag identifies the purpose of the signature, such as a login challenge.
+    /// The fixed `"NTC-MSG"` prefix keeps these signatures apart from any Algorand signature.
+    /// Tags with an Algorand domain separation prefix (like `TX` or `Program`),
+    /// and messages with a `TX` prefix, are refused.
+    Ed25519Message {
+        domain_tag: String,
+
+        #[serde(with = "serde_bytes")]
+        message_bytes: Bytes,
+    },
+    /// An unsigned Ethereum transaction, to sign with the vault's Ethereum account.
+    ///
+    /// This is either an RLP-encoded legacy transaction (6 fields, or 9 fields with
+    /// EIP-155 chain ID), or an EIP-1559 transaction (`0x02 || rlp([...])`).
+    EthereumTransaction {
+        #[serde(with = "serde_bytes")]
+        transaction_bytes: Bytes,
+    },
+}
+
+/// For [`TransactionToSign`]: An Algorand multisig account's parameters.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct AlgorandMultisigParameters {
+    pub version: u8,
+    pub threshold: u8,
+    pub addresses: Vec<AlgorandAddressBase32>,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)] // core
 #[derive(Deserialize, Serialize)] // serde
 pub enum SignTransactionResult {
     Signed(TransactionSigned),
+    /// The vault's [`VaultPolicy`] does not allow signing this transaction.
+    PolicyViolation(String),
     InvalidAuth,
     Failed(String),
 }
@@ -118,6 +195,34 @@ pub enum TransactionSigned {
         #[serde(with = "serde_bytes")]
         signed_transaction_bytes: Bytes,
     },
+
+    /// The vault's signed transactions from an Algorand transaction group.
+    AlgorandTransactionGroupSigned {
+        signed_transactions: Vec<AlgorandGroupTransactionSigned>,
+    },
+
+    /// A detached Ed25519 signature, for [`TransactionToSign::AlgorandBytes`]
+    /// and [`TransactionToSign::Ed25519Message`].
+    Ed25519Signature {
+        #[serde(with = "serde_bytes")]
+        signature_bytes: Bytes,
+    },
+    /// A signed Ethereum transaction, ready to submit with `eth_sendRawTransaction`.
+    EthereumTransactionSigned {
+        #[serde(with = "serde_bytes")]
+        signed_transaction_bytes: Bytes,
+    },
+}
+
+/// For [`TransactionSigned::AlgorandTransactionGroupSigned`]: One signed transaction of the group.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct AlgorandGroupTransactionSigned {
+    /// This transaction's position in the group.
+    pub group_index: u32,
+
+    #[serde(with = "serde_bytes")]
+    pub signed_transaction_bytes: Bytes,
 }
 
 impl TransactionSigned {
@@ -128,12 +233,313 @@ impl TransactionSigned {
         }
     }
 
+    /// Create [`Self::Ed25519Signature`] from bytes.
+    pub fn from_ed25519_signature(signature_bytes: Bytes) -> Self {
+        Self::Ed25519Signature { signature_bytes }
+    }
+
+    /// Create [`Self::EthereumTransactionSigned`] from bytes.
+    pub fn from_ethereum_bytes(signed_transaction_bytes: Bytes) -> Self {
+        Self::EthereumTransactionSigned {
+            signed_transaction_bytes,
+        }
+    }
+
     /// Unwrap [`Self::AlgorandTransactionSigned`] or panic.
     pub fn unwrap_algorand_bytes(self) -> Bytes {
         match self {
             TransactionSigned::AlgorandTransactionSigned {
                 signed_transaction_bytes,
             } => signed_transaction_bytes,
+            otherwise => panic!(
+                "called `TransactionSigned::unwrap_algorand_bytes` on: {:?}",
+                otherwise
+            ),
+        }
+    }
+
+    /// Unwrap [`Self::Ed25519Signature`] or panic.
+    pub fn unwrap_ed25519_signature(self) -> Bytes {
+        match self {
+            TransactionSigned::Ed25519Signature { signature_bytes } => signature_bytes,
+            otherwise => panic!(
+                "called `TransactionSigned::unwrap_ed25519_signature` on: {:?}",
+                otherwise
+            ),
+        }
+    }
+
+    /// Unwrap [`Self::EthereumTransactionSigned`] or panic.
+    pub fn unwrap_ethereum_bytes(self) -> Bytes {
+        match self {
+            TransactionSigned::EthereumTransactionSigned {
+                signed_transaction_bytes,
+            } => signed_transaction_bytes,
+            otherwise => panic!(
+                "called `TransactionSigned::unwrap_ethereum_bytes` on: {:?}",
+                otherwise
+            ),
+        }
+    }
+
+    /// Unwrap [`Self::AlgorandTransactionGroupSigned`] or panic.
+    pub fn unwrap_algorand_group(self) -> Vec<AlgorandGroupTransactionSigned> {
+        match self {
+            TransactionSigned::AlgorandTransactionGroupSigned {
+                signed_transactions,
+            } => signed_transactions,
+            otherwise => panic!(
+                "called `TransactionSigned::unwrap_algorand_group` on: {:?}",
+                otherwise
+            ),
+        }
+    }
+}
+
+/// Decode a transaction to sign, without signing it.
+///
+/// This lets the vault owner review what they are approving,
+/// including whether the vault's [`VaultPolicy`] would allow it.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+#[derive(Zeroize, ZeroizeOnDrop)] // zeroize
+pub struct SummarizeTransaction {
+    pub vault_id: VaultId,
+    pub auth_password: VaultPassword,
+
+    #[zeroize(skip)]
+    pub transaction_to_sign: TransactionToSign,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub enum SummarizeTransactionResult {
+    Summarized(TransactionSummary),
+    InvalidAuth,
+    Failed(String),
+}
+
+impl From<UnlockVaultError> for SummarizeTransactionResult {
+    fn from(err: UnlockVaultError) -> Self {
+        use UnlockVaultError::*;
+        match err {
+            InvalidVaultId => Self::InvalidAuth,
+            InvalidAuthPassword => Self::InvalidAuth,
+            IoError(err) => Self::Failed(err.to_string()),
+        }
+    }
+}
+
+/// For [`SummarizeTransactionResult`]: A decoded [`TransactionToSign`].
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct TransactionSummary {
+    /// The decoded transactions, in order.
+    pub algorand_transactions: Vec<AlgorandTransactionSummary>,
+
+    /// The decoded Ethereum transaction, if any.
+    #[serde(default)]
+    pub ethereum_transactions: Vec<EthereumTransactionSummary>,
+
+    /// The reason that [`SignTransaction`] would fail with [`SignTransactionResult::PolicyViolation`], if any.
+    pub policy_violation: Option<String>,
+}
+
+/// For [`TransactionSummary`]: The human-relevant details of an Algorand transaction.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct AlgorandTransactionSummary {
+    pub transaction_id: String,
+
+    /// Whether the vault would sign this transaction.
+    ///
+    /// This is false for transactions in a group that are sent by other accounts.
+    pub signed_by_vault: bool,
+
+    /// The network transaction type, such as `pay` or `axfer`.
+    pub transaction_type: String,
+    pub sender: AlgorandAddressBase32,
+    pub receiver: Option<AlgorandAddressBase32>,
+    /// In microAlgos for payments, or asset base units for asset transfers.
+    pub amount: Option<u64>,
+    pub asset_id: Option<AlgorandAssetId>,
+    /// If present, the sender's whole remaining balance goes to this address.
+    pub close_remainder_to: Option<AlgorandAddressBase32>,
+    /// If present, the sender's account gets rekeyed to this address.
+    pub rekey_to: Option<AlgorandAddressBase32>,
+
+    /// In microAlgos.
+    pub fee: u64,
+    pub first_valid: u64,
+    pub last_valid: u64,
+    pub genesis_id: Option<String>,
+}
+
+/// For [`TransactionSummary`]: The human-relevant details of an Ethereum transaction.
+///
+/// Amounts in wei can exceed 64 bits, so they are `0x`-prefixed hex quantities,
+/// as in the Ethereum JSON-RPC API.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct EthereumTransactionSummary {
+    /// `legacy` (with or without EIP-155 replay protection), or `eip-1559`.
+    pub transaction_type: String,
+    /// Absent for legacy transactions without EIP-155 replay protection.
+    pub chain_id: Option<u64>,
+    pub nonce: u64,
+    /// Absent for contract creation.
+    pub to: Option<EthereumAddressHex>,
+    /// In wei.
+    pub value: String,
+    /// The length of the call data, in bytes.
+    pub data_len: u64,
+
+    pub gas_limit: u64,
+    /// Legacy transactions: in wei.
+    pub gas_price: Option<String>,
+    /// EIP-1559 transactions: in wei.
+    pub max_priority_fee_per_gas: Option<String>,
+    /// EIP-1559 transactions: in wei.
+    pub max_fee_per_gas: Option<String>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+#[derive(Zeroize, ZeroizeOnDrop)] // zeroize
+pub struct ImportAlgorandAccount {
+    pub username: String,
+    pub auth_password: VaultPassword,
+
+    pub account_to_import: AlgorandAccountToImport,
+}
+
+/// For [`ImportAlgorandAccount`]: The existing Algorand account's secret.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+#[derive(Zeroize, ZeroizeOnDrop)] // zeroize
+pub enum AlgorandAccountToImport {
+    /// An Algorand 25-word mnemonic.
+    Mnemonic { mnemonic: AlgorandAccountMnemonic },
+
+    /// A raw Algorand account seed.
+    Seed {
+        #[serde(with = "serde_bytes_array")]
+        seed_bytes: AlgorandAccountSeedBytes,
+    },
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub enum ImportAlgorandAccountResult {
+    Imported(VaultDisplay),
+    Failed(String),
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+#[derive(Zeroize, ZeroizeOnDrop)] // zeroize
+pub struct ExportAlgorandAccount {
+    pub vault_id: VaultId,
+    pub auth_password: VaultPassword,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub enum ExportAlgorandAccountResult {
+    Exported(AlgorandAccountExported),
+    /// The vault's [`VaultPolicy`] does not allow export.
+    ExportDisabled,
+    InvalidAuth,
+    Failed(String),
+}
+
+impl From<UnlockVaultError> for ExportAlgorandAccountResult {
+    fn from(err: UnlockVaultError) -> Self {
+        use UnlockVaultError::*;
+        match err {
+            InvalidVaultId => Self::InvalidAuth,
+            InvalidAuthPassword => Self::InvalidAuth,
+            IoError(err) => Self::Failed(err.to_string()),
+        }
+    }
+}
+
+/// For [`ExportAlgorandAccountResult`]: The exported Algorand account's secret.
+///
+/// Like all responses, this only leaves the enclave sealed to the requesting client's key.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+#[derive(Zeroize, ZeroizeOnDrop)] // zeroize
+pub struct AlgorandAccountExported {
+    pub mnemonic: AlgorandAccountMnemonic,
+}
+
+/// Replace the vault's [`VaultPolicy`].
+///
+/// Restrictions are one-way: the new policy may only tighten the current one, so that
+/// the auth password alone can't lift the limits that guard against its own theft.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+#[derive(Zeroize, ZeroizeOnDrop)] // zeroize
+pub struct UpdateVaultPolicy {
+    pub vault_id: VaultId,
+    pub auth_password: VaultPassword,
+
+    #[zeroize(skip)]
+    pub policy: VaultPolicy,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub enum UpdateVaultPolicyResult {
+    Updated(VaultPolicy),
+    /// The new policy would loosen the current one.
+    WouldLoosen(String),
+    InvalidAuth,
+    Failed(String),
+}
+
+impl From<UnlockVaultError> for UpdateVaultPolicyResult {
+    fn from(err: UnlockVaultError) -> Self {
+        use UnlockVaultError::*;
+        match err {
+            InvalidVaultId => Self::InvalidAuth,
+            InvalidAuthPassword => Self::InvalidAuth,
+            IoError(err) => Self::Failed(err.to_string()),
+        }
+    }
+}
+
+/// Read a page of the vault's [`AuditLog`](crate::schema::entities::AuditLog).
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+#[derive(Zeroize, ZeroizeOnDrop)] // zeroize
+pub struct GetAuditLog {
+    pub vault_id: VaultId,
+    pub auth_password: VaultPassword,
+
+    /// The sequence number of the first entry to return.
+    pub start_sequence: u64,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub enum GetAuditLogResult {
+    /// The page, after checking its hash chain back to the previous page.
+    ///
+    /// This does not include the entry for this request.
+    Retrieved(AuditLogPage),
+    InvalidAuth,
+    Failed(String),
+}
+
+impl From<UnlockVaultError> for GetAuditLogResult {
+    fn from(err: UnlockVaultError) -> Self {
+        use UnlockVaultError::*;
+        match err {
+            InvalidVaultId => Self::InvalidAuth,
+            InvalidAuthPassword => Self::InvalidAuth,
+            IoError(err) => Self::Failed(err.to_string()),
         }
     }
 }
@@ -146,6 +552,11 @@ pub enum VaultRequest {
     CreateVault(CreateVault),
     OpenVault(OpenVault),
     SignTransaction(SignTransaction),
+    ImportAlgorandAccount(ImportAlgorandAccount),
+    ExportAlgorandAccount(ExportAlgorandAccount),
+    UpdateVaultPolicy(UpdateVaultPolicy),
+    SummarizeTransaction(SummarizeTransaction),
+    GetAuditLog(GetAuditLog),
 }
 
 /// Dispatching enum for action results.
@@ -155,6 +566,11 @@ pub enum VaultResponse {
     CreateVault(CreateVaultResult),
     OpenVault(OpenVaultResult),
     SignTransaction(SignTransactionResult),
+    ImportAlgorandAccount(ImportAlgorandAccountResult),
+    ExportAlgorandAccount(ExportAlgorandAccountResult),
+    UpdateVaultPolicy(UpdateVaultPolicyResult),
+    SummarizeTransaction(SummarizeTransactionResult),
+    GetAuditLog(GetAuditLogResult),
 }
 
 // Convenience conversions:
@@ -176,3 +592,33 @@ impl From<SignTransactionResult> for VaultResponse {
         Self::SignTransaction(result)
     }
 }
+
+impl From<ImportAlgorandAccountResult> for VaultResponse {
+    fn from(result: ImportAlgorandAccountResult) -> Self {
+        Self::ImportAlgorandAccount(result)
+    }
+}
+
+impl From<ExportAlgorandAccountResult> for VaultResponse {
+    fn from(result: ExportAlgorandAccountResult) -> Self {
+        Self::ExportAlgorandAccount(result)
+    }
+}
+
+impl From<UpdateVaultPolicyResult> for VaultResponse {
+    fn from(result: UpdateVaultPolicyResult) -> Self {
+        Self::UpdateVaultPolicy(result)
+    }
+}
+
+impl From<SummarizeTransactionResult> for VaultResponse {
+    fn from(result: SummarizeTransactionResult) -> Self {
+        Self::SummarizeTransaction(result)
+    }
+}
+
+impl From<GetAuditLogResult> for VaultResponse {
+    fn from(result: GetAuditLogResult) -> Self {
+        Self::GetAuditLog(result)
+    }
+}
@@ -1,17 +1,30 @@
 //! Structures representing various entities.
 
-use std::prelude::v1::{String, ToString};
+use std::prelude::v1::{String, ToString, Vec};
 
 use algonaut::transaction::account::Account as AlgonautAccount;
+use k256::ecdsa::SigningKey;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
+use crate::schema::serde_bytes_array;
 use crate::schema::types::{
+    AlgorandAccountMnemonic,
     AlgorandAccountSeedBytes,
     AlgorandAddressBase32,
     AlgorandAddressBytes,
+    AlgorandAssetId,
+    AuditHash,
+    EthereumAddressBytes,
+    EthereumAddressHex,
+    EthereumSecretKeyBytes,
+    UnixTimestamp,
     VaultId,
     VaultPassword,
+    VaultSchemaVersion,
 };
 
 /// A Nautilus vault's basic displayable details.
@@ -25,6 +38,9 @@ pub struct VaultDisplay {
 
     // TODO(Pi): Decouple for multiple accounts per vault.
     pub algorand_address_base32: AlgorandAddressBase32,
+
+    /// `None` for vaults created before Ethereum support.
+    pub ethereum_address_hex: Option<EthereumAddressHex>,
 }
 
 impl From<VaultStorable> for VaultDisplay {
@@ -34,10 +50,20 @@ impl From<VaultStorable> for VaultDisplay {
             username: storable.username.clone(),
 
             algorand_address_base32: storable.algorand_account.address_base32(),
+            ethereum_address_hex: storable
+                .ethereum_account
+                .as_ref()
+                .map(EthereumAccount::address_hex),
         }
     }
 }
 
+/// The current [`VaultStorable`] schema version.
+///
+/// Bump this when changing [`VaultStorable`] in a way that needs more than serde defaults,
+/// and add the upgrade to [`crate::vault_operations::migration::upgrade_vault_schema`].
+pub const VAULT_SCHEMA_VERSION: VaultSchemaVersion = 1;
+
 /// A Nautilus vault's full details.
 ///
 /// This is everything that gets persisted in the vault store.
@@ -45,12 +71,95 @@ impl From<VaultStorable> for VaultDisplay {
 #[derive(Deserialize, Serialize)] // serde
 #[derive(Zeroize, ZeroizeOnDrop)] // zeroize
 pub struct VaultStorable {
+    /// Records from before schema versioning have version 0.
+    #[serde(default)]
+    #[zeroize(skip)]
+    pub schema_version: VaultSchemaVersion,
+
     pub vault_id: VaultId,
     pub auth_password: VaultPassword,
 
     pub username: String,
 
     pub algorand_account: AlgorandAccount,
+
+    /// `None` for vaults created before Ethereum support.
+    #[serde(default)]
+    pub ethereum_account: Option<EthereumAccount>,
+
+    #[serde(default)]
+    #[zeroize(skip)]
+    pub policy: VaultPolicy,
+}
+
+/// A Nautilus vault's policy settings.
+///
+/// These are set by the vault owner, and enforced by the enclave.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+#[serde(default)]
+pub struct VaultPolicy {
+    /// Allow [`ExportAlgorandAccount`] to reveal the vault's Algorand account.
+    ///
+    /// [`ExportAlgorandAccount`]: crate::schema::actions::ExportAlgorandAccount
+    pub allow_algorand_account_export: bool,
+
+    /// Checked before signing any Algorand transaction.
+    pub algorand_signing: AlgorandSigningPolicy,
+}
+
+impl Default for VaultPolicy {
+    fn default() -> Self {
+        Self {
+            allow_algorand_account_export: true,
+            algorand_signing: AlgorandSigningPolicy::default(),
+        }
+    }
+}
+
+/// For [`VaultPolicy`]: Limits on the Algorand transactions that the vault will sign.
+///
+/// Each limit is disabled when `None`. While any limit is set, the vault also refuses
+/// transactions that the limits can't account for: rekeying, transaction types
+/// other than payments (`pay`) and asset transfers (`axfer`), and Ethereum transactions.
+#[derive(Clone, Eq, PartialEq, Debug, Default)] // core
+#[derive(Deserialize, Serialize)] // serde
+#[serde(default)]
+pub struct AlgorandSigningPolicy {
+    /// Maximum amount per transaction, in microAlgos or asset base units.
+    ///
+    /// Transactions that close out an account are treated as exceeding any maximum.
+    pub max_amount: Option<u64>,
+
+    /// Maximum fee per transaction, in microAlgos.
+    pub max_fee: Option<u64>,
+
+    /// Addresses that may receive payments, asset transfers, or closing balances.
+    pub allowed_receivers: Option<Vec<AlgorandAddressBase32>>,
+
+    /// Asset IDs that may be transferred.
+    pub allowed_asset_ids: Option<Vec<AlgorandAssetId>>,
+
+    /// Network genesis IDs that transactions may be valid for.
+    pub allowed_genesis_ids: Option<Vec<String>>,
+}
+
+impl AlgorandSigningPolicy {
+    /// Whether any limit is set.
+    pub fn has_limits(&self) -> bool {
+        let Self {
+            max_amount,
+            max_fee,
+            allowed_receivers,
+            allowed_asset_ids,
+            allowed_genesis_ids,
+        } = self;
+        max_amount.is_some()
+            || max_fee.is_some()
+            || allowed_receivers.is_some()
+            || allowed_asset_ids.is_some()
+            || allowed_genesis_ids.is_some()
+    }
 }
 
 // Algorand entities:
@@ -71,6 +180,23 @@ impl AlgorandAccount {
         }
     }
 
+    pub(crate) fn from_seed(seed_bytes: AlgorandAccountSeedBytes) -> Self {
+        Self { seed_bytes }
+    }
+
+    /// Parse an Algorand 25-word mnemonic.
+    ///
+    /// The error message does not include the mnemonic.
+    pub(crate) fn from_mnemonic(mnemonic: &str) -> Result<Self, String> {
+        let account = AlgonautAccount::from_mnemonic(mnemonic).map_err(|err| err.to_string())?;
+        Ok(Self::from_seed(account.seed()))
+    }
+
+    /// This account's Algorand 25-word mnemonic.
+    pub(crate) fn to_mnemonic(&self) -> AlgorandAccountMnemonic {
+        self.as_algonaut_account().mnemonic()
+    }
+
     // XXX performance: Repeated temporary conversions through AlgonautAccount?
     pub(crate) fn as_algonaut_account(&self) -> AlgonautAccount {
         AlgonautAccount::from_seed(self.seed_bytes)
@@ -90,3 +216,189 @@ impl From<AlgorandAccount> for AlgonautAccount {
         account.as_algonaut_account()
     }
 }
+
+// Ethereum entities:
+
+/// An Ethereum account.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+#[derive(Zeroize, ZeroizeOnDrop)] // zeroize
+pub struct EthereumAccount {
+    pub secret_key_bytes: EthereumSecretKeyBytes,
+}
+
+impl EthereumAccount {
+    pub(crate) fn generate() -> Self {
+        let mut rng = rand::thread_rng();
+        loop {
+            let mut secret_key_bytes = [0; 32];
+            rng.fill_bytes(&mut secret_key_bytes);
+            // Retry the negligible chance of zero, or a value past the curve order.
+            if SigningKey::from_bytes(&secret_key_bytes).is_ok() {
+                return Self { secret_key_bytes };
+            }
+            secret_key_bytes.zeroize();
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If the secret key is not a valid secp256k1 scalar.
+    /// [`Self::generate`] never produces such keys.
+    pub(crate) fn as_signing_key(&self) -> SigningKey {
+        SigningKey::from_bytes(&self.secret_key_bytes)
+            .expect("EthereumAccount: invalid secp256k1 secret key")
+    }
+
+    /// The last 20 bytes of the Keccak-256 hash of the uncompressed public key.
+    pub fn address_bytes(&self) -> EthereumAddressBytes {
+        let public_key = self
+            .as_signing_key()
+            .verifying_key()
+            .to_encoded_point(false);
+        // Skip the SEC1 tag byte.
+        let hash = Keccak256::digest(&public_key.as_bytes()[1..]);
+        let mut address_bytes = [0; 20];
+        address_bytes.copy_from_slice(&hash[12..]);
+        address_bytes
+    }
+
+    pub fn address_hex(&self) -> EthereumAddressHex {
+        ethereum_checksum_address(&self.address_bytes())
+    }
+}
+
+/// Format an Ethereum address with EIP-55 mixed-case checksum encoding.
+///
+/// See: <https://eips.ethereum.org/EIPS/eip-55>
+pub fn ethereum_checksum_address(address_bytes: &EthereumAddressBytes) -> EthereumAddressHex {
+    let lowercase = hex::encode(address_bytes);
+    let hash = Keccak256::digest(lowercase.as_bytes());
+    let checksummed: String = lowercase
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let hash_nibble = match i % 2 {
+                0 => hash[i / 2] >> 4,
+                _ => hash[i / 2] & 0x0f,
+            };
+            match 8 <= hash_nibble {
+                true => c.to_ascii_uppercase(),
+                false => c,
+            }
+        })
+        .collect();
+    format!("0x{}", checksummed)
+}
+
+// Audit log entities:
+
+/// A vault's append-only log of the operations performed on it.
+///
+/// Entries are hash-chained: see [`crate::vault_operations::audit_log`].
+#[derive(Clone, Eq, PartialEq, Debug, Default)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct AuditLog {
+    pub entries: Vec<AuditEntry>,
+}
+
+/// Part of an [`AuditLog`], up to one log segment long.
+#[derive(Clone, Eq, PartialEq, Debug, Default)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct AuditLogPage {
+    pub entries: Vec<AuditEntry>,
+
+    /// Where the next page starts, or [`None`] if this page ends the log.
+    pub next_sequence: Option<u64>,
+}
+
+/// For [`AuditLog`]: One vault operation.
+///
+/// This never records secrets: only what was done, when, and how it turned out.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct AuditEntry {
+    /// This entry's position in the log, from 0.
+    pub sequence: u64,
+
+    /// When the operation was performed.
+    ///
+    /// This comes from the host's clock, so it is not trusted: rely on [`Self::sequence`] for order.
+    pub timestamp: UnixTimestamp,
+
+    pub operation: AuditOperation,
+    pub outcome: AuditOutcome,
+
+    /// For [`AuditOperation::SignTransaction`]: the IDs of the signed transactions, if any.
+    ///
+    /// Algorand transaction IDs are base32, and Ethereum transaction hashes are `0x`-prefixed hex.
+    pub transaction_ids: Vec<String>,
+
+    /// The previous entry's [`Self::entry_hash`], or all zeros for the first entry.
+    #[serde(with = "serde_bytes_array")]
+    pub previous_hash: AuditHash,
+
+    /// Hash of this entry's fields, chained to [`Self::previous_hash`].
+    #[serde(with = "serde_bytes_array")]
+    pub entry_hash: AuditHash,
+}
+
+/// For [`AuditEntry`]: The type of vault operation.
+///
+/// The `vault_operation` ECALL also reports this to the host, as its declaration index:
+/// keep the order of the variants.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub enum AuditOperation {
+    CreateVault,
+    OpenVault,
+    SignTransaction,
+    ImportAlgorandAccount,
+    ExportAlgorandAccount,
+    UpdateVaultPolicy,
+    SummarizeTransaction,
+    GetAuditLog,
+}
+
+impl AuditOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::CreateVault => "CreateVault",
+            Self::OpenVault => "OpenVault",
+            Self::SignTransaction => "SignTransaction",
+            Self::ImportAlgorandAccount => "ImportAlgorandAccount",
+            Self::ExportAlgorandAccount => "ExportAlgorandAccount",
+            Self::UpdateVaultPolicy => "UpdateVaultPolicy",
+            Self::SummarizeTransaction => "SummarizeTransaction",
+            Self::GetAuditLog => "GetAuditLog",
+        }
+    }
+}
+
+/// For [`AuditEntry`]: How the vault operation turned out.
+///
+/// The `vault_operation` ECALL also reports this to the host, as its declaration index:
+/// keep the order of the variants.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub enum AuditOutcome {
+    Succeeded,
+    InvalidAuth,
+    /// Refused by the vault's [`VaultPolicy`].
+    Refused,
+    Failed,
+    /// Logged before an operation that changes the vault, ahead of its outcome.
+    Started,
+}
+
+impl AuditOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Succeeded => "Succeeded",
+            Self::InvalidAuth => "InvalidAuth",
+            Self::Refused => "Refused",
+            Self::Failed => "Failed",
+            Self::Started => "Started",
+        }
+    }
+}
@@ -25,4 +25,6 @@ pub mod entities;
 pub mod msgpack;
 pub mod sealing;
 pub(crate) mod serde_bytes_array;
+pub(crate) mod serde_bytes_seq;
+pub mod session;
 pub mod types;
@@ -7,7 +7,14 @@ use secrecy::{ExposeSecret, Secret};
 use serde::{Deserialize, Serialize};
 use zeroize::Zeroize;
 
-use crate::ported::crypto::{CryptoError, Nonce, PublicKey, SecretBytes, SodaBoxCrypto};
+use crate::ported::crypto::{
+    BoxAlgorithm,
+    CryptoError,
+    Nonce,
+    PublicKey,
+    SecretBytes,
+    SodaBoxCrypto,
+};
 use crate::schema::msgpack::{FromMessagePack, FromMessagePackOwned, ToMessagePack};
 use crate::schema::serde_bytes_array;
 use crate::schema::types::Bytes;
@@ -22,6 +29,17 @@ pub struct SealedMessage {
     pub nonce: Nonce,
     #[serde(with = "serde_bytes_array")]
     pub sender_public_key: PublicKey,
+
+    /// How the message is sealed.
+    ///
+    /// Messages without this field are sealed with the default [`BoxAlgorithm`],
+    /// and messages with the default leave it out.
+    #[serde(default, skip_serializing_if = "is_default_version")]
+    pub version: BoxAlgorithm,
+}
+
+fn is_default_version(version: &BoxAlgorithm) -> bool {
+    *version == BoxAlgorithm::default()
 }
 
 /// Seal message bytes from `sender_crypto` to `receiver_public_key`.
@@ -30,11 +48,28 @@ pub fn seal(
     receiver_public_key: &PublicKey,
     sender_crypto: &mut SodaBoxCrypto,
 ) -> Result<SealedMessage, CryptoError> {
-    let encrypted_message = sender_crypto.encrypt_message(message_bytes, receiver_public_key)?;
+    seal_with(
+        BoxAlgorithm::default(),
+        message_bytes,
+        receiver_public_key,
+        sender_crypto,
+    )
+}
+
+/// [`seal`] with the given [`SealedMessage::version`].
+pub fn seal_with(
+    version: BoxAlgorithm,
+    message_bytes: &SecretBytes,
+    receiver_public_key: &PublicKey,
+    sender_crypto: &mut SodaBoxCrypto,
+) -> Result<SealedMessage, CryptoError> {
+    let encrypted_message =
+        sender_crypto.encrypt_message_with(version, message_bytes, receiver_public_key)?;
     Ok(SealedMessage {
         ciphertext: encrypted_message.ciphertext,
         nonce: encrypted_message.nonce,
         sender_public_key: sender_crypto.get_pubkey(),
+        version,
     })
 }
 
@@ -43,7 +78,8 @@ pub fn unseal(
     sealed_message: &SealedMessage,
     receiver_crypto: &SodaBoxCrypto,
 ) -> Result<SecretBytes, CryptoError> {
-    let message_bytes = receiver_crypto.decrypt_message(
+    let message_bytes = receiver_crypto.decrypt_message_with(
+        sealed_message.version,
         &sealed_message.ciphertext,
         &sealed_message.sender_public_key,
         &sealed_message.nonce,
@@ -51,18 +87,24 @@ pub fn unseal(
     Ok(message_bytes)
 }
 
-/// [`seal] from the current enclave.
+/// [`seal_with`] from the current enclave.
 pub fn seal_from_enclave(
+    version: BoxAlgorithm,
     message_bytes: &SecretBytes,
     receiver_public_key: &PublicKey,
 ) -> Result<SealedMessage, CryptoError> {
-    let mut enclave_crypto = SodaBoxCrypto::new();
-    seal(message_bytes, receiver_public_key, &mut enclave_crypto)
+    let mut enclave_crypto = SodaBoxCrypto::new()?;
+    seal_with(
+        version,
+        message_bytes,
+        receiver_public_key,
+        &mut enclave_crypto,
+    )
 }
 
 /// [`unseal`] to the current enclave.
 pub fn unseal_to_enclave(sealed_message: &SealedMessage) -> Result<SecretBytes, CryptoError> {
-    let enclave_crypto = &SodaBoxCrypto::new();
+    let enclave_crypto = &SodaBoxCrypto::new()?;
     unseal(sealed_message, enclave_crypto)
 }
 
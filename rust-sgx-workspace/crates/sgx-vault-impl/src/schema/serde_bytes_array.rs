@@ -1,4 +1,7 @@
-//! Work around
+//! Work around `serde_bytes` not supporting fixed-size byte arrays: this serializes a `[u8; N]`
+//! as a byte string, for use with `#[serde(with = "serde_bytes_array")]`.
+//!
+//! Without it, the array would serialize as a tuple of integers.
 
 use core::convert::TryInto;
 
@@ -0,0 +1,31 @@
+//! Work around `serde_bytes` only supporting single byte strings: this serializes a sequence of
+//! [`Bytes`] as a sequence of byte strings, for use with `#[serde(with = "serde_bytes_seq")]`.
+//!
+//! Without it, each item would serialize as a sequence of integers.
+
+use std::prelude::v1::Vec;
+
+use serde::{Deserialize, Deserializer, Serializer};
+use serde_bytes::ByteBuf;
+
+use crate::schema::types::Bytes;
+
+/// Like [`serde_bytes::serialize`], but for a sequence of byte strings.
+pub(crate) fn serialize<S>(items: &[Bytes], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_seq(items.iter().map(|item| serde_bytes::Bytes::new(item)))
+}
+
+/// Like [`serde_bytes::deserialize`], but for a sequence of byte strings.
+pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Vec<Bytes>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let items: Vec<ByteBuf> = Deserialize::deserialize(deserializer)?;
+    Ok(items
+        .into_iter()
+        .map(|item| item.into_vec().into_boxed_slice())
+        .collect())
+}
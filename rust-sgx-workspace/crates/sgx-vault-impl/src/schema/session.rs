@@ -0,0 +1,249 @@
+//! Attested sessions: ephemeral keys, for forward secrecy of vault operations.
+//!
+//! # Handshake
+//!
+//! 1. The client generates an ephemeral key pair, and sends a [`SessionHello`]
+//!    with its public key.
+//!
+//! 2. The enclave generates its own ephemeral key pair, and replies with a [`SessionAccept`],
+//!    sealed from its long-term key (the one its attestation report binds) to the client's
+//!    ephemeral key.
+//!
+//!    The long-term key is an X25519 box key, so this box takes the place of a signature:
+//!    only the attested enclave can create it, and the client checks its sender
+//!    (see [`accept_session`]).
+//!
+//! 3. Both sides derive the [`SessionKeys`] from the two ephemeral keys.
+//!
+//! The client then seals vault requests as [`SessionSealedMessage`]s, and the enclave seals
+//! its responses the same way. Neither side keeps its ephemeral private key, and the enclave
+//! forgets the session keys when it evicts the session, so a later compromise of the
+//! long-term key does not expose the session's messages.
+//!
+//! # Counters
+//!
+//! Each request carries a [`SessionCounter`], which the client increases with every request,
+//! starting from 1. The counter is the message's nonce, so it is authenticated along with it:
+//! the enclave rejects requests whose counter does not exceed the last one it accepted for
+//! the session (see `crate::vault_operations::sessions`), so a request can't be replayed.
+//!
+//! Each response carries the counter of its request, under the other direction's key,
+//! and the client checks it: a response can't be passed off as the response to another request.
+
+use std::error::Error;
+use std::prelude::v1::Box;
+
+use secrecy::{ExposeSecret, Secret};
+use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+use crate::ported::crypto::{
+    secretbox_open,
+    secretbox_seal,
+    CryptoError,
+    Nonce,
+    PublicKey,
+    SecretBytes,
+    SecretKey,
+    SodaBoxCrypto,
+};
+use crate::schema::msgpack::FromMessagePack;
+use crate::schema::sealing::{unseal, SealedMessage};
+use crate::schema::serde_bytes_array;
+use crate::schema::types::Bytes;
+
+/// Enclave-chosen identifier of a session.
+pub type SessionId = [u8; 16];
+
+/// Client-chosen number of a request in its session: see [Counters](self#counters).
+pub type SessionCounter = u64;
+
+/// Handshake request: sent unsealed.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct SessionHello {
+    /// The client's ephemeral public key.
+    #[serde(with = "serde_bytes_array")]
+    pub client_public_key: PublicKey,
+}
+
+/// Handshake response: sealed from the enclave's long-term key.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct SessionAccept {
+    #[serde(with = "serde_bytes_array")]
+    pub session_id: SessionId,
+
+    /// The enclave's ephemeral public key.
+    #[serde(with = "serde_bytes_array")]
+    pub enclave_public_key: PublicKey,
+}
+
+/// A message sealed with [`SessionKeys`].
+#[derive(Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct SessionSealedMessage {
+    #[serde(with = "serde_bytes_array")]
+    pub session_id: SessionId,
+
+    /// The request's counter: for a response, the counter of the request it answers.
+    pub counter: SessionCounter,
+
+    #[serde(with = "serde_bytes")]
+    pub ciphertext: Bytes,
+}
+
+/// Symmetric keys of a session: one for each direction.
+#[derive(Clone)] // core
+#[derive(Zeroize, ZeroizeOnDrop)] // zeroize
+pub struct SessionKeys {
+    request_key: [u8; 32],
+    response_key: [u8; 32],
+}
+
+impl SessionKeys {
+    /// Derive the session keys from the ephemeral key exchange.
+    ///
+    /// `shared_key` is [`SodaBoxCrypto::shared_key`] of the two ephemeral key pairs:
+    /// the client and the enclave each compute it from their own side.
+    pub fn derive(
+        shared_key: &SecretKey,
+        client_public_key: &PublicKey,
+        enclave_public_key: &PublicKey,
+    ) -> Self {
+        let derive_key = |label: &[u8]| {
+            let mut input = [
+                shared_key.expose_secret() as &[u8],
+                client_public_key,
+                enclave_public_key,
+                label,
+            ]
+            .concat();
+            let mut hash = [0_u8; 64];
+            sodalite::hash(&mut hash, &input);
+            input.zeroize();
+
+            let mut key = [0_u8; 32];
+            key.copy_from_slice(&hash[..32]);
+            hash.zeroize();
+            key
+        };
+        Self {
+            request_key: derive_key(b"vault session request"),
+            response_key: derive_key(b"vault session response"),
+        }
+    }
+
+    fn request_key(&self) -> SecretKey {
+        Secret::new(self.request_key)
+    }
+
+    fn response_key(&self) -> SecretKey {
+        Secret::new(self.response_key)
+    }
+}
+
+/// Client: Open the enclave's sealed [`SessionAccept`], and derive the session keys.
+///
+/// `enclave_public_key` is the enclave's long-term public key, from a verified attestation
+/// report: this fails if the accept is not from that key.
+pub fn accept_session(
+    sealed_accept_bytes: &[u8],
+    client_crypto: &SodaBoxCrypto,
+    enclave_public_key: &PublicKey,
+) -> Result<(SessionId, SessionKeys), Box<dyn Error>> {
+    let sealed_accept = &SealedMessage::from_msgpack(sealed_accept_bytes)?;
+    if sealed_accept.sender_public_key != *enclave_public_key {
+        return Err("session accept is not from the attested enclave key".into());
+    }
+    let accept_bytes = unseal(sealed_accept, client_crypto)?;
+    let accept = SessionAccept::from_msgpack(accept_bytes.expose_secret())?;
+    let shared_key = &client_crypto.shared_key(&accept.enclave_public_key);
+    let keys = SessionKeys::derive(
+        shared_key,
+        &client_crypto.get_pubkey(),
+        &accept.enclave_public_key,
+    );
+    Ok((accept.session_id, keys))
+}
+
+/// Client: Seal a request for the session, with the next of its counters.
+pub fn seal_session_request(
+    message_bytes: &SecretBytes,
+    session_id: SessionId,
+    counter: SessionCounter,
+    keys: &SessionKeys,
+) -> Result<SessionSealedMessage, CryptoError> {
+    seal_session(message_bytes, session_id, counter, &keys.request_key())
+}
+
+/// Enclave: Unseal a request of the session.
+///
+/// The caller must still check that the request's counter is new:
+/// see `crate::vault_operations::sessions`.
+pub fn unseal_session_request(
+    sealed_message: &SessionSealedMessage,
+    keys: &SessionKeys,
+) -> Result<SecretBytes, CryptoError> {
+    unseal_session(sealed_message, sealed_message.counter, &keys.request_key())
+}
+
+/// Enclave: Seal the response to the request with `request_counter`.
+pub fn seal_session_response(
+    message_bytes: &SecretBytes,
+    session_id: SessionId,
+    request_counter: SessionCounter,
+    keys: &SessionKeys,
+) -> Result<SessionSealedMessage, CryptoError> {
+    seal_session(
+        message_bytes,
+        session_id,
+        request_counter,
+        &keys.response_key(),
+    )
+}
+
+/// Client: Unseal the response to the request with `request_counter`.
+///
+/// This fails if the response is to another request.
+pub fn unseal_session_response(
+    sealed_message: &SessionSealedMessage,
+    request_counter: SessionCounter,
+    keys: &SessionKeys,
+) -> Result<SecretBytes, CryptoError> {
+    if sealed_message.counter != request_counter {
+        return Err(CryptoError::AuthenticationFailed);
+    }
+    unseal_session(sealed_message, request_counter, &keys.response_key())
+}
+
+/// The nonce of a session message: its counter, big-endian, then zeros.
+///
+/// Each direction has its own key, so each nonce seals at most one request and one response.
+fn session_nonce(counter: SessionCounter) -> Nonce {
+    let mut nonce: Nonce = [0; 24];
+    nonce[..8].copy_from_slice(&counter.to_be_bytes());
+    nonce
+}
+
+fn seal_session(
+    message_bytes: &SecretBytes,
+    session_id: SessionId,
+    counter: SessionCounter,
+    key: &SecretKey,
+) -> Result<SessionSealedMessage, CryptoError> {
+    let encrypted_message = secretbox_seal(message_bytes, session_nonce(counter), key)?;
+    Ok(SessionSealedMessage {
+        session_id,
+        counter,
+        ciphertext: encrypted_message.ciphertext,
+    })
+}
+
+fn unseal_session(
+    sealed_message: &SessionSealedMessage,
+    counter: SessionCounter,
+    key: &SecretKey,
+) -> Result<SecretBytes, CryptoError> {
+    secretbox_open(&sealed_message.ciphertext, &session_nonce(counter), key)
+}
@@ -5,17 +5,41 @@ use std::prelude::v1::String;
 
 pub type Bytes = Box<[u8]>;
 
+/// Version of the persisted vault record schema.
+pub type VaultSchemaVersion = u32;
+
 /// Nautilus Vault ID.
 pub type VaultId = String;
 
 /// A vault user's authenticating password.
 pub type VaultPassword = String;
 
+/// SHA-256 hash chaining a vault's audit log entries.
+pub type AuditHash = [u8; 32];
+
+/// Seconds since the Unix epoch.
+pub type UnixTimestamp = u64;
+
 /// Algorand account seed, as bytes.
 pub type AlgorandAccountSeedBytes = [u8; 32];
 
+/// Algorand account seed, as a 25-word mnemonic.
+pub type AlgorandAccountMnemonic = String;
+
 /// Algorand account address, as bytes.
 pub type AlgorandAddressBytes = [u8; 32];
 
 /// Algorand account address, as base32 with checksum.
 pub type AlgorandAddressBase32 = String;
+
+/// Algorand asset ID.
+pub type AlgorandAssetId = u64;
+
+/// Ethereum account secret key (secp256k1 scalar), as big-endian bytes.
+pub type EthereumSecretKeyBytes = [u8; 32];
+
+/// Ethereum account address, as bytes.
+pub type EthereumAddressBytes = [u8; 20];
+
+/// Ethereum account address, as `0x`-prefixed hex with EIP-55 checksum casing.
+pub type EthereumAddressHex = String;
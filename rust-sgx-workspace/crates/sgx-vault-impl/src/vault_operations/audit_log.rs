@@ -0,0 +1,551 @@
+//! Hash-chained audit log of vault operations.
+//!
+//! Each vault has an [`AuditLog`], sealed under the same key policy as the vault itself,
+//! in the [`AUDIT_LOG_DIR_NAME`] subdirectory of the vault store.
+//!
+//! # Segments
+//!
+//! So that appending doesn't rewrite the whole log, only the latest entries are kept
+//! with the vault's key in [`audit_log_store`]. Once that holds [`AUDIT_LOG_SEGMENT_LEN`]
+//! entries, the next append moves them into a closed segment in [`audit_log_segment_store`],
+//! which is written once. [`load_audit_log_page`] reads one segment at a time.
+//!
+//! Failed authentication is logged at most once per [`INVALID_AUTH_LOG_INTERVAL_SECONDS`],
+//! so that repeated attempts by someone without the password don't flood the log.
+//!
+//! [`audit_vault_operation`] appends an entry for every operation on an existing vault
+//! before its response leaves the enclave. If the entry can't be written, the response is
+//! replaced with a failure: no signature or exported secret is released without a record.
+//!
+//! Operations that change the vault ([`changes_vault`]) are also logged write-ahead:
+//! a [`Started`](AuditOutcome::Started) entry is appended before the operation runs,
+//! and the operation is refused if that fails. A change can then not be made without
+//! a record, even if its outcome can't be appended afterwards.
+//!
+//! # Hash chain
+//!
+//! Each entry's [`entry_hash`](AuditEntry::entry_hash) is the SHA-256 of:
+//!
+//! ```text
+//! previous_hash
+//!     || sequence (u64 big-endian) || timestamp (u64 big-endian)
+//!     || operation || 0x00 || outcome || 0x00
+//!     || (transaction_id || 0x00)*
+//! ```
+//!
+//! with names and IDs as UTF-8, and a `previous_hash` of all zeros for the first entry.
+//! [`verify_audit_log`] checks this chain, so an exported log can be checked for altered,
+//! reordered, or removed entries by anyone holding a later entry's hash.
+//! The chain continues across segments and pages.
+//!
+//! The sealed store does not protect against the host rolling back a whole log file
+//! to an older copy: this would need a monotonic counter.
+
+use std::cmp::Ordering;
+use std::io;
+use std::prelude::v1::{Box, String, Vec};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::untrusted::time::SystemTimeEx;
+
+use algonaut::transaction::SignedTransaction;
+use sgx_tcrypto::rsgx_sha256_slice;
+use sgx_types::sgx_status_t;
+use sha3::{Digest, Keccak256};
+use thiserror::Error;
+
+use crate::ported::kv_store::fs::{FsStore, FsStoreError, SgxFiler};
+use crate::ported::kv_store::{Key, KvStore};
+use crate::schema::actions::{
+    CreateVaultResult,
+    ExportAlgorandAccountResult,
+    GetAuditLogResult,
+    ImportAlgorandAccountResult,
+    OpenVaultResult,
+    SignTransactionResult,
+    SummarizeTransactionResult,
+    TransactionSigned,
+    UpdateVaultPolicyResult,
+    VaultRequest,
+    VaultResponse,
+};
+use crate::schema::entities::{AuditEntry, AuditLog, AuditLogPage, AuditOperation, AuditOutcome};
+use crate::schema::types::{AuditHash, UnixTimestamp, VaultId};
+use crate::vault_operations::errors;
+use crate::vault_operations::sign_transaction_algorand::algorand_network_compatible;
+use crate::vault_operations::store::{
+    key_from_id,
+    load_vault,
+    vault_store_dir,
+    VaultCodec,
+    VAULT_STORE_KEY_POLICY,
+};
+
+/// Subdirectory of the vault store directory holding the audit logs.
+pub const AUDIT_LOG_DIR_NAME: &str = "audit_log";
+
+/// Subdirectory of [`AUDIT_LOG_DIR_NAME`] holding closed log segments.
+pub const AUDIT_LOG_SEGMENT_DIR_NAME: &str = "segments";
+
+/// The number of entries in each closed log segment, and in each [`AuditLogPage`].
+pub const AUDIT_LOG_SEGMENT_LEN: u64 = 256;
+
+/// Log at most one [`AuditOutcome::InvalidAuth`] entry per vault in this many seconds.
+pub const INVALID_AUTH_LOG_INTERVAL_SECONDS: UnixTimestamp = 60;
+
+type AuditLogStore = FsStore<SgxFiler, AuditLog, VaultCodec>;
+
+/// The latest entries of each vault's log, by vault key.
+pub fn audit_log_store() -> AuditLogStore {
+    let filer = SgxFiler::new(VAULT_STORE_KEY_POLICY).with_sync_writes(true);
+    FsStore::with_codec(vault_store_dir().join(AUDIT_LOG_DIR_NAME), filer)
+}
+
+/// The closed segments of each vault's log, by [`segment_key`].
+pub fn audit_log_segment_store() -> AuditLogStore {
+    let filer = SgxFiler::new(VAULT_STORE_KEY_POLICY).with_sync_writes(true);
+    let segment_dir = vault_store_dir()
+        .join(AUDIT_LOG_DIR_NAME)
+        .join(AUDIT_LOG_SEGMENT_DIR_NAME);
+    FsStore::with_codec(segment_dir, filer)
+}
+
+/// The key of the closed segment starting at `first_sequence`, for the vault with `vault_key`.
+///
+/// The sequence number has a fixed width, so keys of different vaults can't collide.
+pub fn segment_key(vault_key: &Key, first_sequence: u64) -> Box<Key> {
+    [&first_sequence.to_be_bytes()[..], vault_key]
+        .concat()
+        .into()
+}
+
+#[derive(Debug, Error)]
+pub enum AuditLogError {
+    #[error("audit log entry {sequence} failed verification: {reason}")]
+    BrokenChain { sequence: u64, reason: &'static str },
+
+    #[error("failed to read transaction IDs: {0}")]
+    TransactionIds(String),
+
+    #[error("I/O error in audit log")]
+    IoError(#[from] io::Error),
+
+    #[error(transparent)]
+    StoreError(#[from] FsStoreError),
+
+    #[error("SGX error while hashing audit log entry: {0:?}")]
+    Sgx(sgx_status_t),
+}
+
+impl From<sgx_status_t> for AuditLogError {
+    fn from(status: sgx_status_t) -> Self {
+        AuditLogError::Sgx(status)
+    }
+}
+
+/// A vault operation to record, before it gets its place in the chain.
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+pub struct AuditRecord {
+    pub vault_id: VaultId,
+    pub operation: AuditOperation,
+    pub outcome: AuditOutcome,
+    pub transaction_ids: Vec<String>,
+}
+
+/// Run `operation` for `request`, record it, and return its response if that succeeds.
+///
+/// Otherwise, return a failure of the request's response type. Operations that
+/// [change the vault](changes_vault) only run once their [`Started`](AuditOutcome::Started)
+/// entry is appended.
+pub fn audit_vault_operation(
+    request: &VaultRequest,
+    operation: impl FnOnce() -> VaultResponse,
+) -> VaultResponse {
+    if changes_vault(request) {
+        let (vault_id, operation) = audit_subject(request);
+        let started = &AuditRecord {
+            vault_id: vault_id.clone(),
+            operation,
+            outcome: AuditOutcome::Started,
+            transaction_ids: Vec::new(),
+        };
+        if let Err(err) = append_audit_entry(started, now_timestamp()) {
+            let message = format!("failed to record vault operation in audit log: {}", err);
+            println!("audit_vault_operation: {}", message);
+            return failed_response_for(request, message);
+        }
+    }
+
+    let response = operation();
+    let appended = audit_record_for(request, &response)
+        .and_then(|record| append_audit_entry(&record, now_timestamp()));
+    match appended {
+        Ok(_) => response,
+        Err(err) => {
+            let message = format!("failed to record vault operation in audit log: {}", err);
+            println!("audit_vault_operation: {}", message);
+            failed_response_for(request, message)
+        }
+    }
+}
+
+/// Whether `request` may change the stored vault, rather than only read it.
+pub fn changes_vault(request: &VaultRequest) -> bool {
+    matches!(
+        request,
+        VaultRequest::CreateVault(_)
+            | VaultRequest::ImportAlgorandAccount(_)
+            | VaultRequest::UpdateVaultPolicy(_)
+    )
+}
+
+/// Append `record` to its vault's audit log, and return the new entry.
+///
+/// Return [`None`] if the vault does not exist: only operations on existing vaults are logged,
+/// except for operations that create the vault, which start its log.
+///
+/// Also return [`None`] for an [`AuditOutcome::InvalidAuth`] record if the log's latest entry
+/// is one too, from less than [`INVALID_AUTH_LOG_INTERVAL_SECONDS`] earlier.
+pub fn append_audit_entry(
+    record: &AuditRecord,
+    timestamp: UnixTimestamp,
+) -> Result<Option<AuditEntry>, AuditLogError> {
+    let mut store = audit_log_store();
+    let key = &key_from_id(&record.vault_id)?;
+    loop {
+        let (version, mut log) = match store.load_versioned(key)? {
+            Some((version, log)) => (Some(version), log),
+            None if creates_vault(record.operation) || load_vault(&record.vault_id)?.is_some() => {
+                (None, AuditLog::default())
+            }
+            None => return Ok(None),
+        };
+        if record.outcome == AuditOutcome::InvalidAuth && invalid_auth_logged_since(&log, timestamp)
+        {
+            return Ok(None);
+        }
+        let entry = next_audit_entry(&log, record, timestamp)?;
+        close_full_segments(key, &mut log)?;
+        log.entries.push(entry.clone());
+        // Retry if another operation appended concurrently.
+        if store.compare_and_swap(key, version, Some(&log))? {
+            return Ok(Some(entry));
+        }
+    }
+}
+
+/// Whether the latest entry of `log` is an [`AuditOutcome::InvalidAuth`] entry
+/// from less than [`INVALID_AUTH_LOG_INTERVAL_SECONDS`] before `timestamp`.
+fn invalid_auth_logged_since(log: &AuditLog, timestamp: UnixTimestamp) -> bool {
+    log.entries.last().map_or(false, |last| {
+        last.outcome == AuditOutcome::InvalidAuth
+            && timestamp.saturating_sub(last.timestamp) < INVALID_AUTH_LOG_INTERVAL_SECONDS
+    })
+}
+
+/// Move the latest entries in `log` into a closed segment, if there are enough of them.
+///
+/// The closed segment is saved before `log` is, so an interrupted append leaves the same
+/// entries in both, and the next append saves the same closed segment again.
+fn close_full_segments(vault_key: &Key, log: &mut AuditLog) -> Result<(), AuditLogError> {
+    let full_len = log.entries.len() - log.entries.len() % AUDIT_LOG_SEGMENT_LEN as usize;
+    if full_len == 0 {
+        return Ok(());
+    }
+    let mut segment_store = audit_log_segment_store();
+    for segment in log.entries[..full_len].chunks(AUDIT_LOG_SEGMENT_LEN as usize) {
+        let key = &segment_key(vault_key, segment[0].sequence);
+        segment_store.save(
+            key,
+            &AuditLog {
+                entries: segment.to_vec(),
+            },
+        )?;
+    }
+    log.entries.drain(..full_len);
+    Ok(())
+}
+
+fn creates_vault(operation: AuditOperation) -> bool {
+    matches!(
+        operation,
+        AuditOperation::CreateVault | AuditOperation::ImportAlgorandAccount
+    )
+}
+
+/// Load a vault's whole audit log, and check its hash chain.
+///
+/// Vaults that have no logged operations yet have an empty log.
+pub fn load_audit_log(vault_id: &str) -> Result<AuditLog, AuditLogError> {
+    let mut log = AuditLog::default();
+    let mut next_sequence = Some(0);
+    while let Some(start_sequence) = next_sequence {
+        let page = load_audit_log_page(vault_id, start_sequence)?;
+        log.entries.extend(page.entries);
+        next_sequence = page.next_sequence;
+    }
+    verify_audit_log(&log)?;
+    Ok(log)
+}
+
+/// Load the entries of a vault's audit log from `start_sequence` to the end of their segment.
+///
+/// This checks the hash chain of the page's segment, and its link to the previous segment.
+pub fn load_audit_log_page(
+    vault_id: &str,
+    start_sequence: u64,
+) -> Result<AuditLogPage, AuditLogError> {
+    let key = &key_from_id(vault_id)?;
+    let latest = audit_log_store().load(key)?.unwrap_or_default();
+    let latest_start = latest.entries.first().map_or(0, |first| first.sequence);
+
+    let segment_start = start_sequence - start_sequence % AUDIT_LOG_SEGMENT_LEN;
+    let (segment, next_sequence) = match segment_start.cmp(&latest_start) {
+        Ordering::Less => (
+            load_closed_segment(key, segment_start)?,
+            Some(segment_start + AUDIT_LOG_SEGMENT_LEN),
+        ),
+        Ordering::Equal => (latest, None),
+        // Past the end of the log.
+        Ordering::Greater => return Ok(AuditLogPage::default()),
+    };
+    let previous_hash = match segment_start {
+        0 => [0; 32],
+        _ => {
+            let previous = load_closed_segment(key, segment_start - AUDIT_LOG_SEGMENT_LEN)?;
+            previous
+                .entries
+                .last()
+                .map_or([0; 32], |last| last.entry_hash)
+        }
+    };
+    verify_audit_entries(&segment.entries, segment_start, previous_hash)?;
+
+    Ok(AuditLogPage {
+        entries: segment
+            .entries
+            .into_iter()
+            .filter(|entry| start_sequence <= entry.sequence)
+            .collect(),
+        next_sequence,
+    })
+}
+
+fn load_closed_segment(vault_key: &Key, first_sequence: u64) -> Result<AuditLog, AuditLogError> {
+    audit_log_segment_store()
+        .load(&segment_key(vault_key, first_sequence))?
+        .ok_or(AuditLogError::BrokenChain {
+            sequence: first_sequence,
+            reason: "log segment is missing",
+        })
+}
+
+/// Check the hash chain of `log`.
+pub fn verify_audit_log(log: &AuditLog) -> Result<(), AuditLogError> {
+    verify_audit_entries(&log.entries, 0, [0; 32])
+}
+
+/// Check the hash chain of `entries`, starting at `first_sequence` after `previous_hash`.
+pub fn verify_audit_entries(
+    entries: &[AuditEntry],
+    first_sequence: u64,
+    mut previous_hash: AuditHash,
+) -> Result<(), AuditLogError> {
+    for (sequence, entry) in (first_sequence..).zip(entries) {
+        let broken = |reason| AuditLogError::BrokenChain { sequence, reason };
+        if entry.sequence != sequence {
+            return Err(broken("out of sequence"));
+        }
+        if entry.previous_hash != previous_hash {
+            return Err(broken("does not chain to the previous entry"));
+        }
+        if entry.entry_hash != audit_entry_hash(entry)? {
+            return Err(broken("entry hash mismatch"));
+        }
+        previous_hash = entry.entry_hash;
+    }
+    Ok(())
+}
+
+/// Hash `entry`'s fields, as described in the [module documentation](self).
+pub fn audit_entry_hash(entry: &AuditEntry) -> Result<AuditHash, AuditLogError> {
+    let mut hash_input = Vec::with_capacity(256);
+    hash_input.extend_from_slice(&entry.previous_hash);
+    hash_input.extend_from_slice(&entry.sequence.to_be_bytes());
+    hash_input.extend_from_slice(&entry.timestamp.to_be_bytes());
+    for field in [entry.operation.as_str(), entry.outcome.as_str()]
+        .into_iter()
+        .chain(entry.transaction_ids.iter().map(String::as_str))
+    {
+        hash_input.extend_from_slice(field.as_bytes());
+        hash_input.push(0);
+    }
+    Ok(rsgx_sha256_slice(&hash_input)?)
+}
+
+fn next_audit_entry(
+    log: &AuditLog,
+    record: &AuditRecord,
+    timestamp: UnixTimestamp,
+) -> Result<AuditEntry, AuditLogError> {
+    let mut entry = AuditEntry {
+        sequence: log.entries.last().map_or(0, |last| last.sequence + 1),
+        timestamp,
+        operation: record.operation,
+        outcome: record.outcome,
+        transaction_ids: record.transaction_ids.clone(),
+        previous_hash: log.entries.last().map_or([0; 32], |last| last.entry_hash),
+        entry_hash: [0; 32],
+    };
+    entry.entry_hash = audit_entry_hash(&entry)?;
+    Ok(entry)
+}
+
+/// The host's current time.
+pub(crate) fn now_timestamp() -> UnixTimestamp {
+    // A host clock set before the epoch gets recorded as the epoch.
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Describe the operation of `request`, with its outcome from `response`.
+pub fn audit_record_for(
+    request: &VaultRequest,
+    response: &VaultResponse,
+) -> Result<AuditRecord, AuditLogError> {
+    let (vault_id, operation) = audit_subject(request);
+    let transaction_ids = match response {
+        VaultResponse::SignTransaction(SignTransactionResult::Signed(signed)) => {
+            signed_transaction_ids(signed).map_err(AuditLogError::TransactionIds)?
+        }
+        _ => Vec::new(),
+    };
+    Ok(AuditRecord {
+        vault_id: vault_id.clone(),
+        operation,
+        outcome: audit_outcome(response),
+        transaction_ids,
+    })
+}
+
+/// The vault that `request` operates on, and its type of operation.
+pub fn audit_subject(request: &VaultRequest) -> (&VaultId, AuditOperation) {
+    match request {
+        VaultRequest::CreateVault(request) => (&request.username, AuditOperation::CreateVault),
+        VaultRequest::OpenVault(request) => (&request.vault_id, AuditOperation::OpenVault),
+        VaultRequest::SignTransaction(request) => {
+            (&request.vault_id, AuditOperation::SignTransaction)
+        }
+        VaultRequest::ImportAlgorandAccount(request) => {
+            (&request.username, AuditOperation::ImportAlgorandAccount)
+        }
+        VaultRequest::ExportAlgorandAccount(request) => {
+            (&request.vault_id, AuditOperation::ExportAlgorandAccount)
+        }
+        VaultRequest::UpdateVaultPolicy(request) => {
+            (&request.vault_id, AuditOperation::UpdateVaultPolicy)
+        }
+        VaultRequest::SummarizeTransaction(request) => {
+            (&request.vault_id, AuditOperation::SummarizeTransaction)
+        }
+        VaultRequest::GetAuditLog(request) => (&request.vault_id, AuditOperation::GetAuditLog),
+    }
+}
+
+/// How the operation that produced `response` turned out.
+pub fn audit_outcome(response: &VaultResponse) -> AuditOutcome {
+    use AuditOutcome::*;
+
+    match response {
+        VaultResponse::CreateVault(CreateVaultResult::Created(_))
+        | VaultResponse::OpenVault(OpenVaultResult::Opened(_))
+        | VaultResponse::SignTransaction(SignTransactionResult::Signed(_))
+        | VaultResponse::ImportAlgorandAccount(ImportAlgorandAccountResult::Imported(_))
+        | VaultResponse::ExportAlgorandAccount(ExportAlgorandAccountResult::Exported(_))
+        | VaultResponse::UpdateVaultPolicy(UpdateVaultPolicyResult::Updated(_))
+        | VaultResponse::SummarizeTransaction(SummarizeTransactionResult::Summarized(_))
+        | VaultResponse::GetAuditLog(GetAuditLogResult::Retrieved(_)) => Succeeded,
+
+        VaultResponse::OpenVault(OpenVaultResult::InvalidAuth)
+        | VaultResponse::SignTransaction(SignTransactionResult::InvalidAuth)
+        | VaultResponse::ExportAlgorandAccount(ExportAlgorandAccountResult::InvalidAuth)
+        | VaultResponse::UpdateVaultPolicy(UpdateVaultPolicyResult::InvalidAuth)
+        | VaultResponse::SummarizeTransaction(SummarizeTransactionResult::InvalidAuth)
+        | VaultResponse::GetAuditLog(GetAuditLogResult::InvalidAuth) => InvalidAuth,
+
+        VaultResponse::SignTransaction(SignTransactionResult::PolicyViolation(_))
+        | VaultResponse::ExportAlgorandAccount(ExportAlgorandAccountResult::ExportDisabled)
+        | VaultResponse::UpdateVaultPolicy(UpdateVaultPolicyResult::WouldLoosen(_)) => Refused,
+
+        VaultResponse::CreateVault(CreateVaultResult::Failed(_))
+        | VaultResponse::OpenVault(OpenVaultResult::Failed(_))
+        | VaultResponse::SignTransaction(SignTransactionResult::Failed(_))
+        | VaultResponse::ImportAlgorandAccount(ImportAlgorandAccountResult::Failed(_))
+        | VaultResponse::ExportAlgorandAccount(ExportAlgorandAccountResult::Failed(_))
+        | VaultResponse::UpdateVaultPolicy(UpdateVaultPolicyResult::Failed(_))
+        | VaultResponse::SummarizeTransaction(SummarizeTransactionResult::Failed(_))
+        | VaultResponse::GetAuditLog(GetAuditLogResult::Failed(_)) => Failed,
+    }
+}
+
+/// The network IDs of the transactions in `signed`: none, for plain signatures.
+fn signed_transaction_ids(signed: &TransactionSigned) -> Result<Vec<String>, String> {
+    match signed {
+        TransactionSigned::AlgorandTransactionSigned {
+            signed_transaction_bytes,
+        } => Ok(vec![algorand_transaction_id(signed_transaction_bytes)?]),
+        TransactionSigned::AlgorandTransactionGroupSigned {
+            signed_transactions,
+        } => signed_transactions
+            .iter()
+            .map(|signed| algorand_transaction_id(&signed.signed_transaction_bytes))
+            .collect(),
+        TransactionSigned::Ed25519Signature { .. } => Ok(vec![]),
+        TransactionSigned::EthereumTransactionSigned {
+            signed_transaction_bytes,
+        } => {
+            let hash = Keccak256::digest(signed_transaction_bytes);
+            Ok(vec![format!("0x{}", hex::encode(hash))])
+        }
+    }
+}
+
+fn algorand_transaction_id(signed_transaction_bytes: &[u8]) -> Result<String, String> {
+    let signed: SignedTransaction =
+        algorand_network_compatible::from_msgpack(signed_transaction_bytes).map_err(|err| {
+            errors::message_with_base64(
+                "audit_vault_operation",
+                "failed to unpack signed transaction",
+                err,
+                "signed transaction msgpack",
+                signed_transaction_bytes,
+            )
+        })?;
+    signed.transaction.id().map_err(|err| {
+        errors::message_with_debug_value(
+            "audit_vault_operation",
+            "algonaut error while computing transaction ID",
+            err,
+            "signed transaction",
+            &signed,
+        )
+    })
+}
+
+/// A failure response of the type that `request` gets.
+fn failed_response_for(request: &VaultRequest, message: String) -> VaultResponse {
+    match request {
+        VaultRequest::CreateVault(_) => CreateVaultResult::Failed(message).into(),
+        VaultRequest::OpenVault(_) => OpenVaultResult::Failed(message).into(),
+        VaultRequest::SignTransaction(_) => SignTransactionResult::Failed(message).into(),
+        VaultRequest::ImportAlgorandAccount(_) => {
+            ImportAlgorandAccountResult::Failed(message).into()
+        }
+        VaultRequest::ExportAlgorandAccount(_) => {
+            ExportAlgorandAccountResult::Failed(message).into()
+        }
+        VaultRequest::UpdateVaultPolicy(_) => UpdateVaultPolicyResult::Failed(message).into(),
+        VaultRequest::SummarizeTransaction(_) => SummarizeTransactionResult::Failed(message).into(),
+        VaultRequest::GetAuditLog(_) => GetAuditLogResult::Failed(message).into(),
+    }
+}
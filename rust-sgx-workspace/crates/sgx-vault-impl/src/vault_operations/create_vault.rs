@@ -1,24 +1,47 @@
+use std::io;
 use std::prelude::v1::ToString;
 
+use crate::ported::kv_store::KvStore;
 use crate::schema::actions::{CreateVault, CreateVaultResult};
-use crate::schema::entities::{AlgorandAccount, VaultDisplay, VaultStorable};
-use crate::vault_operations::store::save_new_vault;
+use crate::schema::entities::{
+    AlgorandAccount,
+    EthereumAccount,
+    VaultDisplay,
+    VaultPolicy,
+    VaultStorable,
+    VAULT_SCHEMA_VERSION,
+};
+use crate::vault_operations::store::{save_new_vault_in, vault_store};
 
 type Result = CreateVaultResult;
 
 pub fn create_vault(request: &CreateVault) -> Result {
+    create_vault_in(&mut vault_store(), request)
+}
+
+/// [`create_vault`], in `store`.
+pub fn create_vault_in<S>(store: &mut S, request: &CreateVault) -> Result
+where
+    S: KvStore<VaultStorable>,
+    io::Error: From<S::Error>,
+{
     // TODO(Pi): Pull account / keypair creation into a separate operation.
-    //           For now, just generate Algorand keypairs.
+    //           For now, just generate Algorand and Ethereum keypairs.
     let new_algorand_account = AlgorandAccount::generate();
 
     let storable = VaultStorable {
+        schema_version: VAULT_SCHEMA_VERSION,
+
         vault_id: request.username.clone(),
         username: request.username.clone(),
         auth_password: request.auth_password.clone(),
 
         algorand_account: new_algorand_account,
+        ethereum_account: Some(EthereumAccount::generate()),
+
+        policy: VaultPolicy::default(),
     };
-    match save_new_vault(&storable) {
+    match save_new_vault_in(store, &storable) {
         Ok(()) => Result::Created(VaultDisplay::from(storable)),
         Err(err) => Result::Failed(err.to_string()),
     }
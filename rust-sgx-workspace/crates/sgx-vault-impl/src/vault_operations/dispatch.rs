@@ -1,67 +1,185 @@
 use std::error::Error;
+use std::fmt::Debug;
 use std::prelude::v1::Box;
 
 use secrecy::{ExposeSecret, Secret};
+use serde::Serialize;
+use thiserror::Error;
 
-use crate::ported::crypto::SecretBytes;
+use crate::ported::crypto::{BoxAlgorithm, PublicKey, SecretBytes};
 use crate::schema::actions::{VaultRequest, VaultResponse};
+use crate::schema::entities::{AuditOperation, AuditOutcome};
 use crate::schema::msgpack::{FromMessagePack, ToMessagePack};
 use crate::schema::sealing::{seal_from_enclave, unseal_to_enclave, SealedMessage};
+use crate::schema::session::{
+    seal_session_response,
+    unseal_session_request,
+    SessionCounter,
+    SessionId,
+    SessionKeys,
+    SessionSealedMessage,
+};
+use crate::vault_operations::audit_log::{audit_outcome, audit_subject, audit_vault_operation};
 use crate::vault_operations::create_vault::create_vault;
 use crate::vault_operations::errors;
+use crate::vault_operations::export_algorand_account::export_algorand_account;
+use crate::vault_operations::get_audit_log::get_audit_log;
+use crate::vault_operations::import_algorand_account::import_algorand_account;
 use crate::vault_operations::open_vault::open_vault;
+use crate::vault_operations::sessions::{accept_request_counter, session_keys};
 use crate::vault_operations::sign_transaction::sign_transaction;
+use crate::vault_operations::summarize_transaction::summarize_transaction;
+use crate::vault_operations::update_vault_policy::update_vault_policy;
 
 /// Implementation for [`crate::ecalls::vault_operation::vault_operation`].
 ///
 /// This processes an exchange of the following:
 ///
-/// Request: [`SealedMessage`] of [`VaultRequest`]
+/// Request: [`SealedMessage`] or [`SessionSealedMessage`] of [`VaultRequest`]
 ///
-/// Response: [`SealedMessage`] of [`VaultResponse`]
+/// Response: [`SealedMessage`] or [`SessionSealedMessage`] of [`VaultResponse`], like the request
 ///
-pub fn vault_operation_impl(sealed_request_bytes: &[u8]) -> Box<[u8]> {
-    match vault_operation_impl_sealing(sealed_request_bytes) {
-        Ok(sealed_response_bytes) => sealed_response_bytes,
+/// Also return the request's [`OperationLabel`], for the host's metrics.
+///
+/// # Errors
+///
+/// [`InvalidRequest`] if the request can't be unpacked, unsealed, or decoded,
+/// or if its session is not held (see [`crate::vault_operations::sessions`]).
+/// The request has no effect in this case.
+///
+pub fn vault_operation_impl(
+    sealed_request_bytes: &[u8],
+) -> Result<(Box<[u8]>, OperationLabel), InvalidRequest> {
+    let (reply_to, vault_request) =
+        unseal_vault_request(sealed_request_bytes).map_err(InvalidRequest)?;
+    match vault_operation_impl_sealing(&reply_to, vault_request.expose_secret()) {
+        Ok(sealed_response_and_label) => Ok(sealed_response_and_label),
         Err(error) => panic!("{}", error), // FIXME: better reporting
     }
 }
 
-/// Handle sealing and unsealing the exchange.
-fn vault_operation_impl_sealing(sealed_request_bytes: &[u8]) -> Result<Box<[u8]>, Box<dyn Error>> {
-    // Unseal request
-    let sealed_request = &SealedMessage::from_msgpack(sealed_request_bytes).map_err(|err| {
+/// The type of a vault operation, and how it turned out.
+///
+/// Unlike the request and response, this is not sealed: the host labels its metrics with it.
+/// It says no more than the operation's [`crate::schema::entities::AuditEntry`] without its
+/// timestamp and transaction IDs, and nothing about the vault, the amounts, or the secrets.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)] // core
+pub struct OperationLabel {
+    pub operation: AuditOperation,
+    pub outcome: AuditOutcome,
+}
+
+/// [`vault_operation_impl`] received a request it can't process.
+#[derive(Debug, Error)]
+#[error("invalid vault operation request: {0}")]
+pub struct InvalidRequest(pub(crate) Box<dyn Error>);
+
+/// How to seal the response to a request: the same way as the request.
+enum ReplyTo {
+    /// Seal from the enclave's long-term key to the sender's public key,
+    /// with the request's [`SealedMessage::version`].
+    PublicKey(PublicKey, BoxAlgorithm),
+
+    /// Seal with the session's keys, for the request with this counter.
+    Session(SessionId, SessionCounter, SessionKeys),
+}
+
+/// Handle unsealing the request.
+fn unseal_vault_request(
+    sealed_request_bytes: &[u8],
+) -> Result<(ReplyTo, Secret<VaultRequest>), Box<dyn Error>> {
+    let (reply_to, request_bytes) = match SessionSealedMessage::from_msgpack(sealed_request_bytes) {
+        Ok(sealed_request) => unseal_session_vault_request(&sealed_request)?,
+        Err(_) => unseal_public_key_vault_request(sealed_request_bytes)?,
+    };
+    let vault_request = Secret::new(
+        VaultRequest::from_msgpack(request_bytes.expose_secret()).map_err(|err| {
+            errors::message_with_base64(
+                "unseal_vault_request",
+                "invalid VaultReq",
+                err,
+                "unsealed VaultRequest msgpack",
+                request_bytes.expose_secret(),
+            )
+        })?,
+    );
+    Ok((reply_to, vault_request))
+}
+
+/// Unseal a [`SealedMessage`] request.
+fn unseal_public_key_vault_request(
+    sealed_request_bytes: &[u8],
+) -> Result<(ReplyTo, SecretBytes), Box<dyn Error>> {
+    let sealed_request = SealedMessage::from_msgpack(sealed_request_bytes).map_err(|err| {
         errors::message_with_base64(
-            "vault_operation_impl_sealing",
+            "unseal_vault_request",
             "failed to unpack received sealed request",
             err,
             "sealed request msgpack",
             sealed_request_bytes,
         )
     })?;
-    let request_bytes = &unseal_to_enclave(sealed_request).map_err(|err| {
+    let request_bytes = unseal_to_enclave(&sealed_request).map_err(|err| {
         errors::message_with_debug_value(
-            "vault_operation_impl_sealing",
+            "unseal_vault_request",
             "failed to unseal request",
             err,
             "sealed request",
+            &sealed_request,
+        )
+    })?;
+    let reply_to = ReplyTo::PublicKey(sealed_request.sender_public_key, sealed_request.version);
+    Ok((reply_to, request_bytes))
+}
+
+/// Unseal a [`SessionSealedMessage`] request.
+fn unseal_session_vault_request(
+    sealed_request: &SessionSealedMessage,
+) -> Result<(ReplyTo, SecretBytes), Box<dyn Error>> {
+    let keys = session_keys(&sealed_request.session_id).ok_or_else(|| {
+        errors::message_with_debug_value(
+            "unseal_vault_request",
+            "unknown session (expired, or evicted)",
+            "no session keys",
+            "sealed request",
             sealed_request,
         )
     })?;
-    let vault_request = &Secret::new(
-        VaultRequest::from_msgpack(request_bytes.expose_secret()).map_err(|err| {
-            errors::message_with_base64(
-                "vault_operation_impl_sealing",
-                "invalid VaultReq",
-                err,
-                "unsealed VaultRequest msgpack",
-                request_bytes.expose_secret(),
-            )
-        })?,
-    );
+    let request_bytes = unseal_session_request(sealed_request, &keys).map_err(|err| {
+        errors::message_with_debug_value(
+            "unseal_vault_request",
+            "failed to unseal session request",
+            err,
+            "sealed request",
+            sealed_request,
+        )
+    })?;
+    // Only an authenticated request advances the counter.
+    accept_request_counter(&sealed_request.session_id, sealed_request.counter).map_err(|err| {
+        errors::message_with_debug_value(
+            "unseal_vault_request",
+            "replayed or reordered session request",
+            err,
+            "sealed request",
+            sealed_request,
+        )
+    })?;
+    let reply_to = ReplyTo::Session(sealed_request.session_id, sealed_request.counter, keys);
+    Ok((reply_to, request_bytes))
+}
 
-    // Dispatch
-    let vault_response = vault_operation_impl_dispatch(vault_request.expose_secret());
+/// Handle dispatching the request, and sealing the response.
+fn vault_operation_impl_sealing(
+    reply_to: &ReplyTo,
+    vault_request: &VaultRequest,
+) -> Result<(Box<[u8]>, OperationLabel), Box<dyn Error>> {
+    // Dispatch, and record the operation before its response leaves the enclave.
+    let vault_response =
+        audit_vault_operation(vault_request, || vault_operation_impl_dispatch(vault_request));
+    let label = OperationLabel {
+        operation: audit_subject(vault_request).1,
+        outcome: audit_outcome(&vault_response),
+    };
 
     // Seal response
     let response_bytes = &SecretBytes::new(vault_response.to_msgpack().map_err(|err| {
@@ -73,16 +191,34 @@ fn vault_operation_impl_sealing(sealed_request_bytes: &[u8]) -> Result<Box<[u8]>
             vault_response,
         )
     })?);
-    let sealed_response = seal_from_enclave(response_bytes, &sealed_request.sender_public_key)
-        .map_err(|err| {
-            errors::message_with_base64(
-                "vault_operation_impl_sealing",
-                "failed to seal packed VaultResponse",
-                err,
-                "unsealed VaultResponse msgpack",
-                response_bytes.expose_secret(),
-            )
-        })?;
+    let seal_error = |err| {
+        errors::message_with_base64(
+            "vault_operation_impl_sealing",
+            "failed to seal packed VaultResponse",
+            err,
+            "unsealed VaultResponse msgpack",
+            response_bytes.expose_secret(),
+        )
+    };
+    let sealed_response_bytes = match reply_to {
+        ReplyTo::PublicKey(sender_public_key, version) => {
+            let sealed_response = seal_from_enclave(*version, response_bytes, sender_public_key)
+                .map_err(seal_error)?;
+            sealed_response_to_msgpack(&sealed_response)?
+        }
+        ReplyTo::Session(session_id, request_counter, keys) => {
+            let sealed_response =
+                seal_session_response(response_bytes, *session_id, *request_counter, keys)
+                    .map_err(seal_error)?;
+            sealed_response_to_msgpack(&sealed_response)?
+        }
+    };
+    Ok((sealed_response_bytes, label))
+}
+
+fn sealed_response_to_msgpack(
+    sealed_response: &(impl Serialize + Debug),
+) -> Result<Box<[u8]>, Box<dyn Error>> {
     let sealed_response_bytes = sealed_response.to_msgpack().map_err(|err| {
         errors::message_with_debug_value(
             "vault_operation_impl_sealing",
@@ -107,5 +243,10 @@ fn vault_operation_impl_dispatch(vault_request: &VaultRequest) -> VaultResponse
         VaultRequest::CreateVault(request) => create_vault(request).into(),
         VaultRequest::OpenVault(request) => open_vault(request).into(),
         VaultRequest::SignTransaction(request) => sign_transaction(request).into(),
+        VaultRequest::ImportAlgorandAccount(request) => import_algorand_account(request).into(),
+        VaultRequest::ExportAlgorandAccount(request) => export_algorand_account(request).into(),
+        VaultRequest::UpdateVaultPolicy(request) => update_vault_policy(request).into(),
+        VaultRequest::SummarizeTransaction(request) => summarize_transaction(request).into(),
+        VaultRequest::GetAuditLog(request) => get_audit_log(request).into(),
     }
 }
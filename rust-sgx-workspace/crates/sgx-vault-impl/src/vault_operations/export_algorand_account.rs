@@ -0,0 +1,25 @@
+//! Implement [`ExportAlgorandAccount`].
+
+use crate::schema::actions::{
+    AlgorandAccountExported,
+    ExportAlgorandAccount,
+    ExportAlgorandAccountResult,
+};
+use crate::vault_operations::store::unlock_vault;
+
+type Result = ExportAlgorandAccountResult;
+
+pub fn export_algorand_account(request: &ExportAlgorandAccount) -> Result {
+    let stored = match unlock_vault(&request.vault_id, &request.auth_password) {
+        Ok(stored) => stored,
+        Err(err) => return err.into(),
+    };
+
+    if !stored.policy.allow_algorand_account_export {
+        return Result::ExportDisabled;
+    }
+
+    Result::Exported(AlgorandAccountExported {
+        mnemonic: stored.algorand_account.to_mnemonic(),
+    })
+}
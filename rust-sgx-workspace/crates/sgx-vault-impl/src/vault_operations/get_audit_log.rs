@@ -0,0 +1,20 @@
+//! Implement [`GetAuditLog`].
+
+use std::prelude::v1::ToString;
+
+use crate::schema::actions::{GetAuditLog, GetAuditLogResult};
+use crate::vault_operations::audit_log::load_audit_log_page;
+use crate::vault_operations::store::unlock_vault;
+
+type Result = GetAuditLogResult;
+
+pub fn get_audit_log(request: &GetAuditLog) -> Result {
+    if let Err(err) = unlock_vault(&request.vault_id, &request.auth_password) {
+        return err.into();
+    }
+
+    match load_audit_log_page(&request.vault_id, request.start_sequence) {
+        Ok(page) => Result::Retrieved(page),
+        Err(err) => Result::Failed(err.to_string()),
+    }
+}
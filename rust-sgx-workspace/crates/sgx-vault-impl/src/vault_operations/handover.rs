@@ -0,0 +1,300 @@
+//! Hand over vault records to a successor enclave, using local attestation.
+//!
+//! This is for records that the successor cannot unseal itself, such as records bound to
+//! the old enclave's MRENCLAVE. Both enclaves must run on the same platform, and share
+//! the same signer (MRSIGNER) and product ID. The untrusted host drives the flow:
+//!
+//! 1. Old enclave: [`rsgx_self_target`], for the new enclave to target its report.
+//! 2. New enclave: [`handover_begin`] for the old enclave's target info.
+//!    This starts a handover with a fresh nonce, and binds the new enclave's public key
+//!    and the nonce to its report.
+//! 3. Old enclave: [`handover_export`] for each record.
+//!    This verifies the new enclave's report, and seals the record to its public key,
+//!    with a report of its own that binds the old enclave's public key, the nonce,
+//!    and the sealed record's digest.
+//! 4. New enclave: [`handover_import`] for each record.
+//!    This verifies the old enclave's report and its binding to the current handover,
+//!    unseals the record, upgrades its schema, and saves it under the new enclave's
+//!    key policy, unless the new enclave already has a record with the same vault ID.
+//!
+//! The new enclave can't read or replace the records it takes over, so the host points it
+//! at an empty staging store for the import, and swaps that store in once it is complete.
+//!
+//! The nonce stops the host from replaying records exported in an earlier handover,
+//! and refusing to overwrite stops it from rolling back records that the new enclave
+//! has already taken over.
+//!
+//! [`rsgx_self_target`]: sgx_tse::rsgx_self_target
+
+use std::boxed::Box;
+use std::error::Error as StdError;
+use std::io;
+use std::prelude::v1::Vec;
+use std::sync::{PoisonError, SgxMutex, SgxMutexGuard};
+
+use lazy_static::lazy_static;
+use sgx_tcrypto::rsgx_sha256_slice;
+use sgx_tse::{rsgx_create_report, rsgx_self_report, rsgx_verify_report};
+use sgx_types::{
+    sgx_report_body_t,
+    sgx_report_data_t,
+    sgx_report_t,
+    sgx_sha256_hash_t,
+    sgx_status_t,
+    sgx_target_info_t,
+    SGX_FLAGS_DEBUG,
+};
+use thiserror::Error;
+
+use crate::ported::attestation::create_report_with_user_data;
+use crate::ported::crypto::{fill_random, PublicKey, SodaBoxCrypto};
+use crate::ported::kv_store::fs::FsStoreError;
+use crate::ported::kv_store::{Key, KvStore};
+use crate::schema::entities::VaultStorable;
+use crate::schema::msgpack::FromMessagePack;
+use crate::schema::sealing::{seal_msgpack, unseal_non_secret_msgpack, SealedMessage};
+use crate::vault_operations::migration::{upgrade_vault_schema, UnsupportedSchemaVersion};
+use crate::vault_operations::store::{key_from_id, vault_store};
+
+#[derive(Debug, Error)]
+pub enum HandoverError {
+    #[error("peer enclave report failed verification: {0:?}")]
+    InvalidReport(sgx_status_t),
+
+    #[error("peer enclave is not a valid handover {role}: {reason}")]
+    UntrustedPeer {
+        role: &'static str,
+        reason: &'static str,
+    },
+
+    #[error("peer enclave report does not bind its public key")]
+    PublicKeyMismatch,
+
+    #[error("no handover started: call handover_begin first")]
+    NotStarted,
+
+    #[error("predecessor report does not bind this record to the current handover")]
+    RecordBindingMismatch,
+
+    #[error("vault record not found")]
+    NotFound,
+
+    #[error("vault record already exists: refusing to overwrite it")]
+    AlreadyExists,
+
+    #[error("failed to seal or unseal vault record: {0}")]
+    Sealing(Box<dyn StdError>),
+
+    #[error(transparent)]
+    UnsupportedSchemaVersion(#[from] UnsupportedSchemaVersion),
+
+    #[error("I/O error during handover")]
+    IoError(#[from] io::Error),
+
+    #[error(transparent)]
+    StoreError(#[from] FsStoreError),
+
+    #[error("SGX error during handover: {0:?}")]
+    Sgx(sgx_status_t),
+}
+
+impl From<sgx_status_t> for HandoverError {
+    fn from(status: sgx_status_t) -> Self {
+        HandoverError::Sgx(status)
+    }
+}
+
+/// A per-handover nonce, chosen by the successor enclave.
+pub type HandoverNonce = [u8; 32];
+
+/// Domain separation for [`record_binding`].
+///
+/// This keeps the binding distinct from the `sha256(pubkey)` that every other report binds.
+const RECORD_BINDING_LABEL: &[u8] = b"NTC-HANDOVER-RECORD";
+
+lazy_static! {
+    /// The nonce of the handover in progress, if any.
+    static ref HANDOVER_NONCE: SgxMutex<Option<HandoverNonce>> = SgxMutex::new(None);
+}
+
+/// New enclave: Start a handover from the predecessor enclave with `predecessor_target_info`.
+///
+/// This replaces the nonce of any handover in progress, so only records exported
+/// for this report can be imported.
+///
+/// Return this enclave's public key, and its report for the predecessor.
+pub fn handover_begin(
+    predecessor_target_info: &sgx_target_info_t,
+) -> Result<(PublicKey, sgx_report_t), HandoverError> {
+    let mut nonce = HandoverNonce::default();
+    fill_random(&mut nonce).map_err(|err| HandoverError::Sealing(err.into()))?;
+    let (public_key, report) = create_report_with_user_data(predecessor_target_info, &nonce)?;
+    *lock_handover_nonce() = Some(nonce);
+    Ok((public_key, report))
+}
+
+/// Old enclave: Export the record at `key` to the successor enclave.
+///
+/// Return the sealed record, and this enclave's report for the successor.
+pub fn handover_export(
+    successor_report: &sgx_report_t,
+    successor_public_key: &PublicKey,
+    key: &Key,
+) -> Result<(Box<[u8]>, sgx_report_t), HandoverError> {
+    verify_peer(successor_report, successor_public_key, PeerRole::Successor)?;
+
+    let store = vault_store();
+    let stored: VaultStorable = store.load(key)?.ok_or(HandoverError::NotFound)?;
+
+    let enclave_crypto =
+        &mut SodaBoxCrypto::new().map_err(|err| HandoverError::Sealing(err.into()))?;
+    let sealed_record = seal_msgpack(&stored, successor_public_key, enclave_crypto)
+        .map_err(HandoverError::Sealing)?;
+
+    let mut nonce = HandoverNonce::default();
+    nonce.copy_from_slice(&successor_report.body.report_data.d[32..]);
+    let mut report_data = sgx_report_data_t::default();
+    report_data.d[..32].copy_from_slice(&record_binding(
+        &enclave_crypto.get_pubkey(),
+        &nonce,
+        &sealed_record,
+    )?);
+    let report = rsgx_create_report(&target_info_for(successor_report), &report_data)?;
+    Ok((sealed_record, report))
+}
+
+/// New enclave: Import a record exported by [`handover_export`], for the current handover.
+///
+/// This refuses to replace any existing record with the same vault ID.
+pub fn handover_import(
+    predecessor_report: &sgx_report_t,
+    sealed_record: &[u8],
+) -> Result<(), HandoverError> {
+    let nonce = (*lock_handover_nonce()).ok_or(HandoverError::NotStarted)?;
+    let sealed_message = SealedMessage::from_msgpack(sealed_record)
+        .map_err(|err| HandoverError::Sealing(err.into()))?;
+    verify_peer_report(predecessor_report, PeerRole::Predecessor)?;
+    let binding = record_binding(&sealed_message.sender_public_key, &nonce, sealed_record)?;
+    if predecessor_report.body.report_data.d[..32] != binding[..] {
+        return Err(HandoverError::RecordBindingMismatch);
+    }
+
+    let enclave_crypto = &SodaBoxCrypto::new().map_err(|err| HandoverError::Sealing(err.into()))?;
+    let stored: VaultStorable =
+        unseal_non_secret_msgpack(sealed_record, enclave_crypto).map_err(HandoverError::Sealing)?;
+    let upgraded = upgrade_vault_schema(stored)?;
+
+    let mut store = vault_store();
+    let key = &key_from_id(&upgraded.vault_id)?;
+    match store.try_insert(key, &upgraded)? {
+        None => Ok(()),
+        Some(_) => Err(HandoverError::AlreadyExists),
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+enum PeerRole {
+    Predecessor,
+    Successor,
+}
+
+/// Verify `peer_report`, the peer enclave's identity, and that it binds `peer_public_key`.
+fn verify_peer(
+    peer_report: &sgx_report_t,
+    peer_public_key: &PublicKey,
+    role: PeerRole,
+) -> Result<(), HandoverError> {
+    verify_peer_report(peer_report, role)?;
+
+    let public_key_hash = rsgx_sha256_slice(peer_public_key)?;
+    match peer_report.body.report_data.d[..32] == public_key_hash[..] {
+        true => Ok(()),
+        false => Err(HandoverError::PublicKeyMismatch),
+    }
+}
+
+/// Verify `peer_report`, and the peer enclave's identity.
+fn verify_peer_report(peer_report: &sgx_report_t, role: PeerRole) -> Result<(), HandoverError> {
+    // This checks that the report was created on this platform, targeting this enclave.
+    rsgx_verify_report(peer_report).map_err(HandoverError::InvalidReport)?;
+
+    let own = &rsgx_self_report().body;
+    check_peer_identity(own, &peer_report.body, role)
+}
+
+/// Bind `sealed_record`, sealed by `sender_public_key`, to the handover with `nonce`.
+///
+/// [`RECORD_BINDING_LABEL`] keeps the result out of reach of [`create_report_with_user_data`],
+/// whose report data the host partly chooses.
+fn record_binding(
+    sender_public_key: &PublicKey,
+    nonce: &HandoverNonce,
+    sealed_record: &[u8],
+) -> Result<sgx_sha256_hash_t, HandoverError> {
+    let record_digest = rsgx_sha256_slice(sealed_record)?;
+    let binding_input: Vec<u8> = [
+        RECORD_BINDING_LABEL,
+        sender_public_key,
+        nonce,
+        &record_digest,
+    ]
+    .concat();
+    Ok(rsgx_sha256_slice(&binding_input)?)
+}
+
+fn check_peer_identity(
+    own: &sgx_report_body_t,
+    peer: &sgx_report_body_t,
+    role: PeerRole,
+) -> Result<(), HandoverError> {
+    let untrusted = |reason| {
+        Err(HandoverError::UntrustedPeer {
+            role: match role {
+                PeerRole::Predecessor => "predecessor",
+                PeerRole::Successor => "successor",
+            },
+            reason,
+        })
+    };
+
+    if peer.mr_signer.m != own.mr_signer.m {
+        return untrusted("different signer (MRSIGNER)");
+    }
+    if peer.isv_prod_id != own.isv_prod_id {
+        return untrusted("different product ID");
+    }
+    let is_debug = |body: &sgx_report_body_t| body.attributes.flags & SGX_FLAGS_DEBUG != 0;
+    if is_debug(peer) && !is_debug(own) {
+        return untrusted("debug enclave");
+    }
+    // Never hand records to an older enclave version, or take them from a newer one.
+    let svn_ok = match role {
+        PeerRole::Successor => own.isv_svn <= peer.isv_svn,
+        PeerRole::Predecessor => peer.isv_svn <= own.isv_svn,
+    };
+    if !svn_ok {
+        return untrusted("security version (ISV SVN) downgrade");
+    }
+    Ok(())
+}
+
+/// Target the enclave that created `report`.
+fn target_info_for(report: &sgx_report_t) -> sgx_target_info_t {
+    sgx_target_info_t {
+        mr_enclave: report.body.mr_enclave,
+        attributes: report.body.attributes,
+        config_svn: report.body.config_svn,
+        misc_select: report.body.misc_select,
+        config_id: report.body.config_id,
+        ..Default::default()
+    }
+}
+
+/// Lock the nonce of the handover in progress.
+///
+/// Each update replaces the nonce in one step, so a poisoned lock is safe to reuse.
+fn lock_handover_nonce() -> SgxMutexGuard<'static, Option<HandoverNonce>> {
+    HANDOVER_NONCE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,59 @@
+//! Implement [`ImportAlgorandAccount`].
+
+use std::prelude::v1::ToString;
+
+use crate::ported::kv_store::KvStore;
+use crate::schema::actions::{
+    AlgorandAccountToImport,
+    ImportAlgorandAccount,
+    ImportAlgorandAccountResult,
+};
+use crate::schema::entities::{
+    AlgorandAccount,
+    EthereumAccount,
+    VaultDisplay,
+    VaultPolicy,
+    VaultStorable,
+    VAULT_SCHEMA_VERSION,
+};
+use crate::vault_operations::store::{key_from_id, vault_store};
+
+type Result = ImportAlgorandAccountResult;
+
+/// Like [`crate::vault_operations::create_vault::create_vault`], but with an existing account.
+pub fn import_algorand_account(request: &ImportAlgorandAccount) -> Result {
+    let imported_algorand_account = match &request.account_to_import {
+        AlgorandAccountToImport::Mnemonic { mnemonic } => {
+            match AlgorandAccount::from_mnemonic(mnemonic) {
+                Ok(account) => account,
+                Err(message) => {
+                    return Result::Failed(format!("invalid Algorand mnemonic: {}", message))
+                }
+            }
+        }
+        AlgorandAccountToImport::Seed { seed_bytes } => AlgorandAccount::from_seed(*seed_bytes),
+    };
+
+    let storable = VaultStorable {
+        schema_version: VAULT_SCHEMA_VERSION,
+
+        vault_id: request.username.clone(),
+        username: request.username.clone(),
+        auth_password: request.auth_password.clone(),
+
+        algorand_account: imported_algorand_account,
+        ethereum_account: Some(EthereumAccount::generate()),
+
+        policy: VaultPolicy::default(),
+    };
+    let key = match key_from_id(&storable.vault_id) {
+        Ok(key) => key,
+        Err(err) => return Result::Failed(err.to_string()),
+    };
+    // Unlike creating a vault, the username is the client's choice here: a conflict is not a bug.
+    match vault_store().try_insert(&key, &storable) {
+        Ok(None) => Result::Imported(VaultDisplay::from(storable)),
+        Ok(Some(_)) => Result::Failed(format!("vault {:?} already exists", storable.vault_id)),
+        Err(err) => Result::Failed(err.to_string()),
+    }
+}
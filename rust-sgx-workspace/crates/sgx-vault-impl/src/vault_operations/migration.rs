@@ -0,0 +1,74 @@
+//! Vault store migration across enclave versions.
+//!
+//! Migrating a record upgrades its [`VaultStorable`] schema, and re-seals it under the
+//! current [`VAULT_STORE_KEY_POLICY`](crate::vault_operations::store::VAULT_STORE_KEY_POLICY).
+
+use std::io;
+
+use thiserror::Error;
+
+use crate::ported::kv_store::fs::FsStoreError;
+use crate::ported::kv_store::{Key, KvStore};
+use crate::schema::entities::{VaultStorable, VAULT_SCHEMA_VERSION};
+use crate::schema::types::VaultSchemaVersion;
+use crate::vault_operations::store::{current_key_vault_store, vault_store};
+
+/// The record was written by a newer enclave, with a schema this enclave does not know.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Error)]
+#[error(
+    "unsupported vault schema version {0} (this enclave supports up to {VAULT_SCHEMA_VERSION})"
+)]
+pub struct UnsupportedSchemaVersion(pub VaultSchemaVersion);
+
+impl From<UnsupportedSchemaVersion> for io::Error {
+    fn from(err: UnsupportedSchemaVersion) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    #[error(transparent)]
+    UnsupportedSchemaVersion(#[from] UnsupportedSchemaVersion),
+
+    #[error("I/O error while migrating vault record")]
+    IoError(#[from] io::Error),
+
+    #[error(transparent)]
+    StoreError(#[from] FsStoreError),
+}
+
+/// Upgrade `stored` to [`VAULT_SCHEMA_VERSION`].
+pub fn upgrade_vault_schema(
+    mut stored: VaultStorable,
+) -> Result<VaultStorable, UnsupportedSchemaVersion> {
+    match stored.schema_version {
+        // Version 0 predates versioning: its newer fields already deserialize with defaults.
+        0 => stored.schema_version = 1,
+        VAULT_SCHEMA_VERSION => {}
+        newer => return Err(UnsupportedSchemaVersion(newer)),
+    }
+    Ok(stored)
+}
+
+/// Load the record at `key`, and save it back if its schema or key policy is outdated.
+///
+/// Return `false` if there is no record at `key`, or if it is already current:
+/// the host migrates every record on startup, so current records are not rewritten.
+pub fn migrate_vault_record(key: &Key) -> Result<bool, MigrationError> {
+    // Loading a record sealed under a previous key policy rotates its key,
+    // so check that before loading it.
+    let outdated_key = current_key_vault_store().load(key).is_err();
+    let mut store = vault_store();
+    let stored = match store.load(key)? {
+        None => return Ok(false),
+        Some(stored) => stored,
+    };
+    let stored_schema_version = stored.schema_version;
+    let upgraded = upgrade_vault_schema(stored)?;
+    if upgraded.schema_version == stored_schema_version && !outdated_key {
+        return Ok(false);
+    }
+    store.save(key, &upgraded)?;
+    Ok(true)
+}
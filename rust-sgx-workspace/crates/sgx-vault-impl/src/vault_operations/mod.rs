@@ -1,9 +1,22 @@
 //! Vault operation implementations.
 
+pub mod audit_log;
 pub mod create_vault;
 pub mod dispatch;
 pub(crate) mod errors;
+pub mod export_algorand_account;
+pub mod get_audit_log;
+pub mod handover;
+pub mod import_algorand_account;
+pub mod migration;
 pub mod open_vault;
+pub mod response_cache;
+pub mod sessions;
+pub mod sign_bytes;
 pub mod sign_transaction;
 pub mod sign_transaction_algorand;
+pub mod sign_transaction_ethereum;
+pub mod signing_policy;
 pub mod store;
+pub mod summarize_transaction;
+pub mod update_vault_policy;
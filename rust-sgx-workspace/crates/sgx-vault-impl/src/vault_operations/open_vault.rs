@@ -1,9 +1,21 @@
+use std::io;
+
+use crate::ported::kv_store::KvStore;
 use crate::schema::actions::{OpenVault, OpenVaultResult};
-use crate::schema::entities::VaultDisplay;
-use crate::vault_operations::store::unlock_vault;
+use crate::schema::entities::{VaultDisplay, VaultStorable};
+use crate::vault_operations::store::{unlock_vault_in, vault_store};
 
 pub fn open_vault(request: &OpenVault) -> OpenVaultResult {
-    let stored = match unlock_vault(&request.vault_id, &request.auth_password) {
+    open_vault_in(&vault_store(), request)
+}
+
+/// [`open_vault`], from `store`.
+pub fn open_vault_in<S>(store: &S, request: &OpenVault) -> OpenVaultResult
+where
+    S: KvStore<VaultStorable>,
+    io::Error: From<S::Error>,
+{
+    let stored = match unlock_vault_in(store, &request.vault_id, &request.auth_password) {
         Ok(stored) => stored,
         Err(err) => return err.into(),
     };
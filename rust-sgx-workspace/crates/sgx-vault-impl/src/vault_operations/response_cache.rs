@@ -0,0 +1,75 @@
+//! Sealed responses held for the host to fetch.
+//!
+//! When a sealed response does not fit the host's buffer, the enclave keeps it here,
+//! keyed by the host-chosen request ID, instead of discarding it.
+//! The host then fetches it into a correctly sized buffer, without re-executing the operation.
+//! (See [`crate::ecalls::vault_operation`].)
+
+use std::collections::VecDeque;
+use std::prelude::v1::Box;
+use std::sync::{PoisonError, SgxMutex, SgxMutexGuard};
+
+use lazy_static::lazy_static;
+
+/// Host-chosen identifier of a vault operation request.
+pub type RequestId = u64;
+
+/// How many responses to hold, at most.
+///
+/// Responses the host never fetches are evicted, oldest first.
+pub const MAX_CACHED_RESPONSES: usize = 16;
+
+type Cache = VecDeque<(RequestId, Box<[u8]>)>;
+
+lazy_static! {
+    static ref RESPONSE_CACHE: SgxMutex<Cache> = SgxMutex::new(VecDeque::new());
+}
+
+/// Result of [`take_cached_response`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum CachedResponse {
+    /// No response is held for the request ID (never cached, already taken, or evicted).
+    Missing,
+
+    /// The held response exceeds the given capacity, and remains held.
+    TooLarge(usize),
+
+    /// The held response, which is no longer held.
+    Taken(Box<[u8]>),
+}
+
+/// Hold `sealed_response` for `request_id`, replacing any response already held for it.
+pub fn cache_response(request_id: RequestId, sealed_response: Box<[u8]>) {
+    let mut cache = lock_cache();
+    cache.retain(|(held_id, _)| *held_id != request_id);
+    while MAX_CACHED_RESPONSES <= cache.len() {
+        cache.pop_front();
+    }
+    cache.push_back((request_id, sealed_response));
+}
+
+/// Take the response held for `request_id`, if it fits `capacity`.
+pub fn take_cached_response(request_id: RequestId, capacity: usize) -> CachedResponse {
+    let mut cache = lock_cache();
+    let index = match cache.iter().position(|(held_id, _)| *held_id == request_id) {
+        Some(index) => index,
+        None => return CachedResponse::Missing,
+    };
+    let size = cache[index].1.len();
+    if capacity < size {
+        return CachedResponse::TooLarge(size);
+    }
+    match cache.remove(index) {
+        Some((_, sealed_response)) => CachedResponse::Taken(sealed_response),
+        None => CachedResponse::Missing,
+    }
+}
+
+/// Lock the cache.
+///
+/// Each update leaves the cache consistent, so a poisoned lock is safe to reuse.
+fn lock_cache() -> SgxMutexGuard<'static, Cache> {
+    RESPONSE_CACHE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,251 @@
+//! Sessions held by the enclave: see [`crate::schema::session`].
+//!
+//! The enclave holds only each session's derived keys, in memory: sessions end when they
+//! expire, when evicted, or when the enclave stops.
+//!
+//! Anyone can start a session, so the enclave limits how fast new sessions can push out
+//! existing ones: a session is only evicted once it is [`MIN_SESSION_AGE_SECONDS`] old.
+//! While all [`MAX_SESSIONS`] held sessions are younger, handshakes fail with
+//! [`SessionHandshakeError::TooManySessions`], and the host should retry later.
+//!
+//! Each session also holds the last request counter it accepted, to reject replayed requests:
+//! see [`accept_request_counter`].
+//!
+//! Session ages use the host's clock, like [`crate::vault_operations::audit_log`]:
+//! a host can already deny service, but a client can't.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::prelude::v1::Box;
+use std::sync::{PoisonError, SgxMutex, SgxMutexGuard};
+
+use lazy_static::lazy_static;
+use thiserror::Error;
+
+use crate::ported::crypto::{fill_random, SodaBoxCrypto};
+use crate::schema::msgpack::FromMessagePack;
+use crate::schema::sealing::seal_msgpack;
+use crate::schema::session::{SessionAccept, SessionCounter, SessionHello, SessionId, SessionKeys};
+use crate::schema::types::UnixTimestamp;
+use crate::vault_operations::audit_log::now_timestamp;
+use crate::vault_operations::dispatch::InvalidRequest;
+use crate::vault_operations::errors;
+
+/// How many sessions to hold, at most.
+///
+/// Beyond this, the oldest sessions are evicted, if old enough: their clients must start new ones.
+pub const MAX_SESSIONS: usize = 64;
+
+/// How long a session lasts: after this, its client must start a new one.
+pub const SESSION_LIFETIME_SECONDS: u64 = 60 * 60;
+
+/// How long a session is safe from eviction by newer sessions.
+pub const MIN_SESSION_AGE_SECONDS: u64 = 5 * 60;
+
+lazy_static! {
+    static ref SESSIONS: SgxMutex<Sessions> = SgxMutex::new(Sessions::default());
+}
+
+/// [`session_handshake_impl`] failed to start a session.
+#[derive(Debug, Error)]
+pub enum SessionHandshakeError {
+    #[error(transparent)]
+    InvalidRequest(#[from] InvalidRequest),
+
+    #[error(
+        "too many sessions: all {} held sessions are younger than {} seconds",
+        MAX_SESSIONS,
+        MIN_SESSION_AGE_SECONDS
+    )]
+    TooManySessions,
+
+    #[error("failed to start session: {0}")]
+    Failed(Box<dyn Error>),
+}
+
+/// [`Sessions::accept_request_counter`] rejected a request: it is replayed, or reordered.
+#[derive(Debug, Error)]
+#[error(
+    "session request counter {counter} does not exceed the last accepted counter {last_counter:?}"
+)]
+pub struct StaleRequestCounter {
+    pub counter: SessionCounter,
+
+    /// [`None`] if the session is no longer held.
+    pub last_counter: Option<SessionCounter>,
+}
+
+/// The held sessions, oldest first.
+#[derive(Default)]
+pub struct Sessions {
+    held: VecDeque<HeldSession>,
+}
+
+struct HeldSession {
+    session_id: SessionId,
+    keys: SessionKeys,
+    started: UnixTimestamp,
+
+    /// The counter of the last request accepted, or 0 before the first.
+    last_request_counter: SessionCounter,
+}
+
+impl Sessions {
+    /// Hold `keys` for `session_id`, started at `now`.
+    ///
+    /// This drops expired sessions, and evicts the oldest sessions beyond [`MAX_SESSIONS`]
+    /// that are at least [`MIN_SESSION_AGE_SECONDS`] old.
+    pub fn hold(
+        &mut self,
+        session_id: SessionId,
+        keys: SessionKeys,
+        now: UnixTimestamp,
+    ) -> Result<(), SessionHandshakeError> {
+        self.held.retain(|held| {
+            held.session_id != session_id && session_age(held, now) < SESSION_LIFETIME_SECONDS
+        });
+        while MAX_SESSIONS <= self.held.len() {
+            match self.held.front() {
+                Some(oldest) if MIN_SESSION_AGE_SECONDS <= session_age(oldest, now) => {
+                    self.held.pop_front();
+                }
+                _ => return Err(SessionHandshakeError::TooManySessions),
+            }
+        }
+        self.held.push_back(HeldSession {
+            session_id,
+            keys,
+            started: now,
+            last_request_counter: 0,
+        });
+        Ok(())
+    }
+
+    /// The keys of `session_id`, if it is held, and not expired at `now`.
+    pub fn keys(&self, session_id: &SessionId, now: UnixTimestamp) -> Option<SessionKeys> {
+        self.held
+            .iter()
+            .find(|held| &held.session_id == session_id)
+            .filter(|held| session_age(held, now) < SESSION_LIFETIME_SECONDS)
+            .map(|held| held.keys.clone())
+    }
+
+    /// Accept `counter` for the next request of `session_id`, if it exceeds the last one.
+    ///
+    /// Call this only once the request authenticates under the session's keys.
+    pub fn accept_request_counter(
+        &mut self,
+        session_id: &SessionId,
+        counter: SessionCounter,
+    ) -> Result<(), StaleRequestCounter> {
+        let held = self
+            .held
+            .iter_mut()
+            .find(|held| &held.session_id == session_id)
+            .ok_or(StaleRequestCounter {
+                counter,
+                last_counter: None,
+            })?;
+        if counter <= held.last_request_counter {
+            return Err(StaleRequestCounter {
+                counter,
+                last_counter: Some(held.last_request_counter),
+            });
+        }
+        held.last_request_counter = counter;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.held.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.held.is_empty()
+    }
+}
+
+/// A host clock that goes backwards makes sessions younger, never older.
+fn session_age(held: &HeldSession, now: UnixTimestamp) -> u64 {
+    now.saturating_sub(held.started)
+}
+
+/// Implementation for [`crate::ecalls::session_handshake::session_handshake`].
+///
+/// This processes an exchange of the following:
+///
+/// Request: [`SessionHello`] (unsealed)
+///
+/// Response: [`crate::schema::sealing::SealedMessage`] of [`SessionAccept`],
+/// from the enclave's long-term key
+///
+/// # Errors
+///
+/// [`InvalidRequest`] if the request can't be decoded.
+///
+/// [`SessionHandshakeError::TooManySessions`] if no held session can be evicted yet.
+///
+/// [`SessionHandshakeError::Failed`] if generating or sealing the session fails.
+///
+pub fn session_handshake_impl(hello_bytes: &[u8]) -> Result<Box<[u8]>, SessionHandshakeError> {
+    let hello = SessionHello::from_msgpack(hello_bytes).map_err(|err| {
+        InvalidRequest(
+            errors::message_with_base64(
+                "session_handshake_impl",
+                "invalid SessionHello",
+                err,
+                "SessionHello msgpack",
+                hello_bytes,
+            )
+            .into(),
+        )
+    })?;
+    let (session_id, keys, sealed_accept_bytes) =
+        start_session(&hello).map_err(SessionHandshakeError::Failed)?;
+    lock_sessions().hold(session_id, keys, now_timestamp())?;
+    Ok(sealed_accept_bytes)
+}
+
+/// Generate the enclave's side of a session, and seal the accept.
+fn start_session(
+    hello: &SessionHello,
+) -> Result<(SessionId, SessionKeys, Box<[u8]>), Box<dyn Error>> {
+    let ephemeral_crypto = SodaBoxCrypto::new_ephemeral()?;
+    let mut session_id = SessionId::default();
+    fill_random(&mut session_id)?;
+
+    let shared_key = &ephemeral_crypto.shared_key(&hello.client_public_key);
+    let keys = SessionKeys::derive(
+        shared_key,
+        &hello.client_public_key,
+        &ephemeral_crypto.get_pubkey(),
+    );
+    let accept = &SessionAccept {
+        session_id,
+        enclave_public_key: ephemeral_crypto.get_pubkey(),
+    };
+    let sealed_accept_bytes =
+        seal_msgpack(accept, &hello.client_public_key, &mut SodaBoxCrypto::new()?)?;
+
+    Ok((session_id, keys, sealed_accept_bytes))
+}
+
+/// The keys of `session_id`, if it is held, and not expired.
+pub fn session_keys(session_id: &SessionId) -> Option<SessionKeys> {
+    lock_sessions().keys(session_id, now_timestamp())
+}
+
+/// Accept `counter` for the next request of `session_id`: see [`Sessions::accept_request_counter`].
+pub fn accept_request_counter(
+    session_id: &SessionId,
+    counter: SessionCounter,
+) -> Result<(), StaleRequestCounter> {
+    lock_sessions().accept_request_counter(session_id, counter)
+}
+
+/// Lock the sessions.
+///
+/// Each update leaves the sessions consistent, so a poisoned lock is safe to reuse.
+fn lock_sessions() -> SgxMutexGuard<'static, Sessions> {
+    SESSIONS.lock().unwrap_or_else(PoisonError::into_inner)
+}
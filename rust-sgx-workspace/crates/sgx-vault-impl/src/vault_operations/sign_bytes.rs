@@ -0,0 +1,128 @@
+//! Implement arbitrary data and message signing.
+//!
+//! These modes sign with the vault's Algorand account key, so they must never produce
+//! a signature that Algorand accepts for anything else: a transaction (`TX`), or a
+//! delegated logic signature (`Program`, `ProgData`), which could authorize any transaction.
+
+use std::prelude::v1::{Box, String};
+
+use zeroize::Zeroize;
+
+use crate::schema::entities::AlgorandAccount;
+use crate::schema::types::Bytes;
+use crate::vault_operations::errors;
+
+/// Algorand's domain separation prefix for transactions.
+const ALGORAND_TRANSACTION_PREFIX: &[u8] = b"TX";
+
+/// Algorand's domain separation prefix for arbitrary bytes, as used by `sign_bytes`.
+const ALGORAND_BYTES_PREFIX: &[u8] = b"MX";
+
+/// The vault's own prefix for [`sign_ed25519_message`].
+///
+/// Every raw message the vault signs starts with this, so no domain tag can make it
+/// start with one of Algorand's domain separation prefixes.
+pub const ED25519_MESSAGE_PREFIX: &[u8] = b"NTC-MSG";
+
+/// Algorand's domain separation prefixes: refused as domain tags, as a second line of defence.
+const ALGORAND_RESERVED_PREFIXES: &[&[u8]] = &[
+    ALGORAND_TRANSACTION_PREFIX,
+    ALGORAND_BYTES_PREFIX,
+    b"Program",
+    b"ProgData",
+    b"MultisigProgram",
+    b"TG",
+];
+
+/// The maximum length of a [`sign_ed25519_message`] domain tag.
+pub const ED25519_MAX_DOMAIN_TAG_LEN: usize = 64;
+
+/// Sign `data_bytes` with the `MX` prefix, like algonaut's `Account::sign_bytes`.
+pub(crate) fn sign_algorand_bytes(
+    signing_account: &AlgorandAccount,
+    data_bytes: &Bytes,
+) -> Result<Bytes, String> {
+    reject_transaction_prefix("sign_algorand_bytes", data_bytes)?;
+
+    let signature = signing_account.as_algonaut_account().sign_bytes(data_bytes);
+    Ok(Box::new(signature.0))
+}
+
+/// Sign `ED25519_MESSAGE_PREFIX || domain_tag || 0x00 || message_bytes` with plain Ed25519.
+///
+/// The domain tag must be non-empty printable ASCII, and must not use one of
+/// Algorand's own signing prefixes.
+pub(crate) fn sign_ed25519_message(
+    signing_account: &AlgorandAccount,
+    domain_tag: &str,
+    message_bytes: &Bytes,
+) -> Result<Bytes, String> {
+    check_domain_tag(domain_tag)?;
+    reject_transaction_prefix("sign_ed25519_message", message_bytes)?;
+
+    let signed_message = &[
+        ED25519_MESSAGE_PREFIX,
+        domain_tag.as_bytes(),
+        &[0],
+        &message_bytes[..],
+    ]
+    .concat();
+
+    let mut public_key: sodalite::SignPublicKey = Default::default();
+    let mut secret_key: sodalite::SignSecretKey = [0; sodalite::SIGN_SECRET_KEY_LEN];
+    sodalite::sign_keypair_seed(
+        &mut public_key,
+        &mut secret_key,
+        &signing_account.seed_bytes,
+    );
+
+    let mut signed_output = vec![0; signed_message.len() + sodalite::SIGN_LEN];
+    sodalite::sign_attached(&mut signed_output, signed_message, &secret_key);
+    secret_key.zeroize();
+
+    // `sign_attached` outputs the signature followed by the message.
+    signed_output.truncate(sodalite::SIGN_LEN);
+    Ok(signed_output.into_boxed_slice())
+}
+
+fn check_domain_tag(domain_tag: &str) -> Result<(), String> {
+    let invalid = |reason: &str| {
+        Err(errors::message_with_extra(
+            "sign_ed25519_message",
+            "invalid domain tag",
+            reason,
+            "domain tag",
+            &format!("{:?}", domain_tag),
+        ))
+    };
+
+    if domain_tag.is_empty() || ED25519_MAX_DOMAIN_TAG_LEN < domain_tag.len() {
+        return invalid("expected 1 to 64 characters");
+    }
+    if !domain_tag.bytes().all(|b| b.is_ascii_graphic()) {
+        return invalid("expected printable ASCII without spaces");
+    }
+    let tag_bytes = domain_tag.as_bytes();
+    if ALGORAND_RESERVED_PREFIXES
+        .iter()
+        .any(|prefix| tag_bytes.starts_with(prefix))
+    {
+        return invalid("Algorand's domain separation prefixes are reserved");
+    }
+    Ok(())
+}
+
+/// Refuse data that looks like an Algorand transaction, to catch callers that pass
+/// a transaction where they meant to sign it as such.
+fn reject_transaction_prefix(function: &str, bytes: &[u8]) -> Result<(), String> {
+    match bytes.starts_with(ALGORAND_TRANSACTION_PREFIX) {
+        true => Err(errors::message_with_base64(
+            function,
+            "refusing to sign bytes with an Algorand transaction prefix",
+            "got TX",
+            "data",
+            bytes,
+        )),
+        false => Ok(()),
+    }
+}
@@ -1,27 +1,103 @@
 //! Implement [`SignTransaction`].
 
+use std::io;
 use std::prelude::v1::String;
 
+use crate::ported::kv_store::KvStore;
 use crate::schema::actions::{
     SignTransaction,
     SignTransactionResult,
     TransactionSigned,
     TransactionToSign,
 };
-use crate::vault_operations::sign_transaction_algorand::sign_algorand;
-use crate::vault_operations::store::unlock_vault;
+use crate::schema::entities::VaultStorable;
+use crate::vault_operations::errors;
+use crate::vault_operations::sign_bytes::{sign_algorand_bytes, sign_ed25519_message};
+use crate::vault_operations::sign_transaction_algorand::{
+    append_algorand_multisig,
+    sign_algorand,
+    sign_algorand_group,
+    sign_algorand_multisig,
+};
+use crate::vault_operations::sign_transaction_ethereum::sign_ethereum;
+use crate::vault_operations::signing_policy::check_signing_policy;
+use crate::vault_operations::store::{unlock_vault_in, vault_store};
+use crate::vault_operations::summarize_transaction::summarize_transaction_to_sign;
 
 pub fn sign_transaction(request: &SignTransaction) -> SignTransactionResult {
-    let stored = match unlock_vault(&request.vault_id, &request.auth_password) {
+    sign_transaction_in(&vault_store(), request)
+}
+
+/// [`sign_transaction`], from `store`.
+pub fn sign_transaction_in<S>(store: &S, request: &SignTransaction) -> SignTransactionResult
+where
+    S: KvStore<VaultStorable>,
+    io::Error: From<S::Error>,
+{
+    let stored = match unlock_vault_in(store, &request.vault_id, &request.auth_password) {
         Ok(stored) => stored,
         Err(err) => return err.into(),
     };
 
+    // Check the vault's signing policy before signing anything.
+    let summaries =
+        match summarize_transaction_to_sign(&stored.algorand_account, &request.transaction_to_sign)
+        {
+            Ok(summaries) => summaries,
+            Err(message) => return SignTransactionResult::Failed(message),
+        };
+    if let Err(message) = check_signing_policy(
+        &stored.policy.algorand_signing,
+        &request.transaction_to_sign,
+        &summaries,
+    ) {
+        return SignTransactionResult::PolicyViolation(message);
+    }
+
     let sign_result: Result<TransactionSigned, String> = match &request.transaction_to_sign {
         TransactionToSign::AlgorandTransaction { transaction_bytes } => {
             sign_algorand(&stored.algorand_account, transaction_bytes)
                 .map(TransactionSigned::from_algorand_bytes)
         }
+        TransactionToSign::AlgorandTransactionGroup { transactions_bytes } => {
+            sign_algorand_group(&stored.algorand_account, transactions_bytes).map(
+                |signed_transactions| TransactionSigned::AlgorandTransactionGroupSigned {
+                    signed_transactions,
+                },
+            )
+        }
+        TransactionToSign::AlgorandMultisigTransaction {
+            multisig,
+            transaction_bytes,
+        } => sign_algorand_multisig(&stored.algorand_account, multisig, transaction_bytes)
+            .map(TransactionSigned::from_algorand_bytes),
+        TransactionToSign::AlgorandMultisigSignedTransaction {
+            multisig,
+            signed_transaction_bytes,
+        } => append_algorand_multisig(&stored.algorand_account, multisig, signed_transaction_bytes)
+            .map(TransactionSigned::from_algorand_bytes),
+        TransactionToSign::AlgorandBytes { data_bytes } => {
+            sign_algorand_bytes(&stored.algorand_account, data_bytes)
+                .map(TransactionSigned::from_ed25519_signature)
+        }
+        TransactionToSign::Ed25519Message {
+            domain_tag,
+            message_bytes,
+        } => sign_ed25519_message(&stored.algorand_account, domain_tag, message_bytes)
+            .map(TransactionSigned::from_ed25519_signature),
+        TransactionToSign::EthereumTransaction { transaction_bytes } => {
+            match &stored.ethereum_account {
+                Some(ethereum_account) => sign_ethereum(ethereum_account, transaction_bytes)
+                    .map(TransactionSigned::from_ethereum_bytes),
+                None => Err(errors::message_with_extra(
+                    "sign_ethereum_transaction",
+                    "vault has no Ethereum account",
+                    "created before Ethereum support",
+                    "vault ID",
+                    &stored.vault_id,
+                )),
+            }
+        }
     };
 
     // `Result` → `SignTransactionResult`
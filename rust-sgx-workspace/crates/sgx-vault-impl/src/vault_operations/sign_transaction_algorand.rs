@@ -1,86 +1,332 @@
 //! Implement transaction signing for Algorand.
 
-use std::prelude::v1::String;
+use std::prelude::v1::{String, ToString, Vec};
 
+use algonaut::core::{Address, MultisigAddress};
+use algonaut::crypto::HashDigest;
+use algonaut::transaction::tx_group::TxGroup;
+use algonaut::transaction::{SignedTransaction, Transaction};
+use serde::Deserialize;
+
+use crate::schema::actions::{AlgorandGroupTransactionSigned, AlgorandMultisigParameters};
 use crate::schema::entities::AlgorandAccount;
-use crate::schema::types::Bytes;
+use crate::schema::serde_bytes_array;
+use crate::schema::types::{AlgorandAddressBytes, Bytes};
 use crate::vault_operations::errors;
 
+/// The maximum number of transactions in an Algorand atomic transaction group.
+pub const ALGORAND_MAX_GROUP_SIZE: usize = 16;
+
 pub(crate) fn sign_algorand(
     signing_account: &AlgorandAccount,
     transaction_bytes: &Bytes,
 ) -> Result<Bytes, String> {
-    // Safety: `split_at` panics if len < mid.
-    if transaction_bytes.len() < 2 {
-        return Err(errors::message_with_base64(
-            "sign_transaction",
-            "transaction too short",
-            format!("len = {}", transaction_bytes.len()),
-            "unsigned transaction msgpack",
-            transaction_bytes,
+    let transaction_to_sign = &unpack_algorand_transaction(transaction_bytes)?;
+    sign_algorand_transaction(signing_account, transaction_to_sign)
+}
+
+/// Sign the transactions of an atomic group that are sent by `signing_account`.
+///
+/// This checks that every transaction in the group carries the group's computed group ID.
+pub(crate) fn sign_algorand_group(
+    signing_account: &AlgorandAccount,
+    transactions_bytes: &[Bytes],
+) -> Result<Vec<AlgorandGroupTransactionSigned>, String> {
+    if transactions_bytes.is_empty() || ALGORAND_MAX_GROUP_SIZE < transactions_bytes.len() {
+        return Err(errors::message_with_extra(
+            "sign_transaction_group",
+            "invalid transaction group size",
+            format!("len = {}", transactions_bytes.len()),
+            "maximum group size",
+            &ALGORAND_MAX_GROUP_SIZE.to_string(),
         ));
     }
 
-    // Check and strip the "TX" prefix tag
-    let transaction_bytes_without_prefix = match transaction_bytes.split_at(2) {
-        (b"TX", rest) => rest,
-        (unrecognised, _rest) => {
-            return Err(errors::message_with_base64(
-                "sign_transaction",
-                "transaction prefix tag not recognised",
-                format!("expected TX, got {:?}", unrecognised),
-                "unsigned transaction msgpack",
-                transaction_bytes,
+    let transactions = transactions_bytes
+        .iter()
+        .map(unpack_algorand_transaction)
+        .collect::<Result<Vec<Transaction>, String>>()?;
+
+    let group_id = &compute_group_id(&transactions)?;
+    for (group_index, transaction) in transactions.iter().enumerate() {
+        if transaction.group.as_ref() != Some(group_id) {
+            return Err(errors::message_with_debug_value(
+                "sign_transaction_group",
+                "transaction group ID does not match the group",
+                format!("group index = {}", group_index),
+                "unsigned transaction",
+                transaction,
+            ));
+        }
+    }
+
+    let signing_address = signing_account.address_bytes();
+    let mut signed_transactions = Vec::new();
+    for (group_index, (transaction, transaction_bytes)) in
+        transactions.iter().zip(transactions_bytes).enumerate()
+    {
+        if unpack_algorand_sender(transaction_bytes)? == signing_address {
+            signed_transactions.push(AlgorandGroupTransactionSigned {
+                group_index: group_index as u32,
+                signed_transaction_bytes: sign_algorand_transaction(signing_account, transaction)?,
+            });
+        }
+    }
+
+    if signed_transactions.is_empty() {
+        return Err(errors::message_with_extra(
+            "sign_transaction_group",
+            "no transaction in the group is sent by the vault's account",
+            format!("len = {}", transactions.len()),
+            "vault account address",
+            &signing_account.address_base32(),
+        ));
+    }
+    Ok(signed_transactions)
+}
+
+/// Sign an unsigned transaction sent by `multisig`, as one of its members.
+pub(crate) fn sign_algorand_multisig(
+    signing_account: &AlgorandAccount,
+    multisig: &AlgorandMultisigParameters,
+    transaction_bytes: &Bytes,
+) -> Result<Bytes, String> {
+    let multisig_address = multisig_address_for_member(signing_account, multisig)?;
+    let transaction_to_sign = &unpack_algorand_transaction(transaction_bytes)?;
+
+    let signed_transaction = &match signing_account
+        .as_algonaut_account()
+        .sign_multisig_transaction(multisig_address, transaction_to_sign)
+    {
+        Ok(ok) => ok,
+        Err(err) => {
+            return Err(errors::message_with_debug_value(
+                "sign_multisig_transaction",
+                "algonaut error while signing multisig transaction",
+                err,
+                "unsigned transaction",
+                transaction_to_sign,
             ))
         }
     };
 
-    let transaction_to_sign =
-        &match algorand_network_compatible::from_msgpack(transaction_bytes_without_prefix) {
+    pack_algorand_signed_transaction(signed_transaction)
+}
+
+/// Merge the vault's signature into a partially-signed transaction sent by `multisig`.
+pub(crate) fn append_algorand_multisig(
+    signing_account: &AlgorandAccount,
+    multisig: &AlgorandMultisigParameters,
+    signed_transaction_bytes: &Bytes,
+) -> Result<Bytes, String> {
+    let multisig_address = multisig_address_for_member(signing_account, multisig)?;
+    let partially_signed: &SignedTransaction =
+        &match algorand_network_compatible::from_msgpack(signed_transaction_bytes) {
             Ok(ok) => ok,
             Err(err) => {
                 return Err(errors::message_with_base64(
-                    "sign_transaction",
-                    "failed to unpack received transaction-to-be-signed",
+                    "append_multisig_transaction",
+                    "failed to unpack received partially-signed transaction",
                     err,
-                    "unsigned transaction msgpack",
-                    transaction_bytes,
+                    "signed transaction msgpack",
+                    signed_transaction_bytes,
                 ))
             }
         };
 
     let signed_transaction = &match signing_account
         .as_algonaut_account()
-        .sign_transaction(transaction_to_sign)
+        .append_to_multisig_transaction(multisig_address, partially_signed)
     {
         Ok(ok) => ok,
         Err(err) => {
             return Err(errors::message_with_debug_value(
-                "sign_transaction",
-                "algonaut error while signing transaction",
+                "append_multisig_transaction",
+                "algonaut error while merging multisig signature",
                 err,
-                "unsigned transaction",
-                transaction_to_sign,
+                "partially-signed transaction",
+                partially_signed,
             ))
         }
     };
 
-    // Note: Intentionally serialize with algonaut's `to_msg_pack` helper, not ours.
-    let signed_transaction_bytes = match algorand_network_compatible::to_msgpack(signed_transaction)
+    pack_algorand_signed_transaction(signed_transaction)
+}
+
+/// Check and strip the "TX" prefix tag, and unpack the transaction.
+pub(crate) fn unpack_algorand_transaction(
+    transaction_bytes: &Bytes,
+) -> Result<Transaction, String> {
+    let transaction_bytes_without_prefix = strip_transaction_prefix(transaction_bytes)?;
+
+    match algorand_network_compatible::from_msgpack(transaction_bytes_without_prefix) {
+        Ok(ok) => Ok(ok),
+        Err(err) => Err(errors::message_with_base64(
+            "sign_transaction",
+            "failed to unpack received transaction-to-be-signed",
+            err,
+            "unsigned transaction msgpack",
+            transaction_bytes,
+        )),
+    }
+}
+
+pub(crate) fn strip_transaction_prefix(transaction_bytes: &Bytes) -> Result<&[u8], String> {
+    // Safety: `split_at` panics if len < mid.
+    if transaction_bytes.len() < 2 {
+        return Err(errors::message_with_base64(
+            "sign_transaction",
+            "transaction too short",
+            format!("len = {}", transaction_bytes.len()),
+            "unsigned transaction msgpack",
+            transaction_bytes,
+        ));
+    }
+
+    match transaction_bytes.split_at(2) {
+        (b"TX", rest) => Ok(rest),
+        (unrecognised, _rest) => Err(errors::message_with_base64(
+            "sign_transaction",
+            "transaction prefix tag not recognised",
+            format!("expected TX, got {:?}", unrecognised),
+            "unsigned transaction msgpack",
+            transaction_bytes,
+        )),
+    }
+}
+
+/// Unpack just the sender ("snd") field of an unsigned transaction.
+///
+/// This reads the network field directly, to avoid matching on every transaction type.
+pub(crate) fn unpack_algorand_sender(
+    transaction_bytes: &Bytes,
+) -> Result<AlgorandAddressBytes, String> {
+    #[derive(Deserialize)]
+    struct TransactionSender {
+        #[serde(rename = "snd", with = "serde_bytes_array")]
+        sender: AlgorandAddressBytes,
+    }
+
+    let transaction_bytes_without_prefix = strip_transaction_prefix(transaction_bytes)?;
+    match algorand_network_compatible::from_msgpack::<TransactionSender, _>(
+        transaction_bytes_without_prefix,
+    ) {
+        Ok(ok) => Ok(ok.sender),
+        Err(err) => Err(errors::message_with_base64(
+            "sign_transaction",
+            "failed to unpack sender of received transaction-to-be-signed",
+            err,
+            "unsigned transaction msgpack",
+            transaction_bytes,
+        )),
+    }
+}
+
+/// Compute the group ID of `transactions`, ignoring any group IDs they already carry.
+fn compute_group_id(transactions: &[Transaction]) -> Result<HashDigest, String> {
+    let mut ungrouped: Vec<Transaction> = transactions
+        .iter()
+        .cloned()
+        .map(|transaction| Transaction {
+            group: None,
+            ..transaction
+        })
+        .collect();
+    if let Err(err) = TxGroup::assign_group_id(ungrouped.iter_mut().collect()) {
+        return Err(errors::message_with_debug_value(
+            "sign_transaction_group",
+            "algonaut error while computing group ID",
+            err,
+            "unsigned transactions",
+            transactions,
+        ));
+    }
+    // Safety: the caller checks that the group is not empty.
+    ungrouped
+        .swap_remove(0)
+        .group
+        .ok_or_else(|| "sign_transaction_group: algonaut did not assign group ID".to_string())
+}
+
+/// Resolve `multisig`, and check that `signing_account` is one of its members.
+fn multisig_address_for_member(
+    signing_account: &AlgorandAccount,
+    multisig: &AlgorandMultisigParameters,
+) -> Result<MultisigAddress, String> {
+    let addresses = multisig
+        .addresses
+        .iter()
+        .map(|address| {
+            address.parse::<Address>().map_err(|err| {
+                errors::message_with_extra(
+                    "sign_multisig_transaction",
+                    "invalid multisig member address",
+                    err,
+                    "address",
+                    address,
+                )
+            })
+        })
+        .collect::<Result<Vec<Address>, String>>()?;
+
+    if !addresses.contains(&signing_account.as_algonaut_account().address()) {
+        return Err(errors::message_with_debug_value(
+            "sign_multisig_transaction",
+            "the vault's account is not a member of the multisig account",
+            signing_account.address_base32(),
+            "multisig",
+            multisig,
+        ));
+    }
+
+    MultisigAddress::new(multisig.version, multisig.threshold, &addresses).map_err(|err| {
+        errors::message_with_debug_value(
+            "sign_multisig_transaction",
+            "invalid multisig parameters",
+            err,
+            "multisig",
+            multisig,
+        )
+    })
+}
+
+fn sign_algorand_transaction(
+    signing_account: &AlgorandAccount,
+    transaction_to_sign: &Transaction,
+) -> Result<Bytes, String> {
+    let signed_transaction = &match signing_account
+        .as_algonaut_account()
+        .sign_transaction(transaction_to_sign)
     {
         Ok(ok) => ok,
         Err(err) => {
             return Err(errors::message_with_debug_value(
                 "sign_transaction",
-                "failed to pack signed",
+                "algonaut error while signing transaction",
                 err,
-                "signed transaction msgpack",
-                signed_transaction,
+                "unsigned transaction",
+                transaction_to_sign,
             ))
         }
     };
 
-    Ok(signed_transaction_bytes)
+    pack_algorand_signed_transaction(signed_transaction)
+}
+
+fn pack_algorand_signed_transaction(
+    signed_transaction: &SignedTransaction,
+) -> Result<Bytes, String> {
+    // Note: Intentionally serialize with algonaut's `to_msg_pack` helper, not ours.
+    match algorand_network_compatible::to_msgpack(signed_transaction) {
+        Ok(ok) => Ok(ok),
+        Err(err) => Err(errors::message_with_debug_value(
+            "sign_transaction",
+            "failed to pack signed",
+            err,
+            "signed transaction msgpack",
+            signed_transaction,
+        )),
+    }
 }
 
 /// Algorand network-compatible MessagePack serialization and deserialization.
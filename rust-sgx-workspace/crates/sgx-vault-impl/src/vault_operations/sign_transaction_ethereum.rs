@@ -0,0 +1,347 @@
+//! Implement transaction signing for Ethereum.
+//!
+//! This supports legacy transactions, with or without EIP-155 replay protection,
+//! and EIP-1559 (type 2) transactions.
+//!
+//! See:
+//!
+//! - <https://eips.ethereum.org/EIPS/eip-155>
+//! - <https://eips.ethereum.org/EIPS/eip-1559>
+
+use core::convert::{TryFrom, TryInto};
+use std::prelude::v1::{String, ToString, Vec};
+
+use ecdsa::hazmat::{rfc6979_generate_k, SignPrimitive};
+use k256::ecdsa::recoverable;
+use k256::elliptic_curve::ops::Reduce;
+use k256::{Scalar, Secp256k1, SecretKey, U256};
+use rlp::{Rlp, RlpStream};
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+use zeroize::Zeroizing;
+
+use crate::schema::actions::EthereumTransactionSummary;
+use crate::schema::entities::{ethereum_checksum_address, EthereumAccount};
+use crate::schema::types::{Bytes, EthereumAddressBytes};
+use crate::vault_operations::errors;
+
+/// The EIP-2718 transaction type of EIP-1559 transactions.
+pub const EIP_1559_TRANSACTION_TYPE: u8 = 0x02;
+
+/// Legacy: `[nonce, gasPrice, gasLimit, to, value, data]`
+const LEGACY_FIELD_COUNT: usize = 6;
+
+/// EIP-155: `[nonce, gasPrice, gasLimit, to, value, data, chainId, 0, 0]`
+const EIP_155_FIELD_COUNT: usize = 9;
+
+/// EIP-1559: `[chainId, nonce, maxPriorityFeePerGas, maxFeePerGas, gasLimit, to, value, data, accessList]`
+const EIP_1559_FIELD_COUNT: usize = 9;
+
+/// RLP lists start at this byte; anything below is a string, or a typed transaction's type.
+const RLP_LIST_OFFSET: u8 = 0xc0;
+
+/// RLP encoding of the empty string, which EIP-155 uses for its zero placeholders.
+const RLP_EMPTY_STRING: &[u8] = &[0x80];
+
+pub(crate) fn sign_ethereum(
+    signing_account: &EthereumAccount,
+    transaction_bytes: &Bytes,
+) -> Result<Bytes, String> {
+    match transaction_bytes.first() {
+        Some(&EIP_1559_TRANSACTION_TYPE) => sign_eip_1559(signing_account, transaction_bytes),
+        Some(&first) if RLP_LIST_OFFSET <= first => sign_legacy(signing_account, transaction_bytes),
+        first => Err(unsupported_type_error(first, transaction_bytes)),
+    }
+}
+
+/// Summarize an unsigned Ethereum transaction, as [`sign_ethereum`] would sign it.
+pub(crate) fn summarize_ethereum(
+    transaction_bytes: &Bytes,
+) -> Result<EthereumTransactionSummary, String> {
+    let field = |field: &[u8], name: &str| unpack_field(field, name, transaction_bytes);
+    let quantity = |field: &[u8], name: &str| unpack_quantity(field, name, transaction_bytes);
+    let to = |field: &[u8]| unpack_to(field, transaction_bytes);
+    let data_len = |field: &[u8]| Rlp::new(field).size() as u64;
+
+    match transaction_bytes.first() {
+        Some(&EIP_1559_TRANSACTION_TYPE) => {
+            let fields = unpack_eip_1559_fields(transaction_bytes)?;
+            Ok(EthereumTransactionSummary {
+                transaction_type: "eip-1559".to_string(),
+                chain_id: Some(field(fields[0], "chainId")?),
+                nonce: field(fields[1], "nonce")?,
+                to: to(fields[5])?,
+                value: quantity(fields[6], "value")?,
+                data_len: data_len(fields[7]),
+                gas_limit: field(fields[4], "gasLimit")?,
+                gas_price: None,
+                max_priority_fee_per_gas: Some(quantity(fields[2], "maxPriorityFeePerGas")?),
+                max_fee_per_gas: Some(quantity(fields[3], "maxFeePerGas")?),
+            })
+        }
+        Some(&first) if RLP_LIST_OFFSET <= first => {
+            let (fields, chain_id) = unpack_legacy_fields(transaction_bytes)?;
+            Ok(EthereumTransactionSummary {
+                transaction_type: "legacy".to_string(),
+                chain_id,
+                nonce: field(fields[0], "nonce")?,
+                to: to(fields[3])?,
+                value: quantity(fields[4], "value")?,
+                data_len: data_len(fields[5]),
+                gas_limit: field(fields[2], "gasLimit")?,
+                gas_price: Some(quantity(fields[1], "gasPrice")?),
+                max_priority_fee_per_gas: None,
+                max_fee_per_gas: None,
+            })
+        }
+        first => Err(unsupported_type_error(first, transaction_bytes)),
+    }
+}
+
+fn unsupported_type_error(first: Option<&u8>, transaction_bytes: &Bytes) -> String {
+    errors::message_with_base64(
+        "sign_ethereum_transaction",
+        "transaction type not supported",
+        format!("expected legacy or EIP-1559, got first byte {:?}", first),
+        "unsigned transaction",
+        transaction_bytes,
+    )
+}
+
+/// Sign a legacy transaction, with `v` per EIP-155 if a chain ID is present.
+fn sign_legacy(
+    signing_account: &EthereumAccount,
+    transaction_bytes: &Bytes,
+) -> Result<Bytes, String> {
+    let (fields, chain_id) = unpack_legacy_fields(transaction_bytes)?;
+
+    let (signature, recovery_id) = sign_recoverable(signing_account, transaction_bytes)?;
+    let v = match chain_id {
+        None => Some(27 + recovery_id),
+        Some(chain_id) => chain_id
+            .checked_mul(2)
+            .and_then(|v| v.checked_add(35 + recovery_id)),
+    }
+    .ok_or_else(|| {
+        errors::message_with_base64(
+            "sign_ethereum_transaction",
+            "chain ID too large",
+            format!("chain ID = {:?}", chain_id),
+            "unsigned transaction",
+            transaction_bytes,
+        )
+    })?;
+
+    Ok(pack_signed_fields(&fields[..LEGACY_FIELD_COUNT], v, &signature).into_boxed_slice())
+}
+
+/// Sign an EIP-1559 transaction, with `yParity` as the signature's `v`.
+fn sign_eip_1559(
+    signing_account: &EthereumAccount,
+    transaction_bytes: &Bytes,
+) -> Result<Bytes, String> {
+    let fields = unpack_eip_1559_fields(transaction_bytes)?;
+
+    let (signature, recovery_id) = sign_recoverable(signing_account, transaction_bytes)?;
+    let signed_fields = pack_signed_fields(&fields, recovery_id, &signature);
+    Ok([&[EIP_1559_TRANSACTION_TYPE], &signed_fields[..]]
+        .concat()
+        .into_boxed_slice())
+}
+
+/// Unpack a legacy transaction's fields, and its EIP-155 chain ID, if present.
+fn unpack_legacy_fields(transaction_bytes: &Bytes) -> Result<(Vec<&[u8]>, Option<u64>), String> {
+    let fields = unpack_rlp_fields(transaction_bytes, transaction_bytes)?;
+    let chain_id = match fields.len() {
+        LEGACY_FIELD_COUNT => None,
+        EIP_155_FIELD_COUNT => Some(unpack_eip_155_chain_id(&fields, transaction_bytes)?),
+        count => {
+            return Err(errors::message_with_base64(
+                "sign_ethereum_transaction",
+                "legacy transaction has wrong number of fields",
+                format!("expected 6 or 9, got {}", count),
+                "unsigned transaction",
+                transaction_bytes,
+            ))
+        }
+    };
+    Ok((fields, chain_id))
+}
+
+/// Unpack an EIP-1559 transaction's fields, after its type byte.
+fn unpack_eip_1559_fields(transaction_bytes: &Bytes) -> Result<Vec<&[u8]>, String> {
+    let fields = unpack_rlp_fields(&transaction_bytes[1..], transaction_bytes)?;
+    if fields.len() != EIP_1559_FIELD_COUNT {
+        return Err(errors::message_with_base64(
+            "sign_ethereum_transaction",
+            "EIP-1559 transaction has wrong number of fields",
+            format!("expected 9, got {}", fields.len()),
+            "unsigned transaction",
+            transaction_bytes,
+        ));
+    }
+    Ok(fields)
+}
+
+/// Unpack the raw items of the RLP list in `rlp_bytes`.
+///
+/// This requires `rlp_bytes` to be exactly one list, without trailing bytes.
+fn unpack_rlp_fields<'a>(
+    rlp_bytes: &'a [u8],
+    transaction_bytes: &Bytes,
+) -> Result<Vec<&'a [u8]>, String> {
+    let rlp = Rlp::new(rlp_bytes);
+    let unpack_error = |err: rlp::DecoderError| {
+        errors::message_with_base64(
+            "sign_ethereum_transaction",
+            "failed to unpack transaction RLP",
+            err,
+            "unsigned transaction",
+            transaction_bytes,
+        )
+    };
+
+    let payload_info = rlp.payload_info().map_err(unpack_error)?;
+    if !rlp.is_list() || payload_info.header_len + payload_info.value_len != rlp_bytes.len() {
+        return Err(unpack_error(rlp::DecoderError::Custom(
+            "expected a single RLP list",
+        )));
+    }
+    Ok(rlp.iter().map(|item| item.as_raw()).collect())
+}
+
+/// Unpack the chain ID of an EIP-155 signing payload, and check its zero placeholders.
+fn unpack_eip_155_chain_id(fields: &[&[u8]], transaction_bytes: &Bytes) -> Result<u64, String> {
+    let chain_id: u64 = Rlp::new(fields[6]).as_val().map_err(|err| {
+        errors::message_with_base64(
+            "sign_ethereum_transaction",
+            "failed to unpack EIP-155 chain ID",
+            err,
+            "unsigned transaction",
+            transaction_bytes,
+        )
+    })?;
+    if fields[7] != RLP_EMPTY_STRING || fields[8] != RLP_EMPTY_STRING {
+        return Err(errors::message_with_base64(
+            "sign_ethereum_transaction",
+            "EIP-155 transaction has non-empty signature placeholders",
+            "expected r = 0 and s = 0",
+            "unsigned transaction",
+            transaction_bytes,
+        ));
+    }
+    Ok(chain_id)
+}
+
+/// Unpack the RLP value of the transaction field `name`.
+fn unpack_field<T: rlp::Decodable>(
+    field: &[u8],
+    name: &str,
+    transaction_bytes: &Bytes,
+) -> Result<T, String> {
+    Rlp::new(field).as_val().map_err(|err| {
+        errors::message_with_base64(
+            "sign_ethereum_transaction",
+            "failed to unpack transaction field",
+            format!("{}: {}", name, err),
+            "unsigned transaction",
+            transaction_bytes,
+        )
+    })
+}
+
+/// Unpack the 256-bit integer field `name`, as a `0x`-prefixed hex quantity.
+fn unpack_quantity(field: &[u8], name: &str, transaction_bytes: &Bytes) -> Result<String, String> {
+    let bytes: Vec<u8> = unpack_field(field, name, transaction_bytes)?;
+    if 32 < bytes.len() || bytes.first() == Some(&0) {
+        return Err(errors::message_with_base64(
+            "sign_ethereum_transaction",
+            "failed to unpack transaction field",
+            format!("{}: expected a 256-bit integer without leading zeros", name),
+            "unsigned transaction",
+            transaction_bytes,
+        ));
+    }
+    let digits = hex::encode(bytes);
+    let digits = digits.trim_start_matches('0');
+    Ok(format!(
+        "0x{}",
+        if digits.is_empty() { "0" } else { digits }
+    ))
+}
+
+/// Unpack the `to` field: empty for contract creation, or an address.
+fn unpack_to(field: &[u8], transaction_bytes: &Bytes) -> Result<Option<String>, String> {
+    let bytes: Vec<u8> = unpack_field(field, "to", transaction_bytes)?;
+    if bytes.is_empty() {
+        return Ok(None);
+    }
+    let address: EthereumAddressBytes = bytes.as_slice().try_into().map_err(|_| {
+        errors::message_with_base64(
+            "sign_ethereum_transaction",
+            "failed to unpack transaction field",
+            format!("to: expected 20 bytes, got {}", bytes.len()),
+            "unsigned transaction",
+            transaction_bytes,
+        )
+    })?;
+    Ok(Some(ethereum_checksum_address(&address)))
+}
+
+/// Sign the Keccak-256 hash of `payload`, returning the signature and its recovery ID.
+///
+/// The signature is deterministic (RFC 6979), with normalized low `s`.
+///
+/// Like Ethereum clients (via libsecp256k1), this derives the RFC 6979 nonce with HMAC-SHA-256
+/// over the Keccak-256 hash: k256's own `Signer` uses Keccak-256 for the HMAC too, which gives
+/// valid signatures, but not the ones other clients give for the same key and transaction.
+fn sign_recoverable(
+    signing_account: &EthereumAccount,
+    payload: &[u8],
+) -> Result<(recoverable::Signature, u64), String> {
+    let sign_error = |err: k256::ecdsa::Error| {
+        errors::message_with_base64(
+            "sign_ethereum_transaction",
+            "k256 error while signing transaction",
+            err,
+            "signing payload",
+            payload,
+        )
+    };
+
+    let secret_scalar =
+        Zeroizing::new(SecretKey::from(&signing_account.as_signing_key()).to_nonzero_scalar());
+    let hash = Keccak256::digest(payload);
+    let z = <Scalar as Reduce<U256>>::from_be_bytes_reduced(hash);
+    let k = rfc6979_generate_k::<Secp256k1, Sha256>(&*secret_scalar, &z, &[]);
+    let (signature, recovery_id) = secret_scalar
+        .try_sign_prehashed(**k, z)
+        .map_err(sign_error)?;
+    let recovery_id = recovery_id
+        .ok_or_else(k256::ecdsa::Error::new)
+        .and_then(recoverable::Id::try_from)
+        .map_err(sign_error)?;
+    let signature = recoverable::Signature::new(&signature, recovery_id).map_err(sign_error)?;
+    Ok((signature, u8::from(recovery_id).into()))
+}
+
+/// RLP-encode `fields` followed by the signature's `v`, `r`, and `s`.
+fn pack_signed_fields(fields: &[&[u8]], v: u64, signature: &recoverable::Signature) -> Vec<u8> {
+    let signature_bytes: &[u8] = signature.as_ref();
+    let (r, s) = (&signature_bytes[..32], &signature_bytes[32..64]);
+
+    let mut stream = RlpStream::new_list(fields.len() + 3);
+    for field in fields {
+        stream.append_raw(field, 1);
+    }
+    stream.append(&v);
+    // RLP integers have no leading zeros.
+    stream.append(&strip_leading_zeros(r).to_vec());
+    stream.append(&strip_leading_zeros(s).to_vec());
+    stream.out().to_vec()
+}
+
+fn strip_leading_zeros(bytes: &[u8]) -> &[u8] {
+    let first_nonzero = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+    &bytes[first_nonzero..]
+}
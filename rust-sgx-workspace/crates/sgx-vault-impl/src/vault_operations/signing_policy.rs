@@ -0,0 +1,125 @@
+//! Enforce [`AlgorandSigningPolicy`] before signing.
+
+use std::prelude::v1::String;
+
+use crate::schema::actions::{AlgorandTransactionSummary, TransactionToSign};
+use crate::schema::entities::AlgorandSigningPolicy;
+
+/// The transaction types that [`AlgorandSigningPolicy`]'s limits account for.
+///
+/// Others (like `keyreg`, `appl`, `acfg`, and `afrz`) can move or lock funds in ways that
+/// the limits don't model, so a policy with any limit refuses them.
+const LIMITED_TRANSACTION_TYPES: &[&str] = &["pay", "axfer"];
+
+/// Check `transaction_to_sign`, summarized as `summaries`, against `policy`.
+///
+/// The policy's limits only model Algorand transactions, so a policy with any limit refuses
+/// Ethereum transactions outright, rather than letting them bypass it.
+pub fn check_signing_policy(
+    policy: &AlgorandSigningPolicy,
+    transaction_to_sign: &TransactionToSign,
+    summaries: &[AlgorandTransactionSummary],
+) -> Result<(), String> {
+    if let TransactionToSign::EthereumTransaction { .. } = transaction_to_sign {
+        if policy.has_limits() {
+            return Err(
+                "Ethereum transactions are not allowed while the signing policy has limits".into(),
+            );
+        }
+    }
+    check_algorand_signing_policy(policy, summaries)
+}
+
+/// Check the transactions that the vault would sign against `policy`.
+///
+/// Return the first violation found, as a message for the vault owner.
+pub fn check_algorand_signing_policy(
+    policy: &AlgorandSigningPolicy,
+    summaries: &[AlgorandTransactionSummary],
+) -> Result<(), String> {
+    summaries
+        .iter()
+        .filter(|summary| summary.signed_by_vault)
+        .try_for_each(|summary| check_algorand_transaction(policy, summary))
+}
+
+fn check_algorand_transaction(
+    policy: &AlgorandSigningPolicy,
+    summary: &AlgorandTransactionSummary,
+) -> Result<(), String> {
+    let violation = |message: String| {
+        Err(format!(
+            "transaction {}: {}",
+            summary.transaction_id, message
+        ))
+    };
+
+    if policy.has_limits() {
+        // Rekeying hands the account to another key, whatever the amount.
+        if let Some(rekey_to) = &summary.rekey_to {
+            return violation(format!(
+                "rekeying the account to {} is not allowed",
+                rekey_to
+            ));
+        }
+        if !LIMITED_TRANSACTION_TYPES.contains(&summary.transaction_type.as_str()) {
+            return violation(format!(
+                "transaction type {:?} is not allowed",
+                summary.transaction_type
+            ));
+        }
+    }
+
+    if let Some(allowed_genesis_ids) = &policy.allowed_genesis_ids {
+        let genesis_id = summary.genesis_id.as_deref().unwrap_or_default();
+        if !allowed_genesis_ids
+            .iter()
+            .any(|allowed| allowed == genesis_id)
+        {
+            return violation(format!("network {:?} is not allowed", genesis_id));
+        }
+    }
+
+    if let Some(max_fee) = policy.max_fee {
+        if max_fee < summary.fee {
+            return violation(format!(
+                "fee {} exceeds the maximum of {}",
+                summary.fee, max_fee
+            ));
+        }
+    }
+
+    if let Some(max_amount) = policy.max_amount {
+        if let Some(close_remainder_to) = &summary.close_remainder_to {
+            return violation(format!(
+                "closing the account to {} exceeds the maximum amount of {}",
+                close_remainder_to, max_amount
+            ));
+        }
+        let amount = summary.amount.unwrap_or_default();
+        if max_amount < amount {
+            return violation(format!(
+                "amount {} exceeds the maximum of {}",
+                amount, max_amount
+            ));
+        }
+    }
+
+    if let Some(allowed_receivers) = &policy.allowed_receivers {
+        for receiver in summary.receiver.iter().chain(&summary.close_remainder_to) {
+            if !allowed_receivers.contains(receiver) {
+                return violation(format!("receiver {} is not allowed", receiver));
+            }
+        }
+    }
+
+    if let Some(allowed_asset_ids) = &policy.allowed_asset_ids {
+        if let Some(asset_id) = summary.asset_id {
+            if !allowed_asset_ids.contains(&asset_id) {
+                return violation(format!("asset {} is not allowed", asset_id));
+            }
+        }
+    }
+
+    Ok(())
+}
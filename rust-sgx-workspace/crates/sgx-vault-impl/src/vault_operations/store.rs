@@ -1,24 +1,96 @@
 use std::io;
-use std::prelude::v1::Box;
+use std::path::{Path, PathBuf};
+use std::prelude::v1::{Box, ToOwned};
+use std::sync::SgxRwLock;
+use std::untrusted::fs;
 
+use lazy_static::lazy_static;
 use sgx_trts::memeq::ConsttimeMemEq;
 use thiserror::Error;
 
-use crate::ported::kv_store::fs::{FsStore, SgxFiler};
+use crate::ported::kv_store::codec::{JsonFallbackCodec, MessagePackCodec};
+use crate::ported::kv_store::fs::{FsStore, SgxFileKeyPolicy, SgxFiler};
 use crate::ported::kv_store::{Key, KvStore};
 use crate::schema::entities::VaultStorable;
+use crate::vault_operations::migration::upgrade_vault_schema;
 
-type VaultStore = FsStore<SgxFiler, VaultStorable>;
+/// Vault records are MessagePack, but older records may still be JSON.
+pub type VaultCodec = JsonFallbackCodec<MessagePackCodec>;
 
-// FIXME: Hardcoded
-pub const VAULT_STORE_DIR: &str = "vault_store";
+type VaultStore = FsStore<SgxFiler, VaultStorable, VaultCodec>;
+
+/// Used until [`init_vault_store`] is called, relative to the host's working directory.
+pub const DEFAULT_VAULT_STORE_DIR: &str = "vault_store";
+
+lazy_static! {
+    static ref VAULT_STORE_DIR: SgxRwLock<PathBuf> = SgxRwLock::new(DEFAULT_VAULT_STORE_DIR.into());
+}
+
+/// Vault data is bound to the enclave signer, so upgraded enclaves from the same signer can read it.
+pub const VAULT_STORE_KEY_POLICY: SgxFileKeyPolicy = SgxFileKeyPolicy::MrSigner {
+    key_id: *b"ntc-vault-store/key-v1\0\0\0\0\0\0\0\0\0\0",
+    isv_svn: 0,
+};
+
+/// Use `store_dir` for the vault store, creating it if missing.
+///
+/// The host calls this on enclave initialisation, via the [`vault_store_init`] ECALL.
+///
+/// [`vault_store_init`]: crate::ecalls::vault_store_init::vault_store_init
+pub fn init_vault_store(store_dir: &Path) -> Result<(), io::Error> {
+    if store_dir.as_os_str().is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "vault store directory must not be empty",
+        ));
+    }
+    fs::create_dir_all(store_dir)?;
+    if !fs::metadata(store_dir)?.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("vault store path {:?} is not a directory", store_dir),
+        ));
+    }
+
+    let mut current = VAULT_STORE_DIR
+        .write()
+        .expect("VAULT_STORE_DIR lock poisoned");
+    *current = store_dir.to_owned();
+    Ok(())
+}
+
+/// The directory set by [`init_vault_store`], or [`DEFAULT_VAULT_STORE_DIR`].
+pub fn vault_store_dir() -> PathBuf {
+    VAULT_STORE_DIR
+        .read()
+        .expect("VAULT_STORE_DIR lock poisoned")
+        .clone()
+}
 
 pub fn vault_store() -> VaultStore {
-    FsStore::new(VAULT_STORE_DIR, SgxFiler)
+    // Vaults written before explicit key management used the SDK's auto key:
+    // rotate them to the current key as they are read.
+    let filer =
+        SgxFiler::with_previous_keys(VAULT_STORE_KEY_POLICY, vec![SgxFileKeyPolicy::AutoKey])
+            .with_sync_writes(true);
+    FsStore::with_codec(vault_store_dir(), filer)
+}
+
+/// Like [`vault_store`], but without previous keys: records sealed under them fail to load.
+pub fn current_key_vault_store() -> VaultStore {
+    FsStore::with_codec(vault_store_dir(), SgxFiler::new(VAULT_STORE_KEY_POLICY))
 }
 
 pub fn save_new_vault(new_vault: &VaultStorable) -> Result<(), io::Error> {
-    let mut store = vault_store();
+    save_new_vault_in(&mut vault_store(), new_vault)
+}
+
+/// [`save_new_vault`], in `store`.
+pub fn save_new_vault_in<S>(store: &mut S, new_vault: &VaultStorable) -> Result<(), io::Error>
+where
+    S: KvStore<VaultStorable>,
+    io::Error: From<S::Error>,
+{
     let key = &key_from_id(&new_vault.vault_id)?;
     match store.try_insert(key, new_vault)? {
         None => Ok(()),
@@ -30,10 +102,23 @@ pub fn save_new_vault(new_vault: &VaultStorable) -> Result<(), io::Error> {
 }
 
 /// Return `None` if `vault_id` not found.
+///
+/// Older records get upgraded to the current schema in memory; see [`migrate_vault_record`].
+///
+/// [`migrate_vault_record`]: crate::vault_operations::migration::migrate_vault_record
 pub fn load_vault(vault_id: &str) -> Result<Option<VaultStorable>, io::Error> {
-    let store = vault_store();
+    load_vault_in(&vault_store(), vault_id)
+}
+
+/// [`load_vault`], from `store`.
+pub fn load_vault_in<S>(store: &S, vault_id: &str) -> Result<Option<VaultStorable>, io::Error>
+where
+    S: KvStore<VaultStorable>,
+    io::Error: From<S::Error>,
+{
     let key = &key_from_id(vault_id)?;
-    store.load(key)
+    let loaded = store.load(key)?;
+    Ok(loaded.map(upgrade_vault_schema).transpose()?)
 }
 
 pub fn key_from_id(vault_id: &str) -> Result<Box<Key>, io::Error> {
@@ -44,7 +129,21 @@ pub fn key_from_id(vault_id: &str) -> Result<Box<Key>, io::Error> {
 
 /// Load and authenticate access to a vault.
 pub fn unlock_vault(vault_id: &str, auth_password: &str) -> Result<VaultStorable, UnlockVaultError> {
-    let stored: VaultStorable = load_vault(vault_id)?.ok_or(UnlockVaultError::InvalidVaultId)?;
+    unlock_vault_in(&vault_store(), vault_id, auth_password)
+}
+
+/// [`unlock_vault`], from `store`.
+pub fn unlock_vault_in<S>(
+    store: &S,
+    vault_id: &str,
+    auth_password: &str,
+) -> Result<VaultStorable, UnlockVaultError>
+where
+    S: KvStore<VaultStorable>,
+    io::Error: From<S::Error>,
+{
+    let stored: VaultStorable =
+        load_vault_in(store, vault_id)?.ok_or(UnlockVaultError::InvalidVaultId)?;
 
     match ConsttimeMemEq::consttime_memeq(stored.auth_password.as_bytes(), auth_password.as_bytes()) {
         true => Ok(stored),
@@ -71,11 +170,26 @@ pub enum UnlockVaultError {
     IoError(#[from] io::Error),
 }
 
+/// Apply `mutate_fn` to an existing vault, atomically.
+///
+/// `mutate_fn` is applied again if the vault changes concurrently.
 pub fn mutate_vault(
     vault_id: &str,
-    mutate_fn: impl FnOnce(VaultStorable) -> VaultStorable,
+    mutate_fn: impl FnMut(VaultStorable) -> VaultStorable,
 ) -> Result<Option<VaultStorable>, io::Error> {
-    let mut store = vault_store();
+    mutate_vault_in(&mut vault_store(), vault_id, mutate_fn)
+}
+
+/// [`mutate_vault`], in `store`.
+pub fn mutate_vault_in<S>(
+    store: &mut S,
+    vault_id: &str,
+    mutate_fn: impl FnMut(VaultStorable) -> VaultStorable,
+) -> Result<Option<VaultStorable>, io::Error>
+where
+    S: KvStore<VaultStorable>,
+    io::Error: From<S::Error>,
+{
     let key = &key_from_id(vault_id)?;
-    store.mutate(key, mutate_fn)
+    Ok(store.mutate(key, mutate_fn)?)
 }
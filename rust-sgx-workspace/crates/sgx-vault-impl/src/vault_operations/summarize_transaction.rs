@@ -0,0 +1,224 @@
+//! Implement [`SummarizeTransaction`].
+
+use std::prelude::v1::{String, ToString, Vec};
+
+use algonaut::core::Address;
+use algonaut::transaction::SignedTransaction;
+use serde::Deserialize;
+
+use crate::schema::actions::{
+    AlgorandTransactionSummary,
+    SummarizeTransaction,
+    SummarizeTransactionResult,
+    TransactionSummary,
+    TransactionToSign,
+};
+use crate::schema::entities::AlgorandAccount;
+use crate::schema::types::{AlgorandAssetId, Bytes};
+use crate::vault_operations::errors;
+use crate::vault_operations::sign_transaction_algorand::{
+    algorand_network_compatible,
+    strip_transaction_prefix,
+    unpack_algorand_sender,
+    unpack_algorand_transaction,
+};
+use crate::vault_operations::sign_transaction_ethereum::summarize_ethereum;
+use crate::vault_operations::signing_policy::check_signing_policy;
+use crate::vault_operations::store::unlock_vault;
+
+type Result = SummarizeTransactionResult;
+
+pub fn summarize_transaction(request: &SummarizeTransaction) -> Result {
+    let stored = match unlock_vault(&request.vault_id, &request.auth_password) {
+        Ok(stored) => stored,
+        Err(err) => return err.into(),
+    };
+
+    let algorand_transactions =
+        match summarize_transaction_to_sign(&stored.algorand_account, &request.transaction_to_sign)
+        {
+            Ok(summaries) => summaries,
+            Err(message) => return Result::Failed(message),
+        };
+    let ethereum_transactions = match &request.transaction_to_sign {
+        TransactionToSign::EthereumTransaction { transaction_bytes } => {
+            match summarize_ethereum(transaction_bytes) {
+                Ok(summary) => vec![summary],
+                Err(message) => return Result::Failed(message),
+            }
+        }
+        _ => vec![],
+    };
+    let policy_violation = check_signing_policy(
+        &stored.policy.algorand_signing,
+        &request.transaction_to_sign,
+        &algorand_transactions,
+    )
+    .err();
+
+    Result::Summarized(TransactionSummary {
+        algorand_transactions,
+        ethereum_transactions,
+        policy_violation,
+    })
+}
+
+/// Summarize the transactions in `transaction_to_sign`.
+pub(crate) fn summarize_transaction_to_sign(
+    signing_account: &AlgorandAccount,
+    transaction_to_sign: &TransactionToSign,
+) -> std::result::Result<Vec<AlgorandTransactionSummary>, String> {
+    match transaction_to_sign {
+        TransactionToSign::AlgorandTransaction { transaction_bytes }
+        | TransactionToSign::AlgorandMultisigTransaction {
+            transaction_bytes, ..
+        } => Ok(vec![summarize_algorand(transaction_bytes, true)?]),
+
+        TransactionToSign::AlgorandTransactionGroup { transactions_bytes } => {
+            let signing_address = signing_account.address_bytes();
+            transactions_bytes
+                .iter()
+                .map(|transaction_bytes| {
+                    let signed_by_vault =
+                        unpack_algorand_sender(transaction_bytes)? == signing_address;
+                    summarize_algorand(transaction_bytes, signed_by_vault)
+                })
+                .collect()
+        }
+
+        TransactionToSign::AlgorandMultisigSignedTransaction {
+            signed_transaction_bytes,
+            ..
+        } => {
+            let partially_signed: SignedTransaction = algorand_network_compatible::from_msgpack(
+                signed_transaction_bytes,
+            )
+            .map_err(|err| {
+                errors::message_with_base64(
+                    "summarize_transaction",
+                    "failed to unpack received partially-signed transaction",
+                    err,
+                    "signed transaction msgpack",
+                    signed_transaction_bytes,
+                )
+            })?;
+            let transaction_bytes: Bytes = partially_signed
+                .transaction
+                .bytes_to_sign()
+                .map_err(|err| {
+                    errors::message_with_debug_value(
+                        "summarize_transaction",
+                        "failed to pack transaction of partially-signed transaction",
+                        err,
+                        "partially-signed transaction",
+                        &partially_signed,
+                    )
+                })?
+                .into_boxed_slice();
+            Ok(vec![summarize_algorand(&transaction_bytes, true)?])
+        }
+
+        // Data and message signing involves no transactions.
+        TransactionToSign::AlgorandBytes { .. } | TransactionToSign::Ed25519Message { .. } => {
+            Ok(vec![])
+        }
+
+        // Ethereum transactions have no Algorand summary: see summarize_ethereum.
+        TransactionToSign::EthereumTransaction { .. } => Ok(vec![]),
+    }
+}
+
+/// Summarize a "TX"-prefixed unsigned Algorand transaction.
+fn summarize_algorand(
+    transaction_bytes: &Bytes,
+    signed_by_vault: bool,
+) -> std::result::Result<AlgorandTransactionSummary, String> {
+    let transaction = &unpack_algorand_transaction(transaction_bytes)?;
+    let transaction_id = transaction.id().map_err(|err| {
+        errors::message_with_debug_value(
+            "summarize_transaction",
+            "algonaut error while computing transaction ID",
+            err,
+            "unsigned transaction",
+            transaction,
+        )
+    })?;
+
+    let fields: AlgorandTransactionFields =
+        algorand_network_compatible::from_msgpack(strip_transaction_prefix(transaction_bytes)?)
+            .map_err(|err| {
+                errors::message_with_base64(
+                    "summarize_transaction",
+                    "failed to unpack transaction fields",
+                    err,
+                    "unsigned transaction msgpack",
+                    transaction_bytes,
+                )
+            })?;
+
+    let to_base32 = |address: Address| address.to_string();
+    Ok(AlgorandTransactionSummary {
+        transaction_id,
+        signed_by_vault,
+
+        transaction_type: fields.transaction_type,
+        sender: fields.sender.to_string(),
+        receiver: fields.receiver.or(fields.asset_receiver).map(to_base32),
+        amount: fields.amount.or(fields.asset_amount),
+        asset_id: fields.asset_id,
+        close_remainder_to: fields
+            .close_remainder_to
+            .or(fields.asset_close_to)
+            .map(to_base32),
+        rekey_to: fields.rekey_to.map(to_base32),
+
+        fee: fields.fee,
+        first_valid: fields.first_valid,
+        last_valid: fields.last_valid,
+        genesis_id: fields.genesis_id,
+    })
+}
+
+/// The summarized fields of an Algorand transaction, as named on the network.
+///
+/// Reading these directly avoids matching on every algonaut transaction type.
+/// The network encoding omits zero and empty values, so every field has a default.
+///
+/// See: <https://developer.algorand.org/docs/get-details/transactions/transactions/>
+#[derive(Deserialize)]
+struct AlgorandTransactionFields {
+    #[serde(rename = "type")]
+    transaction_type: String,
+    #[serde(rename = "snd")]
+    sender: Address,
+
+    // Payment
+    #[serde(rename = "rcv", default)]
+    receiver: Option<Address>,
+    #[serde(rename = "amt", default)]
+    amount: Option<u64>,
+    #[serde(rename = "close", default)]
+    close_remainder_to: Option<Address>,
+
+    // Asset transfer
+    #[serde(rename = "xaid", default)]
+    asset_id: Option<AlgorandAssetId>,
+    #[serde(rename = "aamt", default)]
+    asset_amount: Option<u64>,
+    #[serde(rename = "arcv", default)]
+    asset_receiver: Option<Address>,
+    #[serde(rename = "aclose", default)]
+    asset_close_to: Option<Address>,
+
+    // Common
+    #[serde(rename = "rekey", default)]
+    rekey_to: Option<Address>,
+    #[serde(rename = "fee", default)]
+    fee: u64,
+    #[serde(rename = "fv", default)]
+    first_valid: u64,
+    #[serde(rename = "lv", default)]
+    last_valid: u64,
+    #[serde(rename = "gen", default)]
+    genesis_id: Option<String>,
+}
@@ -0,0 +1,102 @@
+//! Implement [`UpdateVaultPolicy`].
+
+use core::fmt::Debug;
+use std::prelude::v1::{String, ToString, Vec};
+
+use crate::schema::actions::{UpdateVaultPolicy, UpdateVaultPolicyResult};
+use crate::schema::entities::{AlgorandSigningPolicy, VaultPolicy, VaultStorable};
+use crate::vault_operations::store::{mutate_vault, unlock_vault};
+
+type Result = UpdateVaultPolicyResult;
+
+pub fn update_vault_policy(request: &UpdateVaultPolicy) -> Result {
+    if let Err(err) = unlock_vault(&request.vault_id, &request.auth_password) {
+        return err.into();
+    }
+
+    // Check the policy that gets replaced, and not the one unlocked above:
+    // a concurrent update may have tightened it since, and this re-runs on retry.
+    let policy = &request.policy;
+    let mut loosened = None;
+    let updated = mutate_vault(&request.vault_id, |mut stored: VaultStorable| {
+        loosened = check_tightens(&stored.policy, policy).err();
+        if loosened.is_none() {
+            stored.policy = policy.clone();
+        }
+        stored
+    });
+    match (updated, loosened) {
+        (Ok(Some(_)), Some(message)) => Result::WouldLoosen(message),
+        (Ok(Some(updated)), None) => Result::Updated(updated.policy),
+        (Ok(None), _) => Result::InvalidAuth,
+        (Err(err), _) => Result::Failed(err.to_string()),
+    }
+}
+
+/// Check that `new` is at least as strict as `current`, in every setting.
+///
+/// Return the first loosened setting, as a message for the vault owner.
+fn check_tightens(current: &VaultPolicy, new: &VaultPolicy) -> std::result::Result<(), String> {
+    if new.allow_algorand_account_export && !current.allow_algorand_account_export {
+        return Err("allow_algorand_account_export can't be re-enabled".to_string());
+    }
+
+    let (current, new) = (&current.algorand_signing, &new.algorand_signing);
+    let AlgorandSigningPolicy {
+        max_amount,
+        max_fee,
+        allowed_receivers,
+        allowed_asset_ids,
+        allowed_genesis_ids,
+    } = current;
+    check_maximum("max_amount", max_amount, &new.max_amount)?;
+    check_maximum("max_fee", max_fee, &new.max_fee)?;
+    check_allowed(
+        "allowed_receivers",
+        allowed_receivers,
+        &new.allowed_receivers,
+    )?;
+    check_allowed(
+        "allowed_asset_ids",
+        allowed_asset_ids,
+        &new.allowed_asset_ids,
+    )?;
+    check_allowed(
+        "allowed_genesis_ids",
+        allowed_genesis_ids,
+        &new.allowed_genesis_ids,
+    )?;
+    Ok(())
+}
+
+/// A set maximum may be lowered, but not raised or removed.
+fn check_maximum(
+    name: &str,
+    current: &Option<u64>,
+    new: &Option<u64>,
+) -> std::result::Result<(), String> {
+    match (current, new) {
+        (Some(_), None) => Err(format!("{} can't be removed", name)),
+        (Some(current), Some(new)) if current < new => Err(format!(
+            "{} can't be raised from {} to {}",
+            name, current, new
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// A set allow-list may be narrowed, but not extended or removed.
+fn check_allowed<T: PartialEq + Debug>(
+    name: &str,
+    current: &Option<Vec<T>>,
+    new: &Option<Vec<T>>,
+) -> std::result::Result<(), String> {
+    match (current, new) {
+        (Some(_), None) => Err(format!("{} can't be removed", name)),
+        (Some(current), Some(new)) => match new.iter().find(|item| !current.contains(item)) {
+            Some(added) => Err(format!("{} can't be extended with {:?}", name, added)),
+            None => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
@@ -10,3 +10,30 @@ extern "C" {
         len: size_t,
     ) -> sgx_status_t;
 }
+extern "C" {
+    pub fn enclave_public_key(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        public_key: *mut [u8; 32usize],
+    ) -> sgx_status_t;
+}
+extern "C" {
+    pub fn load_data_package(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        sealed_package_buffer: *const u8,
+        sealed_package_size: size_t,
+        record_count: *mut size_t,
+    ) -> sgx_status_t;
+}
+extern "C" {
+    pub fn run_computation(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        request_buffer: *const u8,
+        request_size: size_t,
+        result_buffer: *mut u8,
+        result_capacity: size_t,
+        result_used: *mut size_t,
+    ) -> sgx_status_t;
+}
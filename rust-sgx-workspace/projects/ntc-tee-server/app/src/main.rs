@@ -4,7 +4,7 @@ extern crate sgx_urts;
 #[path = "../codegen/Enclave_u.rs"]
 mod enclave_u;
 
-use enclave_u::ecall_test;
+use enclave_u::{ecall_test, enclave_public_key};
 use sgx_types::{
     sgx_attributes_t,
     sgx_launch_token_t,
@@ -70,5 +70,21 @@ fn main() {
 
     println!("[+] ecall_test success...");
 
+    let mut public_key = [0_u8; 32];
+    let result = unsafe { enclave_public_key(enclave.geteid(), &mut retval, &mut public_key) };
+    match (result, retval) {
+        (sgx_status_t::SGX_SUCCESS, sgx_status_t::SGX_SUCCESS) => {}
+        (sgx_status_t::SGX_SUCCESS, status) | (status, _) => {
+            println!("[-] ECALL enclave_public_key Failed {}!", status.as_str());
+            return;
+        }
+    }
+
+    let public_key_hex: String = public_key.iter().map(|b| format!("{:02x}", b)).collect();
+    println!(
+        "[+] Enclave public key for sealed data packages: {}",
+        public_key_hex
+    );
+
     enclave.destroy();
 }
@@ -0,0 +1,83 @@
+//! Sealed data packages: a dataset and its JSON Schema, sealed to the enclave.
+//!
+//! The enclave holds the last package loaded with [`load_data_package`], in memory,
+//! for [`ntc_tee_server_impl::computations`] to run over. See [`ntc_tee_server_impl::data_package`].
+
+use std::sync::{PoisonError, SgxMutex, SgxMutexGuard};
+use std::vec::Vec;
+
+use lazy_static::lazy_static;
+use ntc_tee_server_impl::box_crypto::{Nonce, PublicKey};
+use ntc_tee_server_impl::data_package::{
+    unseal_data_package,
+    validate_data_package,
+    DataPackage,
+    DataPackageError,
+};
+use serde::{Deserialize, Serialize};
+use sgx_types::sgx_status_t;
+use thiserror::Error;
+
+use crate::enclave_crypto::enclave_key_pair;
+use crate::serde_bytes_array;
+
+/// A [`DataPackage`], sealed to the enclave's public key (see [`enclave_key_pair`]).
+///
+/// This is MessagePack, and seals the same way as the vault enclave's `SealedMessage`:
+/// a NaCl box (X25519, XSalsa20-Poly1305) from the data provider's key.
+#[derive(Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+pub struct SealedDataPackage {
+    #[serde(with = "serde_bytes")]
+    pub ciphertext: Vec<u8>,
+    #[serde(with = "serde_bytes_array")]
+    pub nonce: Nonce,
+    #[serde(with = "serde_bytes_array")]
+    pub sender_public_key: PublicKey,
+}
+
+#[derive(Debug, Error)]
+pub enum LoadDataPackageError {
+    #[error("invalid sealed data package: {0}")]
+    InvalidSealedPackage(#[from] rmp_serde::decode::Error),
+
+    #[error("failed to derive the enclave key: {0:?}")]
+    EnclaveKey(sgx_status_t),
+
+    #[error(transparent)]
+    DataPackage(#[from] DataPackageError),
+}
+
+lazy_static! {
+    static ref LOADED_PACKAGE: SgxMutex<Option<DataPackage>> = SgxMutex::new(None);
+}
+
+/// Implementation for [`crate::ecalls::load_data_package`].
+///
+/// Unseal `sealed_package_bytes`, validate each of its records against its schema,
+/// and hold it in place of any package loaded before.
+///
+/// Return the number of records.
+pub fn load_data_package(sealed_package_bytes: &[u8]) -> Result<usize, LoadDataPackageError> {
+    let sealed_package: SealedDataPackage = rmp_serde::from_slice(sealed_package_bytes)?;
+    let key_pair = enclave_key_pair().map_err(LoadDataPackageError::EnclaveKey)?;
+    let package = unseal_data_package(
+        &key_pair,
+        &sealed_package.ciphertext,
+        &sealed_package.nonce,
+        &sealed_package.sender_public_key,
+    )?;
+    validate_data_package(&package)?;
+    let record_count = package.data.len();
+    *lock_loaded_package() = Some(package);
+    Ok(record_count)
+}
+
+/// Lock the loaded package, if any.
+///
+/// Loading replaces the package in one step, so a poisoned lock is safe to reuse.
+pub(crate) fn lock_loaded_package() -> SgxMutexGuard<'static, Option<DataPackage>> {
+    LOADED_PACKAGE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,128 @@
+//! ECALLs of the execution enclave.
+//!
+//! 1. [`enclave_public_key`]: the key that data providers seal data packages to.
+//! 2. [`load_data_package`]: unseal, validate, and hold a data package.
+//! 3. [`run_computation`]: run a built-in computation over the held package.
+
+use std::{ptr, slice};
+
+use ntc_tee_server_impl::computations::{run_computation_json, ComputationError};
+use sgx_types::{sgx_status_t, size_t, uint8_t};
+
+use crate::data_package::{self, lock_loaded_package};
+use crate::enclave_crypto::enclave_key_pair;
+
+/// ECALL: Get the enclave's public key, for [`crate::data_package::SealedDataPackage`].
+///
+/// # Errors
+///
+/// * [`sgx_status_t::SGX_ERROR_INVALID_PARAMETER`] - null pointer passed
+///
+/// * Any error from deriving the enclave key
+///
+/// # Safety
+///
+/// Expects to be called from SGX bridge, with validated input.
+#[no_mangle]
+pub unsafe extern "C" fn enclave_public_key(public_key: *mut [uint8_t; 32]) -> sgx_status_t {
+    if public_key.is_null() {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+
+    match enclave_key_pair() {
+        Ok(key_pair) => {
+            unsafe { *public_key = key_pair.public_key() };
+            sgx_status_t::SGX_SUCCESS
+        }
+        Err(status) => status,
+    }
+}
+
+/// ECALL wrapper for [`data_package::load_data_package`].
+///
+/// # Errors
+///
+/// * [`sgx_status_t::SGX_ERROR_INVALID_PARAMETER`] - received null pointers,
+///   or a package that can't be unsealed, or that does not match its schema
+///   (see [`data_package::LoadDataPackageError`])
+///
+/// # Safety
+///
+/// Expects to be called from SGX bridge, with validated input.
+#[no_mangle]
+pub unsafe extern "C" fn load_data_package(
+    sealed_package_buffer: *const uint8_t,
+    sealed_package_size: size_t,
+    record_count: *mut size_t,
+) -> sgx_status_t {
+    if sealed_package_buffer.is_null() || record_count.is_null() {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+
+    let sealed_package =
+        unsafe { slice::from_raw_parts(sealed_package_buffer, sealed_package_size) };
+
+    match data_package::load_data_package(sealed_package) {
+        Ok(count) => {
+            unsafe { *record_count = count };
+            sgx_status_t::SGX_SUCCESS
+        }
+        Err(err) => {
+            println!("load_data_package: {}", err);
+            sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+        }
+    }
+}
+
+/// ECALL wrapper for [`run_computation_json`].
+///
+/// # Errors
+///
+/// * [`sgx_status_t::SGX_ERROR_INVALID_PARAMETER`] - received null pointers,
+///   or a request that can't be run (see [`ComputationError`])
+///
+/// * [`sgx_status_t::SGX_ERROR_INVALID_STATE`] - no data package is loaded
+///
+/// * [`sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT`] - result exceeds buffer capacity:
+///   `result_used` receives its size
+///
+/// # Safety
+///
+/// Expects to be called from SGX bridge, with validated input.
+#[no_mangle]
+pub unsafe extern "C" fn run_computation(
+    request_buffer: *const uint8_t,
+    request_size: size_t,
+    result_buffer: *mut uint8_t,
+    result_capacity: size_t,
+    result_used: *mut size_t,
+) -> sgx_status_t {
+    if request_buffer.is_null() || result_buffer.is_null() || result_used.is_null() {
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+
+    let request = unsafe { slice::from_raw_parts(request_buffer, request_size) };
+
+    let result = {
+        let loaded_package = lock_loaded_package();
+        run_computation_json(request, loaded_package.as_ref())
+    };
+    let result = match result {
+        Ok(result) => result,
+        Err(err) => {
+            println!("run_computation: {}", err);
+            return match err {
+                ComputationError::NoPackageLoaded => sgx_status_t::SGX_ERROR_INVALID_STATE,
+                _ => sgx_status_t::SGX_ERROR_INVALID_PARAMETER,
+            };
+        }
+    };
+
+    unsafe { *result_used = result.len() };
+    if result.len() <= result_capacity {
+        unsafe { ptr::copy_nonoverlapping(result.as_ptr(), result_buffer, result.len()) };
+        sgx_status_t::SGX_SUCCESS
+    } else {
+        sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT
+    }
+}
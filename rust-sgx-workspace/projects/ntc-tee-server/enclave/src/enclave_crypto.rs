@@ -0,0 +1,40 @@
+//! The enclave's long-term key pair: data providers seal [`crate::data_package`]s to it.
+
+use ntc_tee_server_impl::box_crypto::BoxKeyPair;
+use sgx_tse::{rsgx_get_key, rsgx_self_report};
+use sgx_types::*;
+use zeroize::Zeroize;
+
+/// The enclave's X25519 key pair, derived from its sealing key.
+///
+/// This is stable for the same enclave and signer on the same platform,
+/// so data sealed to [`BoxKeyPair::public_key`] stays readable across enclave restarts.
+pub fn enclave_key_pair() -> SgxResult<BoxKeyPair> {
+    let mut enclave_key = get_enclave_key()?;
+    let mut seed = [0_u8; 32];
+    seed[..16].copy_from_slice(&enclave_key);
+    seed[16..].copy_from_slice(&enclave_key);
+    enclave_key.zeroize();
+    Ok(BoxKeyPair::from_seed(&mut seed))
+}
+
+/// Derive the enclave's sealing key, bound to its identity and signer.
+fn get_enclave_key() -> SgxResult<sgx_key_128bit_t> {
+    let report = rsgx_self_report();
+    let key_request = sgx_key_request_t {
+        key_name: SGX_KEYSELECT_SEAL,
+        key_policy: SGX_KEYPOLICY_MRENCLAVE | SGX_KEYPOLICY_MRSIGNER,
+        isv_svn: report.body.isv_svn,
+        reserved1: 0_u16,
+        cpu_svn: report.body.cpu_svn,
+        attribute_mask: sgx_attributes_t {
+            flags: TSEAL_DEFAULT_FLAGSMASK,
+            xfrm: 0,
+        },
+        key_id: sgx_key_id_t::default(),
+        misc_mask: TSEAL_DEFAULT_MISCMASK,
+        config_svn: report.body.config_svn,
+        reserved2: [0_u8; SGX_KEY_REQUEST_RESERVED2_BYTES],
+    };
+    rsgx_get_key(&key_request)
+}
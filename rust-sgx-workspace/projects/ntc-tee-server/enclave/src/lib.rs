@@ -5,6 +5,11 @@ extern crate sgx_types;
 #[macro_use]
 extern crate sgx_tstd as std;
 
+pub mod data_package;
+pub mod ecalls;
+pub mod enclave_crypto;
+mod serde_bytes_array;
+
 use std::io::{self, Write};
 use std::slice;
 
@@ -5,3 +5,10 @@ use sgx_types::*;
 extern "C" {
     pub fn run_tests_ecall(eid: sgx_enclave_id_t, retval: *mut size_t) -> sgx_status_t;
 }
+extern "C" {
+    pub fn vault_store_init(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        store_dir: *const ::std::os::raw::c_char,
+    ) -> sgx_status_t;
+}
@@ -2,12 +2,13 @@
 mod enclave_u;
 mod safe_ecalls;
 
-use std::fs::create_dir_all;
+use std::env;
+use std::ffi::CString;
 
 use sgx_types::{sgx_attributes_t, sgx_launch_token_t, sgx_misc_attribute_t, SgxResult};
 use sgx_urts::SgxEnclave;
 
-use crate::safe_ecalls::safe_run_tests_ecall;
+use crate::safe_ecalls::{safe_run_tests_ecall, safe_vault_store_init};
 
 static ENCLAVE_FILE: &str = "enclave.signed.so";
 
@@ -33,9 +34,12 @@ fn init_enclave() -> SgxResult<SgxEnclave> {
 fn main() -> Result<(), String> {
     let enclave = init_enclave().map_err(|err| format!("init_enclave failed: {:?}", err))?;
 
-    // FIXME: See VAULT_STORE_DIR
-    create_dir_all("vault_store")
-        .map_err(|err| format!("failed to create test vault_store directory: {:?}", err))?;
+    // Use a separate directory to run test instances side by side.
+    let vault_store_dir = env::var("VAULT_STORE_DIR").unwrap_or_else(|_| "vault_store".into());
+    let vault_store_dir_c = CString::new(vault_store_dir.as_str())
+        .map_err(|err| format!("invalid VAULT_STORE_DIR {:?}: {}", vault_store_dir, err))?;
+    safe_vault_store_init(enclave.geteid(), &vault_store_dir_c)
+        .map_err(|err| format!("vault_store_init({:?}) failed: {:?}", vault_store_dir, err))?;
 
     let failed_tests = safe_run_tests_ecall(enclave.geteid())
         .map_err(|err| format!("run_tests_ecall failed: {:?}", err))?;
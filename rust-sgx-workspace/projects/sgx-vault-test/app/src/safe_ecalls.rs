@@ -1,5 +1,7 @@
 //! Safe Rust wrappers around [`enclave_u`].
 
+use std::ffi::CStr;
+
 use sgx_types::{sgx_enclave_id_t, sgx_status_t, size_t, SgxResult};
 
 use crate::enclave_u;
@@ -11,3 +13,14 @@ pub(crate) fn safe_run_tests_ecall(eid: sgx_enclave_id_t) -> SgxResult<size_t> {
         err => Err(err),
     }
 }
+
+pub(crate) fn safe_vault_store_init(eid: sgx_enclave_id_t, store_dir: &CStr) -> SgxResult<()> {
+    let mut retval = sgx_status_t::SGX_ERROR_UNEXPECTED;
+    match unsafe { enclave_u::vault_store_init(eid, &mut retval, store_dir.as_ptr()) } {
+        sgx_status_t::SGX_SUCCESS => match retval {
+            sgx_status_t::SGX_SUCCESS => Ok(()),
+            err => Err(err),
+        },
+        err => Err(err),
+    }
+}
@@ -1,13 +1,16 @@
 use std::prelude::v1::ToString;
 
-use algonaut::core::{MicroAlgos, Round, SuggestedTransactionParams};
+use algonaut::core::{Address, MicroAlgos, Round, SuggestedTransactionParams};
 use algonaut::crypto::HashDigest;
 use algonaut::transaction::account::Account;
 use algonaut::transaction::{Pay, Transaction, TxnBuilder};
 
 pub(crate) fn create_test_transaction() -> Transaction {
     let account = Account::generate();
+    create_test_transaction_from(account.address())
+}
 
+pub(crate) fn create_test_transaction_from(sender: Address) -> Transaction {
     let params = SuggestedTransactionParams {
         genesis_id: "sandnet-v1".to_string(),
         genesis_hash: HashDigest(Default::default()),
@@ -20,7 +23,7 @@ pub(crate) fn create_test_transaction() -> Transaction {
     TxnBuilder::with(
         params,
         Pay::new(
-            account.address(),
+            sender,
             "4MYUHDWHWXAKA5KA7U5PEN646VYUANBFXVJNONBK3TIMHEMWMD4UBOJBI4"
                 .parse()
                 .unwrap(),
@@ -1,9 +1,11 @@
 use std::prelude::v1::ToString;
 
+use sgx_vault_impl::ported::kv_store::KvStore;
 use sgx_vault_impl::schema::actions;
 use sgx_vault_impl::schema::actions::CreateVaultResult;
 use sgx_vault_impl::schema::entities::VaultDisplay;
 use sgx_vault_impl::vault_operations::create_vault::create_vault;
+use sgx_vault_impl::vault_operations::store::{key_from_id, vault_store};
 
 pub fn create_test_vault() -> VaultDisplay {
     type Result = CreateVaultResult;
@@ -30,3 +32,10 @@ pub fn create_test_vault_with_username(username: &str) -> VaultDisplay {
         otherwise => panic!("{:?}", otherwise),
     }
 }
+
+/// Delete a vault created by [`create_test_vault_with_username`].
+pub fn delete_vault(existing: &VaultDisplay) {
+    let mut store = vault_store();
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    store.delete(key).unwrap();
+}
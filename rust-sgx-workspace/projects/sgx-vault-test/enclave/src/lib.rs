@@ -14,6 +14,9 @@ use std::vec::Vec;
 
 use sgx_tunittest::*;
 
+// Re-export ECALL implementations:
+pub use sgx_vault_impl::ecalls::vault_store_init::vault_store_init;
+
 #[no_mangle]
 pub extern "C" fn run_tests_ecall() -> usize {
     backtrace::enable_backtrace("enclave.signed.so", backtrace::PrintFormat::Short).unwrap();
@@ -21,27 +24,135 @@ pub extern "C" fn run_tests_ecall() -> usize {
     rsgx_unit_tests!(
         ported::proptest_crypto::prop_soda_box_roundtrips,
         ported::test_attestation::create_report_impl_works,
+        ported::test_attestation::create_report_with_user_data_works,
+        ported::test_attestation::create_tls_report_works,
+        ported::test_crypto::get_enclave_key_works,
+        ported::test_crypto::soda_box_counter_nonces_work,
+        ported::test_crypto::soda_box_decrypt_tampered_fails,
         ported::test_crypto::soda_box_decrypt_works,
         ported::test_crypto::soda_box_encrypt_works,
+        ported::test_crypto::soda_box_synthetic_nonces_work,
+        ported::test_crypto::soda_box_with_rng_works,
+        ported::test_crypto::soda_box_xchacha20poly1305_works,
         ported::test_kv_store::test_alter,
+        ported::test_kv_store::test_alter_retries_on_conflict,
+        ported::test_kv_store::test_compare_and_swap,
+        ported::test_kv_store::test_iter,
+        ported::test_kv_store::test_keys,
         ported::test_kv_store::test_load_save_delete,
         ported::test_kv_store::test_mutate,
+        ported::test_kv_store::test_save_fault_keeps_value,
         ported::test_kv_store::test_try_insert,
+        ported::test_kv_store::test_undecodable_index_recovers,
+        ported::test_kv_store::test_unreadable_index_fails,
+        ported::test_kv_store::test_unversioned_value_loads,
+        ported::test_kv_store_codec::codecs_encode_exact_capacity,
+        ported::test_kv_store_codec::codecs_roundtrip,
+        ported::test_kv_store_codec::fs_store_with_codec_works,
         ported::test_kv_store_fs::prop_fs_safe_roundtrip,
+        ported::test_sgx_filer::sgx_filer_reads_exact_size,
+        ported::test_sgx_filer::sgx_filer_roundtrips,
+        ported::test_sgx_filer::sgx_filer_rotates_previous_keys,
+        ported::test_sgx_filer::sgx_filer_sync_writes,
+        ported::test_sgx_filer::sgx_filer_write_fault_keeps_contents,
+        ported::test_sgx_filer::sgx_filer_wrong_key_fails,
+        ported::test_std_filer::std_filer_fs_store_works,
         schema::test_sealing::prop_seal_unseal_msgpack_roundtrips,
         schema::test_sealing::prop_seal_unseal_roundtrips,
+        schema::test_sealing::seal_default_version_is_omitted,
+        schema::test_sealing::seal_unseal_version_works,
+        schema::test_sealing::unseal_wrong_receiver_fails,
+        schema::test_session::session_counter_authenticated,
+        schema::test_session::session_keys_per_direction,
+        schema::test_session::session_seal_unseal_works,
+        vault_operations::test_audit_log::audit_log_chain_works,
+        vault_operations::test_audit_log::audit_log_rate_limits_invalid_auth,
+        vault_operations::test_audit_log::audit_log_segments_work,
+        vault_operations::test_audit_log::audit_log_skips_missing_vault,
+        vault_operations::test_audit_log::audit_log_write_ahead,
+        vault_operations::test_audit_log::audit_log_write_ahead_create_vault,
+        vault_operations::test_audit_log::audit_record_for_ethereum_signing,
+        vault_operations::test_audit_log::get_audit_log_works,
+        vault_operations::test_create_vault::create_vault_in_memory_works,
         vault_operations::test_create_vault::create_vault_works,
+        vault_operations::test_dispatch::vault_operation_invalid_request,
         vault_operations::test_dispatch::vault_operation_sealing_works,
+        vault_operations::test_export_algorand_account::export_algorand_account_bad_auth,
+        vault_operations::test_export_algorand_account::export_algorand_account_disabled,
+        vault_operations::test_export_algorand_account::export_algorand_account_works,
+        vault_operations::test_handover::handover_export_not_found,
+        vault_operations::test_handover::handover_export_public_key_mismatch,
+        vault_operations::test_handover::handover_import_existing,
+        vault_operations::test_handover::handover_import_replayed,
+        vault_operations::test_handover::handover_roundtrip_works,
+        vault_operations::test_handover::handover_store_works,
+        vault_operations::test_import_algorand_account::import_algorand_account_existing_username,
+        vault_operations::test_import_algorand_account::import_algorand_account_invalid_mnemonic,
+        vault_operations::test_import_algorand_account::import_algorand_account_mnemonic_works,
+        vault_operations::test_import_algorand_account::import_algorand_account_seed_works,
+        vault_operations::test_migration::load_vault_unsupported_schema_version,
+        vault_operations::test_migration::migrate_vault_record_current_unchanged,
+        vault_operations::test_migration::migrate_vault_record_not_found,
+        vault_operations::test_migration::migrate_vault_record_rotates_key,
+        vault_operations::test_migration::migrate_vault_record_upgrades_schema,
         vault_operations::test_open_vault::open_vault_bad_pin,
         vault_operations::test_open_vault::open_vault_malformed_vault_id,
         vault_operations::test_open_vault::open_vault_works,
+        vault_operations::test_response_cache::response_cache_evicts_oldest,
+        vault_operations::test_response_cache::response_cache_works,
+        vault_operations::test_sessions::session_accept_checks_enclave_key,
+        vault_operations::test_sessions::session_handshake_invalid_hello,
+        vault_operations::test_sessions::session_vault_operation_replayed,
+        vault_operations::test_sessions::session_vault_operation_unknown_session,
+        vault_operations::test_sessions::session_vault_operation_works,
+        vault_operations::test_sessions::sessions_evict_only_old_sessions,
+        vault_operations::test_sessions::sessions_expire,
+        vault_operations::test_sign_bytes::sign_algorand_bytes_transaction_prefix,
+        vault_operations::test_sign_bytes::sign_algorand_bytes_works,
+        vault_operations::test_sign_bytes::sign_ed25519_message_logic_sig_tag,
+        vault_operations::test_sign_bytes::sign_ed25519_message_reserved_tag,
+        vault_operations::test_sign_bytes::sign_ed25519_message_works,
         vault_operations::test_sign_transaction::sign_transaction_empty,
+        vault_operations::test_sign_transaction::sign_transaction_in_memory_works,
         vault_operations::test_sign_transaction::sign_transaction_malformed_transaction,
         vault_operations::test_sign_transaction::sign_transaction_without_tag,
         vault_operations::test_sign_transaction::sign_transaction_works,
+        vault_operations::test_sign_transaction_ethereum::sign_ethereum_eip_1559_example,
+        vault_operations::test_sign_transaction_ethereum::sign_ethereum_eip_1559_works,
+        vault_operations::test_sign_transaction_ethereum::sign_ethereum_eip_155_example,
+        vault_operations::test_sign_transaction_ethereum::sign_ethereum_legacy_without_chain_id,
+        vault_operations::test_sign_transaction_ethereum::sign_ethereum_policy_limits,
+        vault_operations::test_sign_transaction_ethereum::sign_ethereum_unsupported_type,
+        vault_operations::test_sign_transaction_ethereum::sign_ethereum_without_account,
+        vault_operations::test_sign_transaction_group::sign_transaction_group_empty,
+        vault_operations::test_sign_transaction_group::sign_transaction_group_mismatched_group_id,
+        vault_operations::test_sign_transaction_group::sign_transaction_group_without_vault_sender,
+        vault_operations::test_sign_transaction_group::sign_transaction_group_works,
         vault_operations::test_sign_transaction_msgpack::prop_transaction_msgpack_roundtrips,
+        vault_operations::test_sign_transaction_multisig::sign_transaction_multisig_append_works,
+        vault_operations::test_sign_transaction_multisig::sign_transaction_multisig_not_member,
+        vault_operations::test_sign_transaction_multisig::sign_transaction_multisig_works,
+        vault_operations::test_sign_transaction_policy::sign_transaction_policy_allows,
+        vault_operations::test_sign_transaction_policy::sign_transaction_policy_max_amount,
+        vault_operations::test_sign_transaction_policy::sign_transaction_policy_max_fee,
+        vault_operations::test_sign_transaction_policy::sign_transaction_policy_network,
+        vault_operations::test_sign_transaction_policy::sign_transaction_policy_receivers,
+        vault_operations::test_signing_policy::signing_policy_modelled_types,
+        vault_operations::test_signing_policy::signing_policy_rekey,
+        vault_operations::test_signing_policy::signing_policy_unmodelled_types,
+        vault_operations::test_store::init_vault_store_invalid,
+        vault_operations::test_store::init_vault_store_works,
         vault_operations::test_store::unlock_vault_bad_auth_pin,
         vault_operations::test_store::unlock_vault_not_found,
         vault_operations::test_store::unlock_vault_works,
+        vault_operations::test_store::vault_store_in_memory_works,
+        vault_operations::test_summarize_transaction::summarize_transaction_bad_auth,
+        vault_operations::test_summarize_transaction::summarize_transaction_ethereum,
+        vault_operations::test_summarize_transaction::summarize_transaction_group_marks_vault_sender,
+        vault_operations::test_summarize_transaction::summarize_transaction_works,
+        vault_operations::test_update_vault_policy::update_vault_policy_bad_auth,
+        vault_operations::test_update_vault_policy::update_vault_policy_tightens,
+        vault_operations::test_update_vault_policy::update_vault_policy_works,
+        vault_operations::test_update_vault_policy::update_vault_policy_would_loosen,
     )
 }
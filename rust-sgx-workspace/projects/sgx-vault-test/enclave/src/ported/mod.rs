@@ -2,4 +2,7 @@ pub(crate) mod proptest_crypto;
 pub(crate) mod test_attestation;
 pub(crate) mod test_crypto;
 pub(crate) mod test_kv_store;
+pub(crate) mod test_kv_store_codec;
 pub(crate) mod test_kv_store_fs;
+pub(crate) mod test_sgx_filer;
+pub(crate) mod test_std_filer;
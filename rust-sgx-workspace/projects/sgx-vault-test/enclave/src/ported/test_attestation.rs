@@ -1,10 +1,15 @@
 use sgx_tcrypto::rsgx_sha256_slice;
 use sgx_types::sgx_target_info_t;
-use sgx_vault_impl::ported::attestation::create_report_impl;
+use sgx_vault_impl::ported::attestation::{
+    create_report_impl,
+    create_report_with_user_data,
+    create_tls_report,
+    RA_TLS_LABEL,
+};
 use sgx_vault_impl::ported::crypto::SodaBoxCrypto;
 
 pub fn create_report_impl_works() {
-    let expected_public_key = SodaBoxCrypto::new().get_pubkey();
+    let expected_public_key = SodaBoxCrypto::new().unwrap().get_pubkey();
     let expected_report_data = rsgx_sha256_slice(&expected_public_key).unwrap();
 
     let qe_target_info = &sgx_target_info_t::default();
@@ -15,3 +20,31 @@ pub fn create_report_impl_works() {
     // The rest should be zero.
     assert_eq!(report.body.report_data.d[32..], [0; 32]);
 }
+
+pub fn create_report_with_user_data_works() {
+    let expected_public_key = SodaBoxCrypto::new().unwrap().get_pubkey();
+    let expected_report_data = rsgx_sha256_slice(&expected_public_key).unwrap();
+
+    let qe_target_info = &sgx_target_info_t::default();
+    let user_data = &[0x55; 32];
+    let (public_key, report) = create_report_with_user_data(qe_target_info, user_data).unwrap();
+
+    assert_eq!(public_key, expected_public_key);
+    assert_eq!(report.body.report_data.d[..32], expected_report_data);
+    assert_eq!(&report.body.report_data.d[32..], user_data);
+}
+
+pub fn create_tls_report_works() {
+    let expected_public_key = SodaBoxCrypto::new().unwrap().get_pubkey();
+    let tls_public_key = &[0x66; 65];
+    let expected_binding =
+        rsgx_sha256_slice(&[RA_TLS_LABEL, &expected_public_key, tls_public_key].concat()).unwrap();
+
+    let qe_target_info = &sgx_target_info_t::default();
+    let (public_key, report) = create_tls_report(qe_target_info, tls_public_key).unwrap();
+
+    assert_eq!(public_key, expected_public_key);
+    assert_eq!(report.body.report_data.d[..32], expected_binding);
+    // The rest should be zero.
+    assert_eq!(report.body.report_data.d[32..], [0; 32]);
+}
@@ -1,10 +1,17 @@
 //! XXX see `rtc_tenclave::crypto`
 
+use std::boxed::Box;
 use std::ops::Deref;
 use std::vec;
 
+use rand::rngs::mock::StepRng;
 use secrecy::{ExposeSecret, Secret};
 use sgx_vault_impl::ported::crypto::{
+    get_enclave_key,
+    BoxAlgorithm,
+    CryptoError,
+    NonceStrategy,
+    SecretBytes,
     SodaBoxCrypto,
     CRYPTO_BOX_BOXZEROBYTES,
     CRYPTO_BOX_ZEROBYTES,
@@ -26,7 +33,7 @@ pub fn soda_box_decrypt_works() {
     let mut ciphertext = vec![0_u8; plaintext.len()];
     let nonce = [32_u8; sodalite::BOX_NONCE_LEN];
 
-    let sut = SodaBoxCrypto::new();
+    let sut = SodaBoxCrypto::new().unwrap();
 
     sodalite::box_(
         &mut ciphertext,
@@ -50,7 +57,7 @@ pub fn soda_box_encrypt_works() {
     let message = vec![83_u8; 432];
     let (pub_key, secret_key) = get_test_keypair(&[32_u8; 32]);
 
-    let mut sut = SodaBoxCrypto::new();
+    let mut sut = SodaBoxCrypto::new().unwrap();
 
     let result = sut
         .encrypt_message(&Secret::new(message.clone().into_boxed_slice()), &pub_key)
@@ -77,3 +84,116 @@ pub fn soda_box_encrypt_works() {
         "encrypt_message result: decrypts to left, expected right"
     )
 }
+
+pub fn soda_box_with_rng_works() {
+    let message = &SecretBytes::new(Box::new([83_u8; 64]));
+    let (pub_key, _) = get_test_keypair(&[32_u8; 32]);
+    let new_sut = || SodaBoxCrypto::from_seed([1_u8; 32]).with_rng(Box::new(StepRng::new(5, 1)));
+
+    let first = new_sut().encrypt_message(message, &pub_key).unwrap();
+    let second = new_sut().encrypt_message(message, &pub_key).unwrap();
+
+    assert_eq!(first.nonce, second.nonce);
+    assert_eq!(first.ciphertext, second.ciphertext);
+}
+
+pub fn soda_box_counter_nonces_work() {
+    let message = &SecretBytes::new(Box::new([83_u8; 64]));
+    let (pub_key, _) = get_test_keypair(&[32_u8; 32]);
+    let mut sut = SodaBoxCrypto::from_seed([1_u8; 32]).with_nonce_strategy(NonceStrategy::Counter);
+
+    let first = sut.encrypt_message(message, &pub_key).unwrap();
+    let second = sut.encrypt_message(message, &pub_key).unwrap();
+
+    assert_eq!(first.nonce[..16], second.nonce[..16], "same prefix");
+    assert_eq!(first.nonce[16..], 0_u64.to_be_bytes());
+    assert_eq!(second.nonce[16..], 1_u64.to_be_bytes());
+}
+
+pub fn soda_box_synthetic_nonces_work() {
+    let message = &SecretBytes::new(Box::new([83_u8; 64]));
+    let other_message = &SecretBytes::new(Box::new([84_u8; 64]));
+    let (pub_key, _) = get_test_keypair(&[32_u8; 32]);
+    let (other_pub_key, _) = get_test_keypair(&[33_u8; 32]);
+    let mut sut =
+        SodaBoxCrypto::from_seed([1_u8; 32]).with_nonce_strategy(NonceStrategy::Synthetic);
+    let mut nonce = |message: &SecretBytes, pub_key: &[u8; 32]| {
+        sut.encrypt_message(message, pub_key).unwrap().nonce
+    };
+
+    let first = nonce(message, &pub_key);
+    assert_eq!(first, nonce(message, &pub_key), "same message and receiver");
+    assert_ne!(first, nonce(other_message, &pub_key), "other message");
+    assert_ne!(first, nonce(message, &other_pub_key), "other receiver");
+}
+
+pub fn soda_box_xchacha20poly1305_works() {
+    let message = &SecretBytes::new(Box::new([83_u8; 432]));
+    let mut sender = SodaBoxCrypto::from_seed([1_u8; 32]);
+    let receiver = SodaBoxCrypto::from_seed([2_u8; 32]);
+    let algorithm = BoxAlgorithm::XChaCha20Poly1305;
+
+    let encrypted = sender
+        .encrypt_message_with(algorithm, message, &receiver.get_pubkey())
+        .unwrap();
+    assert_eq!(
+        encrypted.ciphertext.len(),
+        message.expose_secret().len() + 16
+    );
+
+    let decrypted = receiver
+        .decrypt_message_with(
+            algorithm,
+            &encrypted.ciphertext,
+            &sender.get_pubkey(),
+            &encrypted.nonce,
+        )
+        .unwrap();
+    assert_eq!(decrypted.expose_secret(), message.expose_secret());
+
+    let wrong_algorithm = receiver.decrypt_message(
+        &encrypted.ciphertext,
+        &sender.get_pubkey(),
+        &encrypted.nonce,
+    );
+    assert_eq!(
+        wrong_algorithm.unwrap_err(),
+        CryptoError::AuthenticationFailed
+    );
+}
+
+pub fn soda_box_decrypt_tampered_fails() {
+    let message = &SecretBytes::new(Box::new([83_u8; 64]));
+    let mut sender = SodaBoxCrypto::from_seed([1_u8; 32]);
+    let receiver = SodaBoxCrypto::from_seed([2_u8; 32]);
+
+    for algorithm in [
+        BoxAlgorithm::XSalsa20Poly1305,
+        BoxAlgorithm::XChaCha20Poly1305,
+    ] {
+        let encrypted = sender
+            .encrypt_message_with(algorithm, message, &receiver.get_pubkey())
+            .unwrap();
+        let mut ciphertext = encrypted.ciphertext.clone();
+        ciphertext[0] ^= 1;
+
+        let result = receiver.decrypt_message_with(
+            algorithm,
+            &ciphertext,
+            &sender.get_pubkey(),
+            &encrypted.nonce,
+        );
+        assert_eq!(
+            result.unwrap_err(),
+            CryptoError::AuthenticationFailed,
+            "{:?}",
+            algorithm
+        );
+    }
+}
+
+pub fn get_enclave_key_works() {
+    let first = get_enclave_key().unwrap();
+    let second = get_enclave_key().unwrap();
+    assert_eq!(first.expose_secret(), second.expose_secret());
+}
@@ -1,7 +1,18 @@
+use std::prelude::v1::*;
+
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use sgx_vault_impl::ported::kv_store::fs::{FsStore, SgxFiler};
-use sgx_vault_impl::ported::kv_store::KvStore;
+use sgx_vault_impl::ported::kv_store::fs::{
+    encode_to_fs_safe,
+    Filer,
+    FsStore,
+    FsStoreError,
+    SgxFileKeyPolicy,
+    SgxFiler,
+    WriteFault,
+    INDEX_FILE_NAME,
+};
+use sgx_vault_impl::ported::kv_store::{Key, KvStore};
 
 use crate::helpers::temp_dir::TempDir;
 
@@ -11,7 +22,7 @@ where
     V: Serialize + DeserializeOwned,
 {
     let root = &TempDir::create();
-    let mut store = FsStore::new(root, SgxFiler);
+    let mut store = FsStore::new(root, SgxFiler::default());
     f(&mut store);
 }
 
@@ -61,3 +72,169 @@ pub(crate) fn test_try_insert() {
         assert_eq!(store.load(b"existing").unwrap(), Some(5));
     });
 }
+
+pub(crate) fn test_keys() {
+    with_temp_store(|store| {
+        assert_eq!(store.keys().unwrap(), keys(&[]));
+
+        store.save(b"b", &1).unwrap();
+        store.save(b"a", &2).unwrap();
+        store.save(b"b", &3).unwrap();
+        assert_eq!(store.keys().unwrap(), keys(&["a", "b"]));
+
+        store.delete(b"a").unwrap();
+        assert_eq!(store.keys().unwrap(), keys(&["b"]));
+        store.delete(b"missing").unwrap();
+        assert_eq!(store.keys().unwrap(), keys(&["b"]));
+    });
+}
+
+pub(crate) fn test_iter() {
+    with_temp_store(|store| {
+        assert_eq!(store.iter().unwrap().count(), 0);
+
+        store.save(b"b", &1).unwrap();
+        store.save(b"c", &3).unwrap();
+        store.save(b"a", &2).unwrap();
+        let entries: Vec<(Box<Key>, i32)> = store.iter().unwrap().map(Result::unwrap).collect();
+        let expected_keys = keys(&["a", "b", "c"]);
+        assert_eq!(
+            entries,
+            expected_keys.into_iter().zip([2, 1, 3]).collect::<Vec<_>>()
+        );
+    });
+}
+
+pub(crate) fn test_compare_and_swap() {
+    with_temp_store(|store| {
+        assert_eq!(store.load_versioned(b"key").unwrap(), None);
+        assert!(!store.compare_and_swap(b"key", Some(1), Some(&1)).unwrap());
+        assert!(store.compare_and_swap(b"key", None, Some(&1)).unwrap());
+        assert!(!store.compare_and_swap(b"key", None, Some(&2)).unwrap());
+
+        let (version, value) = store.load_versioned(b"key").unwrap().unwrap();
+        assert_eq!(value, 1);
+        assert!(store
+            .compare_and_swap(b"key", Some(version), Some(&2))
+            .unwrap());
+        assert!(!store
+            .compare_and_swap(b"key", Some(version), Some(&3))
+            .unwrap());
+        assert_eq!(store.load(b"key").unwrap(), Some(2));
+
+        // Versions keep increasing across deletes.
+        let (version, _) = store.load_versioned(b"key").unwrap().unwrap();
+        assert!(store.compare_and_swap(b"key", Some(version), None).unwrap());
+        assert_eq!(store.load(b"key").unwrap(), None);
+        store.save(b"key", &4).unwrap();
+        let (new_version, _) = store.load_versioned(b"key").unwrap().unwrap();
+        assert!(new_version > version);
+    });
+}
+
+/// [`KvStore::alter`] applies its function again, if the value changes concurrently.
+pub(crate) fn test_alter_retries_on_conflict() {
+    let root = &TempDir::create();
+    let mut store = FsStore::new(root, SgxFiler::default());
+    let mut other_store = FsStore::new(root, SgxFiler::default());
+    store.save(b"key", &1).unwrap();
+
+    let mut seen = Vec::new();
+    let altered = store.mutate(b"key", |n| {
+        if seen.is_empty() {
+            // Simulate another ECALL saving in the meantime.
+            other_store.save(b"key", &10).unwrap();
+        }
+        seen.push(n);
+        n + 1
+    });
+    assert_eq!(altered.unwrap(), Some(11));
+    assert_eq!(seen, vec![1, 10]);
+    assert_eq!(store.load(b"key").unwrap(), Some(11));
+}
+
+/// A failed save leaves the existing value intact.
+pub(crate) fn test_save_fault_keeps_value() {
+    let root = &TempDir::create();
+    let mut store = FsStore::new(root, SgxFiler::default());
+    store.save(b"key", &"old value".to_string()).unwrap();
+
+    for fault in [WriteFault::PartialWrite(3), WriteFault::BeforeRename] {
+        let faulty_filer = SgxFiler::default().with_write_fault(fault);
+        let mut faulty_store = FsStore::new(root, faulty_filer);
+        faulty_store
+            .save(b"key", &"new value".to_string())
+            .unwrap_err();
+        faulty_store
+            .save(b"new key", &"new value".to_string())
+            .unwrap_err();
+
+        assert_eq!(store.load(b"key").unwrap(), Some("old value".to_string()));
+        assert_eq!(store.load(b"new key").unwrap(), None);
+        let entries: Vec<(Box<Key>, String)> = store.iter().unwrap().map(Result::unwrap).collect();
+        assert_eq!(
+            entries,
+            vec![(keys(&["key"]).remove(0), "old value".to_string())]
+        );
+    }
+}
+
+/// Values stay readable if the index can't be decoded, and get listed again as they are saved.
+pub(crate) fn test_undecodable_index_recovers() {
+    let root = &TempDir::create();
+    let mut store = FsStore::new(root, SgxFiler::default());
+    store.save(b"a", &1).unwrap();
+    store.save(b"b", &2).unwrap();
+    let (version, _) = store.load_versioned(b"a").unwrap().unwrap();
+
+    let index_path = root.as_ref().join(INDEX_FILE_NAME);
+    SgxFiler::default().put(&index_path, b"not JSON").unwrap();
+
+    assert_eq!(store.load_versioned(b"a").unwrap(), Some((version, 1)));
+    assert_eq!(store.keys().unwrap(), keys(&[]));
+    assert!(store
+        .compare_and_swap(b"a", Some(version), Some(&3))
+        .unwrap());
+    assert_eq!(store.keys().unwrap(), keys(&["a"]));
+    assert_eq!(store.load(b"a").unwrap(), Some(3));
+    assert_eq!(store.load(b"b").unwrap(), Some(2));
+}
+
+/// An index that can't be read fails listing and saving new keys, and stays as it is.
+pub(crate) fn test_unreadable_index_fails() {
+    let root = &TempDir::create();
+    let mut store = FsStore::new(root, SgxFiler::default());
+    store.save(b"a", &1).unwrap();
+
+    // Seal the index under a key that the store doesn't use.
+    let index_path = root.as_ref().join(INDEX_FILE_NAME);
+    let index = SgxFiler::default().get(&index_path).unwrap().unwrap();
+    let other_filer = SgxFiler::new(SgxFileKeyPolicy::MrEnclave { key_id: [1; 32] });
+    other_filer.put(&index_path, &index).unwrap();
+
+    let err = store.keys().unwrap_err();
+    assert!(matches!(err, FsStoreError::Read { .. }), "{:?}", err);
+    let err = store.save(b"b", &2).unwrap_err();
+    assert!(matches!(err, FsStoreError::Read { .. }), "{:?}", err);
+    assert_eq!(store.load(b"a").unwrap(), Some(1));
+    assert_eq!(other_filer.get(&index_path).unwrap(), Some(index));
+}
+
+/// Values saved without a version load with version 0, and get one when saved.
+pub(crate) fn test_unversioned_value_loads() {
+    let root = &TempDir::create();
+    let mut store = FsStore::new(root, SgxFiler::default());
+    let value_path = root.as_ref().join(encode_to_fs_safe(b"key"));
+    SgxFiler::default().put(&value_path, b"5").unwrap();
+
+    assert_eq!(store.load_versioned(b"key").unwrap(), Some((0, 5)));
+    assert!(store.compare_and_swap(b"key", Some(0), Some(&6)).unwrap());
+    let (version, value) = store.load_versioned(b"key").unwrap().unwrap();
+    assert!(version > 0);
+    assert_eq!(value, 6);
+}
+
+// Helper: Owned keys, for comparison.
+fn keys(keys: &[&str]) -> Vec<Box<Key>> {
+    keys.iter().map(|key| key.as_bytes().into()).collect()
+}
@@ -0,0 +1,79 @@
+use std::prelude::v1::*;
+
+use serde::{Deserialize, Serialize};
+use sgx_vault_impl::ported::kv_store::codec::{
+    Codec,
+    JsonCodec,
+    JsonFallbackCodec,
+    MessagePackCodec,
+};
+use sgx_vault_impl::ported::kv_store::fs::{FsStore, FsStoreError, SgxFiler};
+use sgx_vault_impl::ported::kv_store::{Key, KvStore};
+
+use crate::helpers::temp_dir::TempDir;
+
+#[derive(Clone, Eq, PartialEq, Debug)] // core
+#[derive(Deserialize, Serialize)] // serde
+struct Example {
+    name: String,
+    seed: [u8; 32],
+}
+
+fn example() -> Example {
+    Example {
+        name: "example".to_string(),
+        seed: [0xAA; 32],
+    }
+}
+
+pub(crate) fn codecs_roundtrip() {
+    let value = &example();
+
+    let json = JsonCodec::encode(value).unwrap();
+    assert_eq!(&JsonCodec::decode::<Example>(&json).unwrap(), value);
+
+    let msgpack = MessagePackCodec::encode(value).unwrap();
+    assert_eq!(
+        &MessagePackCodec::decode::<Example>(&msgpack).unwrap(),
+        value
+    );
+    assert!(
+        msgpack.len() < json.len(),
+        "{} < {}",
+        msgpack.len(),
+        json.len()
+    );
+
+    type Fallback = JsonFallbackCodec<MessagePackCodec>;
+    assert_eq!(&*Fallback::encode(value).unwrap(), &*msgpack);
+    assert_eq!(&Fallback::decode::<Example>(&msgpack).unwrap(), value);
+    assert_eq!(&Fallback::decode::<Example>(&json).unwrap(), value);
+    assert!(MessagePackCodec::decode::<Example>(&json).is_err());
+}
+
+/// Encoded values fill their buffer exactly, even past the size of an initial allocation.
+pub(crate) fn codecs_encode_exact_capacity() {
+    let value: Vec<Example> = (0..100).map(|_| example()).collect();
+
+    let json = JsonCodec::encode(&value).unwrap();
+    assert!(json.len() > 4096, "{}", json.len());
+    assert_eq!(json.capacity(), json.len());
+
+    let msgpack = MessagePackCodec::encode(&value).unwrap();
+    assert!(msgpack.len() > 4096, "{}", msgpack.len());
+    assert_eq!(msgpack.capacity(), msgpack.len());
+}
+
+pub(crate) fn fs_store_with_codec_works() {
+    let root = &TempDir::create();
+    let mut store: FsStore<_, _, MessagePackCodec> = FsStore::with_codec(root, SgxFiler::default());
+
+    store.save(b"key", &example()).unwrap();
+    assert_eq!(store.load(b"key").unwrap(), Some(example()));
+    assert_eq!(store.keys().unwrap(), vec![Box::<Key>::from(&b"key"[..])]);
+
+    // Reading with the wrong codec is a decoding error, not an I/O error.
+    let json_store: FsStore<_, Example> = FsStore::new(root, SgxFiler::default());
+    let err = json_store.load(b"key").unwrap_err();
+    assert!(matches!(err, FsStoreError::Decode { .. }), "{:?}", err);
+}
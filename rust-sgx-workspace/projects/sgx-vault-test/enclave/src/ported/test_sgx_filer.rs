@@ -0,0 +1,116 @@
+use std::prelude::v1::*;
+
+use sgx_vault_impl::ported::kv_store::fs::{Filer, SgxFileKeyPolicy, SgxFiler, WriteFault};
+
+use crate::helpers::temp_dir::TempDir;
+
+const KEY_A: SgxFileKeyPolicy = SgxFileKeyPolicy::MrSigner {
+    key_id: [b'a'; 32],
+    isv_svn: 0,
+};
+
+const KEY_B: SgxFileKeyPolicy = SgxFileKeyPolicy::MrSigner {
+    key_id: [b'b'; 32],
+    isv_svn: 0,
+};
+
+const KEY_ENCLAVE: SgxFileKeyPolicy = SgxFileKeyPolicy::MrEnclave { key_id: [b'a'; 32] };
+
+pub(crate) fn sgx_filer_roundtrips() {
+    let root = &TempDir::create();
+    let path = root.as_ref().join("file");
+
+    for key_policy in [SgxFileKeyPolicy::AutoKey, KEY_A, KEY_ENCLAVE] {
+        let filer = SgxFiler::new(key_policy);
+        assert_eq!(filer.get(&path).unwrap(), None);
+        filer.put(&path, b"contents").unwrap();
+        assert_eq!(
+            filer.get(&path).unwrap(),
+            Some(b"contents".to_vec()),
+            "{:?}",
+            key_policy
+        );
+        filer.delete(&path).unwrap();
+    }
+}
+
+/// Contents spanning several protected file nodes are read into a buffer of their exact size.
+pub(crate) fn sgx_filer_reads_exact_size() {
+    let root = &TempDir::create();
+    let path = root.as_ref().join("file");
+    let contents: Vec<u8> = (0..10_000).map(|i| i as u8).collect();
+
+    let filer = SgxFiler::new(KEY_A);
+    filer.put(&path, &contents).unwrap();
+    let read = filer.get(&path).unwrap().unwrap();
+    assert_eq!(read, contents);
+    assert_eq!(read.capacity(), read.len());
+}
+
+pub(crate) fn sgx_filer_wrong_key_fails() {
+    let root = &TempDir::create();
+    let path = root.as_ref().join("file");
+    SgxFiler::new(KEY_A).put(&path, b"contents").unwrap();
+
+    for key_policy in [SgxFileKeyPolicy::AutoKey, KEY_B, KEY_ENCLAVE] {
+        let result = SgxFiler::new(key_policy).get(&path);
+        assert!(result.is_err(), "{:?}: {:?}", key_policy, result);
+    }
+}
+
+pub(crate) fn sgx_filer_rotates_previous_keys() {
+    let root = &TempDir::create();
+    let path = root.as_ref().join("file");
+    SgxFiler::default().put(&path, b"contents").unwrap();
+
+    let rotating = SgxFiler::with_previous_keys(KEY_A, vec![SgxFileKeyPolicy::AutoKey]);
+    assert_eq!(rotating.get(&path).unwrap(), Some(b"contents".to_vec()));
+
+    // The file is now keyed under the current policy only.
+    assert_eq!(
+        SgxFiler::new(KEY_A).get(&path).unwrap(),
+        Some(b"contents".to_vec())
+    );
+    assert!(SgxFiler::default().get(&path).is_err());
+}
+
+/// An interrupted [`SgxFiler::put`] leaves the existing contents intact.
+pub(crate) fn sgx_filer_write_fault_keeps_contents() {
+    let root = &TempDir::create();
+    let path = root.as_ref().join("file");
+    let new_contents = b"new contents, longer than the old ones";
+
+    for key_policy in [SgxFileKeyPolicy::AutoKey, KEY_A] {
+        let filer = SgxFiler::new(key_policy);
+        filer.put(&path, b"old contents").unwrap();
+
+        let faults = (0..new_contents.len())
+            .map(WriteFault::PartialWrite)
+            .chain([WriteFault::BeforeRename]);
+        for fault in faults {
+            let faulty_filer = filer.clone().with_write_fault(fault);
+            faulty_filer.put(&path, new_contents).unwrap_err();
+            assert_eq!(
+                filer.get(&path).unwrap(),
+                Some(b"old contents".to_vec()),
+                "{:?}, {:?}",
+                key_policy,
+                fault
+            );
+        }
+
+        // A later write succeeds, despite the leftover temporary file.
+        filer.put(&path, new_contents).unwrap();
+        assert_eq!(filer.get(&path).unwrap(), Some(new_contents.to_vec()));
+        filer.delete(&path).unwrap();
+    }
+}
+
+pub(crate) fn sgx_filer_sync_writes() {
+    let root = &TempDir::create();
+    let path = root.as_ref().join("file");
+
+    let filer = SgxFiler::new(KEY_A).with_sync_writes(true);
+    filer.put(&path, b"contents").unwrap();
+    assert_eq!(filer.get(&path).unwrap(), Some(b"contents".to_vec()));
+}
@@ -0,0 +1,20 @@
+//! Test [`StdFiler`] with [`FsStore`] in the enclave.
+//!
+//! The filer itself is tested on the host, in the `kv-store` crate.
+
+use std::prelude::v1::*;
+
+use sgx_vault_impl::ported::kv_store::fs::{FsStore, StdFiler};
+use sgx_vault_impl::ported::kv_store::KvStore;
+
+use crate::helpers::temp_dir::TempDir;
+
+pub(crate) fn std_filer_fs_store_works() {
+    let root = &TempDir::create();
+    let mut store = FsStore::new(root, StdFiler::default());
+
+    store.save(b"key", &"value".to_string()).unwrap();
+    assert_eq!(store.load(b"key").unwrap(), Some("value".to_string()));
+    store.delete(b"key").unwrap();
+    assert_eq!(store.load(b"key").unwrap(), None);
+}
@@ -1 +1,2 @@
 pub(crate) mod test_sealing;
+pub(crate) mod test_session;
@@ -2,8 +2,16 @@ use std::boxed::Box;
 
 use proptest::prelude::*;
 use secrecy::{ExposeSecret, Secret};
-use sgx_vault_impl::ported::crypto::{SecretBytes, SodaBoxCrypto};
-use sgx_vault_impl::schema::sealing::{seal, seal_msgpack, unseal, unseal_secret_msgpack};
+use sgx_vault_impl::ported::crypto::{BoxAlgorithm, CryptoError, SecretBytes, SodaBoxCrypto};
+use sgx_vault_impl::schema::msgpack::{FromMessagePack, ToMessagePack};
+use sgx_vault_impl::schema::sealing::{
+    seal,
+    seal_msgpack,
+    seal_with,
+    unseal,
+    unseal_secret_msgpack,
+    SealedMessage,
+};
 
 /// Roundtrip with [`seal`] and then [`unseal`].
 pub(crate) fn prop_seal_unseal_roundtrips() {
@@ -54,3 +62,50 @@ fn prop_seal_unseal_msgpack_roundtrips_impl(
 
     assert_eq!(unsealed.expose_secret(), message.expose_secret());
 }
+
+pub(crate) fn seal_unseal_version_works() {
+    let message = &SecretBytes::new(Box::new([83_u8; 64]));
+    let sender = &mut SodaBoxCrypto::from_seed([1_u8; 32]);
+    let receiver = &SodaBoxCrypto::from_seed([2_u8; 32]);
+    let version = BoxAlgorithm::XChaCha20Poly1305;
+
+    let sealed_bytes = seal_with(version, message, &receiver.get_pubkey(), sender)
+        .unwrap()
+        .to_msgpack()
+        .unwrap();
+    let sealed = &SealedMessage::from_msgpack(&sealed_bytes).unwrap();
+    assert_eq!(sealed.version, version);
+
+    let unsealed = &unseal(sealed, receiver).unwrap();
+    assert_eq!(unsealed.expose_secret(), message.expose_secret());
+}
+
+/// Messages with the default version leave it out, so they stay readable by older peers.
+pub(crate) fn seal_default_version_is_omitted() {
+    let message = &SecretBytes::new(Box::new([83_u8; 64]));
+    let sender = &mut SodaBoxCrypto::from_seed([1_u8; 32]);
+    let receiver = &SodaBoxCrypto::from_seed([2_u8; 32]);
+
+    let sealed_bytes = seal(message, &receiver.get_pubkey(), sender)
+        .unwrap()
+        .to_msgpack()
+        .unwrap();
+    assert!(!sealed_bytes.windows(7).any(|window| window == b"version"));
+
+    let sealed = &SealedMessage::from_msgpack(&sealed_bytes).unwrap();
+    assert_eq!(sealed.version, BoxAlgorithm::XSalsa20Poly1305);
+    let unsealed = &unseal(sealed, receiver).unwrap();
+    assert_eq!(unsealed.expose_secret(), message.expose_secret());
+}
+
+pub(crate) fn unseal_wrong_receiver_fails() {
+    let message = &SecretBytes::new(Box::new([83_u8; 64]));
+    let sender = &mut SodaBoxCrypto::from_seed([1_u8; 32]);
+    let receiver = &SodaBoxCrypto::from_seed([2_u8; 32]);
+    let other_receiver = &SodaBoxCrypto::from_seed([3_u8; 32]);
+
+    let sealed = &seal(message, &receiver.get_pubkey(), sender).unwrap();
+    let result = unseal(sealed, other_receiver);
+
+    assert_eq!(result.unwrap_err(), CryptoError::AuthenticationFailed);
+}
@@ -0,0 +1,69 @@
+use std::boxed::Box;
+
+use secrecy::ExposeSecret;
+use sgx_vault_impl::ported::crypto::{SecretBytes, SodaBoxCrypto};
+use sgx_vault_impl::schema::session::{
+    seal_session_request,
+    seal_session_response,
+    unseal_session_request,
+    unseal_session_response,
+    SessionKeys,
+};
+
+/// Derive the client's and the enclave's keys of a session.
+fn session_keys() -> (SessionKeys, SessionKeys) {
+    let client = SodaBoxCrypto::from_seed([1; 32]);
+    let enclave = SodaBoxCrypto::from_seed([2; 32]);
+    let client_keys = SessionKeys::derive(
+        &client.shared_key(&enclave.get_pubkey()),
+        &client.get_pubkey(),
+        &enclave.get_pubkey(),
+    );
+    let enclave_keys = SessionKeys::derive(
+        &enclave.shared_key(&client.get_pubkey()),
+        &client.get_pubkey(),
+        &enclave.get_pubkey(),
+    );
+    (client_keys, enclave_keys)
+}
+
+pub(crate) fn session_seal_unseal_works() {
+    let (client_keys, enclave_keys) = &session_keys();
+    let message = &SecretBytes::new(Box::new(*b"hello"));
+
+    let sealed_request = &seal_session_request(message, [7; 16], 1, client_keys).unwrap();
+    assert_eq!(sealed_request.session_id, [7; 16]);
+    assert_eq!(sealed_request.counter, 1);
+    let unsealed = unseal_session_request(sealed_request, enclave_keys).unwrap();
+    assert_eq!(unsealed.expose_secret(), message.expose_secret());
+
+    let sealed_response = &seal_session_response(message, [7; 16], 1, enclave_keys).unwrap();
+    let unsealed = unseal_session_response(sealed_response, 1, client_keys).unwrap();
+    assert_eq!(unsealed.expose_secret(), message.expose_secret());
+}
+
+/// Each direction has its own key, so a response can't be passed off as a request.
+pub(crate) fn session_keys_per_direction() {
+    let (client_keys, enclave_keys) = &session_keys();
+    let message = &SecretBytes::new(Box::new(*b"hello"));
+
+    let sealed_response = &seal_session_response(message, [7; 16], 1, enclave_keys).unwrap();
+    assert!(unseal_session_request(sealed_response, enclave_keys).is_err());
+    let sealed_request = &seal_session_request(message, [7; 16], 1, client_keys).unwrap();
+    assert!(unseal_session_response(sealed_request, 1, client_keys).is_err());
+}
+
+/// The counter is the nonce: it can't be altered, and binds each response to its request.
+pub(crate) fn session_counter_authenticated() {
+    let (client_keys, enclave_keys) = &session_keys();
+    let message = &SecretBytes::new(Box::new(*b"hello"));
+
+    let sealed_request = &mut seal_session_request(message, [7; 16], 5, client_keys).unwrap();
+    sealed_request.counter = 6;
+    assert!(unseal_session_request(sealed_request, enclave_keys).is_err());
+
+    let sealed_response = &mut seal_session_response(message, [7; 16], 5, enclave_keys).unwrap();
+    assert!(unseal_session_response(sealed_response, 6, client_keys).is_err());
+    sealed_response.counter = 6;
+    assert!(unseal_session_response(sealed_response, 6, client_keys).is_err());
+}
@@ -1,6 +1,21 @@
+pub(crate) mod test_audit_log;
 pub(crate) mod test_create_vault;
 pub(crate) mod test_dispatch;
+pub(crate) mod test_export_algorand_account;
+pub(crate) mod test_handover;
+pub(crate) mod test_import_algorand_account;
+pub(crate) mod test_migration;
 pub(crate) mod test_open_vault;
+pub(crate) mod test_response_cache;
+pub(crate) mod test_sessions;
+pub(crate) mod test_sign_bytes;
 pub(crate) mod test_sign_transaction;
+pub(crate) mod test_sign_transaction_ethereum;
+pub(crate) mod test_sign_transaction_group;
 pub(crate) mod test_sign_transaction_msgpack;
+pub(crate) mod test_sign_transaction_multisig;
+pub(crate) mod test_sign_transaction_policy;
+pub(crate) mod test_signing_policy;
 pub(crate) mod test_store;
+pub(crate) mod test_summarize_transaction;
+pub(crate) mod test_update_vault_policy;
@@ -0,0 +1,330 @@
+//! Test [`sgx_vault_impl::vault_operations::audit_log`]
+
+use std::prelude::v1::{String, ToString, Vec};
+use std::vec;
+
+use sgx_vault_impl::ported::kv_store::KvStore;
+use sgx_vault_impl::schema::actions::{
+    CreateVault,
+    CreateVaultResult,
+    GetAuditLog,
+    GetAuditLogResult,
+    OpenVault,
+    OpenVaultResult,
+    SignTransaction,
+    SignTransactionResult,
+    TransactionSigned,
+    TransactionToSign,
+    UpdateVaultPolicy,
+    UpdateVaultPolicyResult,
+    VaultRequest,
+    VaultResponse,
+};
+use sgx_vault_impl::schema::entities::{
+    AuditLog,
+    AuditOperation,
+    AuditOutcome,
+    VaultDisplay,
+    VaultPolicy,
+};
+use sgx_vault_impl::vault_operations::audit_log::{
+    append_audit_entry,
+    audit_log_segment_store,
+    audit_log_store,
+    audit_record_for,
+    audit_vault_operation,
+    load_audit_log,
+    load_audit_log_page,
+    segment_key,
+    verify_audit_log,
+    AuditRecord,
+    AUDIT_LOG_SEGMENT_LEN,
+    INVALID_AUTH_LOG_INTERVAL_SECONDS,
+};
+use sgx_vault_impl::vault_operations::get_audit_log::get_audit_log;
+use sgx_vault_impl::vault_operations::store::{key_from_id, vault_store};
+
+use crate::helpers::vault_store::create_test_vault_with_username;
+
+pub(crate) fn audit_log_chain_works() {
+    let existing = &create_test_vault_with_username("Audit Log Chain Works");
+    let record = |operation, outcome, transaction_ids: &[&str]| AuditRecord {
+        vault_id: existing.vault_id.clone(),
+        operation,
+        outcome,
+        transaction_ids: transaction_ids.iter().map(|id| id.to_string()).collect(),
+    };
+
+    use AuditOperation::*;
+    use AuditOutcome::*;
+    let records = [
+        record(OpenVault, InvalidAuth, &[]),
+        record(SignTransaction, Succeeded, &["TXID1", "TXID2"]),
+        record(ExportAlgorandAccount, Refused, &[]),
+    ];
+    for (timestamp, record) in (1000..).zip(&records) {
+        let entry = append_audit_entry(record, timestamp).unwrap().unwrap();
+        assert_eq!(entry.timestamp, timestamp);
+    }
+
+    let log = load_audit_log(&existing.vault_id).unwrap();
+    assert_eq!(log.entries.len(), 3);
+    for (sequence, (entry, record)) in (0..).zip(log.entries.iter().zip(&records)) {
+        assert_eq!(entry.sequence, sequence);
+        assert_eq!(entry.operation, record.operation);
+        assert_eq!(entry.outcome, record.outcome);
+        assert_eq!(entry.transaction_ids, record.transaction_ids);
+    }
+    assert_eq!(log.entries[0].previous_hash, [0; 32]);
+    assert_eq!(log.entries[1].previous_hash, log.entries[0].entry_hash);
+    assert_eq!(log.entries[2].previous_hash, log.entries[1].entry_hash);
+
+    // Any change breaks the chain.
+    let tampered = |tamper: fn(&mut AuditLog)| {
+        let mut log = log.clone();
+        tamper(&mut log);
+        verify_audit_log(&log).unwrap_err().to_string()
+    };
+    assert_eq!(
+        tampered(|log| log.entries[1].outcome = AuditOutcome::Failed),
+        "audit log entry 1 failed verification: entry hash mismatch"
+    );
+    assert_eq!(
+        tampered(|log| log.entries[1].transaction_ids.truncate(1)),
+        "audit log entry 1 failed verification: entry hash mismatch"
+    );
+    assert_eq!(
+        tampered(|log| {
+            log.entries.remove(1);
+        }),
+        "audit log entry 1 failed verification: out of sequence"
+    );
+    assert_eq!(
+        tampered(|log| log.entries.swap(0, 1)),
+        "audit log entry 0 failed verification: out of sequence"
+    );
+
+    delete_vault_and_log(existing);
+}
+
+pub(crate) fn audit_log_skips_missing_vault() {
+    let record = &AuditRecord {
+        vault_id: "Audit Log Missing Vault".to_string(),
+        operation: AuditOperation::OpenVault,
+        outcome: AuditOutcome::InvalidAuth,
+        transaction_ids: Vec::new(),
+    };
+    assert_eq!(append_audit_entry(record, 0).unwrap(), None);
+    assert_eq!(
+        load_audit_log(&record.vault_id).unwrap(),
+        AuditLog::default()
+    );
+}
+
+pub(crate) fn get_audit_log_works() {
+    let existing = &create_test_vault_with_username("Get Audit Log Works");
+    let request = &VaultRequest::OpenVault(OpenVault {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "000000".to_string(),
+    });
+    assert_eq!(
+        audit_vault_operation(request, || OpenVaultResult::InvalidAuth.into()),
+        OpenVaultResult::InvalidAuth.into()
+    );
+
+    let get_request = |auth_password: &str| GetAuditLog {
+        vault_id: existing.vault_id.clone(),
+        auth_password: auth_password.to_string(),
+        start_sequence: 0,
+    };
+    assert_eq!(
+        get_audit_log(&get_request("000000")),
+        GetAuditLogResult::InvalidAuth
+    );
+    let page = match get_audit_log(&get_request("123456")) {
+        GetAuditLogResult::Retrieved(page) => page,
+        otherwise => panic!("{:?}", otherwise),
+    };
+    assert_eq!(page.entries.len(), 1);
+    assert_eq!(page.entries[0].operation, AuditOperation::OpenVault);
+    assert_eq!(page.entries[0].outcome, AuditOutcome::InvalidAuth);
+    assert_eq!(page.next_sequence, None);
+
+    delete_vault_and_log(existing);
+}
+
+/// Full segments of the log are closed, and read a page at a time.
+pub(crate) fn audit_log_segments_work() {
+    let existing = &create_test_vault_with_username("Audit Log Segments Work");
+    let record = &AuditRecord {
+        vault_id: existing.vault_id.clone(),
+        operation: AuditOperation::OpenVault,
+        outcome: AuditOutcome::Succeeded,
+        transaction_ids: Vec::new(),
+    };
+    let len = AUDIT_LOG_SEGMENT_LEN;
+    for timestamp in 0..(2 * len + 3) {
+        append_audit_entry(record, timestamp).unwrap().unwrap();
+    }
+
+    let page = |start_sequence| {
+        let page = load_audit_log_page(&existing.vault_id, start_sequence).unwrap();
+        let sequences = page.entries.iter().map(|entry| entry.sequence);
+        (sequences.collect::<Vec<_>>(), page.next_sequence)
+    };
+    assert_eq!(page(0), ((0..len).collect(), Some(len)));
+    assert_eq!(
+        page(len + 10),
+        ((len + 10..2 * len).collect(), Some(2 * len))
+    );
+    assert_eq!(page(2 * len), ((2 * len..2 * len + 3).collect(), None));
+    assert_eq!(page(3 * len), (vec![], None));
+
+    let log = load_audit_log(&existing.vault_id).unwrap();
+    assert_eq!(log.entries.len() as u64, 2 * len + 3);
+
+    // A missing segment breaks the chain of the pages after it.
+    let vault_key = &key_from_id(&existing.vault_id).unwrap();
+    let mut segment_store = audit_log_segment_store();
+    segment_store.delete(&segment_key(vault_key, 0)).unwrap();
+    assert_eq!(
+        load_audit_log_page(&existing.vault_id, len)
+            .unwrap_err()
+            .to_string(),
+        "audit log entry 0 failed verification: log segment is missing"
+    );
+
+    segment_store.delete(&segment_key(vault_key, len)).unwrap();
+    delete_vault_and_log(existing);
+}
+
+/// Repeated failed authentication is only logged once per interval.
+pub(crate) fn audit_log_rate_limits_invalid_auth() {
+    let existing = &create_test_vault_with_username("Audit Log Rate Limits Invalid Auth");
+    let record = |outcome| AuditRecord {
+        vault_id: existing.vault_id.clone(),
+        operation: AuditOperation::OpenVault,
+        outcome,
+        transaction_ids: Vec::new(),
+    };
+    let appended = |outcome, timestamp| {
+        append_audit_entry(&record(outcome), timestamp)
+            .unwrap()
+            .is_some()
+    };
+
+    use AuditOutcome::*;
+    let interval = INVALID_AUTH_LOG_INTERVAL_SECONDS;
+    assert!(appended(InvalidAuth, 1000));
+    assert!(!appended(InvalidAuth, 1000 + interval - 1));
+    assert!(appended(InvalidAuth, 1000 + interval));
+    assert!(appended(Succeeded, 1000 + interval));
+    assert!(appended(InvalidAuth, 1000 + interval));
+    assert_eq!(
+        outcomes(&existing.vault_id),
+        [
+            (AuditOperation::OpenVault, InvalidAuth),
+            (AuditOperation::OpenVault, InvalidAuth),
+            (AuditOperation::OpenVault, Succeeded),
+            (AuditOperation::OpenVault, InvalidAuth),
+        ]
+    );
+
+    delete_vault_and_log(existing);
+}
+
+/// Operations that change the vault only run after their `Started` entry is logged.
+pub(crate) fn audit_log_write_ahead() {
+    let existing = &create_test_vault_with_username("Audit Log Write Ahead");
+    let request = &VaultRequest::UpdateVaultPolicy(UpdateVaultPolicy {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "123456".to_string(),
+        policy: VaultPolicy::default(),
+    });
+    let failed =
+        || VaultResponse::from(UpdateVaultPolicyResult::Failed("test failure".to_string()));
+
+    let response = audit_vault_operation(request, || {
+        assert_eq!(
+            outcomes(&existing.vault_id),
+            [(AuditOperation::UpdateVaultPolicy, AuditOutcome::Started)]
+        );
+        failed()
+    });
+    assert_eq!(response, failed());
+    assert_eq!(
+        outcomes(&existing.vault_id),
+        [
+            (AuditOperation::UpdateVaultPolicy, AuditOutcome::Started),
+            (AuditOperation::UpdateVaultPolicy, AuditOutcome::Failed),
+        ]
+    );
+
+    delete_vault_and_log(existing);
+}
+
+/// Creating a vault starts its log, before the vault exists.
+pub(crate) fn audit_log_write_ahead_create_vault() {
+    let vault_id = "Audit Log Write Ahead Create Vault";
+    let request = &VaultRequest::CreateVault(CreateVault {
+        username: vault_id.to_string(),
+        auth_password: "123456".to_string(),
+    });
+    let failed = || VaultResponse::from(CreateVaultResult::Failed("test failure".to_string()));
+
+    let response = audit_vault_operation(request, || {
+        assert_eq!(
+            outcomes(vault_id),
+            [(AuditOperation::CreateVault, AuditOutcome::Started)]
+        );
+        failed()
+    });
+    assert_eq!(response, failed());
+    assert_eq!(
+        outcomes(vault_id),
+        [
+            (AuditOperation::CreateVault, AuditOutcome::Started),
+            (AuditOperation::CreateVault, AuditOutcome::Failed),
+        ]
+    );
+
+    let key = &key_from_id(vault_id).unwrap();
+    audit_log_store().delete(key).unwrap();
+}
+
+pub(crate) fn audit_record_for_ethereum_signing() {
+    let request = &VaultRequest::SignTransaction(SignTransaction {
+        vault_id: "vault".to_string(),
+        auth_password: "123456".to_string(),
+        transaction_to_sign: TransactionToSign::EthereumTransaction {
+            transaction_bytes: Default::default(),
+        },
+    });
+    let signed = TransactionSigned::from_ethereum_bytes(b"signed"[..].into());
+    let response = &SignTransactionResult::Signed(signed).into();
+
+    let record = audit_record_for(request, response).unwrap();
+    assert_eq!(record.vault_id, "vault");
+    assert_eq!(record.operation, AuditOperation::SignTransaction);
+    assert_eq!(record.outcome, AuditOutcome::Succeeded);
+    assert_eq!(record.transaction_ids.len(), 1);
+    let transaction_id: &String = &record.transaction_ids[0];
+    assert!(transaction_id.starts_with("0x"), "{}", transaction_id);
+    assert_eq!(transaction_id.len(), 2 + 64);
+}
+
+// Helper: The operations and outcomes in a vault's audit log.
+fn outcomes(vault_id: &str) -> Vec<(AuditOperation, AuditOutcome)> {
+    let log = load_audit_log(vault_id).unwrap();
+    log.entries
+        .iter()
+        .map(|entry| (entry.operation, entry.outcome))
+        .collect()
+}
+
+// Helper: Clean up after a test vault.
+fn delete_vault_and_log(existing: &VaultDisplay) {
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    vault_store().delete(key).unwrap();
+    audit_log_store().delete(key).unwrap();
+}
@@ -1,10 +1,16 @@
+use sgx_vault_impl::ported::kv_store::in_memory::InMemoryStore;
 use sgx_vault_impl::ported::kv_store::KvStore;
 use std::prelude::v1::ToString;
 
 use sgx_vault_impl::schema::actions;
 use sgx_vault_impl::schema::actions::CreateVaultResult as Result;
-use sgx_vault_impl::vault_operations::create_vault::create_vault;
-use sgx_vault_impl::vault_operations::store::{key_from_id, load_vault, vault_store};
+use sgx_vault_impl::vault_operations::create_vault::{create_vault, create_vault_in};
+use sgx_vault_impl::vault_operations::store::{
+    key_from_id,
+    load_vault,
+    load_vault_in,
+    vault_store,
+};
 
 pub(crate) fn create_vault_works() {
     let mut store = vault_store();
@@ -29,7 +35,33 @@ pub(crate) fn create_vault_works() {
         display.algorand_address_base32,
         stored.algorand_account.address_base32()
     );
+    assert_eq!(
+        display.ethereum_address_hex,
+        stored
+            .ethereum_account
+            .as_ref()
+            .map(|account| account.address_hex())
+    );
 
     let key = &key_from_id(&display.vault_id).unwrap();
     store.delete(key).unwrap();
 }
+
+/// [`create_vault_in`] saves to the given store only.
+pub(crate) fn create_vault_in_memory_works() {
+    let store = &mut InMemoryStore::default();
+
+    let request = &actions::CreateVault {
+        username: "In Memory Username".to_string(),
+        auth_password: "123456".to_string(),
+    };
+    let display = &match create_vault_in(store, request) {
+        Result::Created(created) => created,
+        Result::Failed(failed) => panic!("{}", failed),
+    };
+
+    let stored = load_vault_in(store, &display.vault_id).unwrap().unwrap();
+    assert_eq!(display.vault_id, stored.vault_id);
+    assert_eq!(display.username, stored.username);
+    assert_eq!(load_vault(&display.vault_id).unwrap(), None);
+}
@@ -3,11 +3,12 @@ use std::prelude::v1::ToString;
 use sgx_vault_impl::ported::crypto::SodaBoxCrypto;
 use sgx_vault_impl::schema::actions::{OpenVault, OpenVaultResult, VaultRequest, VaultResponse};
 use sgx_vault_impl::schema::sealing::{seal_msgpack, unseal_non_secret_msgpack};
-use sgx_vault_impl::vault_operations::dispatch::vault_operation_impl;
+use sgx_vault_impl::schema::entities::{AuditOperation, AuditOutcome};
+use sgx_vault_impl::vault_operations::dispatch::{vault_operation_impl, OperationLabel};
 
 pub(crate) fn vault_operation_sealing_works() {
     let client_crypto = &mut SodaBoxCrypto::from_seed([0; 32]);
-    let enclave_crypto = SodaBoxCrypto::new();
+    let enclave_crypto = SodaBoxCrypto::new().unwrap();
 
     // Seal
     let vault_request = &VaultRequest::OpenVault(OpenVault {
@@ -18,7 +19,7 @@ pub(crate) fn vault_operation_sealing_works() {
         &seal_msgpack(vault_request, &enclave_crypto.get_pubkey(), client_crypto).unwrap();
 
     // Call
-    let sealed_response_bytes = &vault_operation_impl(sealed_request_bytes);
+    let (sealed_response_bytes, label) = &vault_operation_impl(sealed_request_bytes).unwrap();
 
     // Unseal
     let unsealed_message: VaultResponse =
@@ -30,4 +31,24 @@ pub(crate) fn vault_operation_sealing_works() {
         OpenVaultResult::InvalidAuth
         .into()
     );
+    assert_eq!(
+        label,
+        &OperationLabel {
+            operation: AuditOperation::OpenVault,
+            outcome: AuditOutcome::InvalidAuth,
+        }
+    );
+}
+
+pub(crate) fn vault_operation_invalid_request() {
+    let err = vault_operation_impl(b"not a sealed request").unwrap_err();
+    let message = err.to_string();
+    assert!(
+        message.starts_with(
+            "invalid vault operation request: ERROR(unseal_vault_request): \
+             failed to unpack received sealed request"
+        ),
+        "{}",
+        message
+    );
 }
@@ -0,0 +1,85 @@
+use std::prelude::v1::ToString;
+
+use algonaut::transaction::account::Account;
+use sgx_vault_impl::ported::kv_store::KvStore;
+use sgx_vault_impl::schema::actions;
+use sgx_vault_impl::schema::actions::{ExportAlgorandAccountResult, UpdateVaultPolicyResult};
+use sgx_vault_impl::schema::entities::VaultPolicy;
+use sgx_vault_impl::vault_operations::export_algorand_account::export_algorand_account;
+use sgx_vault_impl::vault_operations::store::{key_from_id, vault_store};
+use sgx_vault_impl::vault_operations::update_vault_policy::update_vault_policy;
+
+use crate::helpers::vault_store::create_test_vault_with_username;
+
+type Result = ExportAlgorandAccountResult;
+
+pub(crate) fn export_algorand_account_works() {
+    let existing = &create_test_vault_with_username("Export Works");
+
+    let request = &actions::ExportAlgorandAccount {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "123456".to_string(),
+    };
+    let exported = &match export_algorand_account(request) {
+        Result::Exported(exported) => exported,
+        otherwise => panic!("{:?}", otherwise),
+    };
+
+    let recovered = Account::from_mnemonic(&exported.mnemonic).unwrap();
+    assert_eq!(
+        recovered.address().to_string(),
+        existing.algorand_address_base32
+    );
+
+    let mut store = vault_store();
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    store.delete(key).unwrap();
+}
+
+pub(crate) fn export_algorand_account_bad_auth() {
+    let existing = &create_test_vault_with_username("Export Bad Auth");
+
+    let request = &actions::ExportAlgorandAccount {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "000000".to_string(),
+    };
+    match export_algorand_account(request) {
+        Result::InvalidAuth => (),
+        otherwise => panic!("{:?}", otherwise),
+    };
+
+    let mut store = vault_store();
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    store.delete(key).unwrap();
+}
+
+pub(crate) fn export_algorand_account_disabled() {
+    let existing = &create_test_vault_with_username("Export Disabled");
+
+    let policy = VaultPolicy {
+        allow_algorand_account_export: false,
+        ..Default::default()
+    };
+    let update_request = &actions::UpdateVaultPolicy {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "123456".to_string(),
+        policy: policy.clone(),
+    };
+    assert_eq!(
+        update_vault_policy(update_request),
+        UpdateVaultPolicyResult::Updated(policy)
+    );
+
+    let request = &actions::ExportAlgorandAccount {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "123456".to_string(),
+    };
+    match export_algorand_account(request) {
+        Result::ExportDisabled => (),
+        otherwise => panic!("{:?}", otherwise),
+    };
+
+    let mut store = vault_store();
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    store.delete(key).unwrap();
+}
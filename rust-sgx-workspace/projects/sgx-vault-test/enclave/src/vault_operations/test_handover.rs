@@ -0,0 +1,163 @@
+use std::prelude::v1::Vec;
+use std::untrusted::fs;
+
+use sgx_tse::rsgx_self_target;
+use sgx_vault_impl::ported::kv_store::KvStore;
+use sgx_vault_impl::schema::entities::VaultStorable;
+use sgx_vault_impl::vault_operations::handover::{
+    handover_begin,
+    handover_export,
+    handover_import,
+    HandoverError,
+};
+use sgx_vault_impl::vault_operations::store::{
+    init_vault_store,
+    key_from_id,
+    load_vault,
+    vault_store,
+    vault_store_dir,
+};
+
+use crate::helpers::temp_dir::TempDir;
+use crate::helpers::vault_store::create_test_vault_with_username;
+
+/// Hand a record over to ourselves: this enclave is both predecessor and successor.
+///
+/// Like the host, this imports into a separate staging store.
+pub(crate) fn handover_roundtrip_works() {
+    let previous = &vault_store_dir();
+    let existing = &create_test_vault_with_username("Handover");
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    let stored = load_vault(&existing.vault_id).unwrap().unwrap();
+
+    let target_info = &rsgx_self_target().unwrap();
+    let (successor_public_key, successor_report) = handover_begin(target_info).unwrap();
+    let (sealed_record, predecessor_report) =
+        handover_export(&successor_report, &successor_public_key, key).unwrap();
+
+    let staging_dir = TempDir::create();
+    init_vault_store(staging_dir.as_ref()).unwrap();
+    assert_eq!(load_vault(&existing.vault_id).unwrap(), None);
+    handover_import(&predecessor_report, &sealed_record).unwrap();
+    assert_eq!(
+        load_vault(&existing.vault_id).unwrap(),
+        Some(stored.clone())
+    );
+
+    init_vault_store(previous).unwrap();
+    assert_eq!(load_vault(&existing.vault_id).unwrap(), Some(stored));
+    vault_store().delete(key).unwrap();
+}
+
+/// Follow the host's flow (see `handover_vault_store` in the sgx-vault app) for a whole store.
+pub(crate) fn handover_store_works() {
+    let previous = &vault_store_dir();
+    let temp_dir = TempDir::create();
+    let store_dir = &temp_dir.as_ref().join("vault_store");
+    let staging_dir = &temp_dir.as_ref().join("vault_store.handover");
+    let backup_dir = &temp_dir.as_ref().join("vault_store.predecessor");
+
+    init_vault_store(store_dir).unwrap();
+    let stored: Vec<VaultStorable> = ["Handover Store 1", "Handover Store 2"]
+        .iter()
+        .map(|username| {
+            let existing = create_test_vault_with_username(username);
+            load_vault(&existing.vault_id).unwrap().unwrap()
+        })
+        .collect();
+
+    // Predecessor: export every record in the store.
+    let target_info = &rsgx_self_target().unwrap();
+    let (successor_public_key, successor_report) = handover_begin(target_info).unwrap();
+    let exported: Vec<_> = vault_store()
+        .keys()
+        .unwrap()
+        .iter()
+        .map(|key| handover_export(&successor_report, &successor_public_key, key).unwrap())
+        .collect();
+    assert_eq!(exported.len(), stored.len());
+
+    // Successor: import into the staging store, then swap it in.
+    init_vault_store(staging_dir).unwrap();
+    for (sealed_record, predecessor_report) in &exported {
+        handover_import(predecessor_report, sealed_record).unwrap();
+    }
+    fs::rename(store_dir, backup_dir).unwrap();
+    fs::rename(staging_dir, store_dir).unwrap();
+    init_vault_store(store_dir).unwrap();
+
+    for expected in &stored {
+        assert_eq!(
+            load_vault(&expected.vault_id).unwrap().as_ref(),
+            Some(expected)
+        );
+    }
+    let mut keys = vault_store().keys().unwrap();
+    keys.sort();
+    let mut expected_keys: Vec<_> = stored
+        .iter()
+        .map(|expected| key_from_id(&expected.vault_id).unwrap())
+        .collect();
+    expected_keys.sort();
+    assert_eq!(keys, expected_keys);
+
+    init_vault_store(previous).unwrap();
+}
+
+/// Importing never overwrites an existing record.
+pub(crate) fn handover_import_existing() {
+    let existing = &create_test_vault_with_username("Handover Existing");
+    let key = &key_from_id(&existing.vault_id).unwrap();
+
+    let target_info = &rsgx_self_target().unwrap();
+    let (successor_public_key, successor_report) = handover_begin(target_info).unwrap();
+    let (sealed_record, predecessor_report) =
+        handover_export(&successor_report, &successor_public_key, key).unwrap();
+
+    let err = handover_import(&predecessor_report, &sealed_record).unwrap_err();
+    assert!(matches!(err, HandoverError::AlreadyExists), "{:?}", err);
+
+    vault_store().delete(key).unwrap();
+}
+
+/// Records exported for an earlier handover can't be replayed into a later one.
+pub(crate) fn handover_import_replayed() {
+    let existing = &create_test_vault_with_username("Handover Replayed");
+    let key = &key_from_id(&existing.vault_id).unwrap();
+
+    let target_info = &rsgx_self_target().unwrap();
+    let (successor_public_key, successor_report) = handover_begin(target_info).unwrap();
+    let (sealed_record, predecessor_report) =
+        handover_export(&successor_report, &successor_public_key, key).unwrap();
+    vault_store().delete(key).unwrap();
+
+    handover_begin(target_info).unwrap();
+    let err = handover_import(&predecessor_report, &sealed_record).unwrap_err();
+    assert!(
+        matches!(err, HandoverError::RecordBindingMismatch),
+        "{:?}",
+        err
+    );
+    assert_eq!(load_vault(&existing.vault_id).unwrap(), None);
+}
+
+pub(crate) fn handover_export_public_key_mismatch() {
+    let existing = &create_test_vault_with_username("Handover Mismatch");
+    let key = &key_from_id(&existing.vault_id).unwrap();
+
+    let target_info = &rsgx_self_target().unwrap();
+    let (_, successor_report) = handover_begin(target_info).unwrap();
+    let err = handover_export(&successor_report, &[0; 32], key).unwrap_err();
+    assert!(matches!(err, HandoverError::PublicKeyMismatch), "{:?}", err);
+
+    vault_store().delete(key).unwrap();
+}
+
+pub(crate) fn handover_export_not_found() {
+    let key = &key_from_id("Handover Not Found").unwrap();
+
+    let target_info = &rsgx_self_target().unwrap();
+    let (successor_public_key, successor_report) = handover_begin(target_info).unwrap();
+    let err = handover_export(&successor_report, &successor_public_key, key).unwrap_err();
+    assert!(matches!(err, HandoverError::NotFound), "{:?}", err);
+}
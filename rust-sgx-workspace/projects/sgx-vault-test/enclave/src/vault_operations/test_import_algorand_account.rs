@@ -0,0 +1,109 @@
+use std::prelude::v1::ToString;
+
+use algonaut::transaction::account::Account;
+use sgx_vault_impl::ported::kv_store::KvStore;
+use sgx_vault_impl::schema::actions;
+use sgx_vault_impl::schema::actions::{AlgorandAccountToImport, ImportAlgorandAccountResult};
+use sgx_vault_impl::vault_operations::import_algorand_account::import_algorand_account;
+use sgx_vault_impl::vault_operations::store::{key_from_id, load_vault, vault_store};
+
+use crate::helpers::vault_store::{create_test_vault_with_username, delete_vault};
+
+type Result = ImportAlgorandAccountResult;
+
+pub(crate) fn import_algorand_account_mnemonic_works() {
+    let existing_account = Account::generate();
+
+    let request = &actions::ImportAlgorandAccount {
+        username: "Import Mnemonic".to_string(),
+        auth_password: "123456".to_string(),
+        account_to_import: AlgorandAccountToImport::Mnemonic {
+            mnemonic: existing_account.mnemonic(),
+        },
+    };
+    let display = &match import_algorand_account(request) {
+        Result::Imported(imported) => imported,
+        otherwise => panic!("{:?}", otherwise),
+    };
+
+    assert_eq!(display.username, request.username);
+    assert_eq!(
+        display.algorand_address_base32,
+        existing_account.address().to_string()
+    );
+
+    let stored = load_vault(&display.vault_id).unwrap().unwrap();
+    assert_eq!(stored.algorand_account.seed_bytes, existing_account.seed());
+
+    let mut store = vault_store();
+    let key = &key_from_id(&display.vault_id).unwrap();
+    store.delete(key).unwrap();
+}
+
+pub(crate) fn import_algorand_account_seed_works() {
+    let existing_account = Account::generate();
+
+    let request = &actions::ImportAlgorandAccount {
+        username: "Import Seed".to_string(),
+        auth_password: "123456".to_string(),
+        account_to_import: AlgorandAccountToImport::Seed {
+            seed_bytes: existing_account.seed(),
+        },
+    };
+    let display = &match import_algorand_account(request) {
+        Result::Imported(imported) => imported,
+        otherwise => panic!("{:?}", otherwise),
+    };
+
+    assert_eq!(
+        display.algorand_address_base32,
+        existing_account.address().to_string()
+    );
+
+    let mut store = vault_store();
+    let key = &key_from_id(&display.vault_id).unwrap();
+    store.delete(key).unwrap();
+}
+
+pub(crate) fn import_algorand_account_invalid_mnemonic() {
+    let request = &actions::ImportAlgorandAccount {
+        username: "Import Invalid Mnemonic".to_string(),
+        auth_password: "123456".to_string(),
+        account_to_import: AlgorandAccountToImport::Mnemonic {
+            mnemonic: "not a valid mnemonic".to_string(),
+        },
+    };
+    match import_algorand_account(request) {
+        Result::Failed(message) => assert!(
+            message.starts_with("invalid Algorand mnemonic: "),
+            "{}",
+            message
+        ),
+        otherwise => panic!("{:?}", otherwise),
+    };
+
+    assert_eq!(load_vault("Import Invalid Mnemonic").unwrap(), None);
+}
+
+pub(crate) fn import_algorand_account_existing_username() {
+    let existing = &create_test_vault_with_username("Import Existing");
+    let request = &actions::ImportAlgorandAccount {
+        username: existing.username.clone(),
+        auth_password: "123456".to_string(),
+        account_to_import: AlgorandAccountToImport::Seed {
+            seed_bytes: Account::generate().seed(),
+        },
+    };
+    match import_algorand_account(request) {
+        Result::Failed(message) => assert!(message.contains("already exists"), "{}", message),
+        otherwise => panic!("{:?}", otherwise),
+    };
+
+    let stored = load_vault(&existing.vault_id).unwrap().unwrap();
+    assert_eq!(
+        stored.algorand_account.address_base32(),
+        existing.algorand_address_base32
+    );
+
+    delete_vault(existing);
+}
@@ -0,0 +1,104 @@
+use std::io;
+
+use sgx_vault_impl::ported::kv_store::fs::{FsStore, SgxFiler};
+use sgx_vault_impl::ported::kv_store::KvStore;
+use sgx_vault_impl::schema::entities::{VaultStorable, VAULT_SCHEMA_VERSION};
+use sgx_vault_impl::vault_operations::migration::{
+    migrate_vault_record,
+    upgrade_vault_schema,
+    UnsupportedSchemaVersion,
+};
+use sgx_vault_impl::vault_operations::store::{
+    key_from_id,
+    load_vault,
+    mutate_vault,
+    vault_store,
+    vault_store_dir,
+    VaultCodec,
+    VAULT_STORE_KEY_POLICY,
+};
+
+use crate::helpers::vault_store::create_test_vault_with_username;
+
+pub(crate) fn migrate_vault_record_upgrades_schema() {
+    let existing = &create_test_vault_with_username("Migrate Schema");
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    set_schema_version(&existing.vault_id, 0);
+
+    // Loading upgrades in memory only.
+    let loaded = load_vault(&existing.vault_id).unwrap().unwrap();
+    assert_eq!(loaded.schema_version, VAULT_SCHEMA_VERSION);
+    assert_eq!(raw_store().load(key).unwrap().unwrap().schema_version, 0);
+
+    assert!(migrate_vault_record(key).unwrap());
+    let migrated = raw_store().load(key).unwrap().unwrap();
+    assert_eq!(migrated.schema_version, VAULT_SCHEMA_VERSION);
+    assert_eq!(migrated, loaded);
+
+    vault_store().delete(key).unwrap();
+}
+
+pub(crate) fn migrate_vault_record_rotates_key() {
+    let existing = &create_test_vault_with_username("Migrate Key");
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    let stored = raw_store().load(key).unwrap().unwrap();
+
+    // Write the record as before explicit key management, and as JSON.
+    let mut auto_key_store = FsStore::new(vault_store_dir(), SgxFiler::default());
+    auto_key_store.save(key, &stored).unwrap();
+    assert!(raw_store().load(key).is_err());
+
+    assert!(migrate_vault_record(key).unwrap());
+    assert_eq!(raw_store().load(key).unwrap(), Some(stored));
+    assert!(auto_key_store.load(key).is_err());
+
+    vault_store().delete(key).unwrap();
+}
+
+pub(crate) fn migrate_vault_record_current_unchanged() {
+    let existing = &create_test_vault_with_username("Migrate Current");
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    let before = raw_store().load_versioned(key).unwrap();
+
+    assert!(!migrate_vault_record(key).unwrap());
+    assert_eq!(raw_store().load_versioned(key).unwrap(), before);
+
+    vault_store().delete(key).unwrap();
+}
+
+pub(crate) fn migrate_vault_record_not_found() {
+    let key = &key_from_id("Migrate Not Found").unwrap();
+    assert!(!migrate_vault_record(key).unwrap());
+}
+
+pub(crate) fn load_vault_unsupported_schema_version() {
+    let existing = &create_test_vault_with_username("Migrate Unsupported");
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    let newer = VAULT_SCHEMA_VERSION + 1;
+    set_schema_version(&existing.vault_id, newer);
+
+    let stored = raw_store().load(key).unwrap().unwrap();
+    assert_eq!(
+        upgrade_vault_schema(stored),
+        Err(UnsupportedSchemaVersion(newer))
+    );
+    let err = load_vault(&existing.vault_id).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(migrate_vault_record(key).is_err());
+
+    vault_store().delete(key).unwrap();
+}
+
+fn set_schema_version(vault_id: &str, schema_version: u32) {
+    mutate_vault(vault_id, |mut stored: VaultStorable| {
+        stored.schema_version = schema_version;
+        stored
+    })
+    .unwrap()
+    .unwrap();
+}
+
+/// The vault store, without schema upgrades or key rotation.
+fn raw_store() -> FsStore<SgxFiler, VaultStorable, VaultCodec> {
+    FsStore::with_codec(vault_store_dir(), SgxFiler::new(VAULT_STORE_KEY_POLICY))
+}
@@ -0,0 +1,53 @@
+//! Test [`sgx_vault_impl::vault_operations::response_cache`]
+
+use std::prelude::v1::Box;
+
+use sgx_vault_impl::vault_operations::response_cache::{
+    cache_response,
+    take_cached_response,
+    CachedResponse,
+    RequestId,
+    MAX_CACHED_RESPONSES,
+};
+
+pub(crate) fn response_cache_works() {
+    let request_id: RequestId = 1 << 40;
+    let response: Box<[u8]> = b"sealed response"[..].into();
+    cache_response(request_id, response.clone());
+
+    assert_eq!(
+        take_cached_response(request_id, response.len() - 1),
+        CachedResponse::TooLarge(response.len())
+    );
+    assert_eq!(
+        take_cached_response(request_id + 1, response.len()),
+        CachedResponse::Missing
+    );
+    assert_eq!(
+        take_cached_response(request_id, response.len()),
+        CachedResponse::Taken(response)
+    );
+    assert_eq!(
+        take_cached_response(request_id, usize::MAX),
+        CachedResponse::Missing
+    );
+}
+
+pub(crate) fn response_cache_evicts_oldest() {
+    let first_id: RequestId = 2 << 40;
+    let request_ids = first_id..=first_id + MAX_CACHED_RESPONSES as RequestId;
+    for request_id in request_ids.clone() {
+        cache_response(request_id, request_id.to_be_bytes()[..].into());
+    }
+
+    assert_eq!(
+        take_cached_response(first_id, usize::MAX),
+        CachedResponse::Missing
+    );
+    for request_id in request_ids.skip(1) {
+        assert_eq!(
+            take_cached_response(request_id, usize::MAX),
+            CachedResponse::Taken(request_id.to_be_bytes()[..].into())
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use std::prelude::v1::{Box, ToString};
+
+use secrecy::ExposeSecret;
+use sgx_vault_impl::ported::crypto::{SecretBytes, SodaBoxCrypto};
+use sgx_vault_impl::schema::actions::{OpenVault, OpenVaultResult, VaultRequest, VaultResponse};
+use sgx_vault_impl::schema::msgpack::{FromMessagePack, ToMessagePack};
+use sgx_vault_impl::schema::session::{
+    accept_session,
+    seal_session_request,
+    unseal_session_response,
+    SessionHello,
+    SessionKeys,
+    SessionSealedMessage,
+};
+use sgx_vault_impl::vault_operations::dispatch::vault_operation_impl;
+use sgx_vault_impl::vault_operations::sessions::{
+    session_handshake_impl,
+    SessionHandshakeError,
+    Sessions,
+    MAX_SESSIONS,
+    MIN_SESSION_AGE_SECONDS,
+    SESSION_LIFETIME_SECONDS,
+};
+
+fn hello_bytes(client_crypto: &SodaBoxCrypto) -> Box<[u8]> {
+    let hello = SessionHello {
+        client_public_key: client_crypto.get_pubkey(),
+    };
+    hello.to_msgpack().unwrap()
+}
+
+pub(crate) fn session_vault_operation_works() {
+    let client_crypto = &SodaBoxCrypto::new_ephemeral().unwrap();
+    let enclave_public_key = &SodaBoxCrypto::new().unwrap().get_pubkey();
+
+    // Handshake
+    let sealed_accept_bytes = &session_handshake_impl(&hello_bytes(client_crypto)).unwrap();
+    let (session_id, keys) =
+        &accept_session(sealed_accept_bytes, client_crypto, enclave_public_key).unwrap();
+
+    // Seal
+    let vault_request = &VaultRequest::OpenVault(OpenVault {
+        vault_id: "123456".to_string(),
+        auth_password: "1234".to_string(),
+    });
+    let request_bytes = &SecretBytes::new(vault_request.to_msgpack().unwrap());
+    let sealed_request = seal_session_request(request_bytes, *session_id, 1, keys).unwrap();
+    let sealed_request_bytes = &sealed_request.to_msgpack().unwrap();
+
+    // Call
+    let (sealed_response_bytes, _) = &vault_operation_impl(sealed_request_bytes).unwrap();
+
+    // Unseal
+    let sealed_response = &SessionSealedMessage::from_msgpack(sealed_response_bytes).unwrap();
+    assert_eq!(&sealed_response.session_id, session_id);
+    let response_bytes = unseal_session_response(sealed_response, 1, keys).unwrap();
+    let vault_response = VaultResponse::from_msgpack(response_bytes.expose_secret()).unwrap();
+
+    // Check
+    assert_eq!(vault_response, OpenVaultResult::InvalidAuth.into());
+}
+
+pub(crate) fn session_vault_operation_replayed() {
+    let client_crypto = &SodaBoxCrypto::new_ephemeral().unwrap();
+    let enclave_public_key = &SodaBoxCrypto::new().unwrap().get_pubkey();
+    let sealed_accept_bytes = &session_handshake_impl(&hello_bytes(client_crypto)).unwrap();
+    let (session_id, keys) =
+        &accept_session(sealed_accept_bytes, client_crypto, enclave_public_key).unwrap();
+
+    let vault_request = &VaultRequest::OpenVault(OpenVault {
+        vault_id: "123456".to_string(),
+        auth_password: "1234".to_string(),
+    });
+    let request_bytes = &SecretBytes::new(vault_request.to_msgpack().unwrap());
+    let sealed_request_bytes = |counter| {
+        seal_session_request(request_bytes, *session_id, counter, keys)
+            .unwrap()
+            .to_msgpack()
+            .unwrap()
+    };
+    vault_operation_impl(&sealed_request_bytes(2)).unwrap();
+
+    // The same request again, or an earlier one, is refused.
+    for counter in [2, 1] {
+        let err = vault_operation_impl(&sealed_request_bytes(counter)).unwrap_err();
+        let message = err.to_string();
+        assert!(
+            message.starts_with(
+                "invalid vault operation request: ERROR(unseal_vault_request): \
+                 replayed or reordered session request"
+            ),
+            "{}",
+            message
+        );
+    }
+
+    // A later counter is accepted, even if it skips some.
+    vault_operation_impl(&sealed_request_bytes(5)).unwrap();
+}
+
+pub(crate) fn session_accept_checks_enclave_key() {
+    let client_crypto = &SodaBoxCrypto::new_ephemeral().unwrap();
+    let other_public_key = &SodaBoxCrypto::from_seed([3; 32]).get_pubkey();
+
+    let sealed_accept_bytes = &session_handshake_impl(&hello_bytes(client_crypto)).unwrap();
+    let err = accept_session(sealed_accept_bytes, client_crypto, other_public_key)
+        .err()
+        .unwrap();
+    assert_eq!(
+        err.to_string(),
+        "session accept is not from the attested enclave key"
+    );
+}
+
+pub(crate) fn session_handshake_invalid_hello() {
+    let err = session_handshake_impl(b"not a hello").unwrap_err();
+    let message = err.to_string();
+    assert!(
+        message.starts_with(
+            "invalid vault operation request: ERROR(session_handshake_impl): invalid SessionHello"
+        ),
+        "{}",
+        message
+    );
+}
+
+pub(crate) fn session_vault_operation_unknown_session() {
+    let crypto = SodaBoxCrypto::from_seed([4; 32]);
+    let keys = &SessionKeys::derive(&crypto.shared_key(&[5; 32]), &[4; 32], &[5; 32]);
+    let request_bytes = &SecretBytes::new(Box::new(*b"irrelevant"));
+    let sealed_request = seal_session_request(request_bytes, [0xff; 16], 1, keys).unwrap();
+
+    let err = vault_operation_impl(&sealed_request.to_msgpack().unwrap()).unwrap_err();
+    let message = err.to_string();
+    assert!(
+        message.starts_with(
+            "invalid vault operation request: ERROR(unseal_vault_request): \
+             unknown session (expired, or evicted)"
+        ),
+        "{}",
+        message
+    );
+}
+
+fn some_session_keys() -> SessionKeys {
+    let crypto = SodaBoxCrypto::from_seed([4; 32]);
+    SessionKeys::derive(&crypto.shared_key(&[5; 32]), &[4; 32], &[5; 32])
+}
+
+pub(crate) fn sessions_expire() {
+    let sessions = &mut Sessions::default();
+    let started = 1_000_000;
+    sessions
+        .hold([1; 16], some_session_keys(), started)
+        .unwrap();
+
+    let last_second = started + SESSION_LIFETIME_SECONDS - 1;
+    assert!(sessions.keys(&[1; 16], last_second).is_some());
+    let expired = started + SESSION_LIFETIME_SECONDS;
+    assert!(sessions.keys(&[1; 16], expired).is_none());
+
+    // Holding another session drops the expired one.
+    sessions
+        .hold([2; 16], some_session_keys(), expired)
+        .unwrap();
+    assert_eq!(sessions.len(), 1);
+}
+
+pub(crate) fn sessions_evict_only_old_sessions() {
+    let sessions = &mut Sessions::default();
+    let started = 1_000_000;
+    for index in 0..MAX_SESSIONS {
+        let session_id = [index as u8; 16];
+        sessions
+            .hold(session_id, some_session_keys(), started)
+            .unwrap();
+    }
+
+    // A flood of new sessions can't push out recent ones.
+    let err = sessions
+        .hold(
+            [0xff; 16],
+            some_session_keys(),
+            started + MIN_SESSION_AGE_SECONDS - 1,
+        )
+        .unwrap_err();
+    assert!(matches!(err, SessionHandshakeError::TooManySessions));
+    assert!(sessions.keys(&[0; 16], started + 1).is_some());
+
+    // Once old enough, the oldest session gets evicted.
+    let later = started + MIN_SESSION_AGE_SECONDS;
+    sessions
+        .hold([0xff; 16], some_session_keys(), later)
+        .unwrap();
+    assert_eq!(sessions.len(), MAX_SESSIONS);
+    assert!(sessions.keys(&[0; 16], later).is_none());
+    assert!(sessions.keys(&[1; 16], later).is_some());
+    assert!(sessions.keys(&[0xff; 16], later).is_some());
+}
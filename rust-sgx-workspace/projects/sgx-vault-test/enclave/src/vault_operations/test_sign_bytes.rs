@@ -0,0 +1,146 @@
+use std::prelude::v1::ToString;
+
+use algonaut::core::Address;
+use sgx_vault_impl::schema::actions;
+use sgx_vault_impl::schema::actions::{SignTransactionResult, TransactionToSign};
+use sgx_vault_impl::schema::entities::VaultDisplay;
+use sgx_vault_impl::vault_operations::sign_transaction::sign_transaction;
+
+use crate::helpers::vault_store::{create_test_vault_with_username, delete_vault};
+
+type Result = SignTransactionResult;
+
+pub(crate) fn sign_algorand_bytes_works() {
+    let existing = &create_test_vault_with_username("Sign Bytes Works");
+
+    let data_bytes = b"data package attestation".to_vec().into_boxed_slice();
+    let signature = sign(
+        existing,
+        TransactionToSign::AlgorandBytes {
+            data_bytes: data_bytes.clone(),
+        },
+    )
+    .unwrap_signed()
+    .unwrap_ed25519_signature();
+
+    assert!(verify(
+        existing,
+        &signature,
+        &[&b"MX"[..], &data_bytes[..]].concat()
+    ));
+
+    delete_vault(existing);
+}
+
+pub(crate) fn sign_algorand_bytes_transaction_prefix() {
+    let existing = &create_test_vault_with_username("Sign Bytes TX Prefix");
+
+    let result = sign(
+        existing,
+        TransactionToSign::AlgorandBytes {
+            data_bytes: b"TX not really".to_vec().into_boxed_slice(),
+        },
+    );
+    assert_eq!(
+        result,
+        Result::Failed(
+            "ERROR(sign_algorand_bytes): refusing to sign bytes with an Algorand transaction prefix\n\
+             ( error = got TX )\n\
+             [ data = VFggbm90IHJlYWxseQ== ]"
+                .to_string()
+        )
+    );
+
+    delete_vault(existing);
+}
+
+pub(crate) fn sign_ed25519_message_works() {
+    let existing = &create_test_vault_with_username("Sign Message Works");
+
+    let message_bytes = b"login challenge 1234".to_vec().into_boxed_slice();
+    let signature = sign(
+        existing,
+        TransactionToSign::Ed25519Message {
+            domain_tag: "ntc-login-v1".to_string(),
+            message_bytes: message_bytes.clone(),
+        },
+    )
+    .unwrap_signed()
+    .unwrap_ed25519_signature();
+
+    let signed_message = &[&b"NTC-MSG"[..], b"ntc-login-v1", &[0], &message_bytes[..]].concat();
+    assert!(verify(existing, &signature, signed_message));
+
+    let unprefixed_message = &[&b"ntc-login-v1"[..], &[0], &message_bytes[..]].concat();
+    assert!(!verify(existing, &signature, unprefixed_message));
+
+    delete_vault(existing);
+}
+
+pub(crate) fn sign_ed25519_message_reserved_tag() {
+    let existing = &create_test_vault_with_username("Sign Message Reserved Tag");
+
+    for domain_tag in ["TX", "MXsomething", "Program", "ProgData", "", "has space"] {
+        let result = sign(
+            existing,
+            TransactionToSign::Ed25519Message {
+                domain_tag: domain_tag.to_string(),
+                message_bytes: b"message".to_vec().into_boxed_slice(),
+            },
+        );
+        match result {
+            Result::Failed(message) => assert!(
+                message.starts_with("ERROR(sign_ed25519_message): invalid domain tag"),
+                "{}",
+                message
+            ),
+            otherwise => panic!("domain tag {:?}: {:?}", domain_tag, otherwise),
+        }
+    }
+
+    delete_vault(existing);
+}
+
+fn sign(existing: &VaultDisplay, transaction_to_sign: TransactionToSign) -> Result {
+    let request = &actions::SignTransaction {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "123456".to_string(),
+        transaction_to_sign,
+    };
+    sign_transaction(request)
+}
+
+/// Verify a detached signature against the vault's Algorand address.
+fn verify(existing: &VaultDisplay, signature: &[u8], message: &[u8]) -> bool {
+    let address: Address = existing.algorand_address_base32.parse().unwrap();
+    let signed_message = &[signature, message].concat();
+    let mut opened = vec![0; signed_message.len()];
+    sodalite::sign_attached_open(&mut opened, signed_message, &address.0).is_ok()
+}
+
+/// A `Program` tag must not yield a delegated logic signature: `20 01 01 22` is
+/// `#pragma version 1; int 1; return`, which approves any transaction.
+pub(crate) fn sign_ed25519_message_logic_sig_tag() {
+    let existing = &create_test_vault_with_username("Sign Message Logic Sig Tag");
+
+    for domain_tag in ["Program", "ProgData"] {
+        let result = sign(
+            existing,
+            TransactionToSign::Ed25519Message {
+                domain_tag: domain_tag.to_string(),
+                message_bytes: vec![0x20, 0x01, 0x01, 0x22].into_boxed_slice(),
+            },
+        );
+        assert_eq!(
+            result,
+            Result::Failed(format!(
+                "ERROR(sign_ed25519_message): invalid domain tag\n\
+                 ( error = Algorand's domain separation prefixes are reserved )\n\
+                 [ domain tag = {:?} ]",
+                domain_tag
+            ))
+        );
+    }
+
+    delete_vault(existing);
+}
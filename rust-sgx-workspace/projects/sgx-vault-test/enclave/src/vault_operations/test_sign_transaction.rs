@@ -2,11 +2,13 @@ use std::prelude::v1::ToString;
 
 use algonaut::core::ToMsgPack;
 use algonaut::transaction::SignedTransaction as AlgonautSignedTransaction;
+use sgx_vault_impl::ported::kv_store::in_memory::InMemoryStore;
 use sgx_vault_impl::ported::kv_store::KvStore;
 use sgx_vault_impl::schema::actions;
 use sgx_vault_impl::schema::actions::{SignTransactionResult, TransactionToSign};
 use sgx_vault_impl::schema::msgpack::FromMessagePack;
-use sgx_vault_impl::vault_operations::sign_transaction::sign_transaction;
+use sgx_vault_impl::vault_operations::create_vault::create_vault_in;
+use sgx_vault_impl::vault_operations::sign_transaction::{sign_transaction, sign_transaction_in};
 use sgx_vault_impl::vault_operations::store::{key_from_id, vault_store};
 
 use crate::helpers::algonaut::create_test_transaction;
@@ -46,6 +48,46 @@ pub(crate) fn sign_transaction_works() {
     store.delete(key).unwrap();
 }
 
+/// [`sign_transaction_in`] signs with a vault from the given store.
+pub(crate) fn sign_transaction_in_memory_works() {
+    let store = &mut InMemoryStore::default();
+    let existing = &match create_vault_in(
+        store,
+        &actions::CreateVault {
+            username: "In Memory Username".to_string(),
+            auth_password: "123456".to_string(),
+        },
+    ) {
+        actions::CreateVaultResult::Created(created) => created,
+        actions::CreateVaultResult::Failed(failed) => panic!("{}", failed),
+    };
+
+    let algonaut_transaction = create_test_transaction();
+    let transaction_bytes = algonaut_transaction
+        .bytes_to_sign()
+        .unwrap()
+        .into_boxed_slice();
+    let request = &actions::SignTransaction {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "123456".to_string(),
+        transaction_to_sign: TransactionToSign::AlgorandTransaction { transaction_bytes },
+    };
+    let signed = sign_transaction_in(store, request).unwrap_signed();
+
+    let algonaut_signed_transaction =
+        AlgonautSignedTransaction::from_msgpack(&signed.unwrap_algorand_bytes()).unwrap();
+    assert_eq!(
+        algonaut_signed_transaction.transaction,
+        algonaut_transaction
+    );
+
+    // The vault is not in the default store.
+    match sign_transaction(request) {
+        Result::InvalidAuth => (),
+        otherwise => panic!("{:?}", otherwise),
+    };
+}
+
 pub(crate) fn sign_transaction_without_tag() {
     let mut store = vault_store();
     let key = &key_from_id("New Username").unwrap();
@@ -0,0 +1,242 @@
+use std::prelude::v1::{ToString, Vec};
+
+use k256::ecdsa::{recoverable, SigningKey, VerifyingKey};
+use rlp::{Rlp, RlpStream};
+use sgx_vault_impl::schema::actions;
+use sgx_vault_impl::schema::actions::{SignTransactionResult, TransactionToSign};
+use sgx_vault_impl::schema::entities::{
+    AlgorandSigningPolicy,
+    EthereumAccount,
+    VaultDisplay,
+    VaultStorable,
+};
+use sgx_vault_impl::vault_operations::sign_transaction::sign_transaction;
+use sgx_vault_impl::vault_operations::store::mutate_vault;
+
+use crate::helpers::vault_store::{create_test_vault_with_username, delete_vault};
+
+type Result = SignTransactionResult;
+
+/// The example from EIP-155.
+///
+/// See: <https://eips.ethereum.org/EIPS/eip-155#example>
+pub(crate) fn sign_ethereum_eip_155_example() {
+    let existing = &create_test_vault_with_username("Sign Ethereum EIP-155");
+    let display = set_ethereum_account(existing, Some([0x46; 32]));
+    assert_eq!(
+        display.ethereum_address_hex.as_deref(),
+        Some("0x9d8A62f656a8d1615C1294fd71e9CFb3E4855A4F")
+    );
+
+    let transaction_bytes = hex::decode(
+        "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080",
+    )
+    .unwrap();
+    let signed = sign(existing, transaction_bytes).unwrap_signed();
+
+    assert_eq!(
+        hex::encode(signed.unwrap_ethereum_bytes()),
+        "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+    );
+
+    delete_vault(existing);
+}
+
+/// The EIP-155 example's key and transfer, as an EIP-1559 transaction.
+///
+/// The expected signed transaction is from ethers-rs (`LocalWallet::sign_transaction_sync`).
+pub(crate) fn sign_ethereum_eip_1559_example() {
+    let existing = &create_test_vault_with_username("Sign Ethereum EIP-1559 Example");
+    set_ethereum_account(existing, Some([0x46; 32]));
+
+    let transaction_bytes = hex::decode(
+        "02f00109843b9aca008504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080c0",
+    )
+    .unwrap();
+    let signed = sign(existing, transaction_bytes).unwrap_signed();
+
+    assert_eq!(
+        hex::encode(signed.unwrap_ethereum_bytes()),
+        "02f8730109843b9aca008504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080c080a04e87ced8b47d801c979c6baa52bbd78b42c9db2515c9d1f473e06f65d49aaa90a02357671517c59544ebd95012d1988c102292eb570cc840ac9af72bb4c52e5edd"
+    );
+
+    delete_vault(existing);
+}
+
+pub(crate) fn sign_ethereum_legacy_without_chain_id() {
+    let existing = &create_test_vault_with_username("Sign Ethereum Legacy");
+    set_ethereum_account(existing, Some([0x46; 32]));
+
+    let mut stream = RlpStream::new_list(6);
+    stream.append(&9_u64);
+    stream.append(&20_000_000_000_u64);
+    stream.append(&21_000_u64);
+    stream.append(&[0x35_u8; 20].to_vec());
+    stream.append(&1_000_000_000_000_000_000_u64);
+    stream.append_empty_data();
+    let transaction_bytes = stream.out().to_vec();
+
+    let signed_bytes = sign(existing, transaction_bytes.clone())
+        .unwrap_signed()
+        .unwrap_ethereum_bytes();
+
+    let signed = Rlp::new(&signed_bytes);
+    assert_eq!(signed.item_count().unwrap(), 9);
+    let v: u64 = signed.val_at(6).unwrap();
+    assert!(v == 27 || v == 28, "v = {}", v);
+    assert_eq!(
+        recover_signer(&transaction_bytes, &signed, (v - 27) as u8),
+        test_verifying_key()
+    );
+
+    delete_vault(existing);
+}
+
+pub(crate) fn sign_ethereum_eip_1559_works() {
+    let existing = &create_test_vault_with_username("Sign Ethereum EIP-1559");
+    set_ethereum_account(existing, Some([0x46; 32]));
+
+    let mut stream = RlpStream::new_list(9);
+    stream.append(&1_u64); // chainId
+    stream.append(&9_u64); // nonce
+    stream.append(&1_000_000_000_u64); // maxPriorityFeePerGas
+    stream.append(&20_000_000_000_u64); // maxFeePerGas
+    stream.append(&21_000_u64); // gasLimit
+    stream.append(&[0x35_u8; 20].to_vec()); // to
+    stream.append(&1_000_000_000_000_000_000_u64); // value
+    stream.append_empty_data(); // data
+    stream.begin_list(0); // accessList
+    let transaction_bytes = [&[0x02], &stream.out()[..]].concat();
+
+    let signed_bytes = sign(existing, transaction_bytes.clone())
+        .unwrap_signed()
+        .unwrap_ethereum_bytes();
+
+    assert_eq!(signed_bytes[0], 0x02);
+    let signed = Rlp::new(&signed_bytes[1..]);
+    assert_eq!(signed.item_count().unwrap(), 12);
+    let unsigned = Rlp::new(&transaction_bytes[1..]);
+    for i in 0..9 {
+        assert_eq!(
+            signed.at(i).unwrap().as_raw(),
+            unsigned.at(i).unwrap().as_raw()
+        );
+    }
+    let y_parity: u8 = signed.val_at(9).unwrap();
+    assert_eq!(
+        recover_signer(&transaction_bytes, &signed, y_parity),
+        test_verifying_key()
+    );
+
+    delete_vault(existing);
+}
+
+/// The signing policy's limits don't model Ethereum transactions, so they refuse them.
+pub(crate) fn sign_ethereum_policy_limits() {
+    let existing = &create_test_vault_with_username("Sign Ethereum Policy Limits");
+    set_ethereum_account(existing, Some([0x46; 32]));
+    mutate_vault(&existing.vault_id, |mut stored: VaultStorable| {
+        stored.policy.algorand_signing = AlgorandSigningPolicy {
+            max_amount: Some(1_000_000),
+            ..Default::default()
+        };
+        stored
+    })
+    .unwrap()
+    .unwrap();
+
+    let transaction_bytes = hex::decode(
+        "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080",
+    )
+    .unwrap();
+    assert_eq!(
+        sign(existing, transaction_bytes),
+        Result::PolicyViolation(
+            "Ethereum transactions are not allowed while the signing policy has limits".to_string()
+        )
+    );
+
+    delete_vault(existing);
+}
+
+pub(crate) fn sign_ethereum_unsupported_type() {
+    let existing = &create_test_vault_with_username("Sign Ethereum Unsupported");
+
+    // EIP-2930 (type 1) transactions are not supported.
+    let result = sign(existing, vec![0x01, 0xc0]);
+    assert_eq!(
+        result,
+        Result::Failed(
+            "ERROR(sign_ethereum_transaction): transaction type not supported\n\
+             ( error = expected legacy or EIP-1559, got first byte Some(1) )\n\
+             [ unsigned transaction = AcA= ]"
+                .to_string()
+        )
+    );
+
+    delete_vault(existing);
+}
+
+pub(crate) fn sign_ethereum_without_account() {
+    let existing = &create_test_vault_with_username("Sign Ethereum No Account");
+    let display = set_ethereum_account(existing, None);
+    assert_eq!(display.ethereum_address_hex, None);
+
+    let result = sign(existing, vec![0xc0]);
+    assert_eq!(
+        result,
+        Result::Failed(
+            "ERROR(sign_ethereum_transaction): vault has no Ethereum account\n\
+             ( error = created before Ethereum support )\n\
+             [ vault ID = Sign Ethereum No Account ]"
+                .to_string()
+        )
+    );
+
+    delete_vault(existing);
+}
+
+fn set_ethereum_account(
+    existing: &VaultDisplay,
+    secret_key_bytes: Option<[u8; 32]>,
+) -> VaultDisplay {
+    let ethereum_account =
+        secret_key_bytes.map(|secret_key_bytes| EthereumAccount { secret_key_bytes });
+    let mutated = mutate_vault(&existing.vault_id, |mut stored: VaultStorable| {
+        stored.ethereum_account = ethereum_account.clone();
+        stored
+    })
+    .unwrap()
+    .unwrap();
+    VaultDisplay::from(mutated)
+}
+
+fn sign(existing: &VaultDisplay, transaction_bytes: Vec<u8>) -> Result {
+    let request = &actions::SignTransaction {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "123456".to_string(),
+        transaction_to_sign: TransactionToSign::EthereumTransaction {
+            transaction_bytes: transaction_bytes.into_boxed_slice(),
+        },
+    };
+    sign_transaction(request)
+}
+
+/// Recover the signer of `payload` from a signed transaction's trailing `r` and `s` fields.
+fn recover_signer(payload: &[u8], signed: &Rlp, recovery_id: u8) -> VerifyingKey {
+    let item_count = signed.item_count().unwrap();
+    let mut signature_bytes = [0; 65];
+    for (i, range) in [(item_count - 2, 0..32), (item_count - 1, 32..64)] {
+        // Left-pad RLP integers back to 32 bytes.
+        let value: Vec<u8> = signed.val_at(i).unwrap();
+        signature_bytes[range.end - value.len()..range.end].copy_from_slice(&value);
+    }
+    signature_bytes[64] = recovery_id;
+
+    let signature = recoverable::Signature::try_from(&signature_bytes[..]).unwrap();
+    signature.recover_verifying_key(payload).unwrap()
+}
+
+fn test_verifying_key() -> VerifyingKey {
+    SigningKey::from_bytes(&[0x46; 32]).unwrap().verifying_key()
+}
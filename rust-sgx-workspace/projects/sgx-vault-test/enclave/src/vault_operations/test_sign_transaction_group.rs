@@ -0,0 +1,136 @@
+use std::prelude::v1::{ToString, Vec};
+
+use algonaut::core::Address;
+use algonaut::transaction::account::Account;
+use algonaut::transaction::tx_group::TxGroup;
+use algonaut::transaction::{SignedTransaction as AlgonautSignedTransaction, Transaction};
+use sgx_vault_impl::ported::kv_store::KvStore;
+use sgx_vault_impl::schema::actions;
+use sgx_vault_impl::schema::actions::{SignTransactionResult, TransactionToSign};
+use sgx_vault_impl::schema::msgpack::FromMessagePack;
+use sgx_vault_impl::schema::types::Bytes;
+use sgx_vault_impl::vault_operations::sign_transaction::sign_transaction;
+use sgx_vault_impl::vault_operations::store::{key_from_id, vault_store};
+
+use crate::helpers::algonaut::create_test_transaction_from;
+use crate::helpers::vault_store::create_test_vault_with_username;
+
+type Result = SignTransactionResult;
+
+pub(crate) fn sign_transaction_group_works() {
+    let existing = &create_test_vault_with_username("Sign Group Works");
+    let vault_address: Address = existing.algorand_address_base32.parse().unwrap();
+
+    let mut vault_transaction = create_test_transaction_from(vault_address);
+    let mut other_transaction = create_test_transaction_from(Account::generate().address());
+    TxGroup::assign_group_id(vec![&mut vault_transaction, &mut other_transaction]).unwrap();
+
+    let request = &sign_group_request(
+        &existing.vault_id,
+        &[&vault_transaction, &other_transaction],
+    );
+    let signed = sign_transaction(request)
+        .unwrap_signed()
+        .unwrap_algorand_group();
+
+    assert_eq!(signed.len(), 1);
+    assert_eq!(signed[0].group_index, 0);
+    let algonaut_signed_transaction =
+        AlgonautSignedTransaction::from_msgpack(&signed[0].signed_transaction_bytes).unwrap();
+    assert_eq!(algonaut_signed_transaction.transaction, vault_transaction);
+
+    let mut store = vault_store();
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    store.delete(key).unwrap();
+}
+
+pub(crate) fn sign_transaction_group_mismatched_group_id() {
+    let existing = &create_test_vault_with_username("Sign Group Mismatched");
+    let vault_address: Address = existing.algorand_address_base32.parse().unwrap();
+
+    let mut vault_transaction = create_test_transaction_from(vault_address);
+    let mut other_transaction = create_test_transaction_from(Account::generate().address());
+    let mut unrelated_transaction = create_test_transaction_from(Account::generate().address());
+    TxGroup::assign_group_id(vec![&mut vault_transaction, &mut other_transaction]).unwrap();
+    TxGroup::assign_group_id(vec![&mut unrelated_transaction]).unwrap();
+
+    let request = &sign_group_request(
+        &existing.vault_id,
+        &[&vault_transaction, &unrelated_transaction],
+    );
+    match sign_transaction(request) {
+        Result::Failed(err) => assert!(
+            err.starts_with(
+                "ERROR(sign_transaction_group): transaction group ID does not match the group\n\
+                ( error = group index = 0 )"
+            ),
+            "{}",
+            err
+        ),
+        otherwise => panic!("{:?}", otherwise),
+    };
+
+    let mut store = vault_store();
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    store.delete(key).unwrap();
+}
+
+pub(crate) fn sign_transaction_group_without_vault_sender() {
+    let existing = &create_test_vault_with_username("Sign Group Without Sender");
+
+    let mut first_transaction = create_test_transaction_from(Account::generate().address());
+    let mut second_transaction = create_test_transaction_from(Account::generate().address());
+    TxGroup::assign_group_id(vec![&mut first_transaction, &mut second_transaction]).unwrap();
+
+    let request = &sign_group_request(
+        &existing.vault_id,
+        &[&first_transaction, &second_transaction],
+    );
+    match sign_transaction(request) {
+        Result::Failed(err) => assert!(
+            err.starts_with(
+                "ERROR(sign_transaction_group): no transaction in the group is sent by the vault's account\n\
+                ( error = len = 2 )"
+            ),
+            "{}",
+            err
+        ),
+        otherwise => panic!("{:?}", otherwise),
+    };
+
+    let mut store = vault_store();
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    store.delete(key).unwrap();
+}
+
+pub(crate) fn sign_transaction_group_empty() {
+    let existing = &create_test_vault_with_username("Sign Group Empty");
+
+    let request = &sign_group_request(&existing.vault_id, &[]);
+    match sign_transaction(request) {
+        Result::Failed(err) => assert_eq!(
+            err,
+            "ERROR(sign_transaction_group): invalid transaction group size\n\
+            ( error = len = 0 )\n\
+            [ maximum group size = 16 ]"
+        ),
+        otherwise => panic!("{:?}", otherwise),
+    };
+
+    let mut store = vault_store();
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    store.delete(key).unwrap();
+}
+
+/// Helper: Build a group signing request for `transactions`.
+fn sign_group_request(vault_id: &str, transactions: &[&Transaction]) -> actions::SignTransaction {
+    let transactions_bytes: Vec<Bytes> = transactions
+        .iter()
+        .map(|transaction| transaction.bytes_to_sign().unwrap().into_boxed_slice())
+        .collect();
+    actions::SignTransaction {
+        vault_id: vault_id.to_string(),
+        auth_password: "123456".to_string(),
+        transaction_to_sign: TransactionToSign::AlgorandTransactionGroup { transactions_bytes },
+    }
+}
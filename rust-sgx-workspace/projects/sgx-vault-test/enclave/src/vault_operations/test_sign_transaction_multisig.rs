@@ -0,0 +1,150 @@
+use std::prelude::v1::ToString;
+
+use algonaut::core::{Address, MultisigAddress, ToMsgPack};
+use algonaut::transaction::account::Account;
+use algonaut::transaction::transaction::TransactionSignature;
+use algonaut::transaction::SignedTransaction as AlgonautSignedTransaction;
+use sgx_vault_impl::ported::kv_store::KvStore;
+use sgx_vault_impl::schema::actions;
+use sgx_vault_impl::schema::actions::{
+    AlgorandMultisigParameters,
+    SignTransactionResult,
+    TransactionToSign,
+};
+use sgx_vault_impl::schema::msgpack::FromMessagePack;
+use sgx_vault_impl::vault_operations::sign_transaction::sign_transaction;
+use sgx_vault_impl::vault_operations::store::{key_from_id, vault_store};
+
+use crate::helpers::algonaut::create_test_transaction_from;
+use crate::helpers::vault_store::create_test_vault_with_username;
+
+type Result = SignTransactionResult;
+
+pub(crate) fn sign_transaction_multisig_works() {
+    let existing = &create_test_vault_with_username("Sign Multisig Works");
+    let other_account = Account::generate();
+    let (multisig, multisig_address) =
+        multisig_of(&existing.algorand_address_base32, &other_account.address());
+
+    let algonaut_transaction = create_test_transaction_from(multisig_address.address());
+    let transaction_bytes = algonaut_transaction
+        .bytes_to_sign()
+        .unwrap()
+        .into_boxed_slice();
+
+    let request = &actions::SignTransaction {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "123456".to_string(),
+        transaction_to_sign: TransactionToSign::AlgorandMultisigTransaction {
+            multisig,
+            transaction_bytes,
+        },
+    };
+    let signed = sign_transaction(request).unwrap_signed();
+
+    let partially_signed =
+        AlgonautSignedTransaction::from_msgpack(&signed.unwrap_algorand_bytes()).unwrap();
+    assert_eq!(partially_signed.transaction, algonaut_transaction);
+    assert_eq!(multisig_signature_count(&partially_signed), 1);
+
+    let fully_signed = other_account
+        .append_to_multisig_transaction(multisig_address, &partially_signed)
+        .unwrap();
+    assert_eq!(multisig_signature_count(&fully_signed), 2);
+
+    let mut store = vault_store();
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    store.delete(key).unwrap();
+}
+
+pub(crate) fn sign_transaction_multisig_append_works() {
+    let existing = &create_test_vault_with_username("Sign Multisig Append Works");
+    let other_account = Account::generate();
+    let (multisig, multisig_address) =
+        multisig_of(&existing.algorand_address_base32, &other_account.address());
+
+    let algonaut_transaction = create_test_transaction_from(multisig_address.address());
+    let partially_signed = other_account
+        .sign_multisig_transaction(multisig_address, &algonaut_transaction)
+        .unwrap();
+    let signed_transaction_bytes = partially_signed.to_msg_pack().unwrap().into_boxed_slice();
+
+    let request = &actions::SignTransaction {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "123456".to_string(),
+        transaction_to_sign: TransactionToSign::AlgorandMultisigSignedTransaction {
+            multisig,
+            signed_transaction_bytes,
+        },
+    };
+    let signed = sign_transaction(request).unwrap_signed();
+
+    let fully_signed =
+        AlgonautSignedTransaction::from_msgpack(&signed.unwrap_algorand_bytes()).unwrap();
+    assert_eq!(fully_signed.transaction, algonaut_transaction);
+    assert_eq!(multisig_signature_count(&fully_signed), 2);
+
+    let mut store = vault_store();
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    store.delete(key).unwrap();
+}
+
+pub(crate) fn sign_transaction_multisig_not_member() {
+    let existing = &create_test_vault_with_username("Sign Multisig Not Member");
+    let (multisig, multisig_address) = multisig_of(
+        &Account::generate().address().to_string(),
+        &Account::generate().address(),
+    );
+
+    let algonaut_transaction = create_test_transaction_from(multisig_address.address());
+    let transaction_bytes = algonaut_transaction
+        .bytes_to_sign()
+        .unwrap()
+        .into_boxed_slice();
+
+    let request = &actions::SignTransaction {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "123456".to_string(),
+        transaction_to_sign: TransactionToSign::AlgorandMultisigTransaction {
+            multisig,
+            transaction_bytes,
+        },
+    };
+    match sign_transaction(request) {
+        Result::Failed(err) => assert!(
+            err.starts_with(
+                "ERROR(sign_multisig_transaction): the vault's account is not a member of the multisig account\n"
+            ),
+            "{}",
+            err
+        ),
+        otherwise => panic!("{:?}", otherwise),
+    };
+
+    let mut store = vault_store();
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    store.delete(key).unwrap();
+}
+
+/// Helper: A 2-of-2 multisig account of `first` and `second`.
+fn multisig_of(first: &str, second: &Address) -> (AlgorandMultisigParameters, MultisigAddress) {
+    let multisig = AlgorandMultisigParameters {
+        version: 1,
+        threshold: 2,
+        addresses: vec![first.to_string(), second.to_string()],
+    };
+    let multisig_address = MultisigAddress::new(1, 2, &[first.parse().unwrap(), *second]).unwrap();
+    (multisig, multisig_address)
+}
+
+/// Helper: Count the multisig subsignatures present on `signed_transaction`.
+fn multisig_signature_count(signed_transaction: &AlgonautSignedTransaction) -> usize {
+    match &signed_transaction.sig {
+        TransactionSignature::Multi(multisig) => multisig
+            .subsigs
+            .iter()
+            .filter(|subsig| subsig.sig.is_some())
+            .count(),
+        otherwise => panic!("expected multisig signature, got {:?}", otherwise),
+    }
+}
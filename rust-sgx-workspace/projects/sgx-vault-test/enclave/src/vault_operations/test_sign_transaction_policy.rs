@@ -0,0 +1,162 @@
+use std::prelude::v1::{String, ToString};
+
+use algonaut::transaction::Transaction;
+use sgx_vault_impl::schema::actions;
+use sgx_vault_impl::schema::actions::{
+    SignTransactionResult,
+    TransactionToSign,
+    UpdateVaultPolicyResult,
+};
+use sgx_vault_impl::schema::entities::{AlgorandSigningPolicy, VaultDisplay, VaultPolicy};
+use sgx_vault_impl::vault_operations::sign_transaction::sign_transaction;
+use sgx_vault_impl::vault_operations::update_vault_policy::update_vault_policy;
+
+use crate::helpers::algonaut::create_test_transaction_from;
+use crate::helpers::vault_store::{create_test_vault_with_username, delete_vault};
+
+type Result = SignTransactionResult;
+
+const TEST_RECEIVER: &str = "4MYUHDWHWXAKA5KA7U5PEN646VYUANBFXVJNONBK3TIMHEMWMD4UBOJBI4";
+
+pub(crate) fn sign_transaction_policy_allows() {
+    let existing = &create_test_vault_with_username("Policy Allows");
+    set_signing_policy(
+        existing,
+        AlgorandSigningPolicy {
+            max_amount: Some(123_456),
+            max_fee: Some(1000),
+            allowed_receivers: Some(vec![TEST_RECEIVER.to_string()]),
+            allowed_asset_ids: Some(vec![]),
+            allowed_genesis_ids: Some(vec!["sandnet-v1".to_string()]),
+        },
+    );
+
+    let (_, result) = sign_test_transaction(existing);
+    assert!(matches!(result, Result::Signed(_)), "{:?}", result);
+
+    delete_vault(existing);
+}
+
+pub(crate) fn sign_transaction_policy_max_amount() {
+    let existing = &create_test_vault_with_username("Policy Max Amount");
+    set_signing_policy(
+        existing,
+        AlgorandSigningPolicy {
+            max_amount: Some(100_000),
+            ..Default::default()
+        },
+    );
+
+    let (transaction_id, result) = sign_test_transaction(existing);
+    assert_eq!(
+        result,
+        Result::PolicyViolation(format!(
+            "transaction {}: amount 123456 exceeds the maximum of 100000",
+            transaction_id
+        ))
+    );
+
+    delete_vault(existing);
+}
+
+pub(crate) fn sign_transaction_policy_max_fee() {
+    let existing = &create_test_vault_with_username("Policy Max Fee");
+    set_signing_policy(
+        existing,
+        AlgorandSigningPolicy {
+            max_fee: Some(999),
+            ..Default::default()
+        },
+    );
+
+    let (transaction_id, result) = sign_test_transaction(existing);
+    assert_eq!(
+        result,
+        Result::PolicyViolation(format!(
+            "transaction {}: fee 1000 exceeds the maximum of 999",
+            transaction_id
+        ))
+    );
+
+    delete_vault(existing);
+}
+
+pub(crate) fn sign_transaction_policy_receivers() {
+    let existing = &create_test_vault_with_username("Policy Receivers");
+    set_signing_policy(
+        existing,
+        AlgorandSigningPolicy {
+            allowed_receivers: Some(vec![existing.algorand_address_base32.clone()]),
+            ..Default::default()
+        },
+    );
+
+    let (transaction_id, result) = sign_test_transaction(existing);
+    assert_eq!(
+        result,
+        Result::PolicyViolation(format!(
+            "transaction {}: receiver {} is not allowed",
+            transaction_id, TEST_RECEIVER
+        ))
+    );
+
+    delete_vault(existing);
+}
+
+pub(crate) fn sign_transaction_policy_network() {
+    let existing = &create_test_vault_with_username("Policy Network");
+    set_signing_policy(
+        existing,
+        AlgorandSigningPolicy {
+            allowed_genesis_ids: Some(vec!["mainnet-v1.0".to_string()]),
+            ..Default::default()
+        },
+    );
+
+    let (transaction_id, result) = sign_test_transaction(existing);
+    assert_eq!(
+        result,
+        Result::PolicyViolation(format!(
+            "transaction {}: network \"sandnet-v1\" is not allowed",
+            transaction_id
+        ))
+    );
+
+    delete_vault(existing);
+}
+
+fn set_signing_policy(existing: &VaultDisplay, algorand_signing: AlgorandSigningPolicy) {
+    let policy = VaultPolicy {
+        algorand_signing,
+        ..Default::default()
+    };
+    let request = &actions::UpdateVaultPolicy {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "123456".to_string(),
+        policy: policy.clone(),
+    };
+    assert_eq!(
+        update_vault_policy(request),
+        UpdateVaultPolicyResult::Updated(policy)
+    );
+}
+
+/// Sign a test payment from the vault's account, returning its transaction ID and the result.
+fn sign_test_transaction(existing: &VaultDisplay) -> (String, Result) {
+    let algonaut_transaction: Transaction =
+        create_test_transaction_from(existing.algorand_address_base32.parse().unwrap());
+    let transaction_bytes = algonaut_transaction
+        .bytes_to_sign()
+        .unwrap()
+        .into_boxed_slice();
+
+    let request = &actions::SignTransaction {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "123456".to_string(),
+        transaction_to_sign: TransactionToSign::AlgorandTransaction { transaction_bytes },
+    };
+    (
+        algonaut_transaction.id().unwrap(),
+        sign_transaction(request),
+    )
+}
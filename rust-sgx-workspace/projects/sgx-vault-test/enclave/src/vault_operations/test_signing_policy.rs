@@ -0,0 +1,81 @@
+use std::prelude::v1::{String, ToString};
+
+use sgx_vault_impl::schema::actions::AlgorandTransactionSummary;
+use sgx_vault_impl::schema::entities::AlgorandSigningPolicy;
+use sgx_vault_impl::vault_operations::signing_policy::check_algorand_signing_policy;
+
+const TEST_SENDER: &str = "4MYUHDWHWXAKA5KA7U5PEN646VYUANBFXVJNONBK3TIMHEMWMD4UBOJBI4";
+
+pub(crate) fn signing_policy_rekey() {
+    let summary = &AlgorandTransactionSummary {
+        amount: Some(0),
+        rekey_to: Some(TEST_SENDER.to_string()),
+        ..test_summary("pay")
+    };
+
+    assert_eq!(
+        check(&limited_policy(), summary),
+        Err(format!(
+            "transaction TEST: rekeying the account to {} is not allowed",
+            TEST_SENDER
+        ))
+    );
+    assert_eq!(check(&AlgorandSigningPolicy::default(), summary), Ok(()));
+}
+
+pub(crate) fn signing_policy_unmodelled_types() {
+    for transaction_type in ["keyreg", "appl", "acfg", "afrz"] {
+        let summary = &test_summary(transaction_type);
+
+        assert_eq!(
+            check(&limited_policy(), summary),
+            Err(format!(
+                "transaction TEST: transaction type {:?} is not allowed",
+                transaction_type
+            ))
+        );
+        assert_eq!(check(&AlgorandSigningPolicy::default(), summary), Ok(()));
+    }
+}
+
+pub(crate) fn signing_policy_modelled_types() {
+    for transaction_type in ["pay", "axfer"] {
+        assert_eq!(
+            check(&limited_policy(), &test_summary(transaction_type)),
+            Ok(())
+        );
+    }
+}
+
+/// A policy with one generous limit.
+fn limited_policy() -> AlgorandSigningPolicy {
+    AlgorandSigningPolicy {
+        max_fee: Some(1_000_000),
+        ..Default::default()
+    }
+}
+
+fn test_summary(transaction_type: &str) -> AlgorandTransactionSummary {
+    AlgorandTransactionSummary {
+        transaction_id: "TEST".to_string(),
+        signed_by_vault: true,
+        transaction_type: transaction_type.to_string(),
+        sender: TEST_SENDER.to_string(),
+        receiver: None,
+        amount: None,
+        asset_id: None,
+        close_remainder_to: None,
+        rekey_to: None,
+        fee: 1000,
+        first_valid: 1000,
+        last_valid: 2000,
+        genesis_id: Some("sandnet-v1".to_string()),
+    }
+}
+
+fn check(
+    policy: &AlgorandSigningPolicy,
+    summary: &AlgorandTransactionSummary,
+) -> Result<(), String> {
+    check_algorand_signing_policy(policy, &[summary.clone()])
+}
@@ -1,13 +1,65 @@
 //! Test [`sgx_vault_impl::vault_operations::store`]
 
+use std::io;
+use std::path::Path;
 use std::prelude::v1::ToString;
+use std::untrusted::fs;
 
+use sgx_vault_impl::ported::kv_store::fs::encode_to_fs_safe;
+use sgx_vault_impl::ported::kv_store::in_memory::InMemoryStore;
 use sgx_vault_impl::ported::kv_store::KvStore;
-use sgx_vault_impl::schema::entities::VaultDisplay;
-use sgx_vault_impl::vault_operations::store::{key_from_id, unlock_vault, vault_store};
+use sgx_vault_impl::schema::entities::{
+    AlgorandAccount,
+    VaultDisplay,
+    VaultPolicy,
+    VaultStorable,
+    VAULT_SCHEMA_VERSION,
+};
+use sgx_vault_impl::vault_operations::store::{
+    init_vault_store,
+    key_from_id,
+    load_vault_in,
+    mutate_vault_in,
+    save_new_vault_in,
+    unlock_vault,
+    unlock_vault_in,
+    vault_store,
+    vault_store_dir,
+};
 
+use crate::helpers::temp_dir::TempDir;
 use crate::helpers::vault_store::create_test_vault_with_username;
 
+pub(crate) fn init_vault_store_works() {
+    let previous = &vault_store_dir();
+    let temp_dir = TempDir::create();
+    let store_dir = &temp_dir.as_ref().join("nested").join("vault_store");
+
+    init_vault_store(store_dir).unwrap();
+    assert!(fs::metadata(store_dir).unwrap().is_dir());
+    assert_eq!(&vault_store_dir(), store_dir);
+
+    let existing = create_test_vault_with_username("Init Vault Store Works");
+    let key = &key_from_id(&existing.vault_id).unwrap();
+    let record_path = store_dir.join(encode_to_fs_safe(key));
+    assert!(fs::metadata(record_path).unwrap().is_file());
+    vault_store().delete(key).unwrap();
+
+    init_vault_store(previous).unwrap();
+}
+
+pub(crate) fn init_vault_store_invalid() {
+    let previous = &vault_store_dir();
+    let temp_dir = TempDir::create();
+    let file_path = &temp_dir.as_ref().join("file");
+    fs::write(file_path, b"not a directory").unwrap();
+
+    assert!(init_vault_store(file_path).is_err());
+    let err = init_vault_store(Path::new("")).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(&vault_store_dir(), previous);
+}
+
 pub(crate) fn unlock_vault_works() {
     let existing = create_test_vault_with_username("Unlock Vault Works");
     let stored = unlock_vault(&existing.vault_id, "123456").unwrap();
@@ -37,3 +89,53 @@ pub(crate) fn unlock_vault_bad_auth_pin() {
     let key = &key_from_id(&existing.vault_id).unwrap();
     store.delete(key).unwrap();
 }
+
+/// The store functions work with any [`KvStore`], such as [`InMemoryStore`].
+pub(crate) fn vault_store_in_memory_works() {
+    let store = &mut InMemoryStore::default();
+    let vault = &test_vault_storable("In Memory");
+
+    save_new_vault_in(store, vault).unwrap();
+    assert_eq!(
+        load_vault_in(store, &vault.vault_id).unwrap().as_ref(),
+        Some(vault)
+    );
+    assert_eq!(
+        &unlock_vault_in(store, &vault.vault_id, "123456").unwrap(),
+        vault
+    );
+
+    let err = unlock_vault_in(store, &vault.vault_id, "000000").unwrap_err();
+    assert_eq!(err.to_string(), "invalid authentication PIN provided");
+    let err = unlock_vault_in(store, "missing", "123456").unwrap_err();
+    assert_eq!(err.to_string(), "invalid vault ID provided");
+
+    let mutated = mutate_vault_in(store, &vault.vault_id, |mut stored| {
+        stored.username = "Renamed".to_string();
+        stored
+    })
+    .unwrap()
+    .unwrap();
+    assert_eq!(mutated.username, "Renamed");
+    assert_eq!(
+        load_vault_in(store, &vault.vault_id).unwrap(),
+        Some(mutated)
+    );
+    assert_eq!(
+        mutate_vault_in(store, "missing", |stored| stored).unwrap(),
+        None
+    );
+}
+
+// Helper: A vault that is not saved anywhere.
+fn test_vault_storable(username: &str) -> VaultStorable {
+    VaultStorable {
+        schema_version: VAULT_SCHEMA_VERSION,
+        vault_id: username.to_string(),
+        auth_password: "123456".to_string(),
+        username: username.to_string(),
+        algorand_account: AlgorandAccount::generate(),
+        ethereum_account: None,
+        policy: VaultPolicy::default(),
+    }
+}
@@ -0,0 +1,168 @@
+use std::prelude::v1::{ToString, Vec};
+
+use algonaut::transaction::account::Account;
+use sgx_vault_impl::schema::actions;
+use sgx_vault_impl::schema::actions::{
+    AlgorandTransactionSummary,
+    EthereumTransactionSummary,
+    SummarizeTransactionResult,
+    TransactionSummary,
+    TransactionToSign,
+};
+use sgx_vault_impl::vault_operations::summarize_transaction::summarize_transaction;
+
+use crate::helpers::algonaut::create_test_transaction_from;
+use crate::helpers::vault_store::{create_test_vault_with_username, delete_vault};
+
+type Result = SummarizeTransactionResult;
+
+pub(crate) fn summarize_transaction_works() {
+    let existing = &create_test_vault_with_username("Summarize Works");
+
+    let algonaut_transaction =
+        create_test_transaction_from(existing.algorand_address_base32.parse().unwrap());
+    let transaction_bytes = algonaut_transaction
+        .bytes_to_sign()
+        .unwrap()
+        .into_boxed_slice();
+
+    let request = &actions::SummarizeTransaction {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "123456".to_string(),
+        transaction_to_sign: TransactionToSign::AlgorandTransaction { transaction_bytes },
+    };
+    let expected = TransactionSummary {
+        algorand_transactions: vec![AlgorandTransactionSummary {
+            transaction_id: algonaut_transaction.id().unwrap(),
+            signed_by_vault: true,
+            transaction_type: "pay".to_string(),
+            sender: existing.algorand_address_base32.clone(),
+            receiver: Some(
+                "4MYUHDWHWXAKA5KA7U5PEN646VYUANBFXVJNONBK3TIMHEMWMD4UBOJBI4".to_string(),
+            ),
+            amount: Some(123_456),
+            asset_id: None,
+            close_remainder_to: None,
+            rekey_to: None,
+            fee: 1000,
+            first_valid: 1000,
+            last_valid: 2000,
+            genesis_id: Some("sandnet-v1".to_string()),
+        }],
+        ethereum_transactions: vec![],
+        policy_violation: None,
+    };
+    assert_eq!(summarize_transaction(request), Result::Summarized(expected));
+
+    delete_vault(existing);
+}
+
+pub(crate) fn summarize_transaction_group_marks_vault_sender() {
+    let existing = &create_test_vault_with_username("Summarize Group");
+
+    let own_transaction =
+        create_test_transaction_from(existing.algorand_address_base32.parse().unwrap());
+    let other_transaction = create_test_transaction_from(Account::generate().address());
+    let transactions_bytes = vec![
+        own_transaction.bytes_to_sign().unwrap().into_boxed_slice(),
+        other_transaction
+            .bytes_to_sign()
+            .unwrap()
+            .into_boxed_slice(),
+    ];
+
+    let request = &actions::SummarizeTransaction {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "123456".to_string(),
+        transaction_to_sign: TransactionToSign::AlgorandTransactionGroup { transactions_bytes },
+    };
+    let summary = match summarize_transaction(request) {
+        Result::Summarized(summary) => summary,
+        otherwise => panic!("{:?}", otherwise),
+    };
+    let signed_by_vault: Vec<bool> = summary
+        .algorand_transactions
+        .iter()
+        .map(|transaction| transaction.signed_by_vault)
+        .collect();
+    assert_eq!(signed_by_vault, vec![true, false]);
+
+    delete_vault(existing);
+}
+
+pub(crate) fn summarize_transaction_bad_auth() {
+    let existing = &create_test_vault_with_username("Summarize Bad Auth");
+
+    let transaction_bytes =
+        create_test_transaction_from(existing.algorand_address_base32.parse().unwrap())
+            .bytes_to_sign()
+            .unwrap()
+            .into_boxed_slice();
+    let request = &actions::SummarizeTransaction {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "000000".to_string(),
+        transaction_to_sign: TransactionToSign::AlgorandTransaction { transaction_bytes },
+    };
+    assert_eq!(summarize_transaction(request), Result::InvalidAuth);
+
+    delete_vault(existing);
+}
+
+/// The transactions of the EIP-155 and EIP-1559 examples in test_sign_transaction_ethereum.
+pub(crate) fn summarize_transaction_ethereum() {
+    let existing = &create_test_vault_with_username("Summarize Ethereum");
+    let summarize = |transaction_hex: &str| {
+        let request = &actions::SummarizeTransaction {
+            vault_id: existing.vault_id.clone(),
+            auth_password: "123456".to_string(),
+            transaction_to_sign: TransactionToSign::EthereumTransaction {
+                transaction_bytes: hex::decode(transaction_hex).unwrap().into_boxed_slice(),
+            },
+        };
+        match summarize_transaction(request) {
+            Result::Summarized(summary) => {
+                assert_eq!(summary.algorand_transactions, vec![]);
+                summary.ethereum_transactions
+            }
+            otherwise => panic!("{:?}", otherwise),
+        }
+    };
+    let to = Some("0x3535353535353535353535353535353535353535".to_string());
+
+    assert_eq!(
+        summarize(
+            "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080"
+        ),
+        vec![EthereumTransactionSummary {
+            transaction_type: "legacy".to_string(),
+            chain_id: Some(1),
+            nonce: 9,
+            to: to.clone(),
+            value: "0xde0b6b3a7640000".to_string(),
+            data_len: 0,
+            gas_limit: 21_000,
+            gas_price: Some("0x4a817c800".to_string()),
+            max_priority_fee_per_gas: None,
+            max_fee_per_gas: None,
+        }]
+    );
+    assert_eq!(
+        summarize(
+            "02f00109843b9aca008504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080c0"
+        ),
+        vec![EthereumTransactionSummary {
+            transaction_type: "eip-1559".to_string(),
+            chain_id: Some(1),
+            nonce: 9,
+            to,
+            value: "0xde0b6b3a7640000".to_string(),
+            data_len: 0,
+            gas_limit: 21_000,
+            gas_price: None,
+            max_priority_fee_per_gas: Some("0x3b9aca00".to_string()),
+            max_fee_per_gas: Some("0x4a817c800".to_string()),
+        }]
+    );
+
+    delete_vault(existing);
+}
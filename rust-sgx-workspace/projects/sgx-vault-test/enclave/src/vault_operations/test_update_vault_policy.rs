@@ -0,0 +1,134 @@
+use std::prelude::v1::ToString;
+
+use sgx_vault_impl::schema::actions;
+use sgx_vault_impl::schema::actions::UpdateVaultPolicyResult;
+use sgx_vault_impl::schema::entities::{AlgorandSigningPolicy, VaultDisplay, VaultPolicy};
+use sgx_vault_impl::vault_operations::store::load_vault;
+use sgx_vault_impl::vault_operations::update_vault_policy::update_vault_policy;
+
+use crate::helpers::vault_store::{create_test_vault_with_username, delete_vault};
+
+type Result = UpdateVaultPolicyResult;
+
+pub(crate) fn update_vault_policy_works() {
+    let existing = &create_test_vault_with_username("Update Policy Works");
+    let stored = load_vault(&existing.vault_id).unwrap().unwrap();
+    assert_eq!(stored.policy, VaultPolicy::default());
+
+    let policy = VaultPolicy {
+        allow_algorand_account_export: false,
+        ..Default::default()
+    };
+    let request = &actions::UpdateVaultPolicy {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "123456".to_string(),
+        policy: policy.clone(),
+    };
+    assert_eq!(
+        update_vault_policy(request),
+        Result::Updated(policy.clone())
+    );
+
+    let stored = load_vault(&existing.vault_id).unwrap().unwrap();
+    assert_eq!(stored.policy, policy);
+
+    delete_vault(existing);
+}
+
+pub(crate) fn update_vault_policy_bad_auth() {
+    let existing = &create_test_vault_with_username("Update Policy Bad Auth");
+
+    let request = &actions::UpdateVaultPolicy {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "000000".to_string(),
+        policy: VaultPolicy {
+            allow_algorand_account_export: false,
+            ..Default::default()
+        },
+    };
+    assert_eq!(update_vault_policy(request), Result::InvalidAuth);
+
+    let stored = load_vault(&existing.vault_id).unwrap().unwrap();
+    assert_eq!(stored.policy, VaultPolicy::default());
+
+    delete_vault(existing);
+}
+
+pub(crate) fn update_vault_policy_tightens() {
+    let existing = &create_test_vault_with_username("Update Policy Tightens");
+    let mut policy = VaultPolicy {
+        allow_algorand_account_export: false,
+        algorand_signing: AlgorandSigningPolicy {
+            max_amount: Some(1000),
+            allowed_receivers: Some(vec!["A".to_string(), "B".to_string()]),
+            ..Default::default()
+        },
+    };
+    assert_eq!(update(existing, &policy), Result::Updated(policy.clone()));
+
+    policy.algorand_signing.max_amount = Some(999);
+    policy.algorand_signing.max_fee = Some(1000);
+    policy.algorand_signing.allowed_receivers = Some(vec!["B".to_string()]);
+    assert_eq!(update(existing, &policy), Result::Updated(policy.clone()));
+
+    // Setting the same policy again is allowed.
+    assert_eq!(update(existing, &policy), Result::Updated(policy.clone()));
+
+    delete_vault(existing);
+}
+
+pub(crate) fn update_vault_policy_would_loosen() {
+    let existing = &create_test_vault_with_username("Update Policy Would Loosen");
+    let policy = VaultPolicy {
+        allow_algorand_account_export: false,
+        algorand_signing: AlgorandSigningPolicy {
+            max_amount: Some(1000),
+            allowed_asset_ids: Some(vec![1, 2]),
+            ..Default::default()
+        },
+    };
+    assert_eq!(update(existing, &policy), Result::Updated(policy.clone()));
+
+    let loosen = |change: fn(&mut VaultPolicy), message: &str| {
+        let mut loosened = policy.clone();
+        change(&mut loosened);
+        assert_eq!(
+            update(existing, &loosened),
+            Result::WouldLoosen(message.to_string())
+        );
+    };
+    loosen(
+        |loosened| loosened.allow_algorand_account_export = true,
+        "allow_algorand_account_export can't be re-enabled",
+    );
+    loosen(
+        |loosened| loosened.algorand_signing.max_amount = Some(1001),
+        "max_amount can't be raised from 1000 to 1001",
+    );
+    loosen(
+        |loosened| loosened.algorand_signing.max_amount = None,
+        "max_amount can't be removed",
+    );
+    loosen(
+        |loosened| loosened.algorand_signing.allowed_asset_ids = Some(vec![1, 3]),
+        "allowed_asset_ids can't be extended with 3",
+    );
+    loosen(
+        |loosened| loosened.algorand_signing.allowed_asset_ids = None,
+        "allowed_asset_ids can't be removed",
+    );
+
+    let stored = load_vault(&existing.vault_id).unwrap().unwrap();
+    assert_eq!(stored.policy, policy);
+
+    delete_vault(existing);
+}
+
+fn update(existing: &VaultDisplay, policy: &VaultPolicy) -> Result {
+    let request = &actions::UpdateVaultPolicy {
+        vault_id: existing.vault_id.clone(),
+        auth_password: "123456".to_string(),
+        policy: policy.clone(),
+    };
+    update_vault_policy(request)
+}
@@ -1,4 +1,4 @@
-use std::env;
+use std::{env, fs};
 
 fn main() {
     println!("cargo:rerun-if-env-changed=SGX_SDK");
@@ -18,4 +18,24 @@ fn main() {
     }
 
     println!("cargo:rustc-link-lib=dylib=sgx_uprotected_fs");
+    // For RA-TLS quotes: see trait_impls::DcapQuotingEnclave
+    println!("cargo:rustc-link-lib=dylib=sgx_dcap_ql");
+
+    emit_enclave_tcs_num();
+}
+
+/// Pass the enclave's `TCSNum` to the app as `ENCLAVE_TCS_NUM`, to size its enclave worker pool.
+fn emit_enclave_tcs_num() {
+    let config_path = "../enclave/Enclave.config.xml";
+    println!("cargo:rerun-if-changed={}", config_path);
+
+    let config = fs::read_to_string(config_path)
+        .unwrap_or_else(|err| panic!("failed to read {}: {}", config_path, err));
+    let tcs_num: usize = config
+        .split("<TCSNum>")
+        .nth(1)
+        .and_then(|rest| rest.split("</TCSNum>").next())
+        .and_then(|tcs_num| tcs_num.trim().parse().ok())
+        .unwrap_or_else(|| panic!("{}: missing or invalid <TCSNum>", config_path));
+    println!("cargo:rustc-env=ENCLAVE_TCS_NUM={}", tcs_num);
 }
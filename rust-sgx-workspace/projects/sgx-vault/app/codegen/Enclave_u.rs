@@ -7,6 +7,18 @@ extern "C" {
         eid: sgx_enclave_id_t,
         retval: *mut sgx_status_t,
         p_qe3_target: *const sgx_target_info_t,
+        user_data: *const [u8; 32usize],
+        p_report: *mut sgx_report_t,
+        enclave_data: *mut [u8; 32usize],
+    ) -> sgx_status_t;
+}
+extern "C" {
+    pub fn enclave_create_tls_report(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        p_qe3_target: *const sgx_target_info_t,
+        tls_public_key_buffer: *const u8,
+        tls_public_key_size: size_t,
         p_report: *mut sgx_report_t,
         enclave_data: *mut [u8; 32usize],
     ) -> sgx_status_t;
@@ -15,10 +27,87 @@ extern "C" {
     pub fn vault_operation(
         eid: sgx_enclave_id_t,
         retval: *mut sgx_status_t,
+        request_id: u64,
         sealed_request_buffer: *const u8,
         sealed_request_size: size_t,
         sealed_response_buffer: *mut u8,
         sealed_response_capacity: size_t,
         sealed_response_used: *mut size_t,
+        operation_kind: *mut u8,
+        operation_outcome: *mut u8,
+    ) -> sgx_status_t;
+}
+extern "C" {
+    pub fn vault_operation_fetch_response(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        request_id: u64,
+        sealed_response_buffer: *mut u8,
+        sealed_response_capacity: size_t,
+        sealed_response_used: *mut size_t,
+    ) -> sgx_status_t;
+}
+extern "C" {
+    pub fn session_handshake(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        hello_buffer: *const u8,
+        hello_size: size_t,
+        sealed_accept_buffer: *mut u8,
+        sealed_accept_capacity: size_t,
+        sealed_accept_used: *mut size_t,
+    ) -> sgx_status_t;
+}
+extern "C" {
+    pub fn enclave_target_info(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        p_target_info: *mut sgx_target_info_t,
+    ) -> sgx_status_t;
+}
+extern "C" {
+    pub fn vault_store_migrate(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        record_name: *const ::std::os::raw::c_char,
+        migrated: *mut u8,
+    ) -> sgx_status_t;
+}
+extern "C" {
+    pub fn vault_handover_begin(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        p_predecessor_target: *const sgx_target_info_t,
+        p_report: *mut sgx_report_t,
+        successor_public_key: *mut [u8; 32usize],
+    ) -> sgx_status_t;
+}
+extern "C" {
+    pub fn vault_handover_export(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        p_successor_report: *const sgx_report_t,
+        successor_public_key: *const [u8; 32usize],
+        record_name: *const ::std::os::raw::c_char,
+        p_report: *mut sgx_report_t,
+        sealed_record_buffer: *mut u8,
+        sealed_record_capacity: size_t,
+        sealed_record_used: *mut size_t,
+    ) -> sgx_status_t;
+}
+extern "C" {
+    pub fn vault_handover_import(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        p_predecessor_report: *const sgx_report_t,
+        sealed_record_buffer: *const u8,
+        sealed_record_size: size_t,
+    ) -> sgx_status_t;
+}
+extern "C" {
+    pub fn vault_store_init(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        store_dir: *const ::std::os::raw::c_char,
     ) -> sgx_status_t;
 }
@@ -1,22 +1,37 @@
-use std::fs::create_dir_all;
-use std::io;
+use std::ffi::CString;
 use std::net::ToSocketAddrs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use std::{env, io};
 
 use env_var_helpers::env_vars;
+use http_service_impl::config::{CorsOrigins, ServerConfig, TlsConfig};
 use http_service_impl::server::run_server;
 use sgx_types::{sgx_attributes_t, sgx_launch_token_t, sgx_misc_attribute_t, SgxResult};
 use sgx_urts::SgxEnclave;
 
-use crate::trait_impls::VaultEnclaveImpl;
+use crate::trait_impls::{DcapQuotingEnclave, VaultEnclaveImpl};
 
 #[path = "../codegen/Enclave_u.rs"]
 mod enclave_u;
 mod safe_ecalls;
 mod trait_impls;
+mod vault_store_migration;
 
 static ENCLAVE_FILE: &str = "enclave.signed.so";
 
+/// The enclave's `TCSNum`, from its `Enclave.config.xml` (see `build.rs`).
+///
+/// Each concurrent enclave call needs its own TCS, so this caps the enclave workers.
+static ENCLAVE_TCS_NUM: &str = env!("ENCLAVE_TCS_NUM");
+
 fn init_enclave() -> SgxResult<SgxEnclave> {
+    init_enclave_file(ENCLAVE_FILE)
+}
+
+fn init_enclave_file(enclave_file: &str) -> SgxResult<SgxEnclave> {
     let mut launch_token: sgx_launch_token_t = [0; 1024];
     let mut launch_token_updated: i32 = 0;
     // call sgx_create_enclave to initialize an enclave instance
@@ -27,7 +42,7 @@ fn init_enclave() -> SgxResult<SgxEnclave> {
         misc_select: 0,
     };
     SgxEnclave::create(
-        ENCLAVE_FILE,
+        enclave_file,
         debug,
         &mut launch_token,
         &mut launch_token_updated,
@@ -35,6 +50,116 @@ fn init_enclave() -> SgxResult<SgxEnclave> {
     )
 }
 
+/// Point `enclave` at `store_dir`: the enclave checks it, and creates it if missing.
+fn init_vault_store(enclave: &SgxEnclave, store_dir: &Path) -> io::Result<()> {
+    let store_dir_c = store_dir
+        .to_str()
+        .and_then(|s| CString::new(s).ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid vault store directory: {:?}", store_dir),
+            )
+        })?;
+    safe_ecalls::safe_vault_store_init(enclave.geteid(), &store_dir_c)
+        .and_then(|result| result)
+        .map_err(|sgx_error| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("vault_store_init({:?}) failed: {:?}", store_dir, sgx_error),
+            )
+        })
+}
+
+/// Parse the environment variable `name`, if set.
+fn parse_env_var<T>(name: &str) -> io::Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    match env::var(name) {
+        Ok(value) => value.parse().map(Some).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid {} {:?}: {}", name, value, err),
+            )
+        }),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(err) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid {}: {}", name, err),
+        )),
+    }
+}
+
+/// Split a comma-separated list, skipping blanks.
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Read the server configuration from the environment, starting from the defaults.
+fn server_config_from_env() -> io::Result<ServerConfig> {
+    let mut config = ServerConfig::default();
+    config.bind_addr = env_vars::var_default("BIND_ADDR", &config.bind_addr)?;
+    let tcs_num: usize = ENCLAVE_TCS_NUM
+        .parse()
+        .expect("build.rs should emit a valid ENCLAVE_TCS_NUM");
+    config.enclave_workers = match parse_env_var("VAULT_ENCLAVE_WORKERS")? {
+        Some(enclave_workers) if enclave_workers > tcs_num => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "VAULT_ENCLAVE_WORKERS {} exceeds the enclave's TCSNum {}",
+                    enclave_workers, tcs_num
+                ),
+            ))
+        }
+        Some(enclave_workers) => enclave_workers,
+        None => tcs_num,
+    };
+    // Comma-separated origins, or "*" for any. Unset, no cross-origin requests are allowed.
+    if let Ok(origins) = env::var("CORS_ALLOWED_ORIGINS") {
+        config.cors_origins = match origins.trim() {
+            "*" => CorsOrigins::Any,
+            origins => CorsOrigins::List(split_list(origins)),
+        };
+    }
+    if let Some(max_request_size) = parse_env_var("MAX_REQUEST_SIZE")? {
+        config.max_request_size = max_request_size;
+    }
+    if let Some(secs) = parse_env_var("CLIENT_REQUEST_TIMEOUT_SECS")? {
+        config.client_request_timeout = Duration::from_secs(secs);
+    }
+    if let Some(secs) = parse_env_var("KEEP_ALIVE_SECS")? {
+        config.keep_alive = Duration::from_secs(secs);
+    }
+
+    let tls_cert_path = env::var_os("TLS_CERT_PATH").map(PathBuf::from);
+    let tls_key_path = env::var_os("TLS_KEY_PATH").map(PathBuf::from);
+    let ra_tls_names = env::var("RA_TLS_SUBJECT_ALT_NAMES").ok();
+    config.tls = match (tls_cert_path, tls_key_path, ra_tls_names) {
+        (None, None, None) => None,
+        (Some(cert_path), Some(key_path), None) => Some(TlsConfig::Pem {
+            cert_path,
+            key_path,
+        }),
+        (None, None, Some(names)) => Some(TlsConfig::RemoteAttestation {
+            subject_alt_names: split_list(&names),
+        }),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "set TLS_CERT_PATH with TLS_KEY_PATH, or RA_TLS_SUBJECT_ALT_NAMES",
+            ))
+        }
+    };
+    Ok(config)
+}
+
 #[actix_web::main]
 async fn main() -> io::Result<()> {
     let enclave = init_enclave()
@@ -45,16 +170,46 @@ async fn main() -> io::Result<()> {
             )
         })
         .unwrap();
-    let vault_enclave = Box::new(VaultEnclaveImpl { enclave });
-
-    // FIXME: See VAULT_STORE_DIR
-    create_dir_all("vault_store")?;
 
-    let bind_addr = env_vars::var_default("BIND_ADDR", "127.0.0.1:8080")?;
+    let vault_store_dir = &env_vars::var_default("VAULT_STORE_DIR", "vault_store")?;
+    let vault_store_dir = Path::new(vault_store_dir);
+    init_vault_store(&enclave, vault_store_dir)?;
 
-    for socket_addr in bind_addr.to_socket_addrs()? {
-        println!("run_server: binding to http://{}/", socket_addr);
+    // Optionally take over the vault store from a previous enclave version.
+    if let Ok(predecessor_file) = env::var("VAULT_HANDOVER_FROM_ENCLAVE") {
+        let predecessor = init_enclave_file(&predecessor_file).map_err(|sgx_error| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "init_enclave_file({:?}) failed: {:?}",
+                    predecessor_file, sgx_error
+                ),
+            )
+        })?;
+        let handed_over =
+            vault_store_migration::handover_vault_store(&predecessor, &enclave, vault_store_dir)?;
+        println!(
+            "vault store: handed over {} records from {}",
+            handed_over, predecessor_file
+        );
     }
+    let migrated = vault_store_migration::migrate_vault_store(&enclave, vault_store_dir)?;
+    println!("vault store: migrated {} records", migrated);
+
+    let vault_enclave = Arc::new(VaultEnclaveImpl {
+        enclave,
+        store_dir: vault_store_dir.to_path_buf(),
+    });
 
-    run_server(vault_enclave, bind_addr).await
+    let config = server_config_from_env()?;
+    let scheme = match config.tls {
+        Some(_) => "https",
+        None => "http",
+    };
+    for socket_addr in config.bind_addr.to_socket_addrs()? {
+        println!("run_server: binding to {}://{}/", scheme, socket_addr);
+    }
+    println!("run_server: {} enclave workers", config.enclave_workers);
+    println!("run_server: CORS origins: {:?}", config.cors_origins);
+    run_server(vault_enclave, &DcapQuotingEnclave, config).await
 }
@@ -1,5 +1,8 @@
 //! Safe Rust wrappers around [`enclave_u`].
 
+use std::ffi::CStr;
+
+use http_service_impl::traits::{OperationLabel, SealedResponse};
 use sgx_helpers::status::sgx_success_and_then;
 use sgx_types::{sgx_enclave_id_t, sgx_report_t, sgx_status_t, sgx_target_info_t, SgxResult};
 
@@ -8,6 +11,7 @@ use crate::enclave_u;
 pub fn safe_enclave_create_report(
     eid: sgx_enclave_id_t,
     qe3_target: sgx_target_info_t,
+    user_data: [u8; 32],
 ) -> SgxResult<SgxResult<(sgx_report_t, [u8; 32])>> {
     let mut retval = sgx_status_t::SGX_ERROR_UNEXPECTED;
     let mut ret_report: sgx_report_t = sgx_report_t::default();
@@ -18,6 +22,33 @@ pub fn safe_enclave_create_report(
             eid,
             &mut retval,
             &qe3_target,
+            &user_data,
+            &mut ret_report,
+            &mut ret_enclave_data,
+        )
+    };
+
+    sgx_success_and_then(result, || {
+        sgx_success_and_then(retval, || (ret_report, ret_enclave_data))
+    })
+}
+
+pub fn safe_enclave_create_tls_report(
+    eid: sgx_enclave_id_t,
+    qe3_target: sgx_target_info_t,
+    tls_public_key: &[u8],
+) -> SgxResult<SgxResult<(sgx_report_t, [u8; 32])>> {
+    let mut retval = sgx_status_t::SGX_ERROR_UNEXPECTED;
+    let mut ret_report: sgx_report_t = sgx_report_t::default();
+    let mut ret_enclave_data = [0; 32];
+
+    let result = unsafe {
+        enclave_u::enclave_create_tls_report(
+            eid,
+            &mut retval,
+            &qe3_target,
+            tls_public_key.as_ptr(),
+            tls_public_key.len(),
             &mut ret_report,
             &mut ret_enclave_data,
         )
@@ -30,29 +61,215 @@ pub fn safe_enclave_create_report(
 
 pub fn safe_vault_operation(
     eid: sgx_enclave_id_t,
+    request_id: u64,
     sealed_request: &[u8],
     sealed_response_capacity: usize,
-) -> SgxResult<SgxResult<Box<[u8]>>> {
+) -> SgxResult<SgxResult<(SealedResponse, OperationLabel)>> {
     let mut retval = sgx_status_t::SGX_ERROR_UNEXPECTED;
     let mut sealed_response = vec![0; sealed_response_capacity];
     let mut sealed_response_used = 0;
+    let mut label = OperationLabel {
+        operation: 0,
+        outcome: 0,
+    };
 
     let result = unsafe {
         enclave_u::vault_operation(
             eid,
             &mut retval,
+            request_id,
             sealed_request.as_ptr(),
             sealed_request.len(),
             sealed_response.as_mut_ptr(),
             sealed_response.len(),
             &mut sealed_response_used,
+            &mut label.operation,
+            &mut label.outcome,
         )
     };
     sgx_success_and_then(result, || {
-        sgx_success_and_then(retval, || {
-            assert!(sealed_response_used <= sealed_response_capacity);
+        sealed_response_or_pending(retval, sealed_response, sealed_response_used)
+            .map(|sealed_response| (sealed_response, label))
+    })
+}
+
+pub fn safe_vault_operation_fetch_response(
+    eid: sgx_enclave_id_t,
+    request_id: u64,
+    sealed_response_capacity: usize,
+) -> SgxResult<SgxResult<SealedResponse>> {
+    let mut retval = sgx_status_t::SGX_ERROR_UNEXPECTED;
+    let mut sealed_response = vec![0; sealed_response_capacity];
+    let mut sealed_response_used = 0;
+
+    let result = unsafe {
+        enclave_u::vault_operation_fetch_response(
+            eid,
+            &mut retval,
+            request_id,
+            sealed_response.as_mut_ptr(),
+            sealed_response.len(),
+            &mut sealed_response_used,
+        )
+    };
+    sgx_success_and_then(result, || {
+        sealed_response_or_pending(retval, sealed_response, sealed_response_used)
+    })
+}
+
+/// Helper: Interpret the results of [`safe_vault_operation`] and [`safe_vault_operation_fetch_response`].
+///
+/// [`sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT`] means the enclave holds the response,
+/// and `sealed_response_used` gives its size.
+fn sealed_response_or_pending(
+    retval: sgx_status_t,
+    mut sealed_response: Vec<u8>,
+    sealed_response_used: usize,
+) -> SgxResult<SealedResponse> {
+    match retval {
+        sgx_status_t::SGX_SUCCESS => {
+            assert!(sealed_response_used <= sealed_response.len());
             sealed_response.truncate(sealed_response_used);
-            sealed_response.into_boxed_slice()
+            Ok(SealedResponse::Complete(sealed_response.into_boxed_slice()))
+        }
+        sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT => Ok(SealedResponse::Pending {
+            size: sealed_response_used,
+        }),
+        sgx_error => Err(sgx_error),
+    }
+}
+
+pub fn safe_session_handshake(
+    eid: sgx_enclave_id_t,
+    hello: &[u8],
+    sealed_accept_capacity: usize,
+) -> SgxResult<SgxResult<Box<[u8]>>> {
+    let mut retval = sgx_status_t::SGX_ERROR_UNEXPECTED;
+    let mut sealed_accept = vec![0; sealed_accept_capacity];
+    let mut sealed_accept_used = 0;
+
+    let result = unsafe {
+        enclave_u::session_handshake(
+            eid,
+            &mut retval,
+            hello.as_ptr(),
+            hello.len(),
+            sealed_accept.as_mut_ptr(),
+            sealed_accept.len(),
+            &mut sealed_accept_used,
+        )
+    };
+    sgx_success_and_then(result, || {
+        sgx_success_and_then(retval, || {
+            sealed_accept.truncate(sealed_accept_used);
+            sealed_accept.into_boxed_slice()
+        })
+    })
+}
+
+pub fn safe_enclave_target_info(eid: sgx_enclave_id_t) -> SgxResult<SgxResult<sgx_target_info_t>> {
+    let mut retval = sgx_status_t::SGX_ERROR_UNEXPECTED;
+    let mut ret_target_info = sgx_target_info_t::default();
+
+    let result = unsafe { enclave_u::enclave_target_info(eid, &mut retval, &mut ret_target_info) };
+
+    sgx_success_and_then(result, || sgx_success_and_then(retval, || ret_target_info))
+}
+
+/// Return whether the record was found and needed migrating.
+pub fn safe_vault_store_migrate(
+    eid: sgx_enclave_id_t,
+    record_name: &CStr,
+) -> SgxResult<SgxResult<bool>> {
+    let mut retval = sgx_status_t::SGX_ERROR_UNEXPECTED;
+    let mut migrated = 0;
+
+    let result = unsafe {
+        enclave_u::vault_store_migrate(eid, &mut retval, record_name.as_ptr(), &mut migrated)
+    };
+
+    sgx_success_and_then(result, || sgx_success_and_then(retval, || migrated != 0))
+}
+
+pub fn safe_vault_store_init(eid: sgx_enclave_id_t, store_dir: &CStr) -> SgxResult<SgxResult<()>> {
+    let mut retval = sgx_status_t::SGX_ERROR_UNEXPECTED;
+
+    let result = unsafe { enclave_u::vault_store_init(eid, &mut retval, store_dir.as_ptr()) };
+
+    sgx_success_and_then(result, || sgx_success_and_then(retval, || ()))
+}
+
+pub fn safe_vault_handover_begin(
+    eid: sgx_enclave_id_t,
+    predecessor_target: sgx_target_info_t,
+) -> SgxResult<SgxResult<(sgx_report_t, [u8; 32])>> {
+    let mut retval = sgx_status_t::SGX_ERROR_UNEXPECTED;
+    let mut ret_report = sgx_report_t::default();
+    let mut ret_successor_public_key = [0; 32];
+
+    let result = unsafe {
+        enclave_u::vault_handover_begin(
+            eid,
+            &mut retval,
+            &predecessor_target,
+            &mut ret_report,
+            &mut ret_successor_public_key,
+        )
+    };
+    sgx_success_and_then(result, || {
+        sgx_success_and_then(retval, || (ret_report, ret_successor_public_key))
+    })
+}
+
+pub fn safe_vault_handover_export(
+    eid: sgx_enclave_id_t,
+    successor_report: &sgx_report_t,
+    successor_public_key: &[u8; 32],
+    record_name: &CStr,
+    sealed_record_capacity: usize,
+) -> SgxResult<SgxResult<(Box<[u8]>, sgx_report_t)>> {
+    let mut retval = sgx_status_t::SGX_ERROR_UNEXPECTED;
+    let mut ret_report = sgx_report_t::default();
+    let mut sealed_record = vec![0; sealed_record_capacity];
+    let mut sealed_record_used = 0;
+
+    let result = unsafe {
+        enclave_u::vault_handover_export(
+            eid,
+            &mut retval,
+            successor_report,
+            successor_public_key,
+            record_name.as_ptr(),
+            &mut ret_report,
+            sealed_record.as_mut_ptr(),
+            sealed_record.len(),
+            &mut sealed_record_used,
+        )
+    };
+    sgx_success_and_then(result, || {
+        sgx_success_and_then(retval, || {
+            assert!(sealed_record_used <= sealed_record_capacity);
+            sealed_record.truncate(sealed_record_used);
+            (sealed_record.into_boxed_slice(), ret_report)
         })
     })
 }
+
+pub fn safe_vault_handover_import(
+    eid: sgx_enclave_id_t,
+    predecessor_report: &sgx_report_t,
+    sealed_record: &[u8],
+) -> SgxResult<SgxResult<()>> {
+    let mut retval = sgx_status_t::SGX_ERROR_UNEXPECTED;
+
+    let result = unsafe {
+        enclave_u::vault_handover_import(
+            eid,
+            &mut retval,
+            predecessor_report,
+            sealed_record.as_ptr(),
+            sealed_record.len(),
+        )
+    };
+    sgx_success_and_then(result, || sgx_success_and_then(retval, || ()))
+}
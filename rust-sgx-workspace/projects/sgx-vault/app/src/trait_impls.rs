@@ -1,32 +1,122 @@
-//! Implement [`VaultEnclave`] using [`safe_ecalls`].
+//! Implement [`VaultEnclave`] using [`safe_ecalls`], and [`QuotingEnclave`] using DCAP.
 
-use http_service_impl::traits::VaultEnclave;
-use sgx_types::{sgx_report_t, sgx_target_info_t, SgxResult};
+use std::fs;
+use std::path::PathBuf;
+
+use http_service_impl::traits::{OperationLabel, QuotingEnclave, SealedResponse, VaultEnclave};
+use sgx_types::{
+    sgx_qe_get_quote,
+    sgx_qe_get_quote_size,
+    sgx_qe_get_target_info,
+    sgx_quote3_error_t,
+    sgx_report_t,
+    sgx_target_info_t,
+    SgxResult,
+};
 use sgx_urts::SgxEnclave;
 
 use crate::safe_ecalls;
 
+/// Sealed session accepts are around 200 bytes: see `sgx_vault_impl::schema::session`.
+const SEALED_ACCEPT_CAPACITY: usize = 1 << 10;
+
 pub(crate) struct VaultEnclaveImpl {
     pub(crate) enclave: SgxEnclave,
+
+    /// The vault store directory, as given to `vault_store_init`.
+    pub(crate) store_dir: PathBuf,
 }
 
 impl VaultEnclave for VaultEnclaveImpl {
     fn create_report(
         &self,
         target_info: sgx_target_info_t,
+        user_data: [u8; 32],
+    ) -> SgxResult<SgxResult<(sgx_report_t, [u8; 32])>> {
+        safe_ecalls::safe_enclave_create_report(self.enclave.geteid(), target_info, user_data)
+    }
+
+    fn create_tls_report(
+        &self,
+        target_info: sgx_target_info_t,
+        tls_public_key: &[u8],
     ) -> SgxResult<SgxResult<(sgx_report_t, [u8; 32])>> {
-        safe_ecalls::safe_enclave_create_report(self.enclave.geteid(), target_info)
+        safe_ecalls::safe_enclave_create_tls_report(
+            self.enclave.geteid(),
+            target_info,
+            tls_public_key,
+        )
     }
 
     fn vault_operation(
         &self,
+        request_id: u64,
         sealed_request: &[u8],
         sealed_response_capacity: usize,
-    ) -> SgxResult<SgxResult<Box<[u8]>>> {
+    ) -> SgxResult<SgxResult<(SealedResponse, OperationLabel)>> {
         safe_ecalls::safe_vault_operation(
             self.enclave.geteid(),
+            request_id,
             sealed_request,
             sealed_response_capacity,
         )
     }
+
+    fn fetch_vault_response(
+        &self,
+        request_id: u64,
+        sealed_response_capacity: usize,
+    ) -> SgxResult<SgxResult<SealedResponse>> {
+        safe_ecalls::safe_vault_operation_fetch_response(
+            self.enclave.geteid(),
+            request_id,
+            sealed_response_capacity,
+        )
+    }
+
+    fn session_handshake(&self, hello: &[u8]) -> SgxResult<SgxResult<Box<[u8]>>> {
+        safe_ecalls::safe_session_handshake(self.enclave.geteid(), hello, SEALED_ACCEPT_CAPACITY)
+    }
+
+    /// Call into the enclave, and check that the store directory is writable.
+    fn check_ready(&self) -> Result<(), String> {
+        safe_ecalls::safe_enclave_target_info(self.enclave.geteid())
+            .and_then(|result| result)
+            .map_err(|sgx_error| format!("enclave_target_info failed: {:?}", sgx_error))?;
+
+        let probe_path = self.store_dir.join(".readyz-probe");
+        fs::write(&probe_path, b"")
+            .and_then(|()| fs::remove_file(&probe_path))
+            .map_err(|err| format!("vault store {:?} is not writable: {}", self.store_dir, err))
+    }
+}
+
+/// The DCAP quote library's quoting enclave: this needs SGX hardware with FLC.
+pub(crate) struct DcapQuotingEnclave;
+
+impl QuotingEnclave for DcapQuotingEnclave {
+    fn target_info(&self) -> Result<sgx_target_info_t, String> {
+        let mut target_info = sgx_target_info_t::default();
+        let status = unsafe { sgx_qe_get_target_info(&mut target_info) };
+        quote3_success(status, "sgx_qe_get_target_info")?;
+        Ok(target_info)
+    }
+
+    fn quote(&self, report: &sgx_report_t) -> Result<Vec<u8>, String> {
+        let mut quote_size = 0;
+        let status = unsafe { sgx_qe_get_quote_size(&mut quote_size) };
+        quote3_success(status, "sgx_qe_get_quote_size")?;
+
+        let mut quote = vec![0; quote_size as usize];
+        let status = unsafe { sgx_qe_get_quote(report, quote_size, quote.as_mut_ptr()) };
+        quote3_success(status, "sgx_qe_get_quote")?;
+        Ok(quote)
+    }
+}
+
+fn quote3_success(status: sgx_quote3_error_t, function: &str) -> Result<(), String> {
+    match status {
+        sgx_quote3_error_t::SGX_QL_SUCCESS => Ok(()),
+        error => Err(format!("{} failed: {:?}", function, error)),
+    }
 }
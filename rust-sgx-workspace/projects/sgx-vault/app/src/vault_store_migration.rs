@@ -0,0 +1,189 @@
+//! Host side of vault store migration, and handover between enclave versions.
+//!
+//! See `sgx_vault_impl::vault_operations::migration` and `sgx_vault_impl::vault_operations::handover`.
+
+use std::ffi::CString;
+use std::fs::{read_dir, remove_dir_all, rename};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use sgx_types::{sgx_status_t, SgxResult};
+use sgx_urts::SgxEnclave;
+
+use crate::{init_vault_store, safe_ecalls};
+
+/// Migrate every record in `store_dir` to `enclave`'s current schema and key policy.
+///
+/// Return the number of records migrated.
+pub(crate) fn migrate_vault_store(enclave: &SgxEnclave, store_dir: &Path) -> io::Result<usize> {
+    let mut migrated_count = 0;
+    for record_name in record_names(store_dir)? {
+        let migrated = safe_ecalls::safe_vault_store_migrate(enclave.geteid(), &record_name);
+        if flatten_sgx_result("vault_store_migrate", &record_name, migrated)? {
+            migrated_count += 1;
+        }
+    }
+    Ok(migrated_count)
+}
+
+/// Hand over every record in `store_dir` from the `predecessor` enclave to the `successor`.
+///
+/// The successor imports into a fresh staging directory next to `store_dir`, because it
+/// can't replace records in place: it can't even read the ones bound to the predecessor.
+/// Once every record is imported, the staging directory takes the place of `store_dir`,
+/// which is kept as a backup, and the successor is pointed at the new `store_dir`.
+///
+/// An interrupted handover leaves `store_dir` untouched, and is retried from the start.
+/// A finished handover refuses to run again while its backup is in place.
+///
+/// Return the number of records handed over.
+pub(crate) fn handover_vault_store(
+    predecessor: &SgxEnclave,
+    successor: &SgxEnclave,
+    store_dir: &Path,
+) -> io::Result<usize> {
+    let staging_dir = &sibling_dir(store_dir, "handover")?;
+    let backup_dir = &sibling_dir(store_dir, "predecessor")?;
+    if backup_dir.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!(
+                "vault store already handed over: remove {:?} to hand it over again",
+                backup_dir
+            ),
+        ));
+    }
+    if staging_dir.exists() {
+        remove_dir_all(staging_dir)?;
+    }
+    init_vault_store(predecessor, store_dir)?;
+    init_vault_store(successor, staging_dir)?;
+
+    let handed_over_count = handover_records(predecessor, successor, store_dir)?;
+
+    rename(store_dir, backup_dir)?;
+    rename(staging_dir, store_dir)?;
+    init_vault_store(successor, store_dir)?;
+    Ok(handed_over_count)
+}
+
+/// Export every record in `store_dir` from the `predecessor`, and import it into the `successor`.
+fn handover_records(
+    predecessor: &SgxEnclave,
+    successor: &SgxEnclave,
+    store_dir: &Path,
+) -> io::Result<usize> {
+    let no_record = &CString::default();
+    let predecessor_target = flatten_sgx_result(
+        "enclave_target_info",
+        no_record,
+        safe_ecalls::safe_enclave_target_info(predecessor.geteid()),
+    )?;
+    let (successor_report, successor_public_key) = flatten_sgx_result(
+        "vault_handover_begin",
+        no_record,
+        safe_ecalls::safe_vault_handover_begin(successor.geteid(), predecessor_target),
+    )?;
+
+    let mut handed_over_count = 0;
+    for record_name in record_names(store_dir)? {
+        let (sealed_record, predecessor_report) = flatten_sgx_result(
+            "vault_handover_export",
+            &record_name,
+            export_with_retry(
+                predecessor,
+                &successor_report,
+                &successor_public_key,
+                &record_name,
+            ),
+        )?;
+        flatten_sgx_result(
+            "vault_handover_import",
+            &record_name,
+            safe_ecalls::safe_vault_handover_import(
+                successor.geteid(),
+                &predecessor_report,
+                &sealed_record,
+            ),
+        )?;
+        handed_over_count += 1;
+    }
+    Ok(handed_over_count)
+}
+
+/// The directory next to `store_dir`, with `suffix` appended to its name.
+fn sibling_dir(store_dir: &Path, suffix: &str) -> io::Result<PathBuf> {
+    let mut dir_name = store_dir
+        .file_name()
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid vault store directory: {:?}", store_dir),
+            )
+        })?
+        .to_owned();
+    dir_name.push(".");
+    dir_name.push(suffix);
+    Ok(store_dir.with_file_name(dir_name))
+}
+
+/// Retry [`safe_ecalls::safe_vault_handover_export`] with increasing capacity.
+///
+/// Unlike vault operations, exporting a record has no side effects, so retrying is safe.
+fn export_with_retry(
+    predecessor: &SgxEnclave,
+    successor_report: &sgx_types::sgx_report_t,
+    successor_public_key: &[u8; 32],
+    record_name: &CString,
+) -> SgxResult<SgxResult<(Box<[u8]>, sgx_types::sgx_report_t)>> {
+    // Attempt sizes: 1 KiB, 64 KiB, 1 MiB
+    for &sealed_record_capacity in &[1 << 10, 1 << 16, 1 << 20] {
+        match safe_ecalls::safe_vault_handover_export(
+            predecessor.geteid(),
+            successor_report,
+            successor_public_key,
+            record_name,
+            sealed_record_capacity,
+        ) {
+            Ok(Err(sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT)) => continue,
+            result => return result,
+        }
+    }
+    Ok(Err(sgx_status_t::SGX_ERROR_FAAS_BUFFER_TOO_SHORT))
+}
+
+/// The vault store's record file names.
+///
+/// Record file names start with `x` (see `sgx_vault_impl::ported::kv_store::fs::encode_to_fs_safe`):
+/// this skips the store's index file.
+fn record_names(store_dir: &Path) -> io::Result<Vec<CString>> {
+    let mut record_names = Vec::new();
+    for entry in read_dir(store_dir)? {
+        let file_name = entry?.file_name();
+        let file_name = file_name.to_str().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("non-UTF-8 vault store file name: {:?}", file_name),
+            )
+        })?;
+        if file_name.starts_with('x') {
+            record_names.push(CString::new(file_name)?);
+        }
+    }
+    record_names.sort();
+    Ok(record_names)
+}
+
+fn flatten_sgx_result<T>(
+    ecall: &str,
+    record_name: &CString,
+    result: SgxResult<SgxResult<T>>,
+) -> io::Result<T> {
+    match result {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(status)) | Err(status) => Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("{} failed for {:?}: {:?}", ecall, record_name, status),
+        )),
+    }
+}
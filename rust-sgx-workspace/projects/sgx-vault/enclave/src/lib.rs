@@ -3,5 +3,20 @@
 extern crate sgx_tstd as std;
 
 // Re-export ECALL implementations:
-pub use sgx_vault_impl::ecalls::enclave_create_report::enclave_create_report;
-pub use sgx_vault_impl::ecalls::vault_operation::vault_operation;
+pub use sgx_vault_impl::ecalls::enclave_create_report::{
+    enclave_create_report,
+    enclave_create_tls_report,
+};
+pub use sgx_vault_impl::ecalls::enclave_target_info::enclave_target_info;
+pub use sgx_vault_impl::ecalls::session_handshake::session_handshake;
+pub use sgx_vault_impl::ecalls::vault_handover::{
+    vault_handover_begin,
+    vault_handover_export,
+    vault_handover_import,
+};
+pub use sgx_vault_impl::ecalls::vault_operation::{
+    vault_operation,
+    vault_operation_fetch_response,
+};
+pub use sgx_vault_impl::ecalls::vault_store_init::vault_store_init;
+pub use sgx_vault_impl::ecalls::vault_store_migrate::vault_store_migrate;